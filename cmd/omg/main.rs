@@ -1,8 +1,31 @@
 use std::sync::{Arc, Mutex};
 
-use openmusicgang_config::app_config::AppConfig;
-use openmusicgang_postgres::{postgres::DB as PgDB, user::UserService as PgUserService};
-use openmusicgang_redis::redis::DB as RedisDB;
+use openmusicgang_config::{
+    app_config::AppConfig, jwt::JwtAlgorithm, mail::MailBackend, storage::StorageBackend,
+};
+use openmusicgang_crypto::{jwt::JwtSigner, password::PasswordHasher};
+use openmusicgang_http::server::Server as HttpServer;
+use openmusicgang_mail::{
+    maildir::EmailSender as MaildirEmailSender, smtp::EmailSender as SmtpEmailSender,
+};
+use openmusicgang_notify::file::NotificationTransport as FileNotificationTransport;
+use openmusicgang_postgres::{
+    activity::ActivityService as PgActivityService, comment::CommentService as PgCommentService,
+    email::EmailDispatcher as PgEmailDispatcher, follow::FollowService as PgFollowService,
+    gang::GangService as PgGangService, lead_sheet::LeadSheetService as PgLeadSheetService,
+    membership::MembershipService as PgMembershipService,
+    midi_clip::MidiClipService as PgMidiClipService,
+    notification::NotificationDispatcher as PgNotificationDispatcher,
+    notification::NotificationService as PgNotificationService, postgres::DB as PgDB,
+    song::SongService as PgSongService, token::TokenService as PgTokenService,
+    track::TrackService as PgTrackService, user::UserService as PgUserService,
+};
+use openmusicgang_redis::{
+    activity::ActivityService as RedisActivityService, auth::AuthService as RedisAuthService,
+    follow::FollowService as RedisFollowService, redis::DB as RedisDB,
+};
+use openmusicgang_service::{blob_store::BlobStore, email_sender::EmailSender};
+use openmusicgang_storage::{local::BlobStore as LocalBlobStore, s3::BlobStore as S3BlobStore};
 
 fn main() {
     let app = Main::new();
@@ -29,13 +52,144 @@ impl Main {
     }
 
     #[allow(dead_code)]
-    fn close(&self) {
+    fn close(self) {
         drop(self);
     }
 
     fn run(&self) {
-        let _postgres_user_service = PgUserService::new(self.postgres.clone());
-
         println!("current env: {}", self.config.app.env);
+
+        self.postgres.lock().unwrap().open().unwrap();
+        self.redis.lock().unwrap().open().unwrap();
+
+        let password_hasher = PasswordHasher::new(
+            self.config.password.memory_cost,
+            self.config.password.time_cost,
+            self.config.password.parallelism,
+        )
+        .unwrap();
+
+        let jwt_signer = match self.config.jwt.algorithm {
+            JwtAlgorithm::HS256 => JwtSigner::hs256(&self.config.jwt.secret),
+            JwtAlgorithm::EdDSA => {
+                JwtSigner::eddsa(&self.config.jwt.private_key, &self.config.jwt.public_key)
+            }
+        }
+        .unwrap();
+
+        let postgres_user_service = Arc::new(PgUserService::new(
+            self.postgres.clone(),
+            password_hasher.clone(),
+            self.config.verification.ttl,
+            self.config.verification.resend_interval,
+            self.config.verification.url.clone(),
+            self.config.password_reset.ttl,
+            self.config.password_reset.url.clone(),
+        ));
+
+        let postgres_token_service = Arc::new(PgTokenService::new(
+            self.postgres.clone(),
+            password_hasher,
+            jwt_signer,
+            self.config.jwt.access_token_ttl,
+            self.config.jwt.refresh_token_ttl,
+        ));
+
+        let _postgres_gang_service = PgGangService::new(self.postgres.clone());
+
+        let _postgres_membership_service =
+            PgMembershipService::new(self.postgres.clone(), self.config.invitation.ttl);
+
+        let _postgres_song_service = PgSongService::new(self.postgres.clone());
+
+        let _postgres_lead_sheet_service = PgLeadSheetService::new(self.postgres.clone());
+
+        let _postgres_comment_service = PgCommentService::new(self.postgres.clone());
+
+        let blob_store: Arc<dyn BlobStore + Send + Sync> = match self.config.storage.backend {
+            StorageBackend::Local => {
+                Arc::new(LocalBlobStore::new(&self.config.storage.root).unwrap())
+            }
+            StorageBackend::S3 => Arc::new(S3BlobStore::new(
+                &self.config.storage.s3.endpoint,
+                &self.config.storage.s3.region,
+                &self.config.storage.s3.bucket,
+                &self.config.storage.s3.access_key,
+                &self.config.storage.s3.secret_key,
+            )),
+        };
+
+        let _postgres_track_service =
+            PgTrackService::new(self.postgres.clone(), blob_store.clone());
+
+        let _postgres_midi_clip_service = PgMidiClipService::new(self.postgres.clone(), blob_store);
+
+        let _redis_auth_service = RedisAuthService::new(
+            self.redis.clone(),
+            postgres_user_service.clone(),
+            self.config.session.ttl,
+        );
+
+        let postgres_follow_service = Arc::new(PgFollowService::new(self.postgres.clone()));
+
+        let _redis_follow_service = RedisFollowService::new(
+            self.redis.clone(),
+            postgres_follow_service,
+            self.config.follow.counts_ttl,
+        );
+
+        let postgres_activity_service = Arc::new(PgActivityService::new(self.postgres.clone()));
+
+        let _redis_activity_service = RedisActivityService::new(
+            self.redis.clone(),
+            postgres_activity_service,
+            self.config.feed.cache_ttl,
+        );
+
+        let _postgres_notification_service = PgNotificationService::new(self.postgres.clone());
+
+        let notification_transport =
+            Arc::new(FileNotificationTransport::new(&self.config.notification.root).unwrap());
+
+        let notification_dispatcher = Arc::new(PgNotificationDispatcher::new(
+            self.postgres.clone(),
+            notification_transport,
+            self.config.notification.max_attempts,
+            self.config.notification.retry_delay,
+        ));
+
+        let _notification_dispatcher_thread =
+            notification_dispatcher.start(self.config.notification.dispatch_interval);
+
+        let email_sender: Arc<dyn EmailSender + Send + Sync> = match self.config.mail.backend {
+            MailBackend::Smtp => Arc::new(
+                SmtpEmailSender::new(
+                    &self.config.mail.smtp.host,
+                    self.config.mail.smtp.port,
+                    &self.config.mail.smtp.username,
+                    &self.config.mail.smtp.password,
+                    self.config.mail.smtp.starttls,
+                    &self.config.mail.from,
+                )
+                .unwrap(),
+            ),
+            MailBackend::Maildir => Arc::new(
+                MaildirEmailSender::new(&self.config.mail.maildir, &self.config.mail.from).unwrap(),
+            ),
+        };
+
+        let email_dispatcher = Arc::new(PgEmailDispatcher::new(
+            self.postgres.clone(),
+            email_sender,
+            self.config.mail.max_attempts,
+            self.config.mail.retry_delay,
+        ));
+
+        let _email_dispatcher_thread = email_dispatcher.start(self.config.mail.dispatch_interval);
+
+        let http_server = HttpServer::new(postgres_user_service, postgres_token_service);
+
+        println!("listening on {}", self.config.http.addr);
+        http_server.listen(&self.config.http.addr).unwrap();
     }
 }
@@ -0,0 +1,120 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::Validable;
+
+/// ActivityKind is the kind of event recorded as an activity.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ActivityKind {
+    MemberJoined,
+    RevisionCreated,
+    CommentAdded,
+    TrackUploaded,
+}
+
+impl ActivityKind {
+    /// Returns the kind as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActivityKind::MemberJoined => "member_joined",
+            ActivityKind::RevisionCreated => "revision_created",
+            ActivityKind::CommentAdded => "comment_added",
+            ActivityKind::TrackUploaded => "track_uploaded",
+        }
+    }
+}
+
+impl fmt::Display for ActivityKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ActivityKind {
+    type Err = Error;
+
+    /// Returns the kind of the given string.
+    ///
+    /// Returns EINVALID if the string is not a known kind.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "member_joined" => Ok(ActivityKind::MemberJoined),
+            "revision_created" => Ok(ActivityKind::RevisionCreated),
+            "comment_added" => Ok(ActivityKind::CommentAdded),
+            "track_uploaded" => Ok(ActivityKind::TrackUploaded),
+            _ => Err(Error::new(
+                ErrorCode::EINVALID,
+                format!("Unknown activity kind {}", s),
+            )),
+        }
+    }
+}
+
+/// Activity is a struct to represent something a user did in a gang, e.g. "Bob Smith uploaded a new
+/// bass take to Segfault Blues".
+///
+/// Activities are added to the feeds of the members of the gang, and of the users following the gang
+/// or the user who did it unless they are about a song, which only the members can see.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Activity {
+    pub id: i64,
+    pub kind: ActivityKind,
+    /// Id of the user who did it, None if the user was deleted.
+    pub actor_id: Option<i64>,
+    pub gang_id: i64,
+    pub song_id: Option<i64>,
+    /// Number of the song revision the activity is about, if any.
+    pub revision: Option<i32>,
+    /// Id of the track or the comment the activity is about, if any.
+    pub subject_id: Option<i64>,
+    /// Sentence describing the activity, written when it is recorded.
+    pub summary: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Activity {
+    pub fn new(kind: ActivityKind, actor_id: i64, gang_id: i64) -> Activity {
+        Activity {
+            id: 0,
+            kind,
+            actor_id: Some(actor_id),
+            gang_id,
+            song_id: None,
+            revision: None,
+            subject_id: None,
+            summary: "".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl Validable for Activity {
+    fn validate(&self) -> Result<(), Error> {
+        if self.gang_id == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "gang_id is required".to_string(),
+            ));
+        }
+
+        if self.summary.trim().is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "summary is required".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// FeedPage is a page of the activity feed of a user, newest first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FeedPage {
+    pub activities: Vec<Activity>,
+    /// Cursor of the next page, None on the last page.
+    pub next_cursor: Option<i64>,
+}
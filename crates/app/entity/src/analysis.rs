@@ -0,0 +1,12 @@
+/// AudioAnalysis is the estimated tempo and key of a recording, used to pre-fill the tempo and key of a song.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AudioAnalysis {
+    /// Estimated tempo in beats per minute, None if no steady beat was found.
+    pub tempo: Option<f64>,
+    /// Confidence of the tempo estimation, from 0.0 to 1.0.
+    pub tempo_confidence: f64,
+    /// Estimated key, written like the key of a song, e.g. "C#m", None if no pitch was found.
+    pub key: Option<String>,
+    /// Confidence of the key estimation, from 0.0 to 1.0.
+    pub key_confidence: f64,
+}
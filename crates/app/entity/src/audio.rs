@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use openmusicgang_err::error::{Error, ErrorCode};
+
+/// AudioFormat is the container format of an audio file.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AudioFormat {
+    Wav,
+    Flac,
+    Mp3,
+    Ogg,
+}
+
+impl AudioFormat {
+    /// Returns the format as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Ogg => "ogg",
+        }
+    }
+}
+
+impl fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AudioFormat {
+    type Err = Error;
+
+    /// Returns the format of the given string.
+    ///
+    /// Returns EINVALID if the string is not a supported format.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wav" => Ok(AudioFormat::Wav),
+            "flac" => Ok(AudioFormat::Flac),
+            "mp3" => Ok(AudioFormat::Mp3),
+            "ogg" => Ok(AudioFormat::Ogg),
+            _ => Err(Error::new(
+                ErrorCode::EINVALID,
+                format!("Unknown audio format {}", s),
+            )),
+        }
+    }
+}
+
+/// AudioInfo is a struct to represent the properties of an audio file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioInfo {
+    pub format: AudioFormat,
+    /// Number of samples per channel.
+    pub frames: i64,
+    /// Sample rate in Hz.
+    pub sample_rate: i32,
+    /// Bits per sample, None for lossy formats.
+    pub bit_depth: Option<i32>,
+    pub channels: i32,
+    /// Embedded tags, such as ID3v2 frames or Vorbis comments, by lowercase name, e.g. "title".
+    pub tags: BTreeMap<String, String>,
+}
+
+impl AudioInfo {
+    /// Returns the duration of the audio in seconds.
+    pub fn duration(&self) -> f64 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+
+        self.frames as f64 / self.sample_rate as f64
+    }
+}
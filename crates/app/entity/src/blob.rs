@@ -0,0 +1,33 @@
+use chrono::prelude::*;
+
+/// Blob is a struct to represent a stored file, such as the audio of a track.
+///
+/// Blobs are content-addressed, the key of a blob is the lowercase hex SHA-256 digest of its content,
+/// so identical uploads share the same blob.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blob {
+    pub key: String,
+    /// Size of the blob in bytes.
+    pub size: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Returns true if the given string is a valid blob key.
+///
+/// # Example
+/// ```
+/// use openmusicgang_entity::blob::is_blob_key;
+/// assert!(is_blob_key("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"));
+/// assert!(!is_blob_key("../etc/passwd"));
+/// ```
+pub fn is_blob_key(key: &str) -> bool {
+    key.len() == 64 && is_blob_key_prefix(key)
+}
+
+/// Returns true if the given string can be the beginning of a blob key.
+pub fn is_blob_key_prefix(prefix: &str) -> bool {
+    prefix.len() <= 64
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
@@ -0,0 +1,121 @@
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::Validable;
+
+/// Comment is a struct to represent a feedback on a song revision or one of its tracks, e.g.
+/// "at 1:32 the bass is too loud".
+///
+/// A comment starting a thread can be resolved, its replies belong to the same song revision and track.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    pub id: i64,
+    pub song_id: i64,
+    /// Number of the song revision the comment is about.
+    pub revision: i32,
+    /// Id of the track the comment is about, None for the whole song revision.
+    pub track_id: Option<i64>,
+    /// Id of the comment replied to, None for a comment starting a thread.
+    pub parent_id: Option<i64>,
+    /// Start of the time range the comment is about in samples, None for the whole song or track.
+    pub range_start: Option<i64>,
+    /// End of the time range the comment is about in samples, None for a single point in time.
+    pub range_end: Option<i64>,
+    pub body: String,
+    /// Id of the user who wrote the comment, None if the user was deleted.
+    pub author_id: Option<i64>,
+    /// Time the thread was resolved, None while it is open.
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// Id of the user who resolved the thread, None while it is open or if the user was deleted.
+    pub resolved_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Comment {
+    pub fn new() -> Comment {
+        Comment {
+            id: 0,
+            song_id: 0,
+            revision: 0,
+            track_id: None,
+            parent_id: None,
+            range_start: None,
+            range_end: None,
+            body: "".to_string(),
+            author_id: None,
+            resolved_at: None,
+            resolved_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.resolved_at.is_some()
+    }
+
+    /// Returns true if the body mentions the user with the given name as `@Name`, compared
+    /// case-insensitively, e.g. "@bob smith the bass is too loud".
+    pub fn mentions(&self, name: &str) -> bool {
+        let body = self.body.to_lowercase();
+        let mention = format!("@{}", name.to_lowercase());
+
+        body.match_indices(&mention).any(|(start, _)| {
+            body[start + mention.len()..]
+                .chars()
+                .next()
+                .is_none_or(|next| !next.is_alphanumeric())
+        })
+    }
+}
+
+impl Default for Comment {
+    fn default() -> Self {
+        Comment::new()
+    }
+}
+
+impl Validable for Comment {
+    fn validate(&self) -> Result<(), Error> {
+        if self.song_id == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "song_id is required".to_string(),
+            ));
+        }
+
+        if self.body.trim().is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "body is required".to_string(),
+            ));
+        }
+
+        let valid_range = match (self.range_start, self.range_end) {
+            (None, None) => true,
+            (Some(start), None) => start >= 0,
+            (Some(start), Some(end)) => start >= 0 && end >= start,
+            (None, Some(_)) => false,
+        };
+
+        if !valid_range {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "range must start at a positive sample and end after its start".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// CommentEdit is a previous body of a comment, recorded every time its author edits it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommentEdit {
+    pub id: i64,
+    pub comment_id: i64,
+    /// Body of the comment before the edit.
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,151 @@
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::Validable;
+
+/// Email is a struct to represent an email with a plain-text and an HTML part.
+///
+/// Emails are queued in an outbox and sent once the transaction that queued them is committed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Email {
+    pub id: i64,
+    /// Address of the recipient.
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+    /// Number of times the email was tried to be sent.
+    pub attempts: i32,
+    /// Error of the last failed attempt, if any.
+    pub last_error: Option<String>,
+    /// Time the email was sent, None while it is pending.
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Email {
+    pub fn new() -> Email {
+        Email {
+            id: 0,
+            to: "".to_string(),
+            subject: "".to_string(),
+            text: "".to_string(),
+            html: "".to_string(),
+            attempts: 0,
+            last_error: None,
+            sent_at: None,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl Default for Email {
+    fn default() -> Self {
+        Email::new()
+    }
+}
+
+impl Validable for Email {
+    fn validate(&self) -> Result<(), Error> {
+        if !self.to.contains('@') {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "to must be an email address".to_string(),
+            ));
+        }
+
+        if self.subject.trim().is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "subject is required".to_string(),
+            ));
+        }
+
+        if self.text.trim().is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "text is required".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// EmailTemplate is a template of an email, e.g. "Hi {{name}}, confirm your email address".
+///
+/// The `{{variable}}` placeholders of its parts are replaced by the values of the variables, escaped in
+/// the HTML part. Placeholders without a value are left as they are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EmailTemplate {
+    pub subject: &'static str,
+    pub text: &'static str,
+    pub html: &'static str,
+}
+
+impl EmailTemplate {
+    /// Returns the email of the template to the given address.
+    pub fn render(&self, to: &str, variables: &[(&str, &str)]) -> Email {
+        Email {
+            to: to.to_string(),
+            // A line break would end the header of the subject.
+            subject: render_part(self.subject, variables, |value| {
+                value.replace(['\r', '\n'], " ")
+            }),
+            text: render_part(self.text, variables, str::to_string),
+            html: render_part(self.html, variables, escape_html),
+            ..Default::default()
+        }
+    }
+}
+
+/// Returns the part of a template with its placeholders replaced by the encoded values of the variables.
+///
+/// The part is read once, so placeholders in the values are not replaced.
+fn render_part(part: &str, variables: &[(&str, &str)], encode: impl Fn(&str) -> String) -> String {
+    let mut rendered = String::with_capacity(part.len());
+    let mut rest = part;
+
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+
+        let placeholder = &rest[start..];
+        let end = match placeholder.find("}}") {
+            Some(end) => end + 2,
+            None => {
+                rest = placeholder;
+                break;
+            }
+        };
+
+        let name = placeholder[2..end - 2].trim();
+
+        match variables.iter().find(|(variable, _)| *variable == name) {
+            Some((_, value)) => rendered.push_str(&encode(value)),
+            None => rendered.push_str(&placeholder[..end]),
+        }
+
+        rest = &placeholder[end..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Returns the text escaped to be written in HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::Validable;
+
+/// FollowTarget is what a user can follow, another user or a gang.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FollowTarget {
+    User(i64),
+    Gang(i64),
+}
+
+impl FollowTarget {
+    /// Returns the id of the followed user or gang.
+    pub fn id(&self) -> i64 {
+        match self {
+            FollowTarget::User(id) | FollowTarget::Gang(id) => *id,
+        }
+    }
+
+    /// Returns the id of the followed user, None for a gang.
+    pub fn user_id(&self) -> Option<i64> {
+        match self {
+            FollowTarget::User(id) => Some(*id),
+            FollowTarget::Gang(_) => None,
+        }
+    }
+
+    /// Returns the id of the followed gang, None for a user.
+    pub fn gang_id(&self) -> Option<i64> {
+        match self {
+            FollowTarget::User(_) => None,
+            FollowTarget::Gang(id) => Some(*id),
+        }
+    }
+
+    /// Returns the kind of the target as a string.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FollowTarget::User(_) => "user",
+            FollowTarget::Gang(_) => "gang",
+        }
+    }
+}
+
+impl fmt::Display for FollowTarget {
+    /// Formats the target as its kind and id, e.g. "user:1".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.kind(), self.id())
+    }
+}
+
+/// Follow is a struct to represent a user following another user or a gang.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Follow {
+    pub id: i64,
+    /// Id of the user following the target.
+    pub follower_id: i64,
+    pub target: FollowTarget,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Follow {
+    pub fn new(follower_id: i64, target: FollowTarget) -> Follow {
+        Follow {
+            id: 0,
+            follower_id,
+            target,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl Validable for Follow {
+    fn validate(&self) -> Result<(), Error> {
+        if self.follower_id == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "follower_id is required".to_string(),
+            ));
+        }
+
+        if self.target.id() == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "target is required".to_string(),
+            ));
+        }
+
+        if self.target == FollowTarget::User(self.follower_id) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "You cannot follow yourself".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// FollowCounts is the number of followers of a user or a gang, and the number of users and gangs a user follows.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FollowCounts {
+    pub followers: i64,
+    /// Number of users and gangs followed, always 0 for a gang.
+    pub following: i64,
+}
@@ -0,0 +1,120 @@
+use crate::Validable;
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Gang is a struct to represent a gang, a band of musicians making music together.
+///
+/// The slug is the unique, url friendly identifier of the gang.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gang {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub name: String,
+    pub slug: String,
+    pub description: String,
+    pub genre: Option<String>,
+    /// Id of the user who created the gang, None if the user was deleted.
+    pub created_by: Option<i64>,
+}
+
+impl Gang {
+    pub fn new() -> Gang {
+        Gang {
+            id: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            name: "".to_string(),
+            slug: "".to_string(),
+            description: "".to_string(),
+            genre: None,
+            created_by: None,
+        }
+    }
+}
+
+impl Default for Gang {
+    fn default() -> Self {
+        Gang::new()
+    }
+}
+
+impl Validable for Gang {
+    fn validate(&self) -> Result<(), Error> {
+        if self.name.trim().is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "name is required".to_string(),
+            ));
+        }
+
+        if self.slug.is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "slug is required".to_string(),
+            ));
+        }
+
+        if slugify(&self.slug) != self.slug {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "slug can only contain lowercase letters, digits and single dashes".to_string(),
+            ));
+        }
+
+        if self.genre.as_ref().is_some_and(|genre| genre.is_empty()) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "genre cannot be empty if provided".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the slug of the given name.
+///
+/// Latin letters are transliterated to ASCII and lowercased, every run of other characters becomes a single
+/// dash. The slug is empty if the name has no Latin letter or digit, e.g. if it is written in another script.
+///
+/// # Example
+/// ```
+/// use openmusicgang_entity::gang::slugify;
+/// assert_eq!(slugify("The Velvet  Underground!"), "the-velvet-underground");
+/// assert_eq!(slugify("Über Straße"), "uber-strasse");
+/// assert_ne!(slugify("Über"), slugify("Ber"));
+/// assert_eq!(slugify("東京事変"), "");
+/// ```
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+
+    for c in name.nfkd().filter(|c| !is_combining_mark(*c)) {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if let Some(ascii) = transliterate(c) {
+            slug.push_str(ascii);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Returns the ASCII spelling of the Latin letters that do not decompose into a base letter and accents.
+fn transliterate(c: char) -> Option<&'static str> {
+    match c {
+        'ß' => Some("ss"),
+        'æ' | 'Æ' => Some("ae"),
+        'œ' | 'Œ' => Some("oe"),
+        'ø' | 'Ø' => Some("o"),
+        'đ' | 'Đ' | 'ð' | 'Ð' => Some("d"),
+        'ł' | 'Ł' => Some("l"),
+        'þ' | 'Þ' => Some("th"),
+        'ı' => Some("i"),
+        _ => None,
+    }
+}
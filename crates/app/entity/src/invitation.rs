@@ -0,0 +1,131 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::membership::GangRole;
+use crate::Validable;
+
+/// InvitationStatus is the status of an invitation to join a gang.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InvitationStatus {
+    Pending,
+    Accepted,
+    Declined,
+    Expired,
+}
+
+impl InvitationStatus {
+    /// Returns the status as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InvitationStatus::Pending => "pending",
+            InvitationStatus::Accepted => "accepted",
+            InvitationStatus::Declined => "declined",
+            InvitationStatus::Expired => "expired",
+        }
+    }
+}
+
+impl fmt::Display for InvitationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for InvitationStatus {
+    type Err = Error;
+
+    /// Returns the status of the given string.
+    ///
+    /// Returns EINVALID if the string is not a known status.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(InvitationStatus::Pending),
+            "accepted" => Ok(InvitationStatus::Accepted),
+            "declined" => Ok(InvitationStatus::Declined),
+            "expired" => Ok(InvitationStatus::Expired),
+            _ => Err(Error::new(
+                ErrorCode::EINVALID,
+                format!("Unknown invitation status {}", s),
+            )),
+        }
+    }
+}
+
+/// Invitation is a struct to represent an invitation to join a gang.
+///
+/// The invited user is identified either by id or by email, the email allows
+/// to invite people who have not signed up yet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Invitation {
+    pub id: i64,
+    pub gang_id: i64,
+    /// Id of the user who sent the invitation, None if the user was deleted.
+    pub inviter_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub email: Option<String>,
+    pub role: GangRole,
+    pub status: InvitationStatus,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub responded_at: Option<DateTime<Utc>>,
+}
+
+impl Invitation {
+    pub fn new() -> Invitation {
+        Invitation {
+            id: 0,
+            gang_id: 0,
+            inviter_id: None,
+            user_id: None,
+            email: None,
+            role: GangRole::Member,
+            status: InvitationStatus::Pending,
+            created_at: Utc::now(),
+            expires_at: Utc::now(),
+            responded_at: None,
+        }
+    }
+}
+
+impl Default for Invitation {
+    fn default() -> Self {
+        Invitation::new()
+    }
+}
+
+impl Validable for Invitation {
+    fn validate(&self) -> Result<(), Error> {
+        if self.gang_id == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "gang_id is required".to_string(),
+            ));
+        }
+
+        if self.user_id.is_some() == self.email.is_some() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "either user_id or email is required".to_string(),
+            ));
+        }
+
+        if self.email.as_ref().is_some_and(|email| email.is_empty()) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "email cannot be empty if provided".to_string(),
+            ));
+        }
+
+        if self.role == GangRole::Owner {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "cannot invite an owner, transfer the ownership instead".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
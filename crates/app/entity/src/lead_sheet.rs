@@ -0,0 +1,164 @@
+use core::fmt;
+use std::str::FromStr;
+
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_theory::chord::Chord;
+
+use crate::Validable;
+
+/// LeadSheet is a struct to represent the chord chart and lyrics of a song revision, shared by its gang.
+///
+/// A song revision has at most one lead sheet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeadSheet {
+    pub id: i64,
+    pub song_id: i64,
+    /// Number of the song revision the lead sheet belongs to.
+    pub revision: i32,
+    pub sections: Vec<Section>,
+    /// Id of the user who wrote the lead sheet, None if the user was deleted.
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl LeadSheet {
+    pub fn new() -> LeadSheet {
+        LeadSheet {
+            id: 0,
+            song_id: 0,
+            revision: 0,
+            sections: vec![],
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+impl Default for LeadSheet {
+    fn default() -> Self {
+        LeadSheet::new()
+    }
+}
+
+impl Validable for LeadSheet {
+    fn validate(&self) -> Result<(), Error> {
+        if self.song_id == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "song_id is required".to_string(),
+            ));
+        }
+
+        for section in &self.sections {
+            section.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// SectionKind is the part of a song a section of a lead sheet is, such as a verse or a chorus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SectionKind {
+    Verse,
+    Chorus,
+    Bridge,
+}
+
+impl SectionKind {
+    /// Returns the kind as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SectionKind::Verse => "verse",
+            SectionKind::Chorus => "chorus",
+            SectionKind::Bridge => "bridge",
+        }
+    }
+}
+
+impl fmt::Display for SectionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for SectionKind {
+    type Err = Error;
+
+    /// Returns the section kind of the given string.
+    ///
+    /// Returns EINVALID if the string is not a known section kind.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "verse" => Ok(SectionKind::Verse),
+            "chorus" => Ok(SectionKind::Chorus),
+            "bridge" => Ok(SectionKind::Bridge),
+            _ => Err(Error::new(
+                ErrorCode::EINVALID,
+                format!("Unknown section kind {}", s),
+            )),
+        }
+    }
+}
+
+/// Section is a part of a lead sheet, its lyrics lines with the chords played over them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    pub kind: SectionKind,
+    /// Label of the section, e.g. "Verse 2", empty if the section has none.
+    pub label: String,
+    pub lines: Vec<Line>,
+}
+
+impl Validable for Section {
+    fn validate(&self) -> Result<(), Error> {
+        for line in &self.lines {
+            line.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Line is a lyrics line of a section annotated with chords, a line of chords only has empty lyrics.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Line {
+    pub lyrics: String,
+    /// Chords of the line, in order of position.
+    pub chords: Vec<ChordPosition>,
+}
+
+impl Validable for Line {
+    fn validate(&self) -> Result<(), Error> {
+        let length = self.lyrics.chars().count();
+
+        if self.chords.iter().any(|chord| chord.position > length)
+            || self
+                .chords
+                .windows(2)
+                .any(|pair| pair[0].position > pair[1].position)
+        {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "chords must be in order of position within their lyrics".to_string(),
+            ));
+        }
+
+        for position in &self.chords {
+            position.chord.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// ChordPosition is a chord played over a lyrics line, from the character at its position onwards.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChordPosition {
+    /// Index of the character of the lyrics the chord is played on, the length of the lyrics at their end.
+    pub position: usize,
+    pub chord: Chord,
+}
@@ -1,6 +1,29 @@
 use openmusicgang_err::error::Error;
 
+pub mod activity;
+pub mod analysis;
+pub mod audio;
+pub mod blob;
+pub mod comment;
+pub mod email;
+pub mod follow;
+pub mod gang;
+pub mod invitation;
+pub mod lead_sheet;
+pub mod loudness;
+pub mod membership;
+pub mod midi_clip;
+pub mod mixdown;
+pub mod notification;
+pub mod password_reset;
+pub mod session;
+pub mod song;
+pub mod theory;
+pub mod token;
+pub mod track;
 pub mod user;
+pub mod verification;
+pub mod waveform;
 
 pub trait Validable {
     fn validate(&self) -> Result<(), Error>;
@@ -0,0 +1,38 @@
+/// Maximum true peak of normalized audio in dBTP, the headroom left for lossy encoding.
+pub const MAX_TRUE_PEAK: f64 = -1.0;
+
+/// Loudness is the loudness of a recording measured per ITU-R BS.1770 and EBU R128.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Loudness {
+    /// Integrated loudness in LUFS.
+    pub integrated: f64,
+    /// Loudness range in LU.
+    pub range: f64,
+    /// Maximum true peak in dBTP.
+    pub true_peak: f64,
+}
+
+impl Loudness {
+    /// Returns the gain in decibels that brings the integrated loudness to the target, lowered so that
+    /// the true peak stays under MAX_TRUE_PEAK.
+    ///
+    /// # Example
+    /// ```
+    /// use openmusicgang_entity::loudness::Loudness;
+    /// let loudness = Loudness { integrated: -20.0, range: 5.0, true_peak: -6.0 };
+    /// assert_eq!(loudness.normalization_gain(-23.0), -3.0);
+    /// assert_eq!(loudness.normalization_gain(-14.0), 5.0);
+    /// ```
+    pub fn normalization_gain(&self, target: f64) -> f64 {
+        (target - self.integrated).min(MAX_TRUE_PEAK - self.true_peak)
+    }
+
+    /// Returns the loudness of the same recording with a gain in decibels applied.
+    pub fn with_gain(&self, gain: f64) -> Loudness {
+        Loudness {
+            integrated: self.integrated + gain,
+            range: self.range,
+            true_peak: self.true_peak + gain,
+        }
+    }
+}
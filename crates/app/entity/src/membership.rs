@@ -0,0 +1,78 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+/// GangRole is the role of a user in a gang.
+///
+/// Roles are ordered by privileges, an owner has every privilege of an admin and so on.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum GangRole {
+    Guest,
+    Member,
+    Admin,
+    Owner,
+}
+
+impl GangRole {
+    /// Returns the role as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GangRole::Guest => "guest",
+            GangRole::Member => "member",
+            GangRole::Admin => "admin",
+            GangRole::Owner => "owner",
+        }
+    }
+}
+
+impl fmt::Display for GangRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for GangRole {
+    type Err = Error;
+
+    /// Returns the role of the given string.
+    ///
+    /// Returns EINVALID if the string is not a known role.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "guest" => Ok(GangRole::Guest),
+            "member" => Ok(GangRole::Member),
+            "admin" => Ok(GangRole::Admin),
+            "owner" => Ok(GangRole::Owner),
+            _ => Err(Error::new(
+                ErrorCode::EINVALID,
+                format!("Unknown gang role {}", s),
+            )),
+        }
+    }
+}
+
+/// Membership is a struct to represent the membership of a user in a gang.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Membership {
+    pub id: i64,
+    pub gang_id: i64,
+    pub user_id: i64,
+    pub role: GangRole,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Membership {
+    pub fn new(gang_id: i64, user_id: i64, role: GangRole) -> Membership {
+        Membership {
+            id: 0,
+            gang_id,
+            user_id,
+            role,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::Validable;
+
+/// MidiClip is a struct to represent a Standard MIDI File attached to a song, such as a part written in a DAW.
+///
+/// Like a track, a clip belongs to a revision of a song and references its file by the key of a stored blob.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiClip {
+    pub id: i64,
+    pub song_id: i64,
+    /// Number of the song revision the clip belongs to.
+    pub revision: i32,
+    /// Key of the MIDI file blob of the clip.
+    pub blob_key: String,
+    pub name: String,
+    /// Format of the MIDI file, 0 for a single track and 1 for simultaneous tracks.
+    pub format: i32,
+    /// Number of tracks of the MIDI file.
+    pub tracks: i32,
+    /// Number of ticks per quarter note.
+    pub ticks_per_quarter: i32,
+    /// Number of notes played.
+    pub notes: i32,
+    /// Duration in seconds, following the tempo changes of the file.
+    pub duration: f64,
+    /// Id of the user who added the clip, None if the user was deleted.
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl MidiClip {
+    pub fn new() -> MidiClip {
+        MidiClip {
+            id: 0,
+            song_id: 0,
+            revision: 0,
+            blob_key: "".to_string(),
+            name: "".to_string(),
+            format: 0,
+            tracks: 0,
+            ticks_per_quarter: 0,
+            notes: 0,
+            duration: 0.0,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+impl Default for MidiClip {
+    fn default() -> Self {
+        MidiClip::new()
+    }
+}
+
+impl Validable for MidiClip {
+    fn validate(&self) -> Result<(), Error> {
+        if self.song_id == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "song_id is required".to_string(),
+            ));
+        }
+
+        if self.blob_key.is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "blob_key is required".to_string(),
+            ));
+        }
+
+        if self.name.trim().is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "name is required".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,10 @@
+use crate::blob::Blob;
+use crate::loudness::Loudness;
+
+/// Mixdown is a rendered mix of the tracks of a song revision, stored as a blob.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mixdown {
+    pub blob: Blob,
+    /// Loudness of the mix, None if it is silent.
+    pub loudness: Option<Loudness>,
+}
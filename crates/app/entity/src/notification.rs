@@ -0,0 +1,239 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::Validable;
+
+/// NotificationKind is the kind of event a user is notified of.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NotificationKind {
+    /// The user was invited to join a gang.
+    Invitation,
+    /// The user was mentioned in a comment.
+    Mention,
+}
+
+impl NotificationKind {
+    /// Returns the kind as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::Invitation => "invitation",
+            NotificationKind::Mention => "mention",
+        }
+    }
+}
+
+impl fmt::Display for NotificationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for NotificationKind {
+    type Err = Error;
+
+    /// Returns the kind of the given string.
+    ///
+    /// Returns EINVALID if the string is not a known kind.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "invitation" => Ok(NotificationKind::Invitation),
+            "mention" => Ok(NotificationKind::Mention),
+            _ => Err(Error::new(
+                ErrorCode::EINVALID,
+                format!("Unknown notification kind {}", s),
+            )),
+        }
+    }
+}
+
+/// Channel is a way to deliver a notification to a user.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Channel {
+    /// Stored to be read in the application.
+    InApp,
+    /// Sent to the email address of the user.
+    Email,
+    /// Posted to the webhook URL of the user.
+    Webhook,
+}
+
+impl Channel {
+    /// Returns the channel as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::InApp => "in_app",
+            Channel::Email => "email",
+            Channel::Webhook => "webhook",
+        }
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Channel {
+    type Err = Error;
+
+    /// Returns the channel of the given string.
+    ///
+    /// Returns EINVALID if the string is not a known channel.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "in_app" => Ok(Channel::InApp),
+            "email" => Ok(Channel::Email),
+            "webhook" => Ok(Channel::Webhook),
+            _ => Err(Error::new(
+                ErrorCode::EINVALID,
+                format!("Unknown channel {}", s),
+            )),
+        }
+    }
+}
+
+/// Notification is a struct to represent an in-app notification of a user, e.g. "Bob Smith invited you
+/// to join The Rolling Bytes".
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    pub id: i64,
+    /// Id of the notified user.
+    pub user_id: i64,
+    pub kind: NotificationKind,
+    /// Id of the user who caused the notification, None if the user was deleted.
+    pub actor_id: Option<i64>,
+    pub gang_id: Option<i64>,
+    /// Id of the invitation or the comment the notification is about, if any.
+    pub subject_id: Option<i64>,
+    /// Sentence describing the notification, written when it is recorded.
+    pub message: String,
+    /// Time the notification was read, None while it is unread.
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Notification {
+    pub fn new(kind: NotificationKind, user_id: i64, actor_id: i64) -> Notification {
+        Notification {
+            id: 0,
+            user_id,
+            kind,
+            actor_id: Some(actor_id),
+            gang_id: None,
+            subject_id: None,
+            message: "".to_string(),
+            read_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn is_read(&self) -> bool {
+        self.read_at.is_some()
+    }
+}
+
+impl Validable for Notification {
+    fn validate(&self) -> Result<(), Error> {
+        if self.user_id == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "user_id is required".to_string(),
+            ));
+        }
+
+        if self.message.trim().is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "message is required".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// NotificationPreferences is a struct to represent the channels a user is notified on.
+///
+/// The webhook channel is enabled when a webhook URL is set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NotificationPreferences {
+    pub user_id: i64,
+    pub in_app: bool,
+    pub email: bool,
+    pub webhook_url: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NotificationPreferences {
+    /// Returns the preferences of a user who never changed them, notified in-app and by email.
+    pub fn new(user_id: i64) -> NotificationPreferences {
+        NotificationPreferences {
+            user_id,
+            in_app: true,
+            email: true,
+            webhook_url: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Returns the enabled channels.
+    pub fn channels(&self) -> Vec<Channel> {
+        let mut channels = vec![];
+
+        if self.in_app {
+            channels.push(Channel::InApp);
+        }
+
+        if self.email {
+            channels.push(Channel::Email);
+        }
+
+        if self.webhook_url.is_some() {
+            channels.push(Channel::Webhook);
+        }
+
+        channels
+    }
+}
+
+impl Validable for NotificationPreferences {
+    fn validate(&self) -> Result<(), Error> {
+        if let Some(webhook_url) = &self.webhook_url {
+            if !webhook_url.starts_with("https://") && !webhook_url.starts_with("http://") {
+                return Err(Error::new(
+                    ErrorCode::EINVALID,
+                    "webhook_url must be an http or https URL".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Delivery is a struct to represent a notification to send on an outside channel, e.g. a webhook. The
+/// notifications sent by email are queued in the email outbox instead.
+///
+/// Deliveries are sent asynchronously after the notification is recorded and retried on failure.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delivery {
+    pub id: i64,
+    /// Id of the notified user.
+    pub user_id: i64,
+    pub channel: Channel,
+    /// Address the delivery is sent to, e.g. the URL of a webhook.
+    pub address: String,
+    pub kind: NotificationKind,
+    pub message: String,
+    /// Number of times the delivery was tried.
+    pub attempts: i32,
+    /// Error of the last failed attempt, if any.
+    pub last_error: Option<String>,
+    /// Time the delivery was sent, None while it is pending.
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,20 @@
+use chrono::prelude::*;
+
+/// PasswordReset is a struct to represent a token sent to a user who forgot their password.
+///
+/// Only the hash of the token is stored. A token can be used once, before it expires.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PasswordReset {
+    pub id: i64,
+    pub user_id: i64,
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl PasswordReset {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
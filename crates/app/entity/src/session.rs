@@ -0,0 +1,14 @@
+use chrono::prelude::*;
+
+/// Session is a struct to represent an authenticated session of a user.
+///
+/// The token is opaque and must be presented by the client to resolve the session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+    /// Id of the session, the hash of its token, which can be stored without revealing the token.
+    pub id: String,
+    pub token: String,
+    pub user_id: i64,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
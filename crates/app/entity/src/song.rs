@@ -0,0 +1,208 @@
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_theory::key::Key;
+use openmusicgang_theory::time_signature::TimeSignature;
+
+use crate::Validable;
+
+/// Song is a struct to represent a song, the creative project of a gang.
+///
+/// Every change of a song is recorded as an immutable SongRevision,
+/// `revision` is the number of the latest one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Song {
+    pub id: i64,
+    pub gang_id: i64,
+    pub title: String,
+    /// Musical key of the song, e.g. "C#m".
+    pub key: Option<String>,
+    /// Tempo of the song in beats per minute.
+    pub tempo: Option<f64>,
+    /// Changes of tempo along the song, empty if the tempo is constant.
+    pub tempo_map: Vec<TempoChange>,
+    /// Time signature of the song, e.g. "4/4".
+    pub time_signature: String,
+    pub revision: i32,
+    /// Id of the user who created the song, None if the user was deleted.
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Song {
+    pub fn new() -> Song {
+        Song {
+            id: 0,
+            gang_id: 0,
+            title: "".to_string(),
+            key: None,
+            tempo: None,
+            tempo_map: vec![],
+            time_signature: "4/4".to_string(),
+            revision: 0,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Restores the musical content of the song to the one of the given revision.
+    pub fn restore(&mut self, revision: &SongRevision) {
+        self.title = revision.title.clone();
+        self.key = revision.key.clone();
+        self.tempo = revision.tempo;
+        self.tempo_map = revision.tempo_map.clone();
+        self.time_signature = revision.time_signature.clone();
+    }
+}
+
+impl Default for Song {
+    fn default() -> Self {
+        Song::new()
+    }
+}
+
+impl Validable for Song {
+    fn validate(&self) -> Result<(), Error> {
+        if self.gang_id == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "gang_id is required".to_string(),
+            ));
+        }
+
+        if self.title.trim().is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "title is required".to_string(),
+            ));
+        }
+
+        if self.key.as_ref().is_some_and(|key| key.is_empty()) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "key cannot be empty if provided".to_string(),
+            ));
+        }
+
+        if let Some(key) = &self.key {
+            key.parse::<Key>()
+                .map_err(|_| {
+                    Error::new(
+                        ErrorCode::EINVALID,
+                        "key must be like C, F#m or Bbm".to_string(),
+                    )
+                })?
+                .validate()?;
+        }
+
+        if self
+            .tempo
+            .is_some_and(|tempo| !(tempo > 0.0 && tempo <= 999.0))
+        {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "tempo must be between 0 and 999 bpm".to_string(),
+            ));
+        }
+
+        if !is_tempo_map(&self.tempo_map) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "tempo_map must start at beat 0 with increasing beats and tempos between 0 and 999 bpm"
+                    .to_string(),
+            ));
+        }
+
+        if !is_time_signature(&self.time_signature) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "time_signature must be like 4/4, with a power of two as denominator".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns true if the given string is a time signature.
+///
+/// # Example
+/// ```
+/// use openmusicgang_entity::song::is_time_signature;
+/// assert!(is_time_signature("7/8"));
+/// assert!(!is_time_signature("4/3"));
+/// assert!(!is_time_signature("four"));
+/// ```
+pub fn is_time_signature(value: &str) -> bool {
+    value.parse::<TimeSignature>().is_ok()
+}
+
+/// TempoChange is a change of the tempo of a song from a position onwards.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TempoChange {
+    /// Position of the change in quarter notes from the start of the song.
+    pub beat: f64,
+    /// Tempo in beats per minute.
+    pub tempo: f64,
+}
+
+/// Returns true if the tempo changes are a valid tempo map, an empty one meaning a constant tempo.
+///
+/// # Example
+/// ```
+/// use openmusicgang_entity::song::{is_tempo_map, TempoChange};
+/// assert!(is_tempo_map(&[]));
+/// assert!(is_tempo_map(&[
+///     TempoChange { beat: 0.0, tempo: 120.0 },
+///     TempoChange { beat: 64.0, tempo: 90.0 },
+/// ]));
+/// assert!(!is_tempo_map(&[TempoChange { beat: 4.0, tempo: 120.0 }]));
+/// ```
+pub fn is_tempo_map(tempo_map: &[TempoChange]) -> bool {
+    if tempo_map.first().is_some_and(|change| change.beat != 0.0) {
+        return false;
+    }
+
+    tempo_map
+        .iter()
+        .all(|change| change.tempo > 0.0 && change.tempo <= 999.0)
+        && tempo_map.windows(2).all(|pair| pair[0].beat < pair[1].beat)
+}
+
+/// SongRevision is an immutable snapshot of a song, recorded every time the song changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SongRevision {
+    pub id: i64,
+    pub song_id: i64,
+    /// Number of the revision, starting from 1 and increasing by one at every change.
+    pub revision: i32,
+    pub title: String,
+    pub key: Option<String>,
+    pub tempo: Option<f64>,
+    pub tempo_map: Vec<TempoChange>,
+    pub time_signature: String,
+    /// Id of the user who made the change, None if the user was deleted.
+    pub author_id: Option<i64>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SongRevision {
+    /// Create a new SongRevision with the current content of the song.
+    pub fn new(song: &Song, author_id: i64, message: String) -> SongRevision {
+        SongRevision {
+            id: 0,
+            song_id: song.id,
+            revision: song.revision,
+            title: song.title.clone(),
+            key: song.key.clone(),
+            tempo: song.tempo,
+            tempo_map: song.tempo_map.clone(),
+            time_signature: song.time_signature.clone(),
+            author_id: Some(author_id),
+            message,
+            created_at: song.updated_at,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_theory::chord::Chord;
+use openmusicgang_theory::key::Key;
+use openmusicgang_theory::pitch::Pitch;
+use openmusicgang_theory::time_signature::TimeSignature;
+
+use crate::Validable;
+
+impl Validable for Pitch {
+    fn validate(&self) -> Result<(), Error> {
+        if !(-2..=2).contains(&self.accidental) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "pitch cannot have more than two sharps or flats".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Validable for Key {
+    fn validate(&self) -> Result<(), Error> {
+        self.tonic.validate()
+    }
+}
+
+impl Validable for Chord {
+    fn validate(&self) -> Result<(), Error> {
+        self.root.validate()?;
+
+        if let Some(bass) = &self.bass {
+            bass.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Validable for TimeSignature {
+    fn validate(&self) -> Result<(), Error> {
+        if !self.is_valid() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "time_signature must be like 4/4, with a power of two as denominator".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use chrono::prelude::*;
+
+/// TokenPair is a struct to represent the tokens issued to a client.
+///
+/// The access token is a short-lived signed token, the refresh token is an opaque
+/// single-use token to obtain a new pair.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub access_token_expires_at: DateTime<Utc>,
+    pub refresh_token: String,
+    pub refresh_token_expires_at: DateTime<Utc>,
+}
+
+/// AccessToken is a struct to represent the verified content of an access token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessToken {
+    pub user_id: i64,
+    /// Id of the session the token was issued to, the family of its refresh token.
+    pub session_id: String,
+    pub scopes: Vec<String>,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// RefreshToken is a struct to represent a persisted refresh token.
+///
+/// Only the hash of the token is stored. Tokens issued by rotation share the family
+/// of the token they replace, so a whole family can be revoked when a used token is presented again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefreshToken {
+    pub id: i64,
+    pub user_id: i64,
+    pub family: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,124 @@
+use chrono::prelude::*;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::audio::AudioInfo;
+use crate::loudness::Loudness;
+use crate::Validable;
+
+/// Maximum offset of a track in samples, three hours at 192 kHz.
+pub const MAX_TRACK_OFFSET: i64 = 3 * 60 * 60 * 192_000;
+
+/// Track is a struct to represent a track of a song, an audio stem such as drums, bass or vocals.
+///
+/// A track belongs to a revision of a song and references its audio by the key of a stored blob.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    pub id: i64,
+    pub song_id: i64,
+    /// Number of the song revision the track belongs to.
+    pub revision: i32,
+    /// Key of the audio blob of the track.
+    pub blob_key: String,
+    /// Properties of the audio blob, read when the blob is set.
+    pub audio: Option<AudioInfo>,
+    /// Key of the blob of the waveform peaks of the audio, generated when the blob is set.
+    pub waveform_key: Option<String>,
+    /// Loudness of the audio, measured when the blob is set, None if the audio is silent.
+    pub loudness: Option<Loudness>,
+    pub instrument: String,
+    /// Id of the user performing on the track, if any.
+    pub performer_id: Option<i64>,
+    /// Gain of the track in decibels.
+    pub gain: f64,
+    /// Stereo position of the track, from -1.0 (left) to 1.0 (right).
+    pub pan: f64,
+    pub muted: bool,
+    pub solo: bool,
+    /// Offset of the track from the start of the song, in samples.
+    pub offset: i64,
+    /// Position of the track in the song revision, starting from 1.
+    pub position: i32,
+    /// Id of the user who added the track, None if the user was deleted.
+    pub created_by: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Track {
+    pub fn new() -> Track {
+        Track {
+            id: 0,
+            song_id: 0,
+            revision: 0,
+            blob_key: "".to_string(),
+            audio: None,
+            waveform_key: None,
+            loudness: None,
+            instrument: "".to_string(),
+            performer_id: None,
+            gain: 0.0,
+            pan: 0.0,
+            muted: false,
+            solo: false,
+            offset: 0,
+            position: 0,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+impl Default for Track {
+    fn default() -> Self {
+        Track::new()
+    }
+}
+
+impl Validable for Track {
+    fn validate(&self) -> Result<(), Error> {
+        if self.song_id == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "song_id is required".to_string(),
+            ));
+        }
+
+        if self.blob_key.is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "blob_key is required".to_string(),
+            ));
+        }
+
+        if self.instrument.trim().is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "instrument is required".to_string(),
+            ));
+        }
+
+        if !(-96.0..=12.0).contains(&self.gain) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "gain must be between -96 and 12 dB".to_string(),
+            ));
+        }
+
+        if !(-1.0..=1.0).contains(&self.pan) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "pan must be between -1 and 1".to_string(),
+            ));
+        }
+
+        if !(0..=MAX_TRACK_OFFSET).contains(&self.offset) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                format!("offset must be between 0 and {} samples", MAX_TRACK_OFFSET),
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use openmusicgang_err::error::{Error, ErrorCode};
 
 /// User is a struct to represent a user.
 ///
+/// A new user has to verify their email address before creating gangs or uploading audio.
 #[derive(Clone, Debug, PartialEq)]
 pub struct User {
     pub id: i64,
@@ -12,6 +13,15 @@ pub struct User {
     pub name: String,
     pub email: String,
     pub password: Option<String>,
+    /// Time the email address was verified, None while it is not.
+    pub email_verified_at: Option<DateTime<Utc>>,
+    /// Address the user asked to change their email to, swapped in once it is verified.
+    pub pending_email: Option<String>,
+    /// Time the sessions of the user were revoked, e.g. when their password was reset, to the millisecond
+    /// like the issue times of the sessions and tokens. Sessions and tokens issued before are no longer valid.
+    pub sessions_revoked_at: Option<DateTime<Utc>>,
+    /// Id of the session kept valid when the sessions were revoked, the one that changed the password.
+    pub kept_session_id: Option<String>,
 }
 
 impl User {
@@ -23,27 +33,59 @@ impl User {
             name: "".to_string(),
             email: "".to_string(),
             password: None,
+            email_verified_at: None,
+            pending_email: None,
+            sessions_revoked_at: None,
+            kept_session_id: None,
         }
     }
+
+    pub fn is_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+
+    /// Returns true if the session with the given id, or a token it issued at the given time, was revoked.
+    pub fn is_session_revoked(&self, session_id: &str, issued_at: DateTime<Utc>) -> bool {
+        self.sessions_revoked_at.is_some_and(|revoked_at| {
+            issued_at < revoked_at && self.kept_session_id.as_deref() != Some(session_id)
+        })
+    }
+}
+
+impl Default for User {
+    fn default() -> Self {
+        User::new()
+    }
 }
 
 impl Validable for User {
     fn validate(&self) -> Result<(), Error> {
-        if self.name == "" {
+        if self.name.is_empty() {
             return Err(Error::new(
                 ErrorCode::EINVALID,
                 "name is required".to_string(),
             ));
         }
 
-        if self.email == "" {
+        if self.email.is_empty() {
             return Err(Error::new(
                 ErrorCode::EINVALID,
                 "email is required".to_string(),
             ));
         }
 
-        if self.password.is_some() && self.password.as_ref().unwrap().len() == 0 {
+        if !is_email_address(&self.email) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "email must be an email address".to_string(),
+            ));
+        }
+
+        if self
+            .password
+            .as_ref()
+            .is_some_and(|password| password.is_empty())
+        {
             return Err(Error::new(
                 ErrorCode::EINVALID,
                 "password cannot be empty if provided".to_string(),
@@ -53,3 +95,26 @@ impl Validable for User {
         Ok(())
     }
 }
+
+/// Returns the email address in its normalized form, trimmed and case-folded, e.g. " Bob.Smith@Test.com "
+/// becomes "bob.smith@test.com".
+///
+/// Addresses are stored normalized so that two spellings of the same address belong to the same user.
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// Returns true if the text looks like an email address, e.g. "bob.smith@test.com".
+///
+/// Only the shape is checked, the address is proven to exist by verifying it.
+pub fn is_email_address(text: &str) -> bool {
+    match text.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.is_empty()
+                && !domain.contains('@')
+                && !text.chars().any(char::is_whitespace)
+        }
+        None => false,
+    }
+}
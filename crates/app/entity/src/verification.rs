@@ -0,0 +1,23 @@
+use chrono::prelude::*;
+
+/// EmailVerification is a struct to represent a token sent to a user to verify an email address.
+///
+/// Only the hash of the token is stored. A token can be used once, before it expires, and only for
+/// the address it was sent to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmailVerification {
+    pub id: i64,
+    pub user_id: i64,
+    /// Address the token was sent to.
+    pub email: String,
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl EmailVerification {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
@@ -0,0 +1,20 @@
+/// Waveform is the overview of an audio file, the peaks of its samples at several resolutions.
+///
+/// The levels go from the finest to the coarsest, so a player can pick the one matching its width.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Waveform {
+    /// Sample rate of the audio in Hz.
+    pub sample_rate: i32,
+    /// Number of samples per channel of the audio.
+    pub frames: i64,
+    pub levels: Vec<WaveformLevel>,
+}
+
+/// WaveformLevel is a resolution of a waveform.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WaveformLevel {
+    /// Number of frames summarized by each peak.
+    pub samples_per_peak: i32,
+    /// Minimum and maximum sample of each block of frames, across all channels, scaled between -127 and 127.
+    pub peaks: Vec<(i8, i8)>,
+}
@@ -11,6 +11,8 @@ pub enum ErrorCode {
     EUNKNOWN,
     ENOTFOUND,
     ECONFLICT,
+    ETOOMANYREQUESTS,
+    ETOOLARGE,
     ENOTIMPLEMENTED,
 }
 
@@ -31,6 +33,8 @@ impl ErrorCode {
             ErrorCode::ENOTFOUND => "not found",
             ErrorCode::ECONFLICT => "conflict",
             ErrorCode::EUNAUTHORIZED => "unauthorized",
+            ErrorCode::ETOOMANYREQUESTS => "too many requests",
+            ErrorCode::ETOOLARGE => "too large",
             ErrorCode::ENOTIMPLEMENTED => "not implemented",
         }
     }
@@ -45,6 +49,8 @@ impl ErrorCode {
             ErrorCode::ENOTFOUND => 404,
             ErrorCode::ECONFLICT => 409,
             ErrorCode::EUNAUTHORIZED => 401,
+            ErrorCode::ETOOMANYREQUESTS => 429,
+            ErrorCode::ETOOLARGE => 413,
             ErrorCode::ENOTIMPLEMENTED => 501,
         }
     }
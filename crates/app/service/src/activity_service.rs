@@ -0,0 +1,24 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::activity::FeedPage;
+use openmusicgang_err::error::Error;
+
+/// ActivityService is the service for the activity feeds of the users.
+///
+/// Activities are recorded by the services where they happen, when a user joins a gang, saves a song
+/// revision, comments or uploads a track.
+pub trait ActivityService {
+    /// Returns a page of the feed of the user of the context, newest first.
+    fn find_feed(&self, ctx: AppContext, filters: FeedFilter) -> Result<FeedPage, Error>;
+}
+
+// FeedFilter is a struct for the cursors and the size of a page of a feed.
+#[derive(Clone, Debug, Default)]
+pub struct FeedFilter {
+    /// Cursor of the page, only returns activities older than it.
+    pub before: Option<i64>,
+    /// Only returns activities newer than this cursor.
+    pub after: Option<i64>,
+
+    /// Number of activities of the page, 20 if not set and at most 100.
+    pub limit: i64,
+}
@@ -0,0 +1,33 @@
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::session::Session;
+use openmusicgang_entity::user::User;
+use openmusicgang_err::error::Error;
+
+/// AuthService is the service for user authentication.
+pub trait AuthService {
+    /// Authenticates the user by email and password and opens a new session.
+    fn login(&self, ctx: AppContext, email: String, password: String) -> Result<Session, Error>;
+
+    /// Closes the session identified by the token.
+    fn logout(&self, ctx: AppContext, token: String) -> Result<(), Error>;
+
+    /// Returns the session identified by the token and its user, extending the session expiration.
+    fn resolve_session(&self, ctx: AppContext, token: String) -> Result<(Session, User), Error>;
+
+    /// Returns the open sessions of the user.
+    fn find_sessions(&self, ctx: AppContext, user_id: i64) -> Result<Vec<Session>, Error>;
+}
+
+/// Returns a new context carrying the user and the id of the session identified by the token.
+pub fn with_session(
+    auth_service: &dyn AuthService,
+    ctx: AppContext,
+    token: String,
+) -> Result<AppContext, Error> {
+    let (session, user) = auth_service.resolve_session(ctx.clone(), token)?;
+
+    Ok(Context::with_session_id(
+        Context::with_user(ctx, user),
+        session.id,
+    ))
+}
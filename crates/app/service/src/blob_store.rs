@@ -0,0 +1,38 @@
+use std::io::Read;
+
+use openmusicgang_entity::blob::Blob;
+use openmusicgang_err::error::Error;
+
+/// BlobStore is the storage of blobs, the audio files of the tracks.
+///
+/// Blobs are immutable and content-addressed, storing the same content twice returns the same blob.
+/// Permissions are checked by the services referencing the blobs, not by the store.
+pub trait BlobStore {
+    /// Stores the content read from the reader and returns the stored blob.
+    fn put_blob(&self, reader: &mut dyn Read) -> Result<Blob, Error>;
+
+    /// Returns a reader of the content of a blob, or of the given range of it.
+    ///
+    /// Returns EINVALID if the range starts after the end of the blob.
+    fn get_blob(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<Box<dyn Read + Send + Sync>, Error>;
+
+    fn delete_blob(&self, key: &str) -> Result<(), Error>;
+
+    fn stat_blob(&self, key: &str) -> Result<Blob, Error>;
+
+    /// Returns the blobs whose key starts with the given prefix, sorted by key.
+    fn list_blobs(&self, prefix: &str) -> Result<Vec<Blob>, Error>;
+}
+
+/// ByteRange is a range of bytes of a blob.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ByteRange {
+    /// Position of the first byte.
+    pub offset: u64,
+    /// Number of bytes, up to the end of the blob if None.
+    pub length: Option<u64>,
+}
@@ -0,0 +1,64 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::comment::{Comment, CommentEdit};
+use openmusicgang_err::error::Error;
+
+/// CommentService is the service for the comments on songs and tracks.
+///
+/// Every member of the gang of a song, guests included, can read and write comments. A comment can be
+/// edited by its author only and deleted by its author and by the admins of the gang.
+pub trait CommentService {
+    /// Adds a comment to a song revision or to a track, the latest revision if none is given.
+    ///
+    /// A comment on a track belongs to the revision of the track, a reply to the song revision
+    /// and track of the comment starting its thread.
+    fn create_comment(&self, ctx: AppContext, comment: &mut Comment) -> Result<(), Error>;
+
+    /// Changes the body of a comment, its previous body is recorded in its edit history.
+    fn update_comment(
+        &self,
+        ctx: AppContext,
+        id: i64,
+        comment: CommentUpdate,
+    ) -> Result<Comment, Error>;
+
+    /// Deletes a comment with its replies.
+    fn delete_comment(&self, ctx: AppContext, id: i64) -> Result<(), Error>;
+
+    /// Marks the thread started by a comment as resolved.
+    fn resolve_comment(&self, ctx: AppContext, id: i64) -> Result<Comment, Error>;
+
+    /// Opens again the thread started by a resolved comment.
+    fn unresolve_comment(&self, ctx: AppContext, id: i64) -> Result<Comment, Error>;
+
+    fn find_comment_by_id(&self, ctx: AppContext, id: i64) -> Result<Comment, Error>;
+
+    /// Returns the previous bodies of a comment, oldest first.
+    fn find_comment_edits(&self, ctx: AppContext, id: i64) -> Result<Vec<CommentEdit>, Error>;
+
+    /// Returns the comments of a song in order of creation, also returns the total number of comments.
+    fn find_comments(
+        &self,
+        ctx: AppContext,
+        filters: CommentFilter,
+    ) -> Result<(Vec<Comment>, i64), Error>;
+}
+
+/// CommentUpdate is a struct for allowed fields to update a comment.
+#[derive(Clone, Debug, Default)]
+pub struct CommentUpdate {
+    pub body: Option<String>,
+}
+
+// CommentFilter is a struct for possibile filters for comment search.
+#[derive(Clone, Debug, Default)]
+pub struct CommentFilter {
+    pub song_id: i64,
+    pub revision: Option<i32>,
+    pub track_id: Option<i64>,
+    /// Id of a comment to find the replies of.
+    pub parent_id: Option<i64>,
+    pub resolved: Option<bool>,
+
+    pub limit: i64,
+    pub offset: i64,
+}
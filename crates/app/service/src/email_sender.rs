@@ -0,0 +1,13 @@
+use openmusicgang_entity::email::Email;
+use openmusicgang_err::error::Error;
+
+/// EmailSender sends the emails of the outbox.
+///
+/// An email whose sending fails is tried again later, so implementations must not report a failure once
+/// the email was accepted.
+pub trait EmailSender {
+    /// Sends an email.
+    ///
+    /// Returns EINVALID if an address of the email is invalid.
+    fn send_email(&self, email: &Email) -> Result<(), Error>;
+}
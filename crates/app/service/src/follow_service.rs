@@ -0,0 +1,46 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::follow::{Follow, FollowCounts, FollowTarget};
+use openmusicgang_err::error::Error;
+
+/// FollowService is the service for the follow graph between users, and from users to gangs.
+///
+/// Users follow and unfollow on their own behalf, follows are public.
+pub trait FollowService {
+    /// Makes the user of the context follow a user or a gang.
+    fn follow(&self, ctx: AppContext, target: FollowTarget) -> Result<Follow, Error>;
+
+    /// Makes the user of the context stop following a user or a gang.
+    fn unfollow(&self, ctx: AppContext, target: FollowTarget) -> Result<(), Error>;
+
+    /// Returns the follows matching the filters, newest first, also returns the total number of follows.
+    ///
+    /// The followers of a user or a gang are found by target, the users and gangs a user follows by follower.
+    fn find_follows(
+        &self,
+        ctx: AppContext,
+        filters: FollowFilter,
+    ) -> Result<(Vec<Follow>, i64), Error>;
+
+    /// Returns true if two users follow each other.
+    fn is_mutual_follow(&self, ctx: AppContext, user_id: i64, other_id: i64)
+        -> Result<bool, Error>;
+
+    /// Returns the number of followers of a user or a gang, and the number of follows of a user.
+    fn find_follow_counts(
+        &self,
+        ctx: AppContext,
+        target: FollowTarget,
+    ) -> Result<FollowCounts, Error>;
+}
+
+// FollowFilter is a struct for possibile filters for follow search.
+#[derive(Clone, Debug, Default)]
+pub struct FollowFilter {
+    pub follower_id: Option<i64>,
+    pub target: Option<FollowTarget>,
+    /// Only returns the follows between users following each other.
+    pub mutual: bool,
+
+    pub limit: i64,
+    pub offset: i64,
+}
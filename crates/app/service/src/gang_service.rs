@@ -0,0 +1,40 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::gang::Gang;
+use openmusicgang_err::error::Error;
+
+/// GangService is the service for gang management.
+pub trait GangService {
+    fn create_gang(&self, ctx: AppContext, gang: &mut Gang) -> Result<(), Error>;
+
+    fn delete_gang(&self, ctx: AppContext, id: i64) -> Result<(), Error>;
+
+    fn update_gang(&self, ctx: AppContext, id: i64, gang: GangUpdate) -> Result<Gang, Error>;
+
+    fn find_gang_by_id(&self, ctx: AppContext, id: i64) -> Result<Gang, Error>;
+
+    fn find_gang_by_slug(&self, ctx: AppContext, slug: String) -> Result<Gang, Error>;
+
+    fn find_gangs(&self, ctx: AppContext, filters: GangFilter) -> Result<(Vec<Gang>, i64), Error>;
+}
+
+/// GangUpdate is a struct for allowed fields to update a gang.
+#[derive(Clone, Debug, Default)]
+pub struct GangUpdate {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    pub description: Option<String>,
+    pub genre: Option<String>,
+}
+
+// GangFilter is a struct for possibile filters for gang search.
+#[derive(Clone, Debug, Default)]
+pub struct GangFilter {
+    pub id: Option<i64>,
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    pub genre: Option<String>,
+    pub created_by: Option<i64>,
+
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,41 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::lead_sheet::LeadSheet;
+use openmusicgang_err::error::Error;
+
+/// LeadSheetService is the service for the lead sheets of songs, their chord charts and lyrics.
+///
+/// Only members of the gang of a song can write and delete its lead sheets, guests can read them.
+pub trait LeadSheetService {
+    /// Sets the lead sheet of a song revision, the latest revision if none is given,
+    /// replacing the previous lead sheet of the revision.
+    fn save_lead_sheet(&self, ctx: AppContext, sheet: &mut LeadSheet) -> Result<(), Error>;
+
+    /// Sets the lead sheet of a song revision from a ChordPro text, the latest revision if none is given.
+    fn import_lead_sheet(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        chordpro: &str,
+    ) -> Result<LeadSheet, Error>;
+
+    fn delete_lead_sheet(&self, ctx: AppContext, id: i64) -> Result<(), Error>;
+
+    /// Returns the lead sheet of a song revision, the latest revision if none is given.
+    fn find_lead_sheet(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+    ) -> Result<LeadSheet, Error>;
+
+    /// Returns the lead sheet of a song revision as a ChordPro text, the latest revision if none is given,
+    /// with its key and chords transposed by a number of semitones.
+    fn render_lead_sheet(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        semitones: i32,
+    ) -> Result<String, Error>;
+}
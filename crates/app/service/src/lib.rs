@@ -1 +1,16 @@
+pub mod activity_service;
+pub mod auth_service;
+pub mod blob_store;
+pub mod comment_service;
+pub mod email_sender;
+pub mod follow_service;
+pub mod gang_service;
+pub mod lead_sheet_service;
+pub mod membership_service;
+pub mod midi_clip_service;
+pub mod notification_service;
+pub mod notification_transport;
+pub mod song_service;
+pub mod token_service;
+pub mod track_service;
 pub mod user_service;
@@ -0,0 +1,72 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::invitation::{Invitation, InvitationStatus};
+use openmusicgang_entity::membership::{GangRole, Membership};
+use openmusicgang_err::error::Error;
+
+/// MembershipService is the service for the members of gangs and the invitations to join them.
+///
+/// Every method acts on behalf of the user of the context.
+pub trait MembershipService {
+    /// Invites a user to a gang, the user of the context must be an admin of the gang.
+    fn invite_member(&self, ctx: AppContext, invitation: &mut Invitation) -> Result<(), Error>;
+
+    /// Accepts an invitation addressed to the user of the context.
+    fn accept_invitation(&self, ctx: AppContext, id: i64) -> Result<Membership, Error>;
+
+    /// Declines an invitation addressed to the user of the context.
+    fn decline_invitation(&self, ctx: AppContext, id: i64) -> Result<Invitation, Error>;
+
+    /// Marks the pending invitations past their expiration as expired, returns their number.
+    fn expire_invitations(&self, ctx: AppContext) -> Result<i64, Error>;
+
+    fn find_invitations(
+        &self,
+        ctx: AppContext,
+        filters: InvitationFilter,
+    ) -> Result<(Vec<Invitation>, i64), Error>;
+
+    fn find_memberships(
+        &self,
+        ctx: AppContext,
+        filters: MembershipFilter,
+    ) -> Result<(Vec<Membership>, i64), Error>;
+
+    fn update_member_role(
+        &self,
+        ctx: AppContext,
+        gang_id: i64,
+        user_id: i64,
+        role: GangRole,
+    ) -> Result<Membership, Error>;
+
+    fn remove_member(&self, ctx: AppContext, gang_id: i64, user_id: i64) -> Result<(), Error>;
+
+    fn leave_gang(&self, ctx: AppContext, gang_id: i64) -> Result<(), Error>;
+
+    /// Makes the given member the owner of the gang, the current owner becomes an admin.
+    fn transfer_ownership(&self, ctx: AppContext, gang_id: i64, user_id: i64) -> Result<(), Error>;
+}
+
+// InvitationFilter is a struct for possibile filters for invitation search.
+//
+// Without a gang_id only the invitations addressed to the user of the context are returned.
+#[derive(Clone, Debug, Default)]
+pub struct InvitationFilter {
+    pub id: Option<i64>,
+    pub gang_id: Option<i64>,
+    pub status: Option<InvitationStatus>,
+
+    pub limit: i64,
+    pub offset: i64,
+}
+
+// MembershipFilter is a struct for possibile filters for membership search.
+#[derive(Clone, Debug, Default)]
+pub struct MembershipFilter {
+    pub gang_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub role: Option<GangRole>,
+
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,45 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::blob::Blob;
+use openmusicgang_entity::midi_clip::MidiClip;
+use openmusicgang_entity::song::Song;
+use openmusicgang_err::error::Error;
+
+/// MidiClipService is the service for the MIDI clips of songs.
+///
+/// Only members of the gang of a song can add clips, a clip can be deleted by the user
+/// who added it and by the admins of the gang.
+pub trait MidiClipService {
+    /// Adds a clip to a song revision, the latest revision if none is given.
+    /// Its MIDI file is parsed to fill in its format, tracks, notes and duration.
+    fn create_midi_clip(&self, ctx: AppContext, clip: &mut MidiClip) -> Result<(), Error>;
+
+    fn delete_midi_clip(&self, ctx: AppContext, id: i64) -> Result<(), Error>;
+
+    fn find_midi_clip_by_id(&self, ctx: AppContext, id: i64) -> Result<MidiClip, Error>;
+
+    /// Sets the tempo map, time signature and key of the song of a clip to the ones of its MIDI file,
+    /// recording the change as a new revision of the song.
+    fn import_midi_clip_metadata(&self, ctx: AppContext, id: i64) -> Result<Song, Error>;
+
+    /// Writes a song revision and its clips as one format 1 MIDI file, the latest revision if none is given,
+    /// and stores it as a new blob.
+    fn export_song_midi(&self, ctx: AppContext, song_id: i64, revision: i32)
+        -> Result<Blob, Error>;
+
+    /// Returns the clips of a song, also returns the total number of clips.
+    fn find_midi_clips(
+        &self,
+        ctx: AppContext,
+        filters: MidiClipFilter,
+    ) -> Result<(Vec<MidiClip>, i64), Error>;
+}
+
+// MidiClipFilter is a struct for possibile filters for MIDI clip search.
+#[derive(Clone, Debug, Default)]
+pub struct MidiClipFilter {
+    pub song_id: i64,
+    pub revision: Option<i32>,
+
+    pub limit: i64,
+    pub offset: i64,
+}
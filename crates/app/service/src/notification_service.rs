@@ -0,0 +1,53 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::notification::{Notification, NotificationPreferences};
+use openmusicgang_err::error::Error;
+
+/// NotificationService is the service for the notifications of the users.
+///
+/// Notifications are recorded by the services where they happen, when a user is invited to a gang or
+/// mentioned in a comment, on the channels the user enabled in their preferences.
+pub trait NotificationService {
+    /// Returns the in-app notifications of the user of the context, newest first, also returns the
+    /// total number of notifications matching the filters.
+    fn find_notifications(
+        &self,
+        ctx: AppContext,
+        filters: NotificationFilter,
+    ) -> Result<(Vec<Notification>, i64), Error>;
+
+    /// Marks a notification of the user of the context as read.
+    fn mark_notification_read(&self, ctx: AppContext, id: i64) -> Result<Notification, Error>;
+
+    /// Marks all the notifications of the user of the context as read and returns how many were unread.
+    fn mark_all_notifications_read(&self, ctx: AppContext) -> Result<i64, Error>;
+
+    /// Returns the notification preferences of the user of the context.
+    fn find_notification_preferences(
+        &self,
+        ctx: AppContext,
+    ) -> Result<NotificationPreferences, Error>;
+
+    fn update_notification_preferences(
+        &self,
+        ctx: AppContext,
+        preferences: NotificationPreferencesUpdate,
+    ) -> Result<NotificationPreferences, Error>;
+}
+
+/// NotificationPreferencesUpdate is a struct for allowed fields to update notification preferences.
+#[derive(Clone, Debug, Default)]
+pub struct NotificationPreferencesUpdate {
+    pub in_app: Option<bool>,
+    pub email: Option<bool>,
+    /// URL of the webhook, an empty string disables the webhook channel.
+    pub webhook_url: Option<String>,
+}
+
+// NotificationFilter is a struct for possibile filters for notification search.
+#[derive(Clone, Debug, Default)]
+pub struct NotificationFilter {
+    pub read: Option<bool>,
+
+    pub limit: i64,
+    pub offset: i64,
+}
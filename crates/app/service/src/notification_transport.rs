@@ -0,0 +1,9 @@
+use openmusicgang_entity::notification::Delivery;
+use openmusicgang_err::error::Error;
+
+/// NotificationTransport sends the deliveries of the notifications on their channel.
+///
+/// A delivery that fails is tried again later, so sending the same delivery twice must be harmless.
+pub trait NotificationTransport {
+    fn send(&self, delivery: &Delivery) -> Result<(), Error>;
+}
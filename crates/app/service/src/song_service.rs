@@ -0,0 +1,70 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::song::{Song, SongRevision, TempoChange};
+use openmusicgang_err::error::Error;
+
+/// SongService is the service for songs and their history of revisions.
+///
+/// Every change of a song records a new revision, revisions are never modified.
+pub trait SongService {
+    fn create_song(&self, ctx: AppContext, song: &mut Song) -> Result<(), Error>;
+
+    fn delete_song(&self, ctx: AppContext, id: i64) -> Result<(), Error>;
+
+    fn update_song(&self, ctx: AppContext, id: i64, song: SongUpdate) -> Result<Song, Error>;
+
+    fn find_song_by_id(&self, ctx: AppContext, id: i64) -> Result<Song, Error>;
+
+    fn find_songs(&self, ctx: AppContext, filters: SongFilter) -> Result<(Vec<Song>, i64), Error>;
+
+    /// Returns the revision with the given number of a song.
+    fn find_song_revision(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+    ) -> Result<SongRevision, Error>;
+
+    /// Returns the history of a song, latest revision first, also returns the total number of revisions.
+    fn find_song_revisions(
+        &self,
+        ctx: AppContext,
+        filters: SongRevisionFilter,
+    ) -> Result<(Vec<SongRevision>, i64), Error>;
+
+    /// Restores the content of a song to the given revision, recording it as a new revision.
+    fn revert_song(&self, ctx: AppContext, id: i64, revision: i32) -> Result<Song, Error>;
+}
+
+/// SongUpdate is a struct for allowed fields to update a song.
+///
+/// The message describes the change in the history of the song.
+#[derive(Clone, Debug, Default)]
+pub struct SongUpdate {
+    pub title: Option<String>,
+    pub key: Option<String>,
+    pub tempo: Option<f64>,
+    pub time_signature: Option<String>,
+    pub tempo_map: Option<Vec<TempoChange>>,
+    pub message: String,
+}
+
+// SongFilter is a struct for possibile filters for song search.
+#[derive(Clone, Debug, Default)]
+pub struct SongFilter {
+    pub id: Option<i64>,
+    pub gang_id: Option<i64>,
+    pub title: Option<String>,
+
+    pub limit: i64,
+    pub offset: i64,
+}
+
+// SongRevisionFilter is a struct for possibile filters for the history of a song.
+#[derive(Clone, Debug, Default)]
+pub struct SongRevisionFilter {
+    pub song_id: i64,
+    pub author_id: Option<i64>,
+
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,43 @@
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::token::{AccessToken, TokenPair};
+use openmusicgang_entity::user::User;
+use openmusicgang_err::error::Error;
+
+/// TokenService is the service for stateless token authentication.
+pub trait TokenService {
+    /// Authenticates the user by email and password and issues a new token pair with the given scopes.
+    fn issue_tokens(
+        &self,
+        ctx: AppContext,
+        email: String,
+        password: String,
+        scopes: Vec<String>,
+    ) -> Result<TokenPair, Error>;
+
+    /// Exchanges a refresh token for a new token pair, the refresh token cannot be used again.
+    fn refresh_tokens(&self, ctx: AppContext, refresh_token: String) -> Result<TokenPair, Error>;
+
+    /// Revokes the refresh token and every token obtained by rotating it.
+    fn revoke_tokens(&self, ctx: AppContext, refresh_token: String) -> Result<(), Error>;
+
+    /// Returns the content of the access token and its user.
+    fn verify_access_token(
+        &self,
+        ctx: AppContext,
+        access_token: String,
+    ) -> Result<(AccessToken, User), Error>;
+}
+
+/// Returns a new context carrying the user and the session id of the access token.
+pub fn with_access_token(
+    token_service: &dyn TokenService,
+    ctx: AppContext,
+    access_token: String,
+) -> Result<AppContext, Error> {
+    let (access_token, user) = token_service.verify_access_token(ctx.clone(), access_token)?;
+
+    Ok(Context::with_session_id(
+        Context::with_user(ctx, user),
+        access_token.session_id,
+    ))
+}
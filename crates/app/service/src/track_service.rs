@@ -0,0 +1,85 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::analysis::AudioAnalysis;
+use openmusicgang_entity::mixdown::Mixdown;
+use openmusicgang_entity::track::Track;
+use openmusicgang_entity::waveform::Waveform;
+use openmusicgang_err::error::Error;
+
+/// TrackService is the service for the tracks of songs.
+///
+/// Only members of the gang of a song can add tracks, a track can be changed by the user
+/// who added it, by its performer and by the admins of the gang.
+pub trait TrackService {
+    /// Adds a track at the end of a song revision, the latest revision if none is given.
+    fn create_track(&self, ctx: AppContext, track: &mut Track) -> Result<(), Error>;
+
+    fn delete_track(&self, ctx: AppContext, id: i64) -> Result<(), Error>;
+
+    fn update_track(&self, ctx: AppContext, id: i64, track: TrackUpdate) -> Result<Track, Error>;
+
+    /// Replaces the audio of a track with the given blob, its waveform is regenerated.
+    fn replace_track_blob(
+        &self,
+        ctx: AppContext,
+        id: i64,
+        blob_key: String,
+    ) -> Result<Track, Error>;
+
+    /// Sets the order of the tracks of a song revision, every track of the revision must be given.
+    fn reorder_tracks(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        track_ids: Vec<i64>,
+    ) -> Result<Vec<Track>, Error>;
+
+    fn find_track_by_id(&self, ctx: AppContext, id: i64) -> Result<Track, Error>;
+
+    /// Returns the waveform of the audio of a track.
+    fn find_track_waveform(&self, ctx: AppContext, id: i64) -> Result<Waveform, Error>;
+
+    /// Estimates the tempo and key of the audio of a track, to pre-fill the tempo and key of its song.
+    fn analyze_track(&self, ctx: AppContext, id: i64) -> Result<AudioAnalysis, Error>;
+
+    /// Renders the tracks of a song revision as one stereo 24 bits WAV file, the latest revision if none is given,
+    /// and stores it as a new blob. The mix is normalized to the target loudness in LUFS if one is given.
+    fn render_mixdown(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        target_loudness: Option<f64>,
+    ) -> Result<Mixdown, Error>;
+
+    /// Returns the tracks of a song in order, also returns the total number of tracks.
+    fn find_tracks(
+        &self,
+        ctx: AppContext,
+        filters: TrackFilter,
+    ) -> Result<(Vec<Track>, i64), Error>;
+}
+
+/// TrackUpdate is a struct for allowed fields to update a track.
+#[derive(Clone, Debug, Default)]
+pub struct TrackUpdate {
+    pub instrument: Option<String>,
+    pub performer_id: Option<i64>,
+    pub gain: Option<f64>,
+    pub pan: Option<f64>,
+    pub muted: Option<bool>,
+    pub solo: Option<bool>,
+    pub offset: Option<i64>,
+}
+
+// TrackFilter is a struct for possibile filters for track search.
+#[derive(Clone, Debug, Default)]
+pub struct TrackFilter {
+    pub song_id: i64,
+    pub revision: Option<i32>,
+    pub instrument: Option<String>,
+    pub performer_id: Option<i64>,
+
+    pub limit: i64,
+    pub offset: i64,
+}
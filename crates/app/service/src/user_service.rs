@@ -3,6 +3,8 @@ use openmusicgang_entity::user::User;
 use openmusicgang_err::error::Error;
 
 /// UserService is the service for user management.
+///
+/// A new user is sent an email to verify their address, unverified users cannot create gangs or upload audio.
 pub trait UserService {
     fn create_user(&self, ctx: AppContext, user: &mut User) -> Result<(), Error>;
 
@@ -15,6 +17,47 @@ pub trait UserService {
     fn find_user_by_email(&self, ctx: AppContext, email: String) -> Result<User, Error>;
 
     fn find_users(&self, ctx: AppContext, filters: UserFilter) -> Result<(Vec<User>, i64), Error>;
+
+    /// Verifies the password of the user with the given email and returns the user on success.
+    /// Implementations must rehash the stored password if its hashing parameters are outdated.
+    fn verify_password(
+        &self,
+        ctx: AppContext,
+        email: String,
+        password: String,
+    ) -> Result<User, Error>;
+
+    /// Verifies the email address of a user with the token sent to it and returns the verified user.
+    fn confirm_email(&self, ctx: AppContext, token: String) -> Result<User, Error>;
+
+    /// Sends a new verification email to the user of the context, the tokens sent before can no longer be used.
+    fn resend_verification_email(&self, ctx: AppContext) -> Result<(), Error>;
+
+    /// Asks to change the email address of the user of the context and returns the user with the pending address.
+    /// The address is normalized and only swapped in once verified with the token sent to it by confirm_email.
+    fn change_email(&self, ctx: AppContext, email: String) -> Result<User, Error>;
+
+    /// Changes the password of the user of the context, the current password is required.
+    /// Every other session of the user is revoked, the session of the context is kept.
+    fn change_password(
+        &self,
+        ctx: AppContext,
+        current_password: String,
+        new_password: String,
+    ) -> Result<(), Error>;
+
+    /// Sends a password reset email to the user with the given email, if any.
+    /// Implementations must not reveal whether a user has the given email.
+    fn request_password_reset(&self, ctx: AppContext, email: String) -> Result<(), Error>;
+
+    /// Sets a new password with the token sent by request_password_reset and returns the user,
+    /// every session of the user is revoked.
+    fn reset_password(
+        &self,
+        ctx: AppContext,
+        token: String,
+        new_password: String,
+    ) -> Result<User, Error>;
 }
 
 /// UserUpdate is a struct for allowed fields to update a user.
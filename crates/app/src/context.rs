@@ -18,6 +18,7 @@ pub enum Value {
 }
 
 static CONTEXT_KEY_USER: &str = "user";
+static CONTEXT_KEY_SESSION_ID: &str = "session_id";
 
 /// AppContext is an alias for Thread-Safe Context.
 pub type AppContext = Arc<Mutex<Context>>;
@@ -69,13 +70,19 @@ impl Context {
         }
     }
 
+    /// Returns the id of the session the request was authenticated with.
+    /// Returns None if the context carries no session.
+    pub fn session_id_from_context(app: AppContext) -> Option<String> {
+        let ctx = app.lock().unwrap();
+        match ctx.value(CONTEXT_KEY_SESSION_ID.to_string()) {
+            Some(Value::String(session_id)) => Some(session_id),
+            _ => None,
+        }
+    }
+
     /// Returns the value stored in the context, if not found, returns None.
     pub fn value(&self, key: String) -> Option<Value> {
-        let ctx = Some(self);
-
-        let ctx = ctx.unwrap();
-
-        if let Some(value) = ctx.values.get(&key) {
+        if let Some(value) = self.values.get(&key) {
             return Some(value.clone());
         }
 
@@ -87,6 +94,15 @@ impl Context {
         Context::with_value(ctx, CONTEXT_KEY_USER.to_string(), Value::User(user))
     }
 
+    /// Create a new context with the given session id as the value of the key "session_id".
+    pub fn with_session_id(ctx: AppContext, session_id: String) -> AppContext {
+        Context::with_value(
+            ctx,
+            CONTEXT_KEY_SESSION_ID.to_string(),
+            Value::String(session_id),
+        )
+    }
+
     /// Create a new context with the given parent context and key-value pairs.
     pub fn with_value(ctx: AppContext, key: String, value: Value) -> AppContext {
         let mut ctx = ctx.lock().unwrap();
@@ -1,8 +1,8 @@
 #[allow(dead_code)]
-const APP_TITLE: &'static str = "Music Gang";
+const APP_TITLE: &str = "Music Gang";
 
 #[allow(dead_code)]
-const APP_VERSION: &'static str = "0.0.0";
+const APP_VERSION: &str = "0.0.0";
 
 pub mod context;
 pub mod traits;
@@ -0,0 +1,438 @@
+use std::f32::consts::PI;
+use std::io::Read;
+
+use openmusicgang_entity::analysis::AudioAnalysis;
+use openmusicgang_err::error::Error;
+
+use crate::decode::Decoder;
+
+/// Sample rate the audio is reduced to before being analyzed, in Hz.
+const ANALYSIS_SAMPLE_RATE: u32 = 11025;
+
+/// Maximum duration of audio analyzed from the start of a recording, in seconds.
+const ANALYSIS_MAX_SECONDS: u32 = 180;
+
+/// Size and hop of the frames of the onset envelope, in samples.
+const ONSET_FRAME_SIZE: usize = 1024;
+const ONSET_HOP_SIZE: usize = 256;
+
+/// Size and hop of the frames of the chromagram, in samples.
+const CHROMA_FRAME_SIZE: usize = 4096;
+const CHROMA_HOP_SIZE: usize = 2048;
+
+/// Range of the detected tempos, in beats per minute.
+const MIN_TEMPO: f64 = 60.0;
+const MAX_TEMPO: f64 = 240.0;
+
+/// Tempo favored when several multiples of the beat period match, in beats per minute.
+const PREFERRED_TEMPO: f64 = 120.0;
+
+/// Names of the pitch classes, starting from C.
+const PITCH_CLASSES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Krumhansl-Kessler key profiles of the major and minor keys, starting from the tonic.
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Returns the estimated tempo and key of the audio read from the reader.
+///
+/// The tempo is the period of the onset envelope found by autocorrelation, the key is the
+/// Krumhansl-Kessler profile best correlated with the chromagram. Only the first minutes of the
+/// audio are analyzed, downmixed to mono and reduced to about 11 kHz.
+///
+/// Returns EINVALID if the audio cannot be decoded.
+pub fn analyze_audio(reader: Box<dyn Read + Send + Sync>) -> Result<AudioAnalysis, Error> {
+    let (samples, sample_rate) = read_mono(reader)?;
+
+    let (tempo, tempo_confidence) = estimate_tempo(&samples, sample_rate);
+    let (key, key_confidence) = estimate_key(&samples, sample_rate);
+
+    Ok(AudioAnalysis {
+        tempo,
+        tempo_confidence,
+        key,
+        key_confidence,
+    })
+}
+
+/// Returns the decoded audio downmixed to mono and decimated close to ANALYSIS_SAMPLE_RATE, with its sample rate.
+fn read_mono(reader: Box<dyn Read + Send + Sync>) -> Result<(Vec<f32>, u32), Error> {
+    let mut decoder = Decoder::new(reader)?;
+    let channels = decoder.channels();
+
+    let factor = (decoder.sample_rate() / ANALYSIS_SAMPLE_RATE).max(1) as usize;
+    let sample_rate = decoder.sample_rate() / factor as u32;
+    let max_samples = (sample_rate * ANALYSIS_MAX_SECONDS) as usize;
+
+    let mut samples = vec![];
+    let mut sum = 0.0;
+    let mut count = 0;
+
+    while let Some(block) = decoder.next_samples()? {
+        for frame in block.chunks(channels) {
+            // averaging the decimated samples filters a part of the frequencies above the new Nyquist.
+            sum += frame.iter().sum::<f32>() / channels as f32;
+            count += 1;
+
+            if count == factor {
+                samples.push(sum / factor as f32);
+                sum = 0.0;
+                count = 0;
+            }
+        }
+
+        if samples.len() >= max_samples {
+            samples.truncate(max_samples);
+            break;
+        }
+    }
+
+    Ok((samples, sample_rate))
+}
+
+/// Returns the estimated tempo of the samples in beats per minute with its confidence.
+fn estimate_tempo(samples: &[f32], sample_rate: u32) -> (Option<f64>, f64) {
+    let envelope = onset_envelope(samples);
+    let frame_rate = sample_rate as f64 / ONSET_HOP_SIZE as f64;
+
+    let min_lag = (60.0 * frame_rate / MAX_TEMPO).floor().max(1.0) as usize;
+    let max_lag = (60.0 * frame_rate / MIN_TEMPO).ceil() as usize;
+
+    if envelope.len() <= max_lag + 1 {
+        return (None, 0.0);
+    }
+
+    let autocorrelation: Vec<f64> = (0..=max_lag + 1)
+        .map(|lag| {
+            envelope[lag..]
+                .iter()
+                .zip(&envelope)
+                .map(|(a, b)| (a * b) as f64)
+                .sum()
+        })
+        .collect();
+
+    if autocorrelation[0] <= f64::EPSILON {
+        return (None, 0.0);
+    }
+
+    let mut best_lag = 0;
+    let mut best_score = 0.0;
+
+    for (lag, value) in autocorrelation
+        .iter()
+        .enumerate()
+        .take(max_lag + 1)
+        .skip(min_lag)
+    {
+        let tempo = 60.0 * frame_rate / lag as f64;
+        let weight = (-0.5 * (tempo / PREFERRED_TEMPO).log2().powi(2)).exp();
+        let score = value * weight;
+
+        if score > best_score {
+            best_lag = lag;
+            best_score = score;
+        }
+    }
+
+    if best_lag == 0 {
+        return (None, 0.0);
+    }
+
+    // the peak is refined between the lags with a parabolic interpolation.
+    let (before, peak, after) = (
+        autocorrelation[best_lag - 1],
+        autocorrelation[best_lag],
+        autocorrelation[best_lag + 1],
+    );
+    let curvature = before - 2.0 * peak + after;
+    let shift = if curvature < 0.0 {
+        (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+
+    let tempo = 60.0 * frame_rate / (best_lag as f64 + shift);
+    let confidence = (peak / autocorrelation[0]).clamp(0.0, 1.0);
+
+    (Some((tempo * 10.0).round() / 10.0), confidence)
+}
+
+/// Returns the onset strength of each frame, the increase of its log spectrum since the previous frame.
+fn onset_envelope(samples: &[f32]) -> Vec<f32> {
+    let mut envelope = vec![];
+    let mut previous: Option<Vec<f32>> = None;
+
+    for start in (0..samples.len().saturating_sub(ONSET_FRAME_SIZE - 1)).step_by(ONSET_HOP_SIZE) {
+        let spectrum: Vec<f32> = magnitude_spectrum(&samples[start..start + ONSET_FRAME_SIZE])
+            .into_iter()
+            .map(|magnitude| (1.0 + 100.0 * magnitude).ln())
+            .collect();
+
+        if let Some(previous) = previous {
+            let flux = spectrum
+                .iter()
+                .zip(&previous)
+                .map(|(current, previous)| (current - previous).max(0.0))
+                .sum();
+            envelope.push(flux);
+        }
+
+        previous = Some(spectrum);
+    }
+
+    let mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+
+    envelope
+        .into_iter()
+        .map(|value| (value - mean).max(0.0))
+        .collect()
+}
+
+/// Returns the estimated key of the samples with its confidence.
+fn estimate_key(samples: &[f32], sample_rate: u32) -> (Option<String>, f64) {
+    let mut chroma = [0.0f32; 12];
+    let bin_width = sample_rate as f32 / CHROMA_FRAME_SIZE as f32;
+
+    for start in (0..samples.len().saturating_sub(CHROMA_FRAME_SIZE - 1)).step_by(CHROMA_HOP_SIZE) {
+        let spectrum = magnitude_spectrum(&samples[start..start + CHROMA_FRAME_SIZE]);
+
+        for (bin, magnitude) in spectrum.iter().enumerate().skip(1) {
+            let frequency = bin as f32 * bin_width;
+
+            if !(55.0..=2000.0).contains(&frequency) {
+                continue;
+            }
+
+            let pitch = 69.0 + 12.0 * (frequency / 440.0).log2();
+            chroma[(pitch.round() as usize) % 12] += magnitude;
+        }
+    }
+
+    if chroma.iter().sum::<f32>() <= f32::EPSILON {
+        return (None, 0.0);
+    }
+
+    let mut best: Option<(String, f32)> = None;
+
+    for tonic in 0..12 {
+        for (profile, suffix) in [(&MAJOR_PROFILE, ""), (&MINOR_PROFILE, "m")] {
+            let rotated: Vec<f32> = (0..12).map(|pc| profile[(pc + 12 - tonic) % 12]).collect();
+            let score = correlation(&chroma, &rotated);
+
+            if best.as_ref().is_none_or(|(_, best)| score > *best) {
+                best = Some((format!("{}{}", PITCH_CLASSES[tonic], suffix), score));
+            }
+        }
+    }
+
+    match best {
+        Some((key, score)) => (Some(key), score.clamp(0.0, 1.0) as f64),
+        None => (None, 0.0),
+    }
+}
+
+/// Returns the Pearson correlation of two vectors.
+fn correlation(a: &[f32], b: &[f32]) -> f32 {
+    let mean_a = a.iter().sum::<f32>() / a.len() as f32;
+    let mean_b = b.iter().sum::<f32>() / b.len() as f32;
+
+    let mut covariance = 0.0;
+    let mut variance_a = 0.0;
+    let mut variance_b = 0.0;
+
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a).powi(2);
+        variance_b += (y - mean_b).powi(2);
+    }
+
+    if variance_a <= f32::EPSILON || variance_b <= f32::EPSILON {
+        return 0.0;
+    }
+
+    covariance / (variance_a * variance_b).sqrt()
+}
+
+/// Returns the magnitudes of the positive frequencies of a frame, windowed with a Hann window.
+///
+/// The size of the frame must be a power of two.
+fn magnitude_spectrum(frame: &[f32]) -> Vec<f32> {
+    let n = frame.len();
+
+    let mut re: Vec<f32> = frame
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| sample * (0.5 - 0.5 * (2.0 * PI * i as f32 / n as f32).cos()))
+        .collect();
+    let mut im = vec![0.0; n];
+
+    fft(&mut re, &mut im);
+
+    re.iter()
+        .zip(&im)
+        .take(n / 2)
+        .map(|(re, im)| (re * re + im * im).sqrt() / n as f32)
+        .collect()
+}
+
+/// Computes in place the discrete Fourier transform of a complex signal with the iterative radix-2 algorithm.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (w_im, w_re) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+
+                let t_re = re[b] * w_re - im[b] * w_im;
+                let t_im = re[b] * w_im + im[b] * w_re;
+
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+
+    use openmusicgang_err::error::ErrorCode;
+
+    use crate::test_utils::pcm16_wav_file;
+
+    use super::*;
+
+    /// Returns a mono click track, each click is a short decaying 1 kHz tone.
+    fn click_track(sample_rate: u32, tempo: f64, seconds: u32) -> Vec<u8> {
+        let period = 60.0 / tempo * sample_rate as f64;
+        let click_length = sample_rate as usize / 50;
+
+        let mut samples = vec![0i16; (sample_rate * seconds) as usize];
+        let mut beat = 0.0;
+
+        while (beat as usize) < samples.len() {
+            for i in 0..click_length.min(samples.len() - beat as usize) {
+                let t = i as f32 / sample_rate as f32;
+                let decay = 1.0 - i as f32 / click_length as f32;
+                samples[beat as usize + i] =
+                    ((2.0 * PI * 1000.0 * t).sin() * decay * 20000.0) as i16;
+            }
+            beat += period;
+        }
+
+        pcm16_wav_file(sample_rate, 1, &samples)
+    }
+
+    /// Returns a stereo recording of chords of sine tones, each chord given by its MIDI notes lasts one second.
+    fn chords(sample_rate: u32, progression: &[[u8; 3]]) -> Vec<u8> {
+        let mut samples = vec![];
+
+        for chord in progression {
+            for i in 0..sample_rate {
+                let t = i as f32 / sample_rate as f32;
+                let value: f32 = chord
+                    .iter()
+                    .map(|note| {
+                        let frequency = 440.0 * 2f32.powf((*note as f32 - 69.0) / 12.0);
+                        (2.0 * PI * frequency * t).sin()
+                    })
+                    .sum();
+                let sample = (value / 3.0 * 16000.0) as i16;
+                samples.extend([sample, sample]);
+            }
+        }
+
+        pcm16_wav_file(sample_rate, 2, &samples)
+    }
+
+    fn must_analyze(content: Vec<u8>) -> AudioAnalysis {
+        analyze_audio(Box::new(Cursor::new(content))).unwrap()
+    }
+
+    #[test]
+    fn test_estimate_tempo() {
+        for (sample_rate, tempo) in [(22050, 120.0), (44100, 90.0), (22050, 140.0), (48000, 72.0)] {
+            let analysis = must_analyze(click_track(sample_rate, tempo, 12));
+
+            let estimated = analysis.tempo.unwrap();
+            assert!(
+                (estimated - tempo).abs() <= 1.5,
+                "expected {} bpm, got {}",
+                tempo,
+                estimated
+            );
+            assert!(analysis.tempo_confidence > 0.5);
+        }
+    }
+
+    #[test]
+    fn test_estimate_key() {
+        // I-IV-V-I in C major.
+        let analysis = must_analyze(chords(
+            22050,
+            &[[60, 64, 67], [65, 69, 72], [67, 71, 74], [60, 64, 67]],
+        ));
+        assert_eq!(analysis.key.unwrap(), "C");
+        assert!(analysis.key_confidence > 0.5);
+
+        // i-iv-V-i in A minor.
+        let analysis = must_analyze(chords(
+            22050,
+            &[[57, 60, 64], [62, 65, 69], [64, 68, 71], [57, 60, 64]],
+        ));
+        assert_eq!(analysis.key.unwrap(), "Am");
+
+        // I-IV-V-I in F# major.
+        let analysis = must_analyze(chords(
+            44100,
+            &[[66, 70, 73], [71, 75, 78], [73, 77, 80], [66, 70, 73]],
+        ));
+        assert_eq!(analysis.key.unwrap(), "F#");
+    }
+
+    #[test]
+    fn test_analyze_silence() {
+        let analysis = must_analyze(pcm16_wav_file(22050, 1, &[0; 22050 * 5]));
+        assert_eq!(analysis.tempo, None);
+        assert_eq!(analysis.key, None);
+        assert_eq!(analysis.tempo_confidence, 0.0);
+        assert_eq!(analysis.key_confidence, 0.0);
+
+        let res = analyze_audio(Box::new(Cursor::new(b"not audio".to_vec())));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::{ErrorKind, Read};
+
+use openmusicgang_err::error::{Error, ErrorCode};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder as CodecDecoder, DecoderOptions};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatReader;
+
+use crate::probe::{codec_channels, corrupt_error, open_audio};
+
+/// Decoder decodes the default track of an audio file into interleaved 32 bits float samples.
+pub struct Decoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn CodecDecoder>,
+    track_id: u32,
+    sample_rate: u32,
+    channels: usize,
+    buf: Option<SampleBuffer<f32>>,
+}
+
+impl Decoder {
+    /// Create a new Decoder reading the audio from the reader.
+    ///
+    /// Returns EINVALID if the format or the codec is not supported, or the file is corrupt.
+    pub fn new(reader: Box<dyn Read + Send + Sync>) -> Result<Decoder, Error> {
+        let (_, probed) = open_audio(reader)?;
+        let format = probed.format;
+
+        let track = match format.default_track() {
+            Some(track) => track,
+            None => {
+                return Err(Error::new(
+                    ErrorCode::EINVALID,
+                    "Audio file has no audio track".to_string(),
+                ))
+            }
+        };
+
+        let sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+        let channels = codec_channels(&track.codec_params);
+
+        if sample_rate == 0 || channels == 0 {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "Audio file has no sample rate or channels".to_string(),
+            ));
+        }
+
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|error| match error {
+                SymphoniaError::Unsupported(_) => {
+                    Error::new(ErrorCode::EINVALID, "Unsupported audio codec".to_string())
+                }
+                error => corrupt_error(error),
+            })?;
+
+        Ok(Decoder {
+            track_id: track.id,
+            format,
+            decoder,
+            sample_rate,
+            channels,
+            buf: None,
+        })
+    }
+
+    /// Returns the sample rate of the audio in Hz.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns the number of channels of the audio.
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Returns the next block of interleaved samples, between -1 and 1, None at the end of the audio.
+    ///
+    /// Damaged packets are skipped.
+    pub fn next_samples(&mut self) -> Result<Option<&[f32]>, Error> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(error)) if error.kind() == ErrorKind::UnexpectedEof => {
+                    return Ok(None)
+                }
+                Err(error) => return Err(corrupt_error(error)),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(error) => return Err(corrupt_error(error)),
+            };
+
+            if decoded.frames() == 0 {
+                continue;
+            }
+
+            if decoded.spec().channels.count() != self.channels {
+                return Err(Error::new(
+                    ErrorCode::EINVALID,
+                    "Corrupt audio file: the number of channels changed".to_string(),
+                ));
+            }
+
+            let required = decoded.capacity() * self.channels;
+
+            if self.buf.as_ref().map(|buf| buf.capacity()).unwrap_or(0) < required {
+                self.buf = Some(SampleBuffer::new(
+                    decoded.capacity() as u64,
+                    *decoded.spec(),
+                ));
+            }
+
+            let buf = self.buf.as_mut().unwrap();
+            buf.copy_interleaved_ref(decoded);
+
+            return Ok(Some(buf.samples()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+
+    use crate::test_utils::pcm16_wav_file;
+
+    use super::*;
+
+    #[test]
+    fn test_decoder() {
+        let samples: Vec<i16> = (0..10000)
+            .map(|i| if i % 2 == 0 { 16384 } else { -32768 })
+            .collect();
+        let content = pcm16_wav_file(22050, 2, &samples);
+
+        let mut decoder = Decoder::new(Box::new(Cursor::new(content))).unwrap();
+        assert_eq!(decoder.sample_rate(), 22050);
+        assert_eq!(decoder.channels(), 2);
+
+        let mut decoded = vec![];
+        while let Some(block) = decoder.next_samples().unwrap() {
+            decoded.extend_from_slice(block);
+        }
+
+        assert_eq!(decoded.len(), samples.len());
+        assert_eq!(decoded[0], 0.5);
+        assert_eq!(decoded[1], -1.0);
+        assert_eq!(decoded[9999], -1.0);
+
+        let res = Decoder::new(Box::new(Cursor::new(b"not audio".to_vec())));
+        assert!(res.is_err());
+        assert_eq!(res.err().unwrap().code, ErrorCode::EINVALID);
+    }
+}
@@ -0,0 +1,49 @@
+pub mod analysis;
+pub mod decode;
+pub mod loudness;
+pub mod mixdown;
+pub mod probe;
+pub mod waveform;
+
+#[cfg(test)]
+pub mod test_utils {
+
+    /// Returns a PCM WAV file of silence.
+    #[allow(dead_code)]
+    pub fn wav_file(sample_rate: u32, channels: u16, bits: u16, frames: u32) -> Vec<u8> {
+        let data = vec![0u8; (frames * (channels * bits / 8) as u32) as usize];
+
+        wav_file_with_data(sample_rate, channels, bits, data)
+    }
+
+    /// Returns a 16 bits PCM WAV file of the given interleaved samples.
+    #[allow(dead_code)]
+    pub fn pcm16_wav_file(sample_rate: u32, channels: u16, samples: &[i16]) -> Vec<u8> {
+        let data = samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect();
+
+        wav_file_with_data(sample_rate, channels, 16, data)
+    }
+
+    fn wav_file_with_data(sample_rate: u32, channels: u16, bits: u16, data: Vec<u8>) -> Vec<u8> {
+        let block_align = channels * bits / 8;
+
+        let mut buf = vec![];
+        buf.extend(b"RIFF");
+        buf.extend((36 + data.len() as u32).to_le_bytes());
+        buf.extend(b"WAVEfmt ");
+        buf.extend(16u32.to_le_bytes());
+        buf.extend(1u16.to_le_bytes());
+        buf.extend(channels.to_le_bytes());
+        buf.extend(sample_rate.to_le_bytes());
+        buf.extend((sample_rate * block_align as u32).to_le_bytes());
+        buf.extend(block_align.to_le_bytes());
+        buf.extend(bits.to_le_bytes());
+        buf.extend(b"data");
+        buf.extend((data.len() as u32).to_le_bytes());
+        buf.extend(data);
+        buf
+    }
+}
@@ -0,0 +1,391 @@
+use std::f64::consts::PI;
+use std::io::Read;
+
+use openmusicgang_entity::loudness::Loudness;
+use openmusicgang_err::error::Error;
+
+use crate::decode::Decoder;
+
+/// Loudness of a block below which it is ignored, in LUFS.
+const ABSOLUTE_GATE: f64 = -70.0;
+
+/// Gate relative to the mean loudness of the integrated loudness, in LU.
+const INTEGRATED_RELATIVE_GATE: f64 = -10.0;
+
+/// Gate relative to the mean loudness of the loudness range, in LU.
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+
+/// Number of 100 ms steps of a momentary (400 ms) and a short-term (3 s) block.
+const MOMENTARY_STEPS: usize = 4;
+const SHORT_TERM_STEPS: usize = 30;
+
+/// Number of taps of each phase of the true peak interpolation filter.
+const TRUE_PEAK_TAPS: usize = 12;
+
+/// Returns the loudness of the audio read from the reader, None if the audio is silent or shorter
+/// than 400 ms.
+///
+/// The integrated loudness and the true peak follow ITU-R BS.1770-4, the loudness range follows
+/// EBU Tech 3342.
+///
+/// Returns EINVALID if the audio cannot be decoded.
+pub fn measure_loudness(reader: Box<dyn Read + Send + Sync>) -> Result<Option<Loudness>, Error> {
+    let mut decoder = Decoder::new(reader)?;
+    let mut meter = LoudnessMeter::new(decoder.sample_rate(), decoder.channels());
+
+    while let Some(samples) = decoder.next_samples()? {
+        meter.add_samples(samples);
+    }
+
+    Ok(meter.loudness())
+}
+
+/// LoudnessMeter measures the loudness of interleaved samples added block by block.
+pub(crate) struct LoudnessMeter {
+    channels: usize,
+    weights: Vec<f64>,
+    filters: Vec<[Biquad; 2]>,
+    true_peaks: Vec<TruePeak>,
+    /// Number of frames of a 100 ms step.
+    step_frames: usize,
+    /// Number of frames and sum of the squared filtered samples of each channel in the current step.
+    frames: usize,
+    sums: Vec<f64>,
+    /// Weighted mean square of each complete step.
+    powers: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub(crate) fn new(sample_rate: u32, channels: usize) -> LoudnessMeter {
+        let rate = sample_rate as f64;
+
+        LoudnessMeter {
+            channels,
+            weights: (0..channels)
+                .map(|channel| channel_weight(channel, channels))
+                .collect(),
+            filters: (0..channels)
+                .map(|_| [Biquad::high_shelf(rate), Biquad::high_pass(rate)])
+                .collect(),
+            true_peaks: (0..channels).map(|_| TruePeak::new(sample_rate)).collect(),
+            step_frames: ((rate / 10.0).round() as usize).max(1),
+            frames: 0,
+            sums: vec![0.0; channels],
+            powers: vec![],
+        }
+    }
+
+    pub(crate) fn add_samples(&mut self, samples: &[f32]) {
+        for frame in samples.chunks(self.channels) {
+            for (channel, sample) in frame.iter().enumerate() {
+                let sample = *sample as f64;
+
+                let [shelf, pass] = &mut self.filters[channel];
+                let filtered = pass.process(shelf.process(sample));
+
+                self.sums[channel] += filtered * filtered;
+                self.true_peaks[channel].process(sample);
+            }
+
+            self.frames += 1;
+
+            if self.frames == self.step_frames {
+                let power = self
+                    .sums
+                    .iter()
+                    .zip(&self.weights)
+                    .map(|(sum, weight)| weight * sum / self.frames as f64)
+                    .sum();
+
+                self.powers.push(power);
+                self.sums.iter_mut().for_each(|sum| *sum = 0.0);
+                self.frames = 0;
+            }
+        }
+    }
+
+    /// Returns the loudness of the samples added so far, None if they are silent or shorter than 400 ms.
+    pub(crate) fn loudness(&self) -> Option<Loudness> {
+        let momentary = block_powers(&self.powers, MOMENTARY_STEPS);
+        let gated = gate(&momentary, INTEGRATED_RELATIVE_GATE);
+
+        if gated.is_empty() {
+            return None;
+        }
+
+        let integrated = power_loudness(mean(&gated));
+
+        let short_term = block_powers(&self.powers, SHORT_TERM_STEPS);
+        let mut gated: Vec<f64> = gate(&short_term, RANGE_RELATIVE_GATE)
+            .into_iter()
+            .map(power_loudness)
+            .collect();
+        gated.sort_by(|a, b| a.total_cmp(b));
+
+        let range = if gated.is_empty() {
+            0.0
+        } else {
+            percentile(&gated, 0.95) - percentile(&gated, 0.10)
+        };
+
+        let peak = self
+            .true_peaks
+            .iter()
+            .fold(0.0f64, |peak, true_peak| peak.max(true_peak.peak));
+
+        Some(Loudness {
+            integrated,
+            range,
+            true_peak: 20.0 * peak.log10(),
+        })
+    }
+}
+
+/// Returns the weight of a channel, the surround channels of a 5.1 layout are louder and its LFE is ignored.
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (6, 3) => 0.0,
+        (6, 4) | (6, 5) => 1.41,
+        _ => 1.0,
+    }
+}
+
+/// Returns the mean power of the sliding blocks of the given number of steps, one block per step.
+fn block_powers(powers: &[f64], steps: usize) -> Vec<f64> {
+    powers
+        .windows(steps)
+        .map(|window| window.iter().sum::<f64>() / steps as f64)
+        .collect()
+}
+
+/// Returns the powers above the absolute gate and above the mean of those by the relative gate.
+fn gate(powers: &[f64], relative_gate: f64) -> Vec<f64> {
+    let absolute: Vec<f64> = powers
+        .iter()
+        .copied()
+        .filter(|power| power_loudness(*power) > ABSOLUTE_GATE)
+        .collect();
+
+    if absolute.is_empty() {
+        return absolute;
+    }
+
+    let threshold = power_loudness(mean(&absolute)) + relative_gate;
+
+    absolute
+        .into_iter()
+        .filter(|power| power_loudness(*power) > threshold)
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Returns the loudness in LUFS of a weighted mean square.
+fn power_loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Returns the value at the given fraction of sorted values.
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * fraction).round() as usize]
+}
+
+/// Biquad is a second order filter of the K-weighting.
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    /// Returns the high shelf modelling the acoustic effect of the head, +4 dB above 1.5 kHz.
+    fn high_shelf(sample_rate: f64) -> Biquad {
+        let k = (PI * 1681.974450955533 / sample_rate).tan();
+        let q = 0.7071752369554196;
+        let vh = 10f64.powf(3.999843853973347 / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+
+        Biquad {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            state: [0.0; 2],
+        }
+    }
+
+    /// Returns the high pass filter of the revised low-frequency B-curve.
+    fn high_pass(sample_rate: f64) -> Biquad {
+        let k = (PI * 38.13547087602444 / sample_rate).tan();
+        let q = 0.5003270373238773;
+        let a0 = 1.0 + k / q + k * k;
+
+        Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            state: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.state[0];
+        self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// TruePeak measures the peak of a channel oversampled with a polyphase windowed sinc filter.
+struct TruePeak {
+    factor: usize,
+    coefficients: Vec<f64>,
+    /// Last input samples, the most recent first.
+    history: Vec<f64>,
+    peak: f64,
+}
+
+impl TruePeak {
+    /// Returns a meter oversampling 4 times below 96 kHz and 2 times below 192 kHz.
+    fn new(sample_rate: u32) -> TruePeak {
+        let factor = match sample_rate {
+            0..=95_999 => 4,
+            96_000..=191_999 => 2,
+            _ => 1,
+        };
+
+        let length = TRUE_PEAK_TAPS * factor;
+        let center = (length - 1) as f64 / 2.0;
+
+        let mut coefficients: Vec<f64> = (0..length)
+            .map(|n| {
+                let t = (n as f64 - center) / factor as f64;
+                let sinc = if t == 0.0 {
+                    1.0
+                } else {
+                    (PI * t).sin() / (PI * t)
+                };
+                let window = 0.5 - 0.5 * (2.0 * PI * (n as f64 + 0.5) / length as f64).cos();
+                sinc * window
+            })
+            .collect();
+
+        // each phase is normalized so that a constant signal keeps its level.
+        for phase in 0..factor {
+            let sum: f64 = coefficients.iter().skip(phase).step_by(factor).sum();
+            coefficients
+                .iter_mut()
+                .skip(phase)
+                .step_by(factor)
+                .for_each(|coefficient| *coefficient /= sum);
+        }
+
+        TruePeak {
+            factor,
+            coefficients,
+            history: vec![0.0; TRUE_PEAK_TAPS],
+            peak: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f64) {
+        self.history.rotate_right(1);
+        self.history[0] = x;
+        self.peak = self.peak.max(x.abs());
+
+        for phase in 0..self.factor {
+            let y: f64 = self
+                .history
+                .iter()
+                .zip(self.coefficients.iter().skip(phase).step_by(self.factor))
+                .map(|(sample, coefficient)| sample * coefficient)
+                .sum();
+
+            self.peak = self.peak.max(y.abs());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+
+    use openmusicgang_err::error::ErrorCode;
+
+    use crate::test_utils::pcm16_wav_file;
+
+    use super::*;
+
+    /// Returns a sine of the given frequency, amplitude in dBFS and phase, on each channel.
+    fn sine(sample_rate: u32, channels: u16, frequency: f64, dbfs: f64, seconds: f64) -> Vec<i16> {
+        let amplitude = 10f64.powf(dbfs / 20.0) * 32767.0;
+        let frames = (sample_rate as f64 * seconds) as usize;
+
+        let mut samples = vec![];
+        for i in 0..frames {
+            let t = i as f64 / sample_rate as f64;
+            let sample = (amplitude * (2.0 * PI * frequency * t + PI / 4.0).sin()).round() as i16;
+            samples.extend(std::iter::repeat_n(sample, channels as usize));
+        }
+        samples
+    }
+
+    fn must_measure(content: Vec<u8>) -> Option<Loudness> {
+        measure_loudness(Box::new(Cursor::new(content))).unwrap()
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) measure a stereo 1 kHz sine at -23 dBFS, it should be -23 LUFS at any sample rate.
+    /// 2) measure the same sine in mono, it should be 3 LU quieter.
+    /// 3) measure a sine at -20 dBFS then at -30 dBFS, the loudness range should be 10 LU.
+    /// 4) measure a full scale sine at a quarter of the sample rate, its true peak should be above its sample peak.
+    /// 5) measure silence, it should have no loudness.
+    /// 6) measure a file that is not audio, error should be EINVALID.
+    #[test]
+    fn test_measure_loudness() {
+        // 1) measure a stereo 1 kHz sine at -23 dBFS, it should be -23 LUFS at any sample rate.
+        for sample_rate in [44100, 48000] {
+            let samples = sine(sample_rate, 2, 1000.0, -23.0, 5.0);
+            let loudness = must_measure(pcm16_wav_file(sample_rate, 2, &samples)).unwrap();
+
+            assert!((loudness.integrated + 23.0).abs() < 0.1, "{:?}", loudness);
+            assert!(loudness.range < 0.1);
+            assert!((loudness.true_peak + 23.0).abs() < 0.1);
+        }
+
+        // 2) measure the same sine in mono, it should be 3 LU quieter.
+        let samples = sine(48000, 1, 1000.0, -23.0, 5.0);
+        let loudness = must_measure(pcm16_wav_file(48000, 1, &samples)).unwrap();
+        assert!((loudness.integrated + 26.0).abs() < 0.1);
+
+        // 3) measure a sine at -20 dBFS then at -30 dBFS, the loudness range should be 10 LU.
+        let mut samples = sine(48000, 2, 1000.0, -20.0, 10.0);
+        samples.extend(sine(48000, 2, 1000.0, -30.0, 10.0));
+        let loudness = must_measure(pcm16_wav_file(48000, 2, &samples)).unwrap();
+        assert!((loudness.range - 10.0).abs() < 1.0, "{:?}", loudness);
+
+        // 4) measure a full scale sine at a quarter of the sample rate, its true peak should be above its sample peak.
+        let samples = sine(48000, 1, 12000.0, 0.0, 1.0);
+        let loudness = must_measure(pcm16_wav_file(48000, 1, &samples)).unwrap();
+        assert!(
+            loudness.true_peak > -0.5 && loudness.true_peak < 0.5,
+            "{:?}",
+            loudness
+        );
+
+        // 5) measure silence, it should have no loudness.
+        assert_eq!(must_measure(pcm16_wav_file(48000, 2, &[0; 96000])), None);
+        assert_eq!(must_measure(pcm16_wav_file(48000, 1, &[1000; 4800])), None);
+
+        // 6) measure a file that is not audio, error should be EINVALID.
+        let res = measure_loudness(Box::new(Cursor::new(b"not audio".to_vec())));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+    }
+}
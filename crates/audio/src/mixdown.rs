@@ -0,0 +1,423 @@
+use std::io::Read;
+
+use openmusicgang_entity::loudness::Loudness;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::decode::Decoder;
+use crate::loudness::LoudnessMeter;
+
+/// Number of channels of a mixdown.
+const MIXDOWN_CHANNELS: u16 = 2;
+
+/// Number of bits per sample of a mixdown.
+const MIXDOWN_BIT_DEPTH: u16 = 24;
+
+/// Maximum number of frames of a mixdown, the size of the data of a WAV file must fit in 32 bits.
+const MAX_MIXDOWN_FRAMES: u64 =
+    (u32::MAX as u64 - 36) / (MIXDOWN_CHANNELS as u64 * MIXDOWN_BIT_DEPTH as u64 / 8);
+
+/// MixdownTrack is a track to render in a mixdown, with its audio and mixer settings.
+pub struct MixdownTrack {
+    pub reader: Box<dyn Read + Send + Sync>,
+    /// Gain in decibels.
+    pub gain: f64,
+    /// Stereo position, from -1.0 (left) to 1.0 (right).
+    pub pan: f64,
+    pub muted: bool,
+    pub solo: bool,
+    /// Offset from the start of the mix, in samples at the sample rate of the track.
+    pub offset: i64,
+}
+
+/// Returns the mixdown of the tracks as a stereo 24 bits PCM WAV file.
+///
+/// The tracks are resampled with a linear interpolation to the highest sample rate among them.
+/// Muted tracks are skipped, and only the soloed tracks are heard if any track is soloed.
+/// Mono tracks are panned with a constant power pan law, the pan of the other tracks balances
+/// their first two channels. If a target loudness in LUFS is given, the mix is normalized to it
+/// without its true peak exceeding -1 dBTP. The mix is scaled down if its peak would clip.
+///
+/// The rendering is deterministic, the same tracks always give the same file.
+///
+/// Returns the file with the loudness of the mix, None if the mix is silent.
+///
+/// Returns EINVALID if there is no track, the audio of a track cannot be decoded or the mix would be too long
+/// to be stored as a WAV file.
+pub fn render_mixdown(
+    tracks: Vec<MixdownTrack>,
+    target_loudness: Option<f64>,
+) -> Result<(Vec<u8>, Option<Loudness>), Error> {
+    if tracks.is_empty() {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "A mixdown needs at least one track".to_string(),
+        ));
+    }
+
+    let any_solo = tracks.iter().any(|track| track.solo);
+
+    let mut decoded = vec![];
+
+    for track in tracks {
+        let audible = !track.muted && (track.solo || !any_solo);
+        let decoder = Decoder::new(track.reader)?;
+
+        decoded.push((decoder, audible, track.gain, track.pan, track.offset));
+    }
+
+    let sample_rate = decoded
+        .iter()
+        .map(|(decoder, ..)| decoder.sample_rate())
+        .max()
+        .unwrap_or(0);
+
+    // interleaved stereo samples of the mix.
+    let mut mix: Vec<f32> = vec![];
+
+    for (mut decoder, audible, gain, pan, offset) in decoded {
+        if !audible {
+            continue;
+        }
+
+        let source_rate = decoder.sample_rate();
+        let (left_gain, right_gain) = channel_gains(gain, pan, decoder.channels());
+        let frames = read_stereo(&mut decoder, left_gain, right_gain)?;
+
+        let resampled = resample(&frames, source_rate, sample_rate);
+        let length = resampled.len() / 2;
+
+        let offset = (offset.max(0) as u64)
+            .checked_mul(sample_rate as u64)
+            .map(|offset| offset / source_rate as u64)
+            .filter(|offset| offset.saturating_add(length as u64) <= MAX_MIXDOWN_FRAMES)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorCode::EINVALID,
+                    "The mixdown would be too long to be stored as a WAV file".to_string(),
+                )
+            })? as usize;
+
+        let end = (offset + length) * 2;
+        if mix.len() < end {
+            mix.resize(end, 0.0);
+        }
+
+        for (sample, value) in mix[offset * 2..end].iter_mut().zip(resampled) {
+            *sample += value;
+        }
+    }
+
+    let mut meter = LoudnessMeter::new(sample_rate, MIXDOWN_CHANNELS as usize);
+    meter.add_samples(&mix);
+    let loudness = meter.loudness();
+
+    let gain = match (target_loudness, &loudness) {
+        (Some(target), Some(loudness)) => loudness.normalization_gain(target),
+        _ => 0.0,
+    };
+
+    let peak = mix
+        .iter()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    let mut scale = 10f64.powf(gain / 20.0) as f32;
+    if peak * scale > 1.0 {
+        scale = 1.0 / peak;
+    }
+
+    let loudness = loudness.map(|loudness| loudness.with_gain(20.0 * (scale as f64).log10()));
+
+    Ok((wav_file(sample_rate, &mix, scale), loudness))
+}
+
+/// Returns the gains applied to the left and right channels of a track.
+fn channel_gains(gain: f64, pan: f64, channels: usize) -> (f32, f32) {
+    let gain = 10f64.powf(gain / 20.0);
+    let pan = pan.clamp(-1.0, 1.0);
+
+    if channels == 1 {
+        let angle = (pan + 1.0) * std::f64::consts::FRAC_PI_4;
+        return ((gain * angle.cos()) as f32, (gain * angle.sin()) as f32);
+    }
+
+    (
+        (gain * (1.0 - pan).min(1.0)) as f32,
+        (gain * (1.0 + pan).min(1.0)) as f32,
+    )
+}
+
+/// Returns the whole audio of the decoder as interleaved stereo samples, with the gains applied.
+fn read_stereo(decoder: &mut Decoder, left_gain: f32, right_gain: f32) -> Result<Vec<f32>, Error> {
+    let channels = decoder.channels();
+    let mut frames = vec![];
+
+    while let Some(samples) = decoder.next_samples()? {
+        for frame in samples.chunks(channels) {
+            let (left, right) = match frame {
+                [mono] => (*mono, *mono),
+                [left, right, ..] => (*left, *right),
+                [] => continue,
+            };
+
+            frames.push(left * left_gain);
+            frames.push(right * right_gain);
+        }
+    }
+
+    Ok(frames)
+}
+
+/// Returns interleaved stereo samples resampled from one rate to another with a linear interpolation.
+fn resample(frames: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to {
+        return frames.to_vec();
+    }
+
+    let count = frames.len() / 2;
+    let resampled_count = (count as u64 * to as u64).div_ceil(from as u64) as usize;
+    let mut resampled = Vec::with_capacity(resampled_count * 2);
+
+    for index in 0..resampled_count {
+        // the position in the source is computed with integers so that no error accumulates.
+        let position = index as u64 * from as u64;
+        let before = (position / to as u64) as usize;
+        let fraction = (position % to as u64) as f32 / to as f32;
+        let after = (before + 1).min(count - 1);
+
+        for channel in 0..2 {
+            let a = frames[before * 2 + channel];
+            let b = frames[after * 2 + channel];
+            resampled.push(a + (b - a) * fraction);
+        }
+    }
+
+    resampled
+}
+
+/// Returns a stereo 24 bits PCM WAV file of the interleaved samples, multiplied by the scale.
+fn wav_file(sample_rate: u32, samples: &[f32], scale: f32) -> Vec<u8> {
+    let block_align = MIXDOWN_CHANNELS * MIXDOWN_BIT_DEPTH / 8;
+    let data_size = samples.len() as u32 * 3;
+
+    let mut buf = Vec::with_capacity(44 + data_size as usize);
+    buf.extend(b"RIFF");
+    buf.extend((36 + data_size).to_le_bytes());
+    buf.extend(b"WAVEfmt ");
+    buf.extend(16u32.to_le_bytes());
+    buf.extend(1u16.to_le_bytes());
+    buf.extend(MIXDOWN_CHANNELS.to_le_bytes());
+    buf.extend(sample_rate.to_le_bytes());
+    buf.extend((sample_rate * block_align as u32).to_le_bytes());
+    buf.extend(block_align.to_le_bytes());
+    buf.extend(MIXDOWN_BIT_DEPTH.to_le_bytes());
+    buf.extend(b"data");
+    buf.extend(data_size.to_le_bytes());
+
+    for sample in samples {
+        let value = ((sample * scale) as f64 * 8_388_607.0)
+            .round()
+            .clamp(-8_388_608.0, 8_388_607.0) as i32;
+
+        buf.extend(&value.to_le_bytes()[..3]);
+    }
+
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+
+    use openmusicgang_crypto::digest::sha256_hex;
+
+    use crate::loudness::measure_loudness;
+    use crate::test_utils::pcm16_wav_file;
+
+    use super::*;
+
+    fn track(content: Vec<u8>) -> MixdownTrack {
+        MixdownTrack {
+            reader: Box::new(Cursor::new(content)),
+            gain: 0.0,
+            pan: 0.0,
+            muted: false,
+            solo: false,
+            offset: 0,
+        }
+    }
+
+    fn render_wav(tracks: Vec<MixdownTrack>) -> Result<Vec<u8>, Error> {
+        render_mixdown(tracks, None).map(|(wav, _)| wav)
+    }
+
+    /// Returns the 24 bits samples of a mixdown.
+    fn samples(wav: &[u8]) -> Vec<i32> {
+        wav[44..]
+            .chunks(3)
+            .map(|sample| i32::from_le_bytes([0, sample[0], sample[1], sample[2]]) >> 8)
+            .collect()
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) render a stereo track, the samples should be unchanged.
+    /// 2) render it with an offset and a lower gain.
+    /// 3) render a mono track panned hard left.
+    /// 4) resample a track to the rate of another one.
+    /// 5) mute and solo tracks.
+    /// 6) sum two full scale tracks, the mix should be scaled down instead of clipping.
+    /// 7) render the same tracks twice, the outputs should be identical.
+    /// 8) render no track, error should be EINVALID.
+    /// 9) render a sine with a loudness target, the mix should be normalized to it.
+    /// 10) render it with a loud target, the true peak should be limited instead.
+    /// 11) render silence, it should have no loudness.
+    /// 12) render a track with an offset beyond the size of a WAV file, error should be EINVALID.
+    #[test]
+    fn test_render_mixdown() {
+        let stereo = pcm16_wav_file(8000, 2, &[16384, -16384, 8192, -8192]);
+        let mono = pcm16_wav_file(8000, 1, &[16384, 16384]);
+
+        // 1) render a stereo track, the samples should be unchanged.
+        let wav = render_wav(vec![track(stereo.clone())]).unwrap();
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u16::from_le_bytes([wav[22], wav[23]]), 2);
+        assert_eq!(
+            u32::from_le_bytes([wav[24], wav[25], wav[26], wav[27]]),
+            8000
+        );
+        assert_eq!(u16::from_le_bytes([wav[34], wav[35]]), 24);
+        assert_eq!(samples(&wav), vec![4194304, -4194304, 2097152, -2097152]);
+
+        // 2) render it with an offset and a lower gain.
+        let wav = render_wav(vec![MixdownTrack {
+            offset: 1,
+            gain: -6.0,
+            ..track(stereo.clone())
+        }])
+        .unwrap();
+        let mixed = samples(&wav);
+        assert_eq!(mixed.len(), 6);
+        assert_eq!(&mixed[0..2], &[0, 0]);
+        assert_eq!(&mixed[2..], &[2102131, -2102131, 1051066, -1051066]);
+
+        // 3) render a mono track panned hard left.
+        let wav = render_wav(vec![MixdownTrack {
+            pan: -1.0,
+            ..track(mono.clone())
+        }])
+        .unwrap();
+        assert_eq!(samples(&wav), vec![4194304, 0, 4194304, 0]);
+
+        // 4) resample a track to the rate of another one.
+        let fast = pcm16_wav_file(16000, 2, &[0; 16]);
+        let wav = render_wav(vec![track(stereo.clone()), track(fast)]).unwrap();
+        assert_eq!(
+            u32::from_le_bytes([wav[24], wav[25], wav[26], wav[27]]),
+            16000
+        );
+        assert_eq!(
+            &samples(&wav)[0..8],
+            &[4194304, -4194304, 3145728, -3145728, 2097152, -2097152, 2097152, -2097152]
+        );
+
+        // 5) mute and solo tracks.
+        let wav = render_wav(vec![
+            MixdownTrack {
+                muted: true,
+                ..track(stereo.clone())
+            },
+            MixdownTrack {
+                pan: -1.0,
+                ..track(mono.clone())
+            },
+        ])
+        .unwrap();
+        assert_eq!(samples(&wav), vec![4194304, 0, 4194304, 0]);
+
+        let wav = render_wav(vec![
+            MixdownTrack {
+                solo: true,
+                ..track(stereo.clone())
+            },
+            track(mono.clone()),
+        ])
+        .unwrap();
+        assert_eq!(samples(&wav), vec![4194304, -4194304, 2097152, -2097152]);
+
+        // 6) sum two full scale tracks, the mix should be scaled down instead of clipping.
+        let loud = pcm16_wav_file(8000, 2, &[-32768, -32768, -16384, -16384]);
+        let wav = render_wav(vec![track(loud.clone()), track(loud)]).unwrap();
+        assert_eq!(samples(&wav), vec![-8388607, -8388607, -4194304, -4194304]);
+
+        // 7) render the same tracks twice, the outputs should be identical.
+        let render = || {
+            render_wav(vec![
+                track(stereo.clone()),
+                MixdownTrack {
+                    offset: 3,
+                    ..track(pcm16_wav_file(
+                        11025,
+                        2,
+                        &[1000, -1000, 2000, -2000, 3000, -3000],
+                    ))
+                },
+            ])
+            .unwrap()
+        };
+        let hash = sha256_hex(&render());
+        assert_eq!(hash, sha256_hex(&render()));
+        assert_eq!(
+            hash,
+            "7fd6dd73c009442dab421aa24c0a155012c16486b833412f9bc3185a9ef1b376"
+        );
+
+        // 8) render no track, error should be EINVALID.
+        let res = render_wav(vec![]);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 9) render a sine with a loudness target, the mix should be normalized to it.
+        let sine: Vec<i16> = (0..48000 * 2)
+            .map(|i| ((i as f32 * 2.0 * std::f32::consts::PI / 48.0).sin() * 3000.0) as i16)
+            .collect();
+        let sine = pcm16_wav_file(48000, 1, &sine);
+
+        let (_, loudness) = render_mixdown(vec![track(sine.clone())], None).unwrap();
+        let loudness = loudness.unwrap();
+        assert!(loudness.integrated < -23.0);
+
+        let (wav, normalized) = render_mixdown(vec![track(sine.clone())], Some(-16.0)).unwrap();
+        let normalized = normalized.unwrap();
+        assert!((normalized.integrated + 16.0).abs() < 0.01);
+
+        let measured = measure_loudness(Box::new(Cursor::new(wav)))
+            .unwrap()
+            .unwrap();
+        assert!((measured.integrated + 16.0).abs() < 0.05);
+        assert!((measured.true_peak - normalized.true_peak).abs() < 0.05);
+
+        // 10) render it with a loud target, the true peak should be limited instead.
+        let (_, limited) = render_mixdown(vec![track(sine)], Some(0.0)).unwrap();
+        let limited = limited.unwrap();
+        assert!((limited.true_peak + 1.0).abs() < 0.01);
+        assert!(limited.integrated < 0.0);
+
+        // 11) render silence, it should have no loudness.
+        let (_, loudness) = render_mixdown(
+            vec![track(pcm16_wav_file(8000, 2, &[0; 16000]))],
+            Some(-23.0),
+        )
+        .unwrap();
+        assert_eq!(loudness, None);
+
+        // 12) render a track with an offset beyond the size of a WAV file, error should be EINVALID.
+        for offset in [i64::MAX, MAX_MIXDOWN_FRAMES as i64] {
+            let res = render_wav(vec![MixdownTrack {
+                offset,
+                ..track(stereo.clone())
+            }]);
+            assert!(res.is_err());
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+    }
+}
@@ -0,0 +1,490 @@
+use std::collections::BTreeMap;
+use std::io::{Cursor, ErrorKind, Read};
+
+use openmusicgang_entity::audio::{AudioFormat, AudioInfo};
+use openmusicgang_err::error::{Error, ErrorCode};
+use symphonia::core::codecs::CodecParameters;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSourceStream, ReadOnlySource};
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey, Value};
+use symphonia::core::probe::{Hint, ProbeResult};
+
+/// Number of bytes needed to recognize a format by its magic bytes.
+const HEAD_SIZE: usize = 12;
+
+/// Size of the header of an ID3v2 tag.
+const ID3_HEADER_SIZE: usize = 10;
+
+/// Returns the properties of the audio read from the reader.
+///
+/// The format is recognized by its magic bytes, an ID3v2 tag may precede MP3 and FLAC streams.
+/// Only the headers are parsed when the container declares its length, otherwise the packets are
+/// scanned without being decoded.
+///
+/// Returns EINVALID if the format is not supported or the file is corrupt.
+pub fn probe_audio(reader: Box<dyn Read + Send + Sync>) -> Result<AudioInfo, Error> {
+    let (format, mut probed) = open_audio(reader)?;
+
+    let mut tags = BTreeMap::new();
+
+    if let Some(metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.current() {
+            collect_tags(revision, &mut tags);
+        }
+    }
+
+    if let Some(revision) = probed.format.metadata().current() {
+        collect_tags(revision, &mut tags);
+    }
+
+    let track = match probed.format.default_track() {
+        Some(track) => track.clone(),
+        None => {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "Audio file has no audio track".to_string(),
+            ))
+        }
+    };
+
+    let params = &track.codec_params;
+
+    let sample_rate = params.sample_rate.unwrap_or(0);
+    let channels = codec_channels(params);
+
+    if sample_rate == 0 || channels == 0 {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "Audio file has no sample rate or channels".to_string(),
+        ));
+    }
+
+    let frames = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            let mut frames = 0;
+
+            loop {
+                match probed.format.next_packet() {
+                    Ok(packet) if packet.track_id() == track.id => frames += packet.dur,
+                    Ok(_) => {}
+                    Err(SymphoniaError::IoError(error))
+                        if error.kind() == ErrorKind::UnexpectedEof =>
+                    {
+                        break
+                    }
+                    Err(error) => return Err(corrupt_error(error)),
+                }
+            }
+
+            frames
+        }
+    };
+
+    if frames == 0 {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "Audio file has no samples".to_string(),
+        ));
+    }
+
+    Ok(AudioInfo {
+        format,
+        frames: frames as i64,
+        sample_rate: sample_rate as i32,
+        bit_depth: match format {
+            AudioFormat::Wav | AudioFormat::Flac => params.bits_per_sample.map(|bits| bits as i32),
+            AudioFormat::Mp3 | AudioFormat::Ogg => None,
+        },
+        channels: channels as i32,
+        tags,
+    })
+}
+
+/// Recognizes the format of the audio read from the reader and opens it with its format reader.
+///
+/// Returns EINVALID if the format is not supported or its headers are corrupt.
+pub(crate) fn open_audio(
+    mut reader: Box<dyn Read + Send + Sync>,
+) -> Result<(AudioFormat, ProbeResult), Error> {
+    let mut head = read_up_to(&mut reader, HEAD_SIZE)?;
+
+    // the ID3v2 tag is read whole so that the format of the stream after it can be detected.
+    if let Some(size) = id3_tag_size(&head) {
+        head.extend(read_up_to(&mut reader, size + HEAD_SIZE - head.len())?);
+    }
+
+    let offset = id3_tag_size(&head).unwrap_or(0).min(head.len());
+
+    let format = match detect_format(&head[offset..]) {
+        Some(format) => format,
+        None => {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "Unsupported audio format, expected WAV, FLAC, MP3 or Ogg".to_string(),
+            ))
+        }
+    };
+
+    let source = ReadOnlySource::new(Cursor::new(head).chain(reader));
+    let mss = MediaSourceStream::new(Box::new(source), Default::default());
+
+    let mut hint = Hint::new();
+    hint.with_extension(format.as_str());
+
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(corrupt_error)?;
+
+    Ok((format, probed))
+}
+
+/// Returns the number of channels of a track, 0 if unknown.
+pub(crate) fn codec_channels(params: &CodecParameters) -> usize {
+    params
+        .channels
+        .map(|channels| channels.count())
+        .or_else(|| {
+            params
+                .channel_layout
+                .map(|layout| layout.into_channels().count())
+        })
+        .unwrap_or(0)
+}
+
+/// Returns the format of an audio file from its first bytes, after any ID3v2 tag.
+///
+/// # Example
+/// ```
+/// use openmusicgang_audio::probe::detect_format;
+/// use openmusicgang_entity::audio::AudioFormat;
+/// assert_eq!(detect_format(b"fLaC\0\0\0\x22"), Some(AudioFormat::Flac));
+/// assert_eq!(detect_format(b"%PDF-1.7"), None);
+/// ```
+pub fn detect_format(head: &[u8]) -> Option<AudioFormat> {
+    match head {
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => Some(AudioFormat::Wav),
+        [b'f', b'L', b'a', b'C', ..] => Some(AudioFormat::Flac),
+        [b'O', b'g', b'g', b'S', ..] => Some(AudioFormat::Ogg),
+        // MPEG audio frame sync, with a valid version and layer.
+        [0xFF, b, ..] if b & 0xE0 == 0xE0 && b & 0x18 != 0x08 && b & 0x06 != 0 => {
+            Some(AudioFormat::Mp3)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the total size of the ID3v2 tag at the beginning of the buffer, if any.
+fn id3_tag_size(head: &[u8]) -> Option<usize> {
+    match head {
+        [b'I', b'D', b'3', _, _, flags, s0, s1, s2, s3, ..] => {
+            // the size is a 28 bits "syncsafe" integer, the footer is not counted in it.
+            let size = ((*s0 as usize & 0x7F) << 21)
+                | ((*s1 as usize & 0x7F) << 14)
+                | ((*s2 as usize & 0x7F) << 7)
+                | (*s3 as usize & 0x7F);
+            let footer = if flags & 0x10 != 0 { 10 } else { 0 };
+
+            Some(ID3_HEADER_SIZE + size + footer)
+        }
+        _ => None,
+    }
+}
+
+/// Reads up to the given number of bytes, less only at the end of the reader.
+fn read_up_to(reader: &mut dyn Read, size: usize) -> Result<Vec<u8>, Error> {
+    let mut buf = vec![];
+
+    reader
+        .take(size as u64)
+        .read_to_end(&mut buf)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(buf)
+}
+
+/// Adds the text tags of a metadata revision, by their standard name when they have one.
+fn collect_tags(revision: &MetadataRevision, tags: &mut BTreeMap<String, String>) {
+    for tag in revision.tags() {
+        let value = match &tag.value {
+            Value::Binary(_) | Value::Flag => continue,
+            value => value.to_string(),
+        };
+
+        let name = match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => "title".to_string(),
+            Some(StandardTagKey::Artist) => "artist".to_string(),
+            Some(StandardTagKey::Album) => "album".to_string(),
+            Some(StandardTagKey::AlbumArtist) => "album_artist".to_string(),
+            Some(StandardTagKey::Composer) => "composer".to_string(),
+            Some(StandardTagKey::Genre) => "genre".to_string(),
+            Some(StandardTagKey::Date) => "date".to_string(),
+            Some(StandardTagKey::TrackNumber) => "track".to_string(),
+            Some(StandardTagKey::Bpm) => "bpm".to_string(),
+            Some(StandardTagKey::Comment) => "comment".to_string(),
+            Some(StandardTagKey::Encoder) => "encoder".to_string(),
+            _ => tag.key.to_lowercase(),
+        };
+
+        tags.insert(name, value);
+    }
+}
+
+/// Returns the error of a file which could not be parsed.
+pub(crate) fn corrupt_error(error: SymphoniaError) -> Error {
+    match error {
+        SymphoniaError::IoError(error) if error.kind() != ErrorKind::UnexpectedEof => {
+            Error::new(ErrorCode::EINTERNAL, error.to_string())
+        }
+        error => Error::new(
+            ErrorCode::EINVALID,
+            format!("Corrupt audio file: {}", error),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::test_utils::wav_file;
+
+    use super::*;
+
+    /// Returns the comments encoded as a Vorbis comment block, without framing.
+    fn vorbis_comments(comments: &[&str]) -> Vec<u8> {
+        let mut buf = vec![];
+        buf.extend(4u32.to_le_bytes());
+        buf.extend(b"test");
+        buf.extend((comments.len() as u32).to_le_bytes());
+
+        for comment in comments {
+            buf.extend((comment.len() as u32).to_le_bytes());
+            buf.extend(comment.as_bytes());
+        }
+
+        buf
+    }
+
+    /// Returns the metadata blocks of a 44.1 kHz, 16 bits, mono FLAC file
+    /// followed by the header of its first audio frame.
+    fn flac_file(frames: u64) -> Vec<u8> {
+        let mut buf = vec![];
+        buf.extend(b"fLaC");
+
+        // STREAMINFO
+        buf.extend([0x00, 0x00, 0x00, 34]);
+        buf.extend(4096u16.to_be_bytes());
+        buf.extend(4096u16.to_be_bytes());
+        buf.extend([0u8; 6]);
+        let packed: u64 = (44100 << 44) | (15 << 36) | frames;
+        buf.extend(packed.to_be_bytes());
+        buf.extend([0u8; 16]);
+
+        // VORBIS_COMMENT, last metadata block.
+        let comments = vorbis_comments(&["TITLE=Segfault Blues", "BPM=120"]);
+        buf.push(0x80 | 4);
+        buf.extend(&(comments.len() as u32).to_be_bytes()[1..]);
+        buf.extend(comments);
+
+        // frame header: 4096 samples, 44.1 kHz, mono, 16 bits, frame 0, then its CRC-8.
+        let header = [0xFF, 0xF8, 0xC9, 0x08, 0x00];
+        let mut crc: u8 = 0;
+        for byte in header {
+            crc ^= byte;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 {
+                    (crc << 1) ^ 0x07
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        buf.extend(header);
+        buf.push(crc);
+        buf.extend([0u8; 16]);
+        buf
+    }
+
+    /// Returns an ID3v2.3 tag with the given text frames.
+    fn id3_tag(frames: &[(&str, &str)]) -> Vec<u8> {
+        let mut body = vec![];
+
+        for (id, text) in frames {
+            body.extend(id.as_bytes());
+            body.extend(((text.len() + 1) as u32).to_be_bytes());
+            body.extend([0, 0, 0]);
+            body.extend(text.as_bytes());
+        }
+
+        let size = body.len() as u32;
+        let mut buf = vec![b'I', b'D', b'3', 3, 0, 0];
+        buf.extend([
+            ((size >> 21) & 0x7F) as u8,
+            ((size >> 14) & 0x7F) as u8,
+            ((size >> 7) & 0x7F) as u8,
+            (size & 0x7F) as u8,
+        ]);
+        buf.extend(body);
+        buf
+    }
+
+    /// Returns MPEG-1 Layer III frames of 128 kbps at 44100 Hz, mono.
+    fn mp3_frames(count: usize) -> Vec<u8> {
+        let mut buf = vec![];
+
+        for _ in 0..count {
+            buf.extend([0xFF, 0xFB, 0x90, 0xC0]);
+            buf.extend(vec![0u8; 417 - 4]);
+        }
+
+        buf
+    }
+
+    /// Returns an Ogg page holding the given packets.
+    fn ogg_page(header_type: u8, granule: u64, sequence: u32, packets: &[&[u8]]) -> Vec<u8> {
+        let mut lacing = vec![];
+        for packet in packets {
+            lacing.extend(vec![255u8; packet.len() / 255]);
+            lacing.push((packet.len() % 255) as u8);
+        }
+
+        let mut page = vec![];
+        page.extend(b"OggS");
+        page.push(0);
+        page.push(header_type);
+        page.extend(granule.to_le_bytes());
+        page.extend(1u32.to_le_bytes());
+        page.extend(sequence.to_le_bytes());
+        page.extend([0u8; 4]);
+        page.push(lacing.len() as u8);
+        page.extend(lacing);
+        for packet in packets {
+            page.extend(*packet);
+        }
+
+        // CRC-32 with the polynomial 0x04C11DB7, computed with the checksum field set to zero.
+        let mut crc: u32 = 0;
+        for byte in &page {
+            crc ^= (*byte as u32) << 24;
+            for _ in 0..8 {
+                crc = if crc & 0x8000_0000 != 0 {
+                    (crc << 1) ^ 0x04C1_1DB7
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        page[22..26].copy_from_slice(&crc.to_le_bytes());
+        page
+    }
+
+    /// Returns an Ogg Opus file with the given number of 20 ms packets of stereo audio.
+    fn ogg_opus_file(packets: usize) -> Vec<u8> {
+        let mut head = b"OpusHead".to_vec();
+        head.push(1);
+        head.push(2);
+        head.extend(0u16.to_le_bytes());
+        head.extend(48000u32.to_le_bytes());
+        head.extend(0i16.to_le_bytes());
+        head.push(0);
+
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend(vorbis_comments(&["ARTIST=The Rolling Bytes"]));
+
+        // TOC of a single 20 ms CELT fullband frame.
+        let packet = [0xFCu8, 0, 0];
+        let audio: Vec<&[u8]> = (0..packets).map(|_| &packet[..]).collect();
+
+        let mut buf = ogg_page(0x02, 0, 0, &[&head]);
+        buf.extend(ogg_page(0x00, 0, 1, &[&tags]));
+        buf.extend(ogg_page(0x04, packets as u64 * 960, 2, &audio));
+        buf
+    }
+
+    fn must_probe(content: Vec<u8>) -> AudioInfo {
+        match probe_audio(Box::new(Cursor::new(content))) {
+            Ok(info) => info,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    #[test]
+    fn probe_wav() {
+        let info = must_probe(wav_file(48000, 2, 24, 96000));
+
+        assert_eq!(info.format, AudioFormat::Wav);
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.bit_depth, Some(24));
+        assert_eq!(info.frames, 96000);
+        assert_eq!(info.duration(), 2.0);
+    }
+
+    #[test]
+    fn probe_flac() {
+        let info = must_probe(flac_file(441000));
+
+        assert_eq!(info.format, AudioFormat::Flac);
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.channels, 1);
+        assert_eq!(info.bit_depth, Some(16));
+        assert_eq!(info.duration(), 10.0);
+        assert_eq!(info.tags.get("title").unwrap(), "Segfault Blues");
+        assert_eq!(info.tags.get("bpm").unwrap(), "120");
+    }
+
+    #[test]
+    fn probe_mp3() {
+        let mut content = id3_tag(&[("TIT2", "Segfault Blues"), ("TPE1", "The Rolling Bytes")]);
+        content.extend(mp3_frames(10));
+
+        let info = must_probe(content);
+
+        assert_eq!(info.format, AudioFormat::Mp3);
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.channels, 1);
+        assert_eq!(info.bit_depth, None);
+        assert!(info.frames > 0 && info.frames <= 10 * 1152);
+        assert_eq!(info.tags.get("title").unwrap(), "Segfault Blues");
+        assert_eq!(info.tags.get("artist").unwrap(), "The Rolling Bytes");
+    }
+
+    #[test]
+    fn probe_ogg() {
+        let info = must_probe(ogg_opus_file(50));
+
+        assert_eq!(info.format, AudioFormat::Ogg);
+        assert_eq!(info.sample_rate, 48000);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.bit_depth, None);
+        assert_eq!(info.duration(), 1.0);
+        assert_eq!(info.tags.get("artist").unwrap(), "The Rolling Bytes");
+    }
+
+    #[test]
+    fn probe_invalid_files() {
+        let res = probe_audio(Box::new(Cursor::new(b"%PDF-1.7 not audio".to_vec())));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let res = probe_audio(Box::new(Cursor::new(vec![])));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let mut truncated = wav_file(44100, 2, 16, 100);
+        truncated.truncate(30);
+        let res = probe_audio(Box::new(Cursor::new(truncated)));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let res = probe_audio(Box::new(Cursor::new(wav_file(44100, 2, 16, 0))));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+    }
+}
@@ -0,0 +1,342 @@
+use std::io::Read;
+
+use openmusicgang_entity::loudness::Loudness;
+use openmusicgang_entity::waveform::{Waveform, WaveformLevel};
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::decode::Decoder;
+use crate::loudness::LoudnessMeter;
+
+/// Number of frames summarized by each peak of the finest level of a waveform.
+pub const WAVEFORM_BASE_ZOOM: i32 = 256;
+
+/// Number of levels of a waveform, each one 4 times coarser than the previous.
+pub const WAVEFORM_LEVELS: usize = 4;
+
+/// Magic bytes of the binary encoding of a waveform.
+const WAVEFORM_MAGIC: &[u8; 4] = b"OMGW";
+
+/// Version of the binary encoding of a waveform.
+const WAVEFORM_VERSION: u8 = 1;
+
+/// Returns the waveform of the audio read from the reader.
+///
+/// The channels are merged, each peak is the minimum and maximum sample of any channel.
+///
+/// Returns EINVALID if the audio cannot be decoded.
+pub fn generate_waveform(reader: Box<dyn Read + Send + Sync>) -> Result<Waveform, Error> {
+    let mut decoder = Decoder::new(reader)?;
+    let mut builder = WaveformBuilder::new(decoder.sample_rate(), decoder.channels());
+
+    while let Some(samples) = decoder.next_samples()? {
+        builder.add_samples(samples);
+    }
+
+    Ok(builder.waveform())
+}
+
+/// Returns the waveform and the loudness of the audio read from the reader, decoding it once.
+///
+/// The loudness is None if the audio is silent or shorter than 400 ms.
+///
+/// Returns EINVALID if the audio cannot be decoded.
+pub fn generate_waveform_and_loudness(
+    reader: Box<dyn Read + Send + Sync>,
+) -> Result<(Waveform, Option<Loudness>), Error> {
+    let mut decoder = Decoder::new(reader)?;
+    let mut builder = WaveformBuilder::new(decoder.sample_rate(), decoder.channels());
+    let mut meter = LoudnessMeter::new(decoder.sample_rate(), decoder.channels());
+
+    while let Some(samples) = decoder.next_samples()? {
+        builder.add_samples(samples);
+        meter.add_samples(samples);
+    }
+
+    Ok((builder.waveform(), meter.loudness()))
+}
+
+/// WaveformBuilder computes the peaks of interleaved samples added block by block.
+struct WaveformBuilder {
+    sample_rate: u32,
+    channels: usize,
+    peaks: Vec<(i8, i8)>,
+    frames: i64,
+    /// Minimum and maximum sample and number of frames of the current peak.
+    block: (f32, f32),
+    block_frames: i32,
+}
+
+impl WaveformBuilder {
+    fn new(sample_rate: u32, channels: usize) -> WaveformBuilder {
+        WaveformBuilder {
+            sample_rate,
+            channels,
+            peaks: vec![],
+            frames: 0,
+            block: (f32::MAX, f32::MIN),
+            block_frames: 0,
+        }
+    }
+
+    fn add_samples(&mut self, samples: &[f32]) {
+        for frame in samples.chunks(self.channels) {
+            for sample in frame {
+                self.block = (self.block.0.min(*sample), self.block.1.max(*sample));
+            }
+
+            self.frames += 1;
+            self.block_frames += 1;
+
+            if self.block_frames == WAVEFORM_BASE_ZOOM {
+                self.peaks
+                    .push((scale_sample(self.block.0), scale_sample(self.block.1)));
+                self.block = (f32::MAX, f32::MIN);
+                self.block_frames = 0;
+            }
+        }
+    }
+
+    /// Returns the waveform of the samples added, with its coarser levels.
+    fn waveform(mut self) -> Waveform {
+        if self.block_frames > 0 {
+            self.peaks
+                .push((scale_sample(self.block.0), scale_sample(self.block.1)));
+        }
+
+        let mut levels = vec![WaveformLevel {
+            samples_per_peak: WAVEFORM_BASE_ZOOM,
+            peaks: self.peaks,
+        }];
+
+        while levels.len() < WAVEFORM_LEVELS {
+            let finer = &levels[levels.len() - 1];
+
+            let peaks = finer
+                .peaks
+                .chunks(4)
+                .map(|chunk| {
+                    chunk.iter().fold((i8::MAX, i8::MIN), |acc, peak| {
+                        (acc.0.min(peak.0), acc.1.max(peak.1))
+                    })
+                })
+                .collect();
+
+            levels.push(WaveformLevel {
+                samples_per_peak: finer.samples_per_peak * 4,
+                peaks,
+            });
+        }
+
+        Waveform {
+            sample_rate: self.sample_rate as i32,
+            frames: self.frames,
+            levels,
+        }
+    }
+}
+
+/// Returns the compact binary encoding of a waveform.
+///
+/// The encoding is the magic bytes `OMGW`, the version on 1 byte, 3 reserved bytes, the sample
+/// rate as a u32, the number of frames as a u64 and the number of levels as a u32. Each level is
+/// then its number of samples per peak as a u32, its number of peaks as a u32 and the peaks as
+/// pairs of i8 minimum and maximum. Integers are little endian.
+pub fn encode_waveform(waveform: &Waveform) -> Vec<u8> {
+    let mut buf = vec![];
+
+    buf.extend(WAVEFORM_MAGIC);
+    buf.extend([WAVEFORM_VERSION, 0, 0, 0]);
+    buf.extend((waveform.sample_rate as u32).to_le_bytes());
+    buf.extend((waveform.frames as u64).to_le_bytes());
+    buf.extend((waveform.levels.len() as u32).to_le_bytes());
+
+    for level in &waveform.levels {
+        buf.extend((level.samples_per_peak as u32).to_le_bytes());
+        buf.extend((level.peaks.len() as u32).to_le_bytes());
+
+        for (min, max) in &level.peaks {
+            buf.extend([*min as u8, *max as u8]);
+        }
+    }
+
+    buf
+}
+
+/// Returns the waveform of its binary encoding, see encode_waveform.
+///
+/// Returns EINVALID if the encoding is not a valid waveform.
+pub fn decode_waveform(buf: &[u8]) -> Result<Waveform, Error> {
+    let mut cursor = buf;
+
+    if take(&mut cursor, 4)? != WAVEFORM_MAGIC || take(&mut cursor, 4)?[0] != WAVEFORM_VERSION {
+        return Err(corrupt_waveform());
+    }
+
+    let sample_rate = take_u32(&mut cursor)? as i32;
+    let frames = u64::from_le_bytes(take(&mut cursor, 8)?.try_into().unwrap()) as i64;
+    let level_count = take_u32(&mut cursor)?;
+
+    let mut levels = vec![];
+
+    for _ in 0..level_count {
+        let samples_per_peak = take_u32(&mut cursor)? as i32;
+        let peak_count = take_u32(&mut cursor)? as usize;
+
+        let peaks = take(&mut cursor, peak_count * 2)?
+            .chunks(2)
+            .map(|peak| (peak[0] as i8, peak[1] as i8))
+            .collect();
+
+        levels.push(WaveformLevel {
+            samples_per_peak,
+            peaks,
+        });
+    }
+
+    if !cursor.is_empty() {
+        return Err(corrupt_waveform());
+    }
+
+    Ok(Waveform {
+        sample_rate,
+        frames,
+        levels,
+    })
+}
+
+/// Returns the JSON representation of a waveform.
+///
+/// The peaks of each level are flattened as `[min, max, min, max, ...]`.
+pub fn waveform_json(waveform: &Waveform) -> String {
+    let levels: Vec<serde_json::Value> = waveform
+        .levels
+        .iter()
+        .map(|level| {
+            let peaks: Vec<i8> = level
+                .peaks
+                .iter()
+                .flat_map(|(min, max)| [*min, *max])
+                .collect();
+
+            serde_json::json!({
+                "samples_per_peak": level.samples_per_peak,
+                "peaks": peaks,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "sample_rate": waveform.sample_rate,
+        "frames": waveform.frames,
+        "levels": levels,
+    })
+    .to_string()
+}
+
+/// Returns a sample between -1 and 1 scaled between -127 and 127.
+fn scale_sample(sample: f32) -> i8 {
+    (sample * 127.0).round().clamp(-127.0, 127.0) as i8
+}
+
+/// Returns the next n bytes of the cursor and advances it.
+fn take<'a>(cursor: &mut &'a [u8], n: usize) -> Result<&'a [u8], Error> {
+    if cursor.len() < n {
+        return Err(corrupt_waveform());
+    }
+
+    let (head, tail) = cursor.split_at(n);
+    *cursor = tail;
+
+    Ok(head)
+}
+
+fn take_u32(cursor: &mut &[u8]) -> Result<u32, Error> {
+    Ok(u32::from_le_bytes(take(cursor, 4)?.try_into().unwrap()))
+}
+
+fn corrupt_waveform() -> Error {
+    Error::new(ErrorCode::EINVALID, "Corrupt waveform".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::Cursor;
+
+    use crate::loudness::measure_loudness;
+    use crate::test_utils::pcm16_wav_file;
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) generate the waveform of a stereo file louder on its second half.
+    /// 2) check the peaks of the finest and coarsest levels.
+    /// 3) encode and decode the waveform, it should be unchanged.
+    /// 4) render the waveform as JSON.
+    /// 5) decode a truncated waveform, error should be EINVALID.
+    /// 6) generate the waveform and the loudness in one pass, they should match the separate measures.
+    #[test]
+    fn test_waveform() {
+        // 1) generate the waveform of a stereo file louder on its second half.
+        let mut samples = vec![];
+        for frame in 0..2000 {
+            let (left, right) = if frame < 1024 {
+                (8192, -8192)
+            } else {
+                (32767, -32768)
+            };
+            samples.extend([left, right]);
+        }
+        let content = pcm16_wav_file(8000, 2, &samples);
+
+        let waveform = generate_waveform(Box::new(Cursor::new(content))).unwrap();
+
+        assert_eq!(waveform.sample_rate, 8000);
+        assert_eq!(waveform.frames, 2000);
+        assert_eq!(waveform.levels.len(), WAVEFORM_LEVELS);
+
+        // 2) check the peaks of the finest and coarsest levels.
+        let finest = &waveform.levels[0];
+        assert_eq!(finest.samples_per_peak, 256);
+        assert_eq!(finest.peaks.len(), 8);
+        assert_eq!(finest.peaks[0], (-32, 32));
+        assert_eq!(finest.peaks[7], (-127, 127));
+
+        let coarsest = &waveform.levels[3];
+        assert_eq!(coarsest.samples_per_peak, 16384);
+        assert_eq!(coarsest.peaks, vec![(-127, 127)]);
+        assert_eq!(waveform.levels[1].peaks, vec![(-32, 32), (-127, 127)]);
+
+        // 3) encode and decode the waveform, it should be unchanged.
+        let encoded = encode_waveform(&waveform);
+        assert_eq!(&encoded[0..4], b"OMGW");
+        assert_eq!(decode_waveform(&encoded).unwrap(), waveform);
+
+        // 4) render the waveform as JSON.
+        let json: serde_json::Value = serde_json::from_str(&waveform_json(&waveform)).unwrap();
+        assert_eq!(json["frames"], 2000);
+        assert_eq!(json["levels"][3]["samples_per_peak"], 16384);
+        assert_eq!(json["levels"][3]["peaks"], serde_json::json!([-127, 127]));
+
+        // 5) decode a truncated waveform, error should be EINVALID.
+        let res = decode_waveform(&encoded[..encoded.len() - 1]);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 6) generate the waveform and the loudness in one pass, they should match the separate measures.
+        let content = pcm16_wav_file(8000, 2, &samples.repeat(4));
+
+        let (combined, loudness) =
+            generate_waveform_and_loudness(Box::new(Cursor::new(content.clone()))).unwrap();
+        assert_eq!(
+            combined,
+            generate_waveform(Box::new(Cursor::new(content.clone()))).unwrap()
+        );
+        assert!(loudness.is_some());
+        assert_eq!(
+            loudness,
+            measure_loudness(Box::new(Cursor::new(content))).unwrap()
+        );
+    }
+}
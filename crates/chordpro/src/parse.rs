@@ -0,0 +1,257 @@
+use openmusicgang_entity::lead_sheet::{ChordPosition, Line, Section, SectionKind};
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_theory::chord::Chord;
+
+/// Directive is a line of a ChordPro text between braces, e.g. "{start_of_chorus: Chorus 2}".
+struct Directive<'a> {
+    name: String,
+    value: &'a str,
+}
+
+/// Returns the directive of a line, None if the line is not a directive.
+///
+/// The name of a directive is separated from its value by a colon or a space.
+fn parse_directive(line: &str) -> Option<Directive<'_>> {
+    let inner = line.strip_prefix('{')?.strip_suffix('}')?.trim();
+
+    let (name, value) = match inner.find([':', ' ']) {
+        Some(index) => (&inner[..index], inner[index + 1..].trim()),
+        None => (inner, ""),
+    };
+
+    Some(Directive {
+        name: name.to_lowercase(),
+        value,
+    })
+}
+
+/// Returns the kind of the section started by a directive, with the directive ending it.
+fn start_of_section(name: &str) -> Option<(SectionKind, [&'static str; 2])> {
+    match name {
+        "start_of_verse" | "sov" => Some((SectionKind::Verse, ["end_of_verse", "eov"])),
+        "start_of_chorus" | "soc" => Some((SectionKind::Chorus, ["end_of_chorus", "eoc"])),
+        "start_of_bridge" | "sob" => Some((SectionKind::Bridge, ["end_of_bridge", "eob"])),
+        _ => None,
+    }
+}
+
+/// Returns true if a directive ends a section.
+fn is_end_of_section(name: &str) -> bool {
+    matches!(
+        name,
+        "end_of_verse" | "eov" | "end_of_chorus" | "eoc" | "end_of_bridge" | "eob"
+    )
+}
+
+/// Returns the lyrics line of a line of ChordPro text, with the chords between square brackets
+/// taken out of the lyrics, e.g. "[Am]Hello [G/B]world".
+///
+/// Returns EINVALID if a chord is not closed or is not a known chord symbol.
+pub fn parse_line(text: &str, number: usize) -> Result<Line, Error> {
+    let mut line = Line::default();
+    let mut length = 0;
+    let mut rest = text;
+
+    while let Some(start) = rest.find('[') {
+        line.lyrics.push_str(&rest[..start]);
+        length += rest[..start].chars().count();
+
+        let end = rest[start..].find(']').ok_or_else(|| {
+            Error::new(
+                ErrorCode::EINVALID,
+                format!("Unclosed chord at line {}", number),
+            )
+        })? + start;
+
+        let symbol = rest[start + 1..end].trim();
+        let chord = symbol.parse::<Chord>().map_err(|_| {
+            Error::new(
+                ErrorCode::EINVALID,
+                format!("Invalid chord symbol {} at line {}", symbol, number),
+            )
+        })?;
+
+        line.chords.push(ChordPosition {
+            position: length,
+            chord,
+        });
+
+        rest = &rest[end + 1..];
+    }
+
+    line.lyrics.push_str(rest);
+
+    Ok(line)
+}
+
+/// Returns the sections of a ChordPro text.
+///
+/// Verses, choruses and bridges are delimited by their start and end directives, whose value is the label
+/// of the section. Lines outside of them are verses without a label, separated by blank lines.
+/// Comments and other directives, such as the title or the key, are skipped as they belong to the song.
+///
+/// Returns EINVALID if a section is not closed, sections are nested or a line has an invalid chord.
+///
+/// # Example
+/// ```
+/// use openmusicgang_chordpro::parse::parse_chordpro;
+/// use openmusicgang_entity::lead_sheet::SectionKind;
+/// let sections = parse_chordpro("{title: Segfault}\n{soc: Chorus}\n[C]Core [G/B]dumped\n{eoc}").unwrap();
+/// assert_eq!(sections[0].kind, SectionKind::Chorus);
+/// assert_eq!(sections[0].lines[0].lyrics, "Core dumped");
+/// assert_eq!(sections[0].lines[0].chords[1].position, 5);
+/// ```
+pub fn parse_chordpro(text: &str) -> Result<Vec<Section>, Error> {
+    let mut sections: Vec<Section> = vec![];
+    // Section being read with the directives ending it, None for a verse without directives.
+    let mut current: Option<(Section, [&str; 2])> = None;
+    let mut verse: Option<Section> = None;
+
+    for (index, raw) in text.lines().enumerate() {
+        let number = index + 1;
+        let trimmed = raw.trim();
+
+        if trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some(directive) = parse_directive(trimmed) {
+            if let Some((kind, end)) = start_of_section(&directive.name) {
+                if current.is_some() {
+                    return Err(Error::new(
+                        ErrorCode::EINVALID,
+                        format!("Unexpected {} at line {}", directive.name, number),
+                    ));
+                }
+
+                sections.extend(verse.take());
+                current = Some((
+                    Section {
+                        kind,
+                        label: directive.value.to_string(),
+                        lines: vec![],
+                    },
+                    end,
+                ));
+            } else if is_end_of_section(&directive.name) {
+                match current.take() {
+                    Some((section, end)) if end.contains(&directive.name.as_str()) => {
+                        sections.push(section)
+                    }
+                    _ => {
+                        return Err(Error::new(
+                            ErrorCode::EINVALID,
+                            format!("Unexpected {} at line {}", directive.name, number),
+                        ))
+                    }
+                }
+            }
+
+            continue;
+        }
+
+        match (&mut current, trimmed.is_empty()) {
+            (Some(_), true) => {}
+            (Some((section, _)), false) => section.lines.push(parse_line(raw.trim_end(), number)?),
+            (None, true) => sections.extend(verse.take()),
+            (None, false) => verse
+                .get_or_insert_with(|| Section {
+                    kind: SectionKind::Verse,
+                    label: "".to_string(),
+                    lines: vec![],
+                })
+                .lines
+                .push(parse_line(raw.trim_end(), number)?),
+        }
+    }
+
+    if let Some((section, _)) = current {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            format!("Unterminated {} section", section.kind),
+        ));
+    }
+
+    sections.extend(verse);
+
+    Ok(sections)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) parse a line, chords should be taken out of the lyrics at their position.
+    /// 2) parse a text, sections should be read from directives and blank lines.
+    /// 3) parse invalid texts, error should be EINVALID.
+    #[test]
+    fn test_parse_chordpro() {
+        // 1) parse a line, chords should be taken out of the lyrics at their position.
+        let line = parse_line("[Am]Übel [F#m7b5]ist [G/B]", 1).unwrap();
+        assert_eq!(line.lyrics, "Übel ist ");
+        assert_eq!(
+            line.chords
+                .iter()
+                .map(|chord| (chord.position, chord.chord.to_string()))
+                .collect::<Vec<_>>(),
+            [
+                (0, "Am".to_string()),
+                (5, "F#m7b5".to_string()),
+                (9, "G/B".to_string())
+            ]
+        );
+
+        // 2) parse a text, sections should be read from directives and blank lines.
+        let text = "{title: Segfault Boogie}
+# intro riff
+[E]Pointers [A]everywhere
+[E]Nothing [B7]there
+
+{start_of_chorus: Chorus}
+[A]Segfault, [E]segfault
+
+[B7]Core dumped [E]again
+{end_of_chorus}
+{c: Solo}
+{sob}
+[C#m]Valgrind [A]knows
+{eob}
+Last verse without chords
+";
+
+        let sections = parse_chordpro(text).unwrap();
+        assert_eq!(sections.len(), 4);
+
+        assert_eq!(sections[0].kind, SectionKind::Verse);
+        assert_eq!(sections[0].label, "");
+        assert_eq!(sections[0].lines.len(), 2);
+        assert_eq!(sections[0].lines[1].lyrics, "Nothing there");
+
+        assert_eq!(sections[1].kind, SectionKind::Chorus);
+        assert_eq!(sections[1].label, "Chorus");
+        assert_eq!(sections[1].lines.len(), 2);
+
+        assert_eq!(sections[2].kind, SectionKind::Bridge);
+        assert_eq!(sections[2].lines[0].chords[0].chord.to_string(), "C#m");
+
+        assert_eq!(sections[3].lines[0].lyrics, "Last verse without chords");
+        assert!(sections[3].lines[0].chords.is_empty());
+
+        // 3) parse invalid texts, error should be EINVALID.
+        for text in [
+            "[Am Hello",
+            "[H]Hello",
+            "{soc}\nHello",
+            "{soc}\n{sov}\nHello\n{eov}\n{eoc}",
+            "{sov}\nHello\n{eoc}",
+            "Hello\n{eov}",
+        ] {
+            let res = parse_chordpro(text);
+            assert!(res.is_err(), "{}", text);
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+    }
+}
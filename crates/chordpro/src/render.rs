@@ -0,0 +1,231 @@
+use std::fmt::Write;
+
+use openmusicgang_entity::lead_sheet::{LeadSheet, Line, Section};
+use openmusicgang_entity::song::Song;
+use openmusicgang_theory::chord::Chord;
+use openmusicgang_theory::key::Key;
+use openmusicgang_theory::pitch::{Pitch, Spelling};
+
+/// Returns the key a number of semitones away, spelled with the fewest sharps or flats.
+///
+/// Enharmonic keys with as many accidentals, such as "F#" and "Gb", keep the accidentals of the key.
+pub fn transpose_key(key: &Key, semitones: i32) -> Key {
+    let spellings = if key.sharps() < 0 {
+        [Spelling::Flats, Spelling::Sharps]
+    } else {
+        [Spelling::Sharps, Spelling::Flats]
+    };
+
+    spellings
+        .into_iter()
+        .map(|spelling| {
+            let tonic = Pitch::from_semitone(key.tonic.semitone() + semitones, spelling);
+            Key::new(tonic, key.mode).simplify()
+        })
+        .min_by_key(|key| key.sharps().abs())
+        .unwrap_or(*key)
+}
+
+/// Returns the chord a number of semitones away, spelled like in the given key if any,
+/// otherwise with the accidentals of its root.
+pub fn transpose_chord(chord: &Chord, semitones: i32, key: Option<&Key>) -> Chord {
+    let spelling = if chord.root.accidental < 0 {
+        Spelling::Flats
+    } else {
+        Spelling::Sharps
+    };
+
+    let transpose = |pitch: Pitch| match key {
+        Some(key) => key.spell(pitch.semitone() + semitones),
+        None => Pitch::from_semitone(pitch.semitone() + semitones, spelling),
+    };
+
+    Chord {
+        root: transpose(chord.root),
+        kind: chord.kind,
+        bass: chord.bass.map(transpose),
+    }
+}
+
+/// Returns the ChordPro text of a lyrics line, with each chord between square brackets before its position.
+fn render_line(line: &Line, transpose: &dyn Fn(&Chord) -> Chord) -> String {
+    let mut text = String::new();
+    let mut chords = line.chords.iter().peekable();
+
+    for (index, c) in line.lyrics.chars().enumerate() {
+        while let Some(position) = chords.next_if(|position| position.position <= index) {
+            let _ = write!(text, "[{}]", transpose(&position.chord));
+        }
+
+        text.push(c);
+    }
+
+    for position in chords {
+        let _ = write!(text, "[{}]", transpose(&position.chord));
+    }
+
+    text
+}
+
+/// Returns the ChordPro text of a section, between its start and end directives.
+fn render_section(section: &Section, transpose: &dyn Fn(&Chord) -> Chord) -> String {
+    let mut text = match section.label.is_empty() {
+        true => format!("{{start_of_{}}}\n", section.kind),
+        false => format!("{{start_of_{}: {}}}\n", section.kind, section.label),
+    };
+
+    for line in &section.lines {
+        text.push_str(&render_line(line, transpose));
+        text.push('\n');
+    }
+
+    let _ = writeln!(text, "{{end_of_{}}}", section.kind);
+
+    text
+}
+
+/// Returns the ChordPro text of the lead sheet of a song, with its chords transposed by a number of semitones.
+///
+/// The text starts with the title, key, tempo and time signature of the song, the key being transposed
+/// with the chords. Transposed chords are spelled like in the transposed key, chords are kept as written
+/// when the sheet is not transposed.
+///
+/// # Example
+/// ```
+/// use openmusicgang_chordpro::parse::parse_chordpro;
+/// use openmusicgang_chordpro::render::render_chordpro;
+/// use openmusicgang_entity::lead_sheet::LeadSheet;
+/// use openmusicgang_entity::song::Song;
+/// let song = Song { title: "Segfault".to_string(), key: Some("F".to_string()), ..Default::default() };
+/// let sheet = LeadSheet { sections: parse_chordpro("[F]Core [C/E]dumped").unwrap(), ..Default::default() };
+/// let text = render_chordpro(&song, &sheet, 1);
+/// assert!(text.contains("{key: Gb}"));
+/// assert!(text.contains("[Gb]Core [Db/F]dumped"));
+/// ```
+pub fn render_chordpro(song: &Song, sheet: &LeadSheet, semitones: i32) -> String {
+    let mut text = format!("{{title: {}}}\n", song.title);
+
+    let key = song
+        .key
+        .as_ref()
+        .and_then(|key| key.parse::<Key>().ok())
+        .map(|key| transpose_key(&key, semitones));
+
+    if let Some(key) = &key {
+        let _ = writeln!(text, "{{key: {}}}", key);
+    }
+
+    if let Some(tempo) = song.tempo {
+        let _ = writeln!(text, "{{tempo: {}}}", tempo);
+    }
+
+    let _ = writeln!(text, "{{time: {}}}", song.time_signature);
+
+    let transpose = |chord: &Chord| match semitones {
+        0 => *chord,
+        _ => transpose_chord(chord, semitones, key.as_ref()),
+    };
+
+    for section in &sheet.sections {
+        text.push('\n');
+        text.push_str(&render_section(section, &transpose));
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_entity::lead_sheet::SectionKind;
+
+    use crate::parse::parse_chordpro;
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) transpose keys, they should be spelled with the fewest accidentals.
+    /// 2) transpose chords, with and without a key.
+    /// 3) render a lead sheet as written, it should be parsed back the same.
+    /// 4) render a transposed lead sheet, chords should be spelled in the new key.
+    #[test]
+    fn test_render_chordpro() {
+        let key = |s: &str| s.parse::<Key>().unwrap();
+        let chord = |s: &str| s.parse::<Chord>().unwrap();
+
+        // 1) transpose keys, they should be spelled with the fewest accidentals.
+        assert_eq!(transpose_key(&key("C"), 1).to_string(), "Db");
+        assert_eq!(transpose_key(&key("Am"), 1).to_string(), "Bbm");
+        assert_eq!(transpose_key(&key("E"), -1).to_string(), "Eb");
+        assert_eq!(transpose_key(&key("B"), 7).to_string(), "F#");
+        assert_eq!(transpose_key(&key("Db"), 5).to_string(), "Gb");
+        assert_eq!(transpose_key(&key("G"), -12).to_string(), "G");
+
+        // 2) transpose chords, with and without a key.
+        let in_key = |s: &str, semitones: i32, k: &str| {
+            transpose_chord(&chord(s), semitones, Some(&key(k))).to_string()
+        };
+        assert_eq!(in_key("E7", 1, "F"), "F7");
+        assert_eq!(in_key("A/C#", 1, "Bb"), "Bb/D");
+        assert_eq!(in_key("C#m7b5", -2, "Bm"), "Bm7b5");
+        assert_eq!(in_key("G#dim7", 2, "Am"), "A#dim7");
+
+        let no_key =
+            |s: &str, semitones: i32| transpose_chord(&chord(s), semitones, None).to_string();
+        assert_eq!(no_key("Bb", 2), "C");
+        assert_eq!(no_key("Gb", -1), "F");
+        assert_eq!(no_key("C", 1), "C#");
+        assert_eq!(no_key("Bb", 3), "Db");
+
+        // 3) render a lead sheet as written, it should be parsed back the same.
+        let song = Song {
+            title: "Segfault Boogie".to_string(),
+            key: Some("E".to_string()),
+            tempo: Some(132.0),
+            ..Default::default()
+        };
+
+        let sheet = LeadSheet {
+            sections: parse_chordpro(
+                "[E]Pointers [A]everywhere
+[E]Nothing [B7]there
+{soc: Chorus}
+[A]Segfault, [E]segfault[B7]
+{eoc}",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        let text = render_chordpro(&song, &sheet, 0);
+        assert_eq!(
+            text,
+            "{title: Segfault Boogie}
+{key: E}
+{tempo: 132}
+{time: 4/4}
+
+{start_of_verse}
+[E]Pointers [A]everywhere
+[E]Nothing [B7]there
+{end_of_verse}
+
+{start_of_chorus: Chorus}
+[A]Segfault, [E]segfault[B7]
+{end_of_chorus}
+"
+        );
+        assert_eq!(parse_chordpro(&text).unwrap(), sheet.sections);
+
+        // 4) render a transposed lead sheet, chords should be spelled in the new key.
+        let text = render_chordpro(&song, &sheet, -1);
+        assert!(text.contains("{key: Eb}"));
+        assert!(text.contains("[Eb]Pointers [Ab]everywhere"));
+        assert!(text.contains("[Ab]Segfault, [Eb]segfault[Bb7]"));
+
+        let sections = parse_chordpro(&text).unwrap();
+        assert_eq!(sections[1].kind, SectionKind::Chorus);
+        assert_eq!(sections[1].lines[0].lyrics, "Segfault, segfault");
+    }
+}
@@ -3,6 +3,9 @@ use openmusicgang_err::error::{Error, ErrorCode};
 use serde::Deserialize;
 
 use crate::env::Env;
+use crate::jwt::JwtAlgorithm;
+use crate::mail::MailBackend;
+use crate::storage::StorageBackend;
 use openmusicgang_app::traits::DeserializeWith;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -27,11 +30,328 @@ pub struct Redis {
     pub password: String,
 }
 
+/// Password holds the Argon2id cost parameters used to hash passwords.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Password {
+    /// Memory size in KiB.
+    pub memory_cost: u32,
+    /// Number of iterations.
+    pub time_cost: u32,
+    /// Degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl Default for Password {
+    fn default() -> Self {
+        Password {
+            memory_cost: 19456,
+            time_cost: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Session holds the settings of authenticated sessions.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Session {
+    /// Seconds of inactivity after which a session expires.
+    pub ttl: u64,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session { ttl: 1209600 }
+    }
+}
+
+/// Jwt holds the settings of access and refresh tokens.
+///
+/// HS256 signs tokens with `secret`, EdDSA with the PEM encoded Ed25519 `private_key` and `public_key`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Jwt {
+    #[serde(deserialize_with = "JwtAlgorithm::deserialize_with")]
+    pub algorithm: JwtAlgorithm,
+    pub secret: String,
+    pub private_key: String,
+    pub public_key: String,
+    /// Seconds after which an access token expires.
+    pub access_token_ttl: u64,
+    /// Seconds after which a refresh token expires.
+    pub refresh_token_ttl: u64,
+}
+
+impl Default for Jwt {
+    fn default() -> Self {
+        Jwt {
+            algorithm: JwtAlgorithm::HS256,
+            secret: "".to_string(),
+            private_key: "".to_string(),
+            public_key: "".to_string(),
+            access_token_ttl: 900,
+            refresh_token_ttl: 2592000,
+        }
+    }
+}
+
+/// Verification holds the settings of the verification of email addresses.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Verification {
+    /// Seconds after which a verification token expires.
+    pub ttl: u64,
+    /// Seconds a user has to wait before being sent another verification or password reset email.
+    pub resend_interval: u64,
+    /// URL of the page confirming an email address, the token is appended to it.
+    pub url: String,
+}
+
+impl Default for Verification {
+    fn default() -> Self {
+        Verification {
+            ttl: 86400,
+            resend_interval: 60,
+            url: "http://127.0.0.1:8080/verify-email?token=".to_string(),
+        }
+    }
+}
+
+/// PasswordReset holds the settings of the reset of forgotten passwords.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PasswordReset {
+    /// Seconds after which a password reset token expires.
+    pub ttl: u64,
+    /// URL of the page resetting a password, the token is appended to it.
+    pub url: String,
+}
+
+impl Default for PasswordReset {
+    fn default() -> Self {
+        PasswordReset {
+            ttl: 3600,
+            url: "http://127.0.0.1:8080/reset-password?token=".to_string(),
+        }
+    }
+}
+
+/// Http holds the settings of the HTTP server.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Http {
+    /// Address the server listens on.
+    pub addr: String,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Http {
+            addr: "127.0.0.1:8080".to_string(),
+        }
+    }
+}
+
+/// Invitation holds the settings of the invitations to join a gang.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Invitation {
+    /// Seconds after which a pending invitation expires.
+    pub ttl: u64,
+}
+
+impl Default for Invitation {
+    fn default() -> Self {
+        Invitation { ttl: 604800 }
+    }
+}
+
+/// Follow holds the settings of the follow graph.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Follow {
+    /// Seconds during which the follow counts of a user or a gang are cached.
+    pub counts_ttl: u64,
+}
+
+impl Default for Follow {
+    fn default() -> Self {
+        Follow { counts_ttl: 300 }
+    }
+}
+
+/// Feed holds the settings of the activity feeds.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Feed {
+    /// Seconds after which the cached recent activities of a feed are read again.
+    pub cache_ttl: u64,
+}
+
+impl Default for Feed {
+    fn default() -> Self {
+        Feed { cache_ttl: 600 }
+    }
+}
+
+/// Notification holds the settings of the delivery of the notifications.
+///
+/// Deliveries are written to files under `root` until a transport to outside services is configured.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Notification {
+    /// Directory of the file transport.
+    pub root: String,
+    /// Seconds between two dispatches of the pending deliveries.
+    pub dispatch_interval: u64,
+    /// Number of times a delivery is tried before giving up.
+    pub max_attempts: i32,
+    /// Seconds before a failed delivery is tried again the first time, doubled at every attempt.
+    pub retry_delay: u64,
+}
+
+impl Default for Notification {
+    fn default() -> Self {
+        Notification {
+            root: "data/notifications".to_string(),
+            dispatch_interval: 5,
+            max_attempts: 5,
+            retry_delay: 60,
+        }
+    }
+}
+
+/// Mail holds the settings of the sending of emails.
+///
+/// The maildir backend writes emails as files under `maildir`, the SMTP backend sends them to the relay
+/// configured in `smtp`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Mail {
+    #[serde(deserialize_with = "MailBackend::deserialize_with")]
+    pub backend: MailBackend,
+    /// Address the emails are sent from, e.g. "OpenMusicGang <no-reply@openmusicgang.com>".
+    pub from: String,
+    /// Directory of the maildir backend.
+    pub maildir: String,
+    pub smtp: Smtp,
+    /// Seconds between two dispatches of the pending emails.
+    pub dispatch_interval: u64,
+    /// Number of times an email is tried before giving up.
+    pub max_attempts: i32,
+    /// Seconds before a failed email is tried again the first time, doubled at every attempt.
+    pub retry_delay: u64,
+}
+
+impl Default for Mail {
+    fn default() -> Self {
+        Mail {
+            backend: MailBackend::Maildir,
+            from: "OpenMusicGang <no-reply@localhost>".to_string(),
+            maildir: "data/maildir".to_string(),
+            smtp: Smtp::default(),
+            dispatch_interval: 5,
+            max_attempts: 5,
+            retry_delay: 60,
+        }
+    }
+}
+
+/// Smtp holds the settings of an SMTP relay.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Smtp {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// Upgrades the connection with STARTTLS, otherwise it is not encrypted, e.g. for a local SMTP sink.
+    pub starttls: bool,
+}
+
+impl Default for Smtp {
+    fn default() -> Self {
+        Smtp {
+            host: "localhost".to_string(),
+            port: 587,
+            username: "".to_string(),
+            password: "".to_string(),
+            starttls: true,
+        }
+    }
+}
+
+/// Storage holds the settings of the blob storage.
+///
+/// The local backend stores blobs under `root`, the S3 backend in the bucket configured in `s3`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Storage {
+    #[serde(deserialize_with = "StorageBackend::deserialize_with")]
+    pub backend: StorageBackend,
+    /// Directory of the local backend.
+    pub root: String,
+    pub s3: S3,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Storage {
+            backend: StorageBackend::Local,
+            root: "data/blobs".to_string(),
+            s3: S3::default(),
+        }
+    }
+}
+
+/// S3 holds the settings of an S3-compatible object storage, such as AWS S3 or MinIO.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct S3 {
+    /// Base URL of the service, buckets are addressed in the path.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+impl Default for S3 {
+    fn default() -> Self {
+        S3 {
+            endpoint: "".to_string(),
+            region: "us-east-1".to_string(),
+            bucket: "openmusicgang".to_string(),
+            access_key: "".to_string(),
+            secret_key: "".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct AppConfig {
     pub app: App,
     pub postgres: Postgres,
     pub redis: Redis,
+    #[serde(default)]
+    pub password: Password,
+    #[serde(default)]
+    pub session: Session,
+    #[serde(default)]
+    pub jwt: Jwt,
+    #[serde(default)]
+    pub verification: Verification,
+    #[serde(default)]
+    pub password_reset: PasswordReset,
+    #[serde(default)]
+    pub http: Http,
+    #[serde(default)]
+    pub invitation: Invitation,
+    #[serde(default)]
+    pub storage: Storage,
+    #[serde(default)]
+    pub follow: Follow,
+    #[serde(default)]
+    pub feed: Feed,
+    #[serde(default)]
+    pub notification: Notification,
+    #[serde(default)]
+    pub mail: Mail,
 }
 
 impl AppConfig {
@@ -92,4 +412,82 @@ mod tests {
                 || cfg.app.env == Env::Testing
         );
     }
+
+    #[test]
+    fn unknown_jwt_algorithm() {
+        let jwt = |algorithm: &str| {
+            Config::builder()
+                .add_source(File::from_str(
+                    &format!("algorithm = \"{}\"", algorithm),
+                    config::FileFormat::Toml,
+                ))
+                .build()
+                .unwrap()
+                .try_deserialize::<Jwt>()
+        };
+
+        assert_eq!(jwt("EdDSA").unwrap().algorithm, JwtAlgorithm::EdDSA);
+        assert_eq!(jwt("HS256").unwrap().algorithm, JwtAlgorithm::HS256);
+
+        for algorithm in ["EdDSA ", "ed25519", "hs256"] {
+            let res = jwt(algorithm);
+            assert!(res.is_err());
+            assert!(res
+                .unwrap_err()
+                .to_string()
+                .contains("unknown jwt algorithm"));
+        }
+    }
+
+    #[test]
+    fn unknown_storage_backend() {
+        let storage = |backend: &str| {
+            Config::builder()
+                .add_source(File::from_str(
+                    &format!("backend = \"{}\"", backend),
+                    config::FileFormat::Toml,
+                ))
+                .build()
+                .unwrap()
+                .try_deserialize::<Storage>()
+        };
+
+        assert_eq!(storage("local").unwrap().backend, StorageBackend::Local);
+        assert_eq!(storage("s3").unwrap().backend, StorageBackend::S3);
+
+        for backend in ["S3", "gcs", ""] {
+            let res = storage(backend);
+            assert!(res.is_err());
+            assert!(res
+                .unwrap_err()
+                .to_string()
+                .contains("unknown storage backend"));
+        }
+    }
+
+    #[test]
+    fn unknown_mail_backend() {
+        let mail = |backend: &str| {
+            Config::builder()
+                .add_source(File::from_str(
+                    &format!("backend = \"{}\"", backend),
+                    config::FileFormat::Toml,
+                ))
+                .build()
+                .unwrap()
+                .try_deserialize::<Mail>()
+        };
+
+        assert_eq!(mail("smtp").unwrap().backend, MailBackend::Smtp);
+        assert_eq!(mail("maildir").unwrap().backend, MailBackend::Maildir);
+
+        for backend in ["SMTP", "sendmail", ""] {
+            let res = mail(backend);
+            assert!(res.is_err());
+            assert!(res
+                .unwrap_err()
+                .to_string()
+                .contains("unknown mail backend"));
+        }
+    }
 }
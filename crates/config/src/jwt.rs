@@ -0,0 +1,42 @@
+use std::fmt::Display;
+
+use openmusicgang_app::traits::DeserializeWith;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+/// JwtAlgorithm is the algorithm used to sign access tokens.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub enum JwtAlgorithm {
+    #[default]
+    HS256,
+    EdDSA,
+}
+
+impl Display for JwtAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JwtAlgorithm::HS256 => write!(f, "HS256"),
+            JwtAlgorithm::EdDSA => write!(f, "EdDSA"),
+        }
+    }
+}
+
+/// Unknown algorithms are rejected instead of falling back to HS256, so a typo cannot make the server sign
+/// tokens with the secret it was meant to stop using.
+impl DeserializeWith for JwtAlgorithm {
+    fn deserialize_with<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+
+        match s.as_ref() {
+            "HS256" => Ok(JwtAlgorithm::HS256),
+            "EdDSA" => Ok(JwtAlgorithm::EdDSA),
+            _ => Err(D::Error::custom(format!(
+                "unknown jwt algorithm \"{}\", expected \"HS256\" or \"EdDSA\"",
+                s
+            ))),
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod app_config;
 pub mod env;
+pub mod jwt;
+pub mod mail;
+pub mod storage;
@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+use openmusicgang_app::traits::DeserializeWith;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+/// MailBackend is the backend used to send emails.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub enum MailBackend {
+    Smtp,
+    #[default]
+    Maildir,
+}
+
+impl Display for MailBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailBackend::Smtp => write!(f, "smtp"),
+            MailBackend::Maildir => write!(f, "maildir"),
+        }
+    }
+}
+
+impl DeserializeWith for MailBackend {
+    fn deserialize_with<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+
+        match s.as_ref() {
+            "smtp" => Ok(MailBackend::Smtp),
+            "maildir" => Ok(MailBackend::Maildir),
+            _ => Err(D::Error::custom(format!(
+                "unknown mail backend \"{}\", expected \"smtp\" or \"maildir\"",
+                s
+            ))),
+        }
+    }
+}
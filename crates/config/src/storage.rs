@@ -0,0 +1,40 @@
+use std::fmt::Display;
+
+use openmusicgang_app::traits::DeserializeWith;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+
+/// StorageBackend is the backend used to store blobs.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub enum StorageBackend {
+    #[default]
+    Local,
+    S3,
+}
+
+impl Display for StorageBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageBackend::Local => write!(f, "local"),
+            StorageBackend::S3 => write!(f, "s3"),
+        }
+    }
+}
+
+impl DeserializeWith for StorageBackend {
+    fn deserialize_with<'de, D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+
+        match s.as_ref() {
+            "local" => Ok(StorageBackend::Local),
+            "s3" => Ok(StorageBackend::S3),
+            _ => Err(D::Error::custom(format!(
+                "unknown storage backend \"{}\", expected \"local\" or \"s3\"",
+                s
+            ))),
+        }
+    }
+}
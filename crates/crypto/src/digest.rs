@@ -0,0 +1,27 @@
+use sha2::{Digest, Sha256};
+
+/// Returns the SHA-256 digest of the given data, encoded as a lowercase hex string.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn sha256_hex_digest() {
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256_hex(b"music gang"),
+            sha256_hex("music gang".to_string().as_bytes())
+        );
+    }
+}
@@ -0,0 +1,4 @@
+pub mod digest;
+pub mod jwt;
+pub mod password;
+pub mod random;
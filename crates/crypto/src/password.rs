@@ -0,0 +1,162 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use openmusicgang_err::error::{Error, ErrorCode};
+use password_hash::rand_core::OsRng;
+use password_hash::{PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// PasswordHasher hashes and verifies passwords using Argon2id.
+///
+/// Hashes are encoded in the PHC string format, so the parameters used to create them
+/// are stored alongside the hash and can be compared with the current configuration.
+#[derive(Clone, Debug)]
+pub struct PasswordHasher {
+    params: Params,
+}
+
+impl PasswordHasher {
+    /// Create a new PasswordHasher with the given Argon2id cost parameters.
+    ///
+    /// # Arguments
+    /// * `memory_cost` - Memory size in KiB.
+    /// * `time_cost` - Number of iterations.
+    /// * `parallelism` - Degree of parallelism.
+    ///
+    /// Returns EINVALID if the parameters are out of the range accepted by Argon2.
+    pub fn new(memory_cost: u32, time_cost: u32, parallelism: u32) -> Result<Self, Error> {
+        let params = Params::new(memory_cost, time_cost, parallelism, None)
+            .map_err(|error| Error::new(ErrorCode::EINVALID, error.to_string()))?;
+
+        Ok(PasswordHasher { params })
+    }
+
+    /// Returns the PHC string of the given password, salted with a random salt.
+    pub fn hash_password(&self, password: &str) -> Result<String, Error> {
+        let salt = SaltString::generate(&mut OsRng);
+
+        self.argon2()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))
+    }
+
+    /// Returns true if the password matches the given PHC string.
+    ///
+    /// The parameters stored in the PHC string are used, so hashes created with
+    /// a different configuration can still be verified.
+    ///
+    /// A value that is not a PHC string is a legacy plaintext password, it is compared
+    /// in constant time and needs_rehash returns true for it.
+    pub fn verify_password(&self, password: &str, phc: &str) -> Result<bool, Error> {
+        let hash = match PasswordHash::new(phc) {
+            Ok(hash) => hash,
+            Err(_) => {
+                let expected = Sha256::digest(phc.as_bytes());
+                let actual = Sha256::digest(password.as_bytes());
+
+                return Ok(expected.ct_eq(&actual).into());
+            }
+        };
+
+        match self.argon2().verify_password(password.as_bytes(), &hash) {
+            Ok(()) => Ok(true),
+            Err(password_hash::Error::Password) => Ok(false),
+            Err(error) => Err(Error::new(ErrorCode::EINTERNAL, error.to_string())),
+        }
+    }
+
+    /// Hashes the password and discards the result, so that rejecting a user who does not exist
+    /// or has no password takes as long as verifying a password.
+    pub fn verify_dummy_password(&self, password: &str) {
+        let _ = self.hash_password(password);
+    }
+
+    /// Returns true if the given PHC string was not created with Argon2id
+    /// and the current cost parameters, so it should be hashed again.
+    pub fn needs_rehash(&self, phc: &str) -> bool {
+        let hash = match PasswordHash::new(phc) {
+            Ok(hash) => hash,
+            Err(_) => return true,
+        };
+
+        if hash.algorithm != Algorithm::Argon2id.ident()
+            || hash.version != Some(Version::V0x13.into())
+        {
+            return true;
+        }
+
+        match Params::try_from(&hash) {
+            Ok(params) => {
+                params.m_cost() != self.params.m_cost()
+                    || params.t_cost() != self.params.t_cost()
+                    || params.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+
+    fn argon2(&self) -> Argon2<'_> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+    }
+}
+
+impl Default for PasswordHasher {
+    /// Returns a PasswordHasher with the Argon2id parameters recommended by OWASP.
+    fn default() -> Self {
+        PasswordHasher {
+            params: Params::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn hash_and_verify_password() {
+        let hasher = PasswordHasher::new(64, 1, 1).unwrap();
+
+        let phc = hasher.hash_password("password").unwrap();
+        assert!(phc.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+
+        assert!(hasher.verify_password("password", &phc).unwrap());
+        assert!(!hasher.verify_password("wrong password", &phc).unwrap());
+
+        let another = hasher.hash_password("password").unwrap();
+        assert_ne!(phc, another);
+    }
+
+    #[test]
+    fn verify_legacy_password() {
+        let hasher = PasswordHasher::new(64, 1, 1).unwrap();
+
+        assert!(hasher.verify_password("password", "password").unwrap());
+        assert!(!hasher
+            .verify_password("wrong password", "password")
+            .unwrap());
+        assert!(!hasher.verify_password("", "password").unwrap());
+        assert!(hasher.needs_rehash("password"));
+    }
+
+    #[test]
+    fn needs_rehash() {
+        let hasher = PasswordHasher::new(64, 1, 1).unwrap();
+        let phc = hasher.hash_password("password").unwrap();
+
+        assert!(!hasher.needs_rehash(&phc));
+        assert!(hasher.needs_rehash("password"));
+
+        let stronger = PasswordHasher::new(128, 2, 1).unwrap();
+        assert!(stronger.needs_rehash(&phc));
+        assert!(stronger.verify_password("password", &phc).unwrap());
+    }
+
+    #[test]
+    fn invalid_params() {
+        let res = PasswordHasher::new(0, 0, 0);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+    }
+}
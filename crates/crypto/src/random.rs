@@ -0,0 +1,27 @@
+use password_hash::rand_core::{OsRng, RngCore};
+
+/// Returns a token made of the given number of random bytes, encoded as a lowercase hex string.
+///
+/// The bytes are read from the operating system random number generator,
+/// so the token is suitable for session identifiers and single-use secrets.
+pub fn random_token(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buf);
+
+    buf.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn random_token_length() {
+        let token = random_token(32);
+        assert_eq!(token.len(), 64);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+
+        assert_ne!(random_token(32), random_token(32));
+    }
+}
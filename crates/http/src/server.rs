@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Arc;
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::token_service::{with_access_token, TokenService as TokenServiceTrait};
+use openmusicgang_service::user_service::UserService as UserServiceTrait;
+use serde::Serialize;
+
+use crate::user;
+
+/// MAX_BODY_BYTES is the maximum size of the body of a request.
+static MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+/// Request is a struct to represent an HTTP request received by the server.
+#[derive(Clone, Debug, Default)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    /// Headers of the request, names are lowercase.
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Create a new Request with the given method and url, the url may contain a query string.
+    pub fn new(method: &str, url: &str) -> Request {
+        let (path, query) = match url.split_once('?') {
+            Some((path, query)) => (path, query),
+            None => (url, ""),
+        };
+
+        Request {
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            query: query.to_string(),
+            headers: HashMap::new(),
+            body: vec![],
+        }
+    }
+
+    /// Returns the request with the given header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Request {
+        self.headers.insert(name.to_lowercase(), value.to_string());
+        self
+    }
+
+    /// Returns the request with the given body.
+    pub fn with_body(mut self, body: &str) -> Request {
+        self.body = body.as_bytes().to_vec();
+        self
+    }
+
+    /// Returns the decoded parameters of the query string.
+    pub fn query_params(&self) -> HashMap<String, String> {
+        form_urlencoded::parse(self.query.as_bytes())
+            .into_owned()
+            .collect()
+    }
+
+    /// Returns the token of the Authorization header, if the bearer scheme is used.
+    pub fn bearer_token(&self) -> Option<String> {
+        let value = self.headers.get("authorization")?;
+        let (scheme, token) = value.split_once(' ')?;
+
+        if !scheme.eq_ignore_ascii_case("bearer") || token.trim().is_empty() {
+            return None;
+        }
+
+        Some(token.trim().to_string())
+    }
+}
+
+/// Response is a struct to represent an HTTP response returned by the server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    /// JSON encoded body, empty if the response has no content.
+    pub body: Vec<u8>,
+}
+
+/// ErrorResponse is the body of an error response.
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: ErrorDetail,
+}
+
+#[derive(Serialize)]
+struct ErrorDetail {
+    code: String,
+    message: String,
+}
+
+impl Response {
+    /// Returns a response with the given status and value encoded as JSON.
+    pub fn json<T: Serialize>(status: u16, value: &T) -> Response {
+        match serde_json::to_vec(value) {
+            Ok(body) => Response { status, body },
+            Err(error) => Response::error(&Error::new(ErrorCode::EINTERNAL, error.to_string())),
+        }
+    }
+
+    /// Returns an empty response.
+    pub fn no_content() -> Response {
+        Response {
+            status: 204,
+            body: vec![],
+        }
+    }
+
+    /// Returns the response of the given error, the status is the http status of its code.
+    pub fn error(error: &Error) -> Response {
+        let body = ErrorResponse {
+            error: ErrorDetail {
+                code: error.code.as_str().to_string(),
+                message: error.message.clone(),
+            },
+        };
+
+        Response {
+            status: error.code.as_http_status(),
+            body: serde_json::to_vec(&body).unwrap_or_default(),
+        }
+    }
+}
+
+/// Server is the HTTP server exposing the services of the application as JSON endpoints.
+pub struct Server {
+    pub(crate) user_service: Arc<dyn UserServiceTrait + Send + Sync>,
+    pub(crate) token_service: Arc<dyn TokenServiceTrait + Send + Sync>,
+}
+
+impl Server {
+    /// Create a new Server struct
+    pub fn new(
+        user_service: Arc<dyn UserServiceTrait + Send + Sync>,
+        token_service: Arc<dyn TokenServiceTrait + Send + Sync>,
+    ) -> Server {
+        Server {
+            user_service,
+            token_service,
+        }
+    }
+
+    /// Handles a request and returns its response.
+    pub fn handle(&self, request: &Request) -> Response {
+        let ctx = match self.context(request) {
+            Ok(ctx) => ctx,
+            Err(error) => return Response::error(&error),
+        };
+
+        let path = request.path.trim_matches('/');
+        let segments: Vec<&str> = path.split('/').collect();
+
+        let res = match (request.method.as_str(), segments.as_slice()) {
+            ("POST", ["users"]) => user::create_user(self, ctx, request),
+            ("POST", ["users", "verify-email"]) => user::confirm_email(self, ctx, request),
+            ("POST", ["users", "verify-email", "resend"]) => {
+                user::resend_verification_email(self, ctx)
+            }
+            ("POST", ["users", "email"]) => user::change_email(self, ctx, request),
+            ("POST", ["users", "password"]) => user::change_password(self, ctx, request),
+            ("POST", ["users", "password", "forgot"]) => {
+                user::request_password_reset(self, ctx, request)
+            }
+            ("POST", ["users", "password", "reset"]) => user::reset_password(self, ctx, request),
+            ("GET", ["users"]) => user::find_users(self, ctx, request),
+            ("GET", ["users", id]) => user::find_user_by_id(self, ctx, id),
+            ("PATCH", ["users", id]) => user::update_user(self, ctx, id, request),
+            ("DELETE", ["users", id]) => user::delete_user(self, ctx, id),
+            _ => Err(Error::new(
+                ErrorCode::ENOTFOUND,
+                "Route not found".to_string(),
+            )),
+        };
+
+        res.unwrap_or_else(|error| Response::error(&error))
+    }
+
+    /// Returns the context of the request.
+    ///
+    /// The context carries the user of the bearer token, if one is provided.
+    fn context(&self, request: &Request) -> Result<AppContext, Error> {
+        match request.bearer_token() {
+            Some(token) => with_access_token(&*self.token_service, Context::background(), token),
+            None => Ok(Context::background()),
+        }
+    }
+
+    /// Listens for requests on the given address, requests are handled one at a time.
+    ///
+    /// Blocks until the listener is closed.
+    pub fn listen(&self, addr: &str) -> Result<(), Error> {
+        let listener = tiny_http::Server::http(addr)
+            .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+        for mut incoming in listener.incoming_requests() {
+            let mut request = Request::new(incoming.method().as_str(), incoming.url());
+
+            for header in incoming.headers() {
+                request =
+                    request.with_header(header.field.as_str().as_str(), header.value.as_str());
+            }
+
+            let response = match read_body(incoming.as_reader(), &mut request.body) {
+                Ok(_) => self.handle(&request),
+                Err(error) => Response::error(&error),
+            };
+
+            let mut reply =
+                tiny_http::Response::from_data(response.body).with_status_code(response.status);
+
+            if response.status != 204 {
+                if let Ok(header) =
+                    tiny_http::Header::from_bytes("Content-Type", "application/json")
+                {
+                    reply = reply.with_header(header);
+                }
+            }
+
+            // The client may have gone away, there is nobody left to report the error to.
+            let _ = incoming.respond(reply);
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the body of a request, at most MAX_BODY_BYTES.
+///
+/// Returns ETOOLARGE if the body is larger, EINVALID if it cannot be read.
+fn read_body(reader: impl Read, body: &mut Vec<u8>) -> Result<(), Error> {
+    reader
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_end(body)
+        .map_err(|error| Error::new(ErrorCode::EINVALID, error.to_string()))?;
+
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Err(Error::new(
+            ErrorCode::ETOOLARGE,
+            format!("Request body is larger than {} bytes", MAX_BODY_BYTES),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn new_request() {
+        let request = Request::new("get", "/users?name=Bob%20Smith&limit=10")
+            .with_header("Authorization", "Bearer token");
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/users");
+
+        let params = request.query_params();
+        assert_eq!(params.get("name"), Some(&"Bob Smith".to_string()));
+        assert_eq!(params.get("limit"), Some(&"10".to_string()));
+
+        assert_eq!(request.bearer_token(), Some("token".to_string()));
+
+        let request = Request::new("GET", "/users").with_header("Authorization", "Basic token");
+        assert_eq!(request.bearer_token(), None);
+    }
+
+    #[test]
+    fn error_response() {
+        let response = Response::error(&Error::new(
+            ErrorCode::ENOTFOUND,
+            "User not found".to_string(),
+        ));
+
+        assert_eq!(response.status, 404);
+        assert_eq!(
+            String::from_utf8(response.body).unwrap(),
+            r#"{"error":{"code":"not found","message":"User not found"}}"#
+        );
+    }
+
+    #[test]
+    fn read_request_body() {
+        let mut body = vec![];
+        read_body(&b"{}"[..], &mut body).unwrap();
+        assert_eq!(body, b"{}");
+
+        let content = vec![b' '; MAX_BODY_BYTES as usize];
+        let mut body = vec![];
+        read_body(content.as_slice(), &mut body).unwrap();
+        assert_eq!(body.len(), MAX_BODY_BYTES as usize);
+
+        let content = vec![b' '; MAX_BODY_BYTES as usize + 1];
+        let res = read_body(content.as_slice(), &mut vec![]);
+        assert!(res.is_err());
+
+        let response = Response::error(&res.unwrap_err());
+        assert_eq!(response.status, 413);
+    }
+}
@@ -0,0 +1,689 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::user::{normalize_email, User};
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::user_service::{UserFilter, UserUpdate};
+
+use crate::server::{Request, Response, Server};
+
+/// CreateUserRequest is the body of a request to create a user.
+#[derive(Deserialize)]
+pub struct CreateUserRequest {
+    pub name: String,
+    pub email: String,
+    pub password: Option<String>,
+}
+
+/// UpdateUserRequest is the body of a request to update a user.
+#[derive(Deserialize)]
+pub struct UpdateUserRequest {
+    pub name: Option<String>,
+}
+
+/// ConfirmEmailRequest is the body of a request to verify an email address.
+#[derive(Deserialize)]
+pub struct ConfirmEmailRequest {
+    pub token: String,
+}
+
+/// ChangeEmailRequest is the body of a request to change the email address of the logged in user.
+#[derive(Deserialize)]
+pub struct ChangeEmailRequest {
+    pub email: String,
+}
+
+/// ChangePasswordRequest is the body of a request to change the password of the logged in user.
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// ForgotPasswordRequest is the body of a request to be sent a password reset email.
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+/// ResetPasswordRequest is the body of a request to reset a password with a password reset token.
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+}
+
+/// UserResponse is the representation of a user returned by the server, the password is never exposed.
+///
+/// The email addresses are only exposed to the user themselves.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct UserResponse {
+    pub id: i64,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        UserResponse {
+            id: user.id,
+            name: user.name,
+            email: Some(user.email),
+            email_verified_at: user.email_verified_at,
+            pending_email: user.pending_email,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+impl UserResponse {
+    /// Returns the representation of a user as seen by the user with caller_id, without the email
+    /// addresses unless it is the user themselves.
+    pub fn visible_to(user: User, caller_id: i64) -> Self {
+        let is_caller = user.id == caller_id;
+        let mut response = UserResponse::from(user);
+
+        if !is_caller {
+            response.email = None;
+            response.pending_email = None;
+        }
+
+        response
+    }
+}
+
+/// UsersResponse is the body of the response of a user search.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct UsersResponse {
+    pub users: Vec<UserResponse>,
+    pub total: i64,
+}
+
+/// Returns the user id of a path segment.
+///
+/// Returns EINVALID if the segment is not a number.
+fn parse_id(segment: &str) -> Result<i64, Error> {
+    segment
+        .parse::<i64>()
+        .map_err(|_| Error::new(ErrorCode::EINVALID, "Invalid user id".to_string()))
+}
+
+/// Returns the body of the request decoded from JSON.
+///
+/// Returns EINVALID if the body is not valid JSON for the expected type.
+pub(crate) fn parse_body<'a, T: Deserialize<'a>>(request: &'a Request) -> Result<T, Error> {
+    serde_json::from_slice(&request.body)
+        .map_err(|error| Error::new(ErrorCode::EINVALID, error.to_string()))
+}
+
+/// Returns the numeric query parameter with the given name, None if not provided.
+///
+/// Returns EINVALID if the parameter is not a number.
+pub(crate) fn parse_query_i64(request: &Request, name: &str) -> Result<Option<i64>, Error> {
+    match request.query_params().get(name) {
+        Some(value) => value.parse::<i64>().map(Some).map_err(|_| {
+            Error::new(
+                ErrorCode::EINVALID,
+                format!("Invalid query parameter {}", name),
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+/// POST /users
+pub(crate) fn create_user(
+    server: &Server,
+    ctx: AppContext,
+    request: &Request,
+) -> Result<Response, Error> {
+    let body: CreateUserRequest = parse_body(request)?;
+
+    let mut user = User::new();
+    user.name = body.name;
+    user.email = body.email;
+    user.password = body.password;
+
+    server.user_service.create_user(ctx, &mut user)?;
+
+    Ok(Response::json(201, &UserResponse::from(user)))
+}
+
+/// GET /users
+pub(crate) fn find_users(
+    server: &Server,
+    ctx: AppContext,
+    request: &Request,
+) -> Result<Response, Error> {
+    let caller_id = Context::user_id_from_context(ctx.clone());
+
+    if caller_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in to list users".to_string(),
+        ));
+    }
+
+    let params = request.query_params();
+
+    // Only the email address of the caller can be looked up, others must not learn who is registered.
+    if let Some(email) = params.get("email") {
+        let caller = Context::user_from_context(ctx.clone()).unwrap_or_default();
+
+        if normalize_email(email) != caller.email {
+            return Err(Error::new(
+                ErrorCode::EFORBIDDEN,
+                "You can only filter users by your own email address".to_string(),
+            ));
+        }
+    }
+
+    let filters = UserFilter {
+        id: parse_query_i64(request, "id")?,
+        name: params.get("name").cloned(),
+        email: params.get("email").cloned(),
+        limit: parse_query_i64(request, "limit")?.unwrap_or_default(),
+        offset: parse_query_i64(request, "offset")?.unwrap_or_default(),
+    };
+
+    let (users, total) = server.user_service.find_users(ctx, filters)?;
+
+    Ok(Response::json(
+        200,
+        &UsersResponse {
+            users: users
+                .into_iter()
+                .map(|user| UserResponse::visible_to(user, caller_id))
+                .collect(),
+            total,
+        },
+    ))
+}
+
+/// GET /users/:id
+pub(crate) fn find_user_by_id(
+    server: &Server,
+    ctx: AppContext,
+    id: &str,
+) -> Result<Response, Error> {
+    let caller_id = Context::user_id_from_context(ctx.clone());
+    let user = server.user_service.find_user_by_id(ctx, parse_id(id)?)?;
+
+    Ok(Response::json(
+        200,
+        &UserResponse::visible_to(user, caller_id),
+    ))
+}
+
+/// PATCH /users/:id
+pub(crate) fn update_user(
+    server: &Server,
+    ctx: AppContext,
+    id: &str,
+    request: &Request,
+) -> Result<Response, Error> {
+    let id = parse_id(id)?;
+    let body: UpdateUserRequest = parse_body(request)?;
+
+    let user = server
+        .user_service
+        .update_user(ctx, id, UserUpdate { name: body.name })?;
+
+    Ok(Response::json(200, &UserResponse::from(user)))
+}
+
+/// DELETE /users/:id
+pub(crate) fn delete_user(server: &Server, ctx: AppContext, id: &str) -> Result<Response, Error> {
+    server.user_service.delete_user(ctx, parse_id(id)?)?;
+
+    Ok(Response::no_content())
+}
+
+/// POST /users/verify-email
+pub(crate) fn confirm_email(
+    server: &Server,
+    ctx: AppContext,
+    request: &Request,
+) -> Result<Response, Error> {
+    let body: ConfirmEmailRequest = parse_body(request)?;
+
+    let user = server.user_service.confirm_email(ctx, body.token)?;
+
+    Ok(Response::json(200, &UserResponse::from(user)))
+}
+
+/// POST /users/verify-email/resend
+pub(crate) fn resend_verification_email(
+    server: &Server,
+    ctx: AppContext,
+) -> Result<Response, Error> {
+    server.user_service.resend_verification_email(ctx)?;
+
+    Ok(Response::no_content())
+}
+
+/// POST /users/email
+pub(crate) fn change_email(
+    server: &Server,
+    ctx: AppContext,
+    request: &Request,
+) -> Result<Response, Error> {
+    let body: ChangeEmailRequest = parse_body(request)?;
+
+    let user = server.user_service.change_email(ctx, body.email)?;
+
+    Ok(Response::json(200, &UserResponse::from(user)))
+}
+
+/// POST /users/password
+pub(crate) fn change_password(
+    server: &Server,
+    ctx: AppContext,
+    request: &Request,
+) -> Result<Response, Error> {
+    let body: ChangePasswordRequest = parse_body(request)?;
+
+    server
+        .user_service
+        .change_password(ctx, body.current_password, body.new_password)?;
+
+    Ok(Response::no_content())
+}
+
+/// POST /users/password/forgot
+pub(crate) fn request_password_reset(
+    server: &Server,
+    ctx: AppContext,
+    request: &Request,
+) -> Result<Response, Error> {
+    let body: ForgotPasswordRequest = parse_body(request)?;
+
+    server
+        .user_service
+        .request_password_reset(ctx, body.email)?;
+
+    Ok(Response::no_content())
+}
+
+/// POST /users/password/reset
+pub(crate) fn reset_password(
+    server: &Server,
+    ctx: AppContext,
+    request: &Request,
+) -> Result<Response, Error> {
+    let body: ResetPasswordRequest = parse_body(request)?;
+
+    let user = server
+        .user_service
+        .reset_password(ctx, body.token, body.password)?;
+
+    Ok(Response::json(200, &UserResponse::from(user)))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use openmusicgang_app::context::Context;
+    use openmusicgang_entity::token::AccessToken;
+    use openmusicgang_mock::token::TokenService as MockTokenService;
+    use openmusicgang_mock::user::UserService as MockUserService;
+
+    use super::*;
+
+    fn bob() -> User {
+        let mut user = User::new();
+        user.id = 1;
+        user.name = "Bob Smith".to_string();
+        user.email = "bob.smith@test.com".to_string();
+        user.password = Some("$argon2id$hash".to_string());
+        user.created_at = Utc.with_ymd_and_hms(2022, 5, 1, 0, 0, 0).unwrap();
+        user.updated_at = user.created_at;
+        user
+    }
+
+    fn not_found() -> Error {
+        Error::new(ErrorCode::ENOTFOUND, "User not found".to_string())
+    }
+
+    fn server(user_service: MockUserService) -> Server {
+        let token_service = MockTokenService {
+            verify_access_token_fn: Some(|_, token| match token.as_str() {
+                "bob" => Ok((
+                    AccessToken {
+                        user_id: 1,
+                        session_id: "bob".to_string(),
+                        scopes: vec![],
+                        issued_at: Utc::now(),
+                        expires_at: Utc::now(),
+                    },
+                    bob(),
+                )),
+                _ => Err(Error::new(
+                    ErrorCode::EUNAUTHORIZED,
+                    "Invalid access token".to_string(),
+                )),
+            }),
+            ..Default::default()
+        };
+
+        Server::new(Arc::new(user_service), Arc::new(token_service))
+    }
+
+    fn body<'a, T: Deserialize<'a>>(response: &'a Response) -> T {
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn create_user() {
+        let server = server(MockUserService {
+            create_user_fn: Some(|_, user| {
+                if user.email == "taken@test.com" {
+                    return Err(Error::new(
+                        ErrorCode::ECONFLICT,
+                        "Email already taken".to_string(),
+                    ));
+                }
+                user.id = 1;
+                Ok(())
+            }),
+            ..Default::default()
+        });
+
+        let response = server.handle(&Request::new("POST", "/users").with_body(
+            r#"{"name":"Bob Smith","email":"bob.smith@test.com","password":"password"}"#,
+        ));
+        assert_eq!(response.status, 201);
+
+        let user: UserResponse = body(&response);
+        assert_eq!(user.id, 1);
+        assert_eq!(user.name, "Bob Smith");
+        assert!(!String::from_utf8_lossy(&response.body).contains("password"));
+
+        let response = server.handle(
+            &Request::new("POST", "/users")
+                .with_body(r#"{"name":"Bob Smith","email":"taken@test.com"}"#),
+        );
+        assert_eq!(response.status, 409);
+
+        let response = server.handle(&Request::new("POST", "/users").with_body("{"));
+        assert_eq!(response.status, 400);
+    }
+
+    #[test]
+    fn find_user() {
+        let server = server(MockUserService {
+            find_user_by_id_fn: Some(|_, id| match id {
+                1 => Ok(bob()),
+                _ => Err(not_found()),
+            }),
+            ..Default::default()
+        });
+
+        let response = server
+            .handle(&Request::new("GET", "/users/1").with_header("Authorization", "Bearer bob"));
+        assert_eq!(response.status, 200);
+        assert_eq!(body::<UserResponse>(&response), UserResponse::from(bob()));
+
+        let response = server.handle(&Request::new("GET", "/users/1"));
+        assert_eq!(response.status, 200);
+        assert_eq!(body::<UserResponse>(&response).email, None);
+        assert!(!String::from_utf8_lossy(&response.body).contains("bob.smith@test.com"));
+
+        let response = server.handle(&Request::new("GET", "/users/2"));
+        assert_eq!(response.status, 404);
+
+        let response = server.handle(&Request::new("GET", "/users/bob"));
+        assert_eq!(response.status, 400);
+
+        let response = server.handle(&Request::new("GET", "/users/by-email/bob.smith%40test.com"));
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn find_users() {
+        let server = server(MockUserService {
+            find_users_fn: Some(|_, filters| {
+                assert_eq!(filters.name, Some("Bob Smith".to_string()));
+                assert_eq!(filters.limit, 10);
+                assert_eq!(filters.offset, 20);
+                let mut mark = bob();
+                mark.id = 2;
+                mark.email = "mark.smith@test.com".to_string();
+                mark.pending_email = Some("mark@test.com".to_string());
+                Ok((vec![bob(), mark], 21))
+            }),
+            ..Default::default()
+        });
+
+        let request = Request::new("GET", "/users?name=Bob+Smith&limit=10&offset=20");
+
+        let response = server.handle(&request);
+        assert_eq!(response.status, 401);
+
+        let response = server.handle(&request.with_header("Authorization", "Bearer bob"));
+        assert_eq!(response.status, 200);
+
+        let users: UsersResponse = body(&response);
+        assert_eq!(users.total, 21);
+        assert_eq!(users.users[0], UserResponse::from(bob()));
+        assert_eq!(users.users[1].id, 2);
+        assert_eq!(users.users[1].email, None);
+        assert_eq!(users.users[1].pending_email, None);
+        assert!(!String::from_utf8_lossy(&response.body).contains("mark"));
+
+        let response = server.handle(
+            &Request::new("GET", "/users?limit=ten").with_header("Authorization", "Bearer bob"),
+        );
+        assert_eq!(response.status, 400);
+
+        let response = server.handle(
+            &Request::new("GET", "/users?email=mark.smith%40test.com")
+                .with_header("Authorization", "Bearer bob"),
+        );
+        assert_eq!(response.status, 403);
+
+        let response = server.handle(
+            &Request::new(
+                "GET",
+                "/users?name=Bob+Smith&limit=10&offset=20&email=Bob.Smith%40test.com",
+            )
+            .with_header("Authorization", "Bearer bob"),
+        );
+        assert_eq!(response.status, 200);
+    }
+
+    #[test]
+    fn update_and_delete_user() {
+        let server = server(MockUserService {
+            update_user_fn: Some(|ctx, id, update| {
+                if Context::user_id_from_context(ctx) != id {
+                    return Err(Error::new(
+                        ErrorCode::EUNAUTHORIZED,
+                        "You do not have permission to update this user".to_string(),
+                    ));
+                }
+                let mut user = bob();
+                user.name = update.name.unwrap_or(user.name);
+                Ok(user)
+            }),
+            delete_user_fn: Some(|ctx, id| {
+                if Context::user_id_from_context(ctx) != id {
+                    return Err(Error::new(
+                        ErrorCode::EUNAUTHORIZED,
+                        "You do not have permission to delete this user".to_string(),
+                    ));
+                }
+                Ok(())
+            }),
+            ..Default::default()
+        });
+
+        let response = server.handle(
+            &Request::new("PATCH", "/users/1")
+                .with_header("Authorization", "Bearer bob")
+                .with_body(r#"{"name":"Mark Smith"}"#),
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(body::<UserResponse>(&response).name, "Mark Smith");
+
+        let response =
+            server.handle(&Request::new("PATCH", "/users/1").with_body(r#"{"name":"Mark Smith"}"#));
+        assert_eq!(response.status, 401);
+
+        let response = server.handle(
+            &Request::new("DELETE", "/users/1").with_header("Authorization", "Bearer invalid"),
+        );
+        assert_eq!(response.status, 401);
+
+        let response = server
+            .handle(&Request::new("DELETE", "/users/1").with_header("Authorization", "Bearer bob"));
+        assert_eq!(response.status, 204);
+        assert!(response.body.is_empty());
+
+        let response = server.handle(&Request::new("PUT", "/users/1"));
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn verify_email() {
+        let server = server(MockUserService {
+            confirm_email_fn: Some(|_, token| match token.as_str() {
+                "valid" => {
+                    let mut user = bob();
+                    user.email_verified_at = Some(Utc::now());
+                    Ok(user)
+                }
+                _ => Err(Error::new(
+                    ErrorCode::EINVALID,
+                    "Invalid verification token".to_string(),
+                )),
+            }),
+            resend_verification_email_fn: Some(|ctx| match Context::user_id_from_context(ctx) {
+                0 => Err(Error::new(
+                    ErrorCode::EUNAUTHORIZED,
+                    "You must be logged in to verify your email address".to_string(),
+                )),
+                _ => Err(Error::new(
+                    ErrorCode::ETOOMANYREQUESTS,
+                    "A verification email was sent recently, try again later".to_string(),
+                )),
+            }),
+            change_email_fn: Some(|_, email| match email.as_str() {
+                "mark.smith@test.com" => Err(Error::new(
+                    ErrorCode::ECONFLICT,
+                    "Email address is already taken".to_string(),
+                )),
+                _ => {
+                    let mut user = bob();
+                    user.pending_email = Some(email);
+                    Ok(user)
+                }
+            }),
+            ..Default::default()
+        });
+
+        let response = server
+            .handle(&Request::new("POST", "/users/verify-email").with_body(r#"{"token":"valid"}"#));
+        assert_eq!(response.status, 200);
+        assert!(body::<UserResponse>(&response).email_verified_at.is_some());
+
+        let response = server.handle(
+            &Request::new("POST", "/users/verify-email").with_body(r#"{"token":"invalid"}"#),
+        );
+        assert_eq!(response.status, 400);
+
+        let response = server.handle(&Request::new("POST", "/users/verify-email/resend"));
+        assert_eq!(response.status, 401);
+
+        let response = server.handle(
+            &Request::new("POST", "/users/verify-email/resend")
+                .with_header("Authorization", "Bearer bob"),
+        );
+        assert_eq!(response.status, 429);
+
+        let response = server.handle(
+            &Request::new("POST", "/users/email")
+                .with_header("Authorization", "Bearer bob")
+                .with_body(r#"{"email":"bob.smith@example.com"}"#),
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            body::<UserResponse>(&response).pending_email.as_deref(),
+            Some("bob.smith@example.com")
+        );
+
+        let response = server.handle(
+            &Request::new("POST", "/users/email")
+                .with_header("Authorization", "Bearer bob")
+                .with_body(r#"{"email":"mark.smith@test.com"}"#),
+        );
+        assert_eq!(response.status, 409);
+    }
+
+    #[test]
+    fn reset_password() {
+        let server = server(MockUserService {
+            change_password_fn: Some(|ctx, current_password, _| {
+                if Context::user_id_from_context(ctx) == 0 || current_password != "password" {
+                    return Err(Error::new(
+                        ErrorCode::EUNAUTHORIZED,
+                        "Invalid current password".to_string(),
+                    ));
+                }
+                Ok(())
+            }),
+            request_password_reset_fn: Some(|_, _| Ok(())),
+            reset_password_fn: Some(|_, token, _| match token.as_str() {
+                "valid" => Ok(bob()),
+                _ => Err(Error::new(
+                    ErrorCode::EINVALID,
+                    "Invalid password reset token".to_string(),
+                )),
+            }),
+            ..Default::default()
+        });
+
+        let response = server.handle(
+            &Request::new("POST", "/users/password")
+                .with_header("Authorization", "Bearer bob")
+                .with_body(r#"{"current_password":"password","new_password":"secret"}"#),
+        );
+        assert_eq!(response.status, 204);
+
+        let response = server.handle(
+            &Request::new("POST", "/users/password")
+                .with_header("Authorization", "Bearer bob")
+                .with_body(r#"{"current_password":"wrong","new_password":"secret"}"#),
+        );
+        assert_eq!(response.status, 401);
+
+        let response = server.handle(
+            &Request::new("POST", "/users/password/forgot")
+                .with_body(r#"{"email":"nobody@test.com"}"#),
+        );
+        assert_eq!(response.status, 204);
+
+        let response = server.handle(
+            &Request::new("POST", "/users/password/reset")
+                .with_body(r#"{"token":"valid","password":"secret"}"#),
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(body::<UserResponse>(&response).id, 1);
+
+        let response = server.handle(
+            &Request::new("POST", "/users/password/reset")
+                .with_body(r#"{"token":"invalid","password":"secret"}"#),
+        );
+        assert_eq!(response.status, 400);
+    }
+}
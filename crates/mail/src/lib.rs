@@ -0,0 +1,3 @@
+pub mod maildir;
+mod message;
+pub mod smtp;
@@ -0,0 +1,117 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lettre::message::Mailbox;
+
+use openmusicgang_crypto::random::random_token;
+use openmusicgang_entity::email::Email;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::email_sender::EmailSender as EmailSenderTrait;
+
+use crate::message::{build_message, parse_mailbox};
+
+/// EmailSender is a struct that implements the EmailSenderTrait on a local maildir, for the local and
+/// testing environments.
+///
+/// Every email is written to `tmp/` first and moved to `new/` once complete, so a mail client reading
+/// the maildir never sees a partial email.
+pub struct EmailSender {
+    root: PathBuf,
+    from: Mailbox,
+}
+
+impl EmailSender {
+    /// Create a new EmailSender struct, creating the directories of the maildir if they do not exist.
+    ///
+    /// Returns EINVALID if the address of the sender is invalid.
+    pub fn new(root: impl Into<PathBuf>, from: &str) -> Result<EmailSender, Error> {
+        let root = root.into();
+
+        for dir in ["tmp", "new", "cur"] {
+            fs::create_dir_all(root.join(dir)).map_err(io_error)?;
+        }
+
+        Ok(EmailSender {
+            root,
+            from: parse_mailbox(from)?,
+        })
+    }
+}
+
+impl EmailSenderTrait for EmailSender {
+    /// Writes the email to a new file of the maildir.
+    fn send_email(&self, email: &Email) -> Result<(), Error> {
+        let message = build_message(&self.from, email)?;
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+        let name = format!("{}.{}.openmusicgang", time.as_secs(), random_token(16));
+
+        let path = self.root.join("tmp").join(&name);
+        fs::write(&path, message.formatted()).map_err(io_error)?;
+        fs::rename(&path, self.root.join("new").join(&name)).map_err(io_error)?;
+
+        Ok(())
+    }
+}
+
+/// Returns an EINTERNAL error of an IO error.
+fn io_error(error: std::io::Error) -> Error {
+    Error::new(ErrorCode::EINTERNAL, error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) create a maildir in a new directory.
+    /// 2) send an email, it should be written to `new/` with both its parts.
+    /// 3) send an email to an invalid address, error should be EINVALID.
+    #[test]
+    fn test_maildir_email_sender() {
+        // 1) create a maildir in a new directory.
+        let root = std::env::temp_dir().join(format!("openmusicgang-{}", random_token(8)));
+        let sender = EmailSender::new(&root, "OpenMusicGang <noreply@openmusicgang.org>").unwrap();
+
+        // 2) send an email, it should be written to `new/` with both its parts.
+        let email = Email {
+            to: "bob.smith@test.com".to_string(),
+            subject: "Welcome to OpenMusicGang".to_string(),
+            text: "Hi Bob, welcome aboard.".to_string(),
+            html: "<p>Hi Bob, welcome aboard.</p>".to_string(),
+            ..Default::default()
+        };
+        sender.send_email(&email).unwrap();
+
+        let files: Vec<PathBuf> = fs::read_dir(root.join("new"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(fs::read_dir(root.join("tmp")).unwrap().count(), 0);
+
+        let content = fs::read_to_string(&files[0]).unwrap();
+        assert!(content.contains("From: OpenMusicGang <noreply@openmusicgang.org>"));
+        assert!(content.contains("To: bob.smith@test.com"));
+        assert!(content.contains("Subject: Welcome to OpenMusicGang"));
+        assert!(content.contains("multipart/alternative"));
+        assert!(content.contains("Hi Bob, welcome aboard."));
+        assert!(content.contains("<p>Hi Bob, welcome aboard.</p>"));
+
+        // 3) send an email to an invalid address, error should be EINVALID.
+        let email = Email {
+            to: "bob.smith".to_string(),
+            ..email
+        };
+        let res = sender.send_email(&email);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
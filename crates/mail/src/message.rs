@@ -0,0 +1,41 @@
+use lettre::message::header::ContentType;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::Message;
+
+use openmusicgang_entity::email::Email;
+use openmusicgang_err::error::{Error, ErrorCode};
+
+/// Returns the mailbox of an address, e.g. "OpenMusicGang <noreply@openmusicgang.org>".
+///
+/// Returns EINVALID if the address is invalid.
+pub(crate) fn parse_mailbox(address: &str) -> Result<Mailbox, Error> {
+    address.parse().map_err(|_| {
+        Error::new(
+            ErrorCode::EINVALID,
+            format!("Invalid email address {}", address),
+        )
+    })
+}
+
+/// Returns the MIME message of an email sent by the given mailbox, a multipart/alternative of its
+/// plain-text and HTML parts, or only its plain-text part if it has no HTML part.
+///
+/// Returns EINVALID if the address of the recipient is invalid.
+pub(crate) fn build_message(from: &Mailbox, email: &Email) -> Result<Message, Error> {
+    let builder = Message::builder()
+        .from(from.clone())
+        .to(parse_mailbox(&email.to)?)
+        .subject(email.subject.as_str());
+
+    let message = match email.html.is_empty() {
+        true => builder
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.text.clone()),
+        false => builder.multipart(MultiPart::alternative_plain_html(
+            email.text.clone(),
+            email.html.clone(),
+        )),
+    };
+
+    message.map_err(|error| Error::new(ErrorCode::EINVALID, error.to_string()))
+}
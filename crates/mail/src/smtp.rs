@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{SmtpTransport, Transport};
+
+use openmusicgang_entity::email::Email;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::email_sender::EmailSender as EmailSenderTrait;
+
+use crate::message::{build_message, parse_mailbox};
+
+/// TIMEOUT is the time after which a connection to the SMTP server is abandoned.
+static TIMEOUT: Duration = Duration::from_secs(30);
+
+/// EmailSender is a struct that implements the EmailSenderTrait with an SMTP server.
+pub struct EmailSender {
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl EmailSender {
+    /// Create a new EmailSender struct sending with the SMTP server at host:port.
+    ///
+    /// The connection is upgraded with STARTTLS if starttls is true, otherwise it is not encrypted, e.g.
+    /// for a local SMTP sink. The credentials are only sent if the username is not empty.
+    ///
+    /// Returns EINVALID if the address of the sender is invalid.
+    pub fn new(
+        host: &str,
+        port: u16,
+        username: &str,
+        password: &str,
+        starttls: bool,
+        from: &str,
+    ) -> Result<EmailSender, Error> {
+        let mut builder = match starttls {
+            true => SmtpTransport::starttls_relay(host)
+                .map_err(|error| Error::new(ErrorCode::EINVALID, error.to_string()))?,
+            false => SmtpTransport::builder_dangerous(host),
+        }
+        .port(port)
+        .timeout(Some(TIMEOUT));
+
+        if !username.is_empty() {
+            builder =
+                builder.credentials(Credentials::new(username.to_string(), password.to_string()));
+        }
+
+        Ok(EmailSender {
+            transport: builder.build(),
+            from: parse_mailbox(from)?,
+        })
+    }
+}
+
+impl EmailSenderTrait for EmailSender {
+    /// Sends the email with the SMTP server.
+    ///
+    /// Returns EINTERNAL if the server cannot be reached or rejects the email.
+    fn send_email(&self, email: &Email) -> Result<(), Error> {
+        let message = build_message(&self.from, email)?;
+
+        self.transport
+            .send(&message)
+            .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    use super::*;
+
+    /// Accepts a single SMTP session on a local port, returns the port and a handle returning the
+    /// envelope and the data of the received email.
+    fn spawn_smtp_sink() -> (u16, JoinHandle<(Vec<String>, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut envelope = vec![];
+            let mut data = String::new();
+
+            stream.write_all(b"220 localhost ESMTP sink\r\n").unwrap();
+
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+
+                let command = line.trim_end().to_uppercase();
+
+                if command.starts_with("DATA") {
+                    stream
+                        .write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")
+                        .unwrap();
+
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line == ".\r\n" {
+                            break;
+                        }
+                        data.push_str(&line);
+                    }
+
+                    stream.write_all(b"250 OK queued\r\n").unwrap();
+                } else if command.starts_with("QUIT") {
+                    stream.write_all(b"221 Bye\r\n").unwrap();
+                    break;
+                } else {
+                    if command.starts_with("MAIL") || command.starts_with("RCPT") {
+                        envelope.push(line.trim_end().to_string());
+                    }
+                    stream.write_all(b"250 OK\r\n").unwrap();
+                }
+            }
+
+            (envelope, data)
+        });
+
+        (port, handle)
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) create a sender with an invalid address, error should be EINVALID.
+    /// 2) send an email to a local SMTP sink, it should receive it with both its parts.
+    /// 3) send an email to a closed port, error should be EINTERNAL.
+    #[test]
+    fn test_smtp_email_sender() {
+        // 1) create a sender with an invalid address, error should be EINVALID.
+        let res = EmailSender::new("localhost", 25, "", "", false, "noreply");
+        assert!(res.is_err());
+        assert_eq!(res.err().unwrap().code, ErrorCode::EINVALID);
+
+        // 2) send an email to a local SMTP sink, it should receive it with both its parts.
+        let (port, sink) = spawn_smtp_sink();
+        let sender = EmailSender::new(
+            "127.0.0.1",
+            port,
+            "",
+            "",
+            false,
+            "OpenMusicGang <noreply@openmusicgang.org>",
+        )
+        .unwrap();
+
+        let email = Email {
+            to: "bob.smith@test.com".to_string(),
+            subject: "Welcome to OpenMusicGang".to_string(),
+            text: "Hi Bob, welcome aboard.".to_string(),
+            html: "<p>Hi Bob, welcome aboard.</p>".to_string(),
+            ..Default::default()
+        };
+        sender.send_email(&email).unwrap();
+
+        let (envelope, data) = sink.join().unwrap();
+        assert_eq!(
+            envelope,
+            vec![
+                "MAIL FROM:<noreply@openmusicgang.org>",
+                "RCPT TO:<bob.smith@test.com>"
+            ]
+        );
+        assert!(data.contains("To: bob.smith@test.com"));
+        assert!(data.contains("Subject: Welcome to OpenMusicGang"));
+        assert!(data.contains("multipart/alternative"));
+        assert!(data.contains("Hi Bob, welcome aboard."));
+        assert!(data.contains("<p>Hi Bob, welcome aboard.</p>"));
+
+        // 3) send an email to a closed port, error should be EINTERNAL.
+        let closed_port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let sender = EmailSender::new(
+            "127.0.0.1",
+            closed_port,
+            "",
+            "",
+            false,
+            "noreply@openmusicgang.org",
+        )
+        .unwrap();
+        let res = sender.send_email(&email);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINTERNAL);
+    }
+}
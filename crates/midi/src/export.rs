@@ -0,0 +1,279 @@
+use openmusicgang_entity::song::Song;
+
+use crate::metadata::{MAJOR_KEYS, MINOR_KEYS};
+use crate::smf::{
+    MidiEvent, MidiFile, MidiMessage, META_END_OF_TRACK, META_KEY_SIGNATURE, META_TEMPO,
+    META_TIME_SIGNATURE, META_TRACK_NAME,
+};
+
+/// Number of ticks per quarter note of an exported file without clips.
+pub const DEFAULT_DIVISION: u16 = 480;
+
+/// Returns a format 1 MIDI file of a song and its clips.
+///
+/// The first track is a conductor track with the title, the tempo map, the time signature and the
+/// key of the song, followed by the tracks of every clip. The tempo, time and key signature events
+/// of the clips are replaced by the ones of the song, and their positions are rescaled to the
+/// highest division among the clips.
+pub fn song_midi_file(song: &Song, clips: &[MidiFile]) -> MidiFile {
+    let division = clips
+        .iter()
+        .map(|clip| clip.division)
+        .max()
+        .unwrap_or(DEFAULT_DIVISION);
+
+    let mut tracks = vec![conductor_track(song, division)];
+
+    for clip in clips {
+        for track in &clip.tracks {
+            let mut events = vec![];
+            let mut tick = 0u64;
+            let mut previous = 0u64;
+
+            for event in track {
+                tick += event.delta as u64;
+
+                if [META_TEMPO, META_TIME_SIGNATURE, META_KEY_SIGNATURE]
+                    .iter()
+                    .any(|kind| event.message.is_meta(*kind))
+                {
+                    continue;
+                }
+
+                let scaled = tick * division as u64 / clip.division as u64;
+                events.push(MidiEvent {
+                    delta: (scaled - previous) as u32,
+                    message: event.message.clone(),
+                });
+                previous = scaled;
+            }
+
+            tracks.push(events);
+        }
+    }
+
+    MidiFile {
+        format: 1,
+        division,
+        tracks,
+    }
+}
+
+/// Returns the conductor track of a song.
+fn conductor_track(song: &Song, division: u16) -> Vec<MidiEvent> {
+    let mut events = vec![MidiEvent {
+        delta: 0,
+        message: MidiMessage::Meta {
+            kind: META_TRACK_NAME,
+            data: song.title.as_bytes().to_vec(),
+        },
+    }];
+
+    if let Some((beats, unit)) = song.time_signature.split_once('/') {
+        if let (Ok(beats), Ok(unit)) = (beats.parse::<u8>(), unit.parse::<u8>()) {
+            events.push(MidiEvent {
+                delta: 0,
+                message: MidiMessage::Meta {
+                    kind: META_TIME_SIGNATURE,
+                    data: vec![beats, unit.trailing_zeros() as u8, 24, 8],
+                },
+            });
+        }
+    }
+
+    if let Some(data) = song.key.as_deref().and_then(key_signature_data) {
+        events.push(MidiEvent {
+            delta: 0,
+            message: MidiMessage::Meta {
+                kind: META_KEY_SIGNATURE,
+                data: data.to_vec(),
+            },
+        });
+    }
+
+    let tempo_map: Vec<(f64, f64)> = match (song.tempo_map.is_empty(), song.tempo) {
+        (false, _) => song
+            .tempo_map
+            .iter()
+            .map(|change| (change.beat, change.tempo))
+            .collect(),
+        (true, Some(tempo)) => vec![(0.0, tempo)],
+        (true, None) => vec![],
+    };
+
+    let mut previous = 0;
+
+    for (beat, tempo) in tempo_map {
+        let tick = (beat * division as f64).round() as u64;
+        let tempo = ((60_000_000.0 / tempo).round() as u32).min(0xFF_FFFF);
+
+        events.push(MidiEvent {
+            delta: (tick - previous) as u32,
+            message: MidiMessage::Meta {
+                kind: META_TEMPO,
+                data: tempo.to_be_bytes()[1..].to_vec(),
+            },
+        });
+        previous = tick;
+    }
+
+    events.push(MidiEvent {
+        delta: 0,
+        message: MidiMessage::Meta {
+            kind: META_END_OF_TRACK,
+            data: vec![],
+        },
+    });
+
+    events
+}
+
+/// Returns the data of the key signature event of a key, e.g. "Bb" or "F#m", None if it is not a key.
+///
+/// Keys without a signature of at most 7 sharps or flats use their enharmonic equivalent, e.g. "A#" is written as "Bb".
+fn key_signature_data(key: &str) -> Option<[u8; 2]> {
+    let (tonic, minor) = match key.strip_suffix('m') {
+        Some(tonic) => (tonic, true),
+        None => (key, false),
+    };
+
+    let mut chars = tonic.chars();
+    let mut pitch_class: i32 = match chars.next()? {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+
+    for accidental in chars {
+        pitch_class += match accidental {
+            '#' => 1,
+            'b' => -1,
+            _ => return None,
+        };
+    }
+
+    let names = if minor { &MINOR_KEYS } else { &MAJOR_KEYS };
+    let relative = if minor { 9 } else { 0 };
+
+    let sharps = match names.iter().position(|name| *name == key) {
+        Some(index) => index as i32 - 7,
+        None => (-6..=6)
+            .filter(|sharps: &i32| (sharps * 7 + relative - pitch_class).rem_euclid(12) == 0)
+            .min_by_key(|sharps| sharps.abs())?,
+    };
+
+    Some([sharps as i8 as u8, minor as u8])
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_entity::song::TempoChange;
+
+    use crate::metadata::{key_signature, tempo_map, time_signature};
+    use crate::smf::{parse_midi, write_midi};
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) export a song with a tempo map and two clips of different divisions.
+    /// 2) parse the written file, the metadata of the song should be extracted back.
+    /// 3) check that the clip events are rescaled and their tempo events replaced.
+    /// 4) export a song without clips and with an enharmonic key.
+    #[test]
+    fn test_song_midi_file() {
+        let note = |delta, key| MidiEvent {
+            delta,
+            message: MidiMessage::NoteOn {
+                channel: 0,
+                key,
+                velocity: 100,
+            },
+        };
+        let tempo = |delta| MidiEvent {
+            delta,
+            message: MidiMessage::Meta {
+                kind: META_TEMPO,
+                data: vec![0x07, 0xA1, 0x20],
+            },
+        };
+
+        // 1) export a song with a tempo map and two clips of different divisions.
+        let song = Song {
+            title: "Segfault Blues".to_string(),
+            key: Some("Ebm".to_string()),
+            tempo: Some(90.0),
+            tempo_map: vec![
+                TempoChange {
+                    beat: 0.0,
+                    tempo: 90.0,
+                },
+                TempoChange {
+                    beat: 16.5,
+                    tempo: 150.0,
+                },
+            ],
+            time_signature: "12/8".to_string(),
+            ..Default::default()
+        };
+        let bass = MidiFile {
+            format: 0,
+            division: 96,
+            tracks: vec![vec![tempo(0), note(0, 40), note(96, 43)]],
+        };
+        let keys = MidiFile {
+            format: 1,
+            division: 480,
+            tracks: vec![vec![tempo(0)], vec![note(240, 60), note(240, 64)]],
+        };
+
+        let file = song_midi_file(&song, &[bass, keys]);
+        assert_eq!(file.format, 1);
+        assert_eq!(file.division, 480);
+        assert_eq!(file.tracks.len(), 4);
+
+        // 2) parse the written file, the metadata of the song should be extracted back.
+        let parsed = parse_midi(&write_midi(&file)).unwrap();
+        assert_eq!(tempo_map(&parsed), song.tempo_map);
+        assert_eq!(time_signature(&parsed), Some("12/8".to_string()));
+        assert_eq!(key_signature(&parsed), Some("Ebm".to_string()));
+        assert_eq!(
+            parsed.tracks[0][0].message,
+            MidiMessage::Meta {
+                kind: META_TRACK_NAME,
+                data: b"Segfault Blues".to_vec()
+            }
+        );
+
+        // 3) check that the clip events are rescaled and their tempo events replaced.
+        assert_eq!(parsed.tracks[1][..2], [note(0, 40), note(480, 43)]);
+        assert_eq!(parsed.tracks[2].len(), 1);
+        assert_eq!(parsed.tracks[3][..2], [note(240, 60), note(240, 64)]);
+
+        // 4) export a song without clips and with an enharmonic key.
+        let song = Song {
+            key: Some("A#".to_string()),
+            tempo: Some(120.0),
+            tempo_map: vec![],
+            ..song
+        };
+        let file = song_midi_file(&song, &[]);
+        assert_eq!(file.division, DEFAULT_DIVISION);
+        assert_eq!(key_signature(&file), Some("Bb".to_string()));
+        assert_eq!(
+            tempo_map(&file),
+            vec![TempoChange {
+                beat: 0.0,
+                tempo: 120.0
+            }]
+        );
+        assert_eq!(key_signature_data("H"), None);
+        assert_eq!(key_signature_data("Cbm"), Some([2, 1]));
+    }
+}
@@ -0,0 +1,289 @@
+use openmusicgang_entity::song::{is_time_signature, Song, TempoChange};
+
+use crate::smf::{MidiFile, MidiMessage, META_KEY_SIGNATURE, META_TEMPO, META_TIME_SIGNATURE};
+
+/// Tempo of a MIDI file before its first tempo event, in microseconds per quarter note (120 bpm).
+pub const DEFAULT_TEMPO: u32 = 500_000;
+
+/// Names of the major and minor keys by number of sharps, from 7 flats to 7 sharps.
+pub(crate) const MAJOR_KEYS: [&str; 15] = [
+    "Cb", "Gb", "Db", "Ab", "Eb", "Bb", "F", "C", "G", "D", "A", "E", "B", "F#", "C#",
+];
+pub(crate) const MINOR_KEYS: [&str; 15] = [
+    "Abm", "Ebm", "Bbm", "Fm", "Cm", "Gm", "Dm", "Am", "Em", "Bm", "F#m", "C#m", "G#m", "D#m",
+    "A#m",
+];
+
+/// Returns the messages of every track of a file with their position in ticks, in order.
+///
+/// Messages at the same position keep the order of their tracks.
+pub fn timed_messages(file: &MidiFile) -> Vec<(u64, &MidiMessage)> {
+    let mut messages = vec![];
+
+    for track in &file.tracks {
+        let mut tick = 0;
+
+        for event in track {
+            tick += event.delta as u64;
+            messages.push((tick, &event.message));
+        }
+    }
+
+    messages.sort_by_key(|(tick, _)| *tick);
+    messages
+}
+
+/// Returns the tempo changes of a file, empty if it has no tempo event.
+///
+/// The default tempo of 120 bpm starts the map if the first tempo event is not at the start.
+pub fn tempo_map(file: &MidiFile) -> Vec<TempoChange> {
+    let mut tempo_map: Vec<TempoChange> = vec![];
+
+    for (tick, tempo) in tempo_events(file) {
+        let change = TempoChange {
+            beat: tick as f64 / file.division as f64,
+            tempo: (60_000_000_000.0 / tempo as f64).round() / 1000.0,
+        };
+
+        if tempo_map.is_empty() && tick > 0 {
+            tempo_map.push(TempoChange {
+                beat: 0.0,
+                tempo: 60_000_000.0 / DEFAULT_TEMPO as f64,
+            });
+        }
+
+        match tempo_map.last_mut() {
+            Some(last) if last.beat == change.beat => *last = change,
+            Some(last) if last.tempo == change.tempo => {}
+            _ => tempo_map.push(change),
+        }
+    }
+
+    tempo_map
+}
+
+/// Returns the position in ticks and the tempo in microseconds per quarter note of the tempo events of a file.
+fn tempo_events(file: &MidiFile) -> Vec<(u64, u32)> {
+    timed_messages(file)
+        .into_iter()
+        .filter_map(|(tick, message)| match message {
+            MidiMessage::Meta { kind, data } if *kind == META_TEMPO && data.len() == 3 => {
+                let tempo = u32::from_be_bytes([0, data[0], data[1], data[2]]);
+                (tempo > 0).then_some((tick, tempo))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the first time signature of a file, e.g. "6/8".
+pub fn time_signature(file: &MidiFile) -> Option<String> {
+    timed_messages(file)
+        .into_iter()
+        .find_map(|(_, message)| match message {
+            MidiMessage::Meta { kind, data } if *kind == META_TIME_SIGNATURE && data.len() >= 2 => {
+                let unit = 1u32.checked_shl(data[1] as u32)?;
+                let time_signature = format!("{}/{}", data[0], unit);
+                is_time_signature(&time_signature).then_some(time_signature)
+            }
+            _ => None,
+        })
+}
+
+/// Returns the first key signature of a file, written like the key of a song, e.g. "Bb" or "F#m".
+pub fn key_signature(file: &MidiFile) -> Option<String> {
+    timed_messages(file)
+        .into_iter()
+        .find_map(|(_, message)| match message {
+            MidiMessage::Meta { kind, data } if *kind == META_KEY_SIGNATURE && data.len() == 2 => {
+                let index = usize::try_from(data[0] as i8 as i32 + 7).ok()?;
+                match data[1] {
+                    0 => MAJOR_KEYS.get(index),
+                    1 => MINOR_KEYS.get(index),
+                    _ => None,
+                }
+                .map(|key| key.to_string())
+            }
+            _ => None,
+        })
+}
+
+/// Sets the tempo, time signature and key of a song to the ones of a file, the ones missing from the file are kept.
+///
+/// The tempo map of the song is only kept when the tempo of the file changes.
+pub fn import_song_metadata(file: &MidiFile, song: &mut Song) {
+    let tempo_map = tempo_map(file);
+
+    if let Some(first) = tempo_map.first() {
+        song.tempo = Some(first.tempo);
+        song.tempo_map = if tempo_map.len() > 1 {
+            tempo_map
+        } else {
+            vec![]
+        };
+    }
+
+    if let Some(time_signature) = time_signature(file) {
+        song.time_signature = time_signature;
+    }
+
+    if let Some(key) = key_signature(file) {
+        song.key = Some(key);
+    }
+}
+
+/// Returns the number of notes played in a file.
+pub fn note_count(file: &MidiFile) -> i32 {
+    file.tracks
+        .iter()
+        .flatten()
+        .filter(
+            |event| matches!(event.message, MidiMessage::NoteOn { velocity, .. } if velocity > 0),
+        )
+        .count() as i32
+}
+
+/// Returns the duration of a file in seconds, up to the end of its longest track.
+pub fn duration(file: &MidiFile) -> f64 {
+    let end = file
+        .tracks
+        .iter()
+        .map(|track| track.iter().map(|event| event.delta as u64).sum::<u64>())
+        .max()
+        .unwrap_or(0);
+
+    let mut seconds = 0.0;
+    let mut tick = 0;
+    let mut tempo = DEFAULT_TEMPO;
+
+    for (change_tick, change_tempo) in tempo_events(file) {
+        if change_tick >= end {
+            break;
+        }
+
+        seconds += ticks_seconds(change_tick - tick, tempo, file.division);
+        tick = change_tick;
+        tempo = change_tempo;
+    }
+
+    seconds + ticks_seconds(end - tick, tempo, file.division)
+}
+
+fn ticks_seconds(ticks: u64, tempo: u32, division: u16) -> f64 {
+    ticks as f64 * tempo as f64 / division as f64 / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::smf::{MidiEvent, META_END_OF_TRACK};
+
+    use super::*;
+
+    fn meta(delta: u32, kind: u8, data: Vec<u8>) -> MidiEvent {
+        MidiEvent {
+            delta,
+            message: MidiMessage::Meta { kind, data },
+        }
+    }
+
+    fn note(delta: u32, key: u8, velocity: u8) -> MidiEvent {
+        MidiEvent {
+            delta,
+            message: MidiMessage::NoteOn {
+                channel: 0,
+                key,
+                velocity,
+            },
+        }
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) extract the tempo map of a conductor track, the default tempo should start it.
+    /// 2) extract the time and key signatures, the first valid ones should be kept.
+    /// 3) count the notes and compute the duration along the tempo changes.
+    /// 4) import the metadata into a song.
+    /// 5) extract the metadata of a file without meta events, the song should be unchanged.
+    #[test]
+    fn test_metadata() {
+        let file = MidiFile {
+            format: 1,
+            division: 480,
+            tracks: vec![
+                vec![
+                    meta(0, META_TIME_SIGNATURE, vec![7, 9, 24, 8]),
+                    meta(0, META_TIME_SIGNATURE, vec![6, 3, 24, 8]),
+                    meta(0, META_KEY_SIGNATURE, vec![(-2i8) as u8, 1]),
+                    meta(960, META_TEMPO, vec![0x09, 0x27, 0xC0]),
+                    meta(960, META_TEMPO, vec![0x09, 0x27, 0xC0]),
+                    meta(480, META_TEMPO, vec![0x03, 0xD0, 0x90]),
+                    meta(0, META_KEY_SIGNATURE, vec![3, 0]),
+                    meta(0, META_END_OF_TRACK, vec![]),
+                ],
+                vec![
+                    note(0, 60, 100),
+                    note(480, 60, 0),
+                    note(0, 64, 90),
+                    note(3360, 64, 0),
+                    meta(0, META_END_OF_TRACK, vec![]),
+                ],
+            ],
+        };
+
+        // 1) extract the tempo map of a conductor track, the default tempo should start it.
+        assert_eq!(
+            tempo_map(&file),
+            vec![
+                TempoChange {
+                    beat: 0.0,
+                    tempo: 120.0
+                },
+                TempoChange {
+                    beat: 2.0,
+                    tempo: 100.0
+                },
+                TempoChange {
+                    beat: 5.0,
+                    tempo: 240.0
+                },
+            ]
+        );
+
+        // 2) extract the time and key signatures, the first valid ones should be kept.
+        assert_eq!(time_signature(&file), Some("6/8".to_string()));
+        assert_eq!(key_signature(&file), Some("Gm".to_string()));
+
+        // 3) count the notes and compute the duration along the tempo changes.
+        assert_eq!(note_count(&file), 2);
+        assert!((duration(&file) - (1.0 + 1.8 + 0.75)).abs() < 1e-9);
+
+        // 4) import the metadata into a song.
+        let mut song = Song {
+            title: "Segfault Blues".to_string(),
+            key: Some("E".to_string()),
+            tempo: Some(90.0),
+            ..Default::default()
+        };
+        import_song_metadata(&file, &mut song);
+        assert_eq!(song.tempo, Some(120.0));
+        assert_eq!(song.tempo_map, tempo_map(&file));
+        assert_eq!(song.time_signature, "6/8");
+        assert_eq!(song.key, Some("Gm".to_string()));
+
+        // 5) extract the metadata of a file without meta events, the song should be unchanged.
+        let file = MidiFile {
+            format: 0,
+            division: 96,
+            tracks: vec![vec![note(0, 60, 100), note(192, 60, 0)]],
+        };
+        assert_eq!(tempo_map(&file), vec![]);
+        assert_eq!(time_signature(&file), None);
+        assert_eq!(key_signature(&file), None);
+        assert_eq!(duration(&file), 1.0);
+
+        let unchanged = song.clone();
+        import_song_metadata(&file, &mut song);
+        assert_eq!(song, unchanged);
+    }
+}
@@ -0,0 +1,568 @@
+use openmusicgang_err::error::{Error, ErrorCode};
+
+/// Type of the meta event giving the name of a track.
+pub const META_TRACK_NAME: u8 = 0x03;
+
+/// Type of the meta event ending a track.
+pub const META_END_OF_TRACK: u8 = 0x2F;
+
+/// Type of the meta event setting the tempo, in microseconds per quarter note.
+pub const META_TEMPO: u8 = 0x51;
+
+/// Type of the meta event setting the time signature.
+pub const META_TIME_SIGNATURE: u8 = 0x58;
+
+/// Type of the meta event setting the key signature.
+pub const META_KEY_SIGNATURE: u8 = 0x59;
+
+/// MidiFile is a Standard MIDI File of format 0 or 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiFile {
+    /// Format of the file, 0 for a single track and 1 for simultaneous tracks.
+    pub format: u16,
+    /// Number of ticks per quarter note.
+    pub division: u16,
+    pub tracks: Vec<Vec<MidiEvent>>,
+}
+
+/// MidiEvent is an event of a track of a MIDI file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiEvent {
+    /// Number of ticks since the previous event of the track.
+    pub delta: u32,
+    pub message: MidiMessage,
+}
+
+/// MidiMessage is the content of an event of a MIDI file.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MidiMessage {
+    NoteOff {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    NoteOn {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    KeyPressure {
+        channel: u8,
+        key: u8,
+        pressure: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    ProgramChange {
+        channel: u8,
+        program: u8,
+    },
+    ChannelPressure {
+        channel: u8,
+        pressure: u8,
+    },
+    /// Pitch bend from 0 to 16383, 8192 being the center.
+    PitchBend {
+        channel: u8,
+        value: u16,
+    },
+    /// System exclusive message, the bytes following the F0 status.
+    SysEx(Vec<u8>),
+    /// Escaped bytes sent as they are, following the F7 status.
+    Escape(Vec<u8>),
+    Meta {
+        kind: u8,
+        data: Vec<u8>,
+    },
+}
+
+impl MidiMessage {
+    /// Returns true if the message is the meta event of the given type.
+    pub fn is_meta(&self, meta_kind: u8) -> bool {
+        matches!(self, MidiMessage::Meta { kind, .. } if *kind == meta_kind)
+    }
+}
+
+/// Returns the MIDI file of its Standard MIDI File encoding.
+///
+/// Unknown chunks are skipped, running status is supported.
+///
+/// Returns EINVALID if the file is not a valid MIDI file, is of format 2 or has a SMPTE time division.
+pub fn parse_midi(buf: &[u8]) -> Result<MidiFile, Error> {
+    let mut cursor = buf;
+
+    if take(&mut cursor, 4)? != b"MThd" {
+        return Err(corrupt_midi());
+    }
+
+    let length = take_u32(&mut cursor)? as usize;
+    let mut header = take(&mut cursor, length)?;
+    let format = take_u16(&mut header)?;
+    let track_count = take_u16(&mut header)?;
+    let division = take_u16(&mut header)?;
+
+    if format > 1 {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            format!("Unsupported MIDI format {}", format),
+        ));
+    }
+
+    if division & 0x8000 != 0 {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "SMPTE time division is not supported".to_string(),
+        ));
+    }
+
+    if division == 0 || (format == 0 && track_count != 1) {
+        return Err(corrupt_midi());
+    }
+
+    let mut tracks = vec![];
+
+    while tracks.len() < track_count as usize {
+        let id = take(&mut cursor, 4)?;
+        let length = take_u32(&mut cursor)? as usize;
+        let chunk = take(&mut cursor, length)?;
+
+        if id == b"MTrk" {
+            tracks.push(parse_track(chunk)?);
+        }
+    }
+
+    Ok(MidiFile {
+        format,
+        division,
+        tracks,
+    })
+}
+
+/// Returns the events of a track chunk, up to its end of track event.
+fn parse_track(mut cursor: &[u8]) -> Result<Vec<MidiEvent>, Error> {
+    let mut events = vec![];
+    let mut running_status = None;
+
+    while !cursor.is_empty() {
+        let delta = take_vlq(&mut cursor)?;
+
+        let status = match cursor.first() {
+            Some(byte) if byte & 0x80 != 0 => {
+                cursor = &cursor[1..];
+                *byte
+            }
+            _ => running_status.ok_or_else(corrupt_midi)?,
+        };
+
+        let message = match status {
+            0xFF => {
+                running_status = None;
+                let kind = take(&mut cursor, 1)?[0];
+                let length = take_vlq(&mut cursor)? as usize;
+                MidiMessage::Meta {
+                    kind,
+                    data: take(&mut cursor, length)?.to_vec(),
+                }
+            }
+            0xF0 | 0xF7 => {
+                running_status = None;
+                let length = take_vlq(&mut cursor)? as usize;
+                let data = take(&mut cursor, length)?.to_vec();
+                if status == 0xF0 {
+                    MidiMessage::SysEx(data)
+                } else {
+                    MidiMessage::Escape(data)
+                }
+            }
+            0x80..=0xEF => {
+                running_status = Some(status);
+                parse_channel_message(status, &mut cursor)?
+            }
+            _ => return Err(corrupt_midi()),
+        };
+
+        let end = message.is_meta(META_END_OF_TRACK);
+        events.push(MidiEvent { delta, message });
+
+        if end {
+            break;
+        }
+    }
+
+    Ok(events)
+}
+
+/// Returns the channel message of the given status, reading its data bytes from the cursor.
+fn parse_channel_message(status: u8, cursor: &mut &[u8]) -> Result<MidiMessage, Error> {
+    let channel = status & 0x0F;
+    let length = if matches!(status & 0xF0, 0xC0 | 0xD0) {
+        1
+    } else {
+        2
+    };
+
+    let data = take(cursor, length)?;
+    if data.iter().any(|byte| byte & 0x80 != 0) {
+        return Err(corrupt_midi());
+    }
+
+    Ok(match status & 0xF0 {
+        0x80 => MidiMessage::NoteOff {
+            channel,
+            key: data[0],
+            velocity: data[1],
+        },
+        0x90 => MidiMessage::NoteOn {
+            channel,
+            key: data[0],
+            velocity: data[1],
+        },
+        0xA0 => MidiMessage::KeyPressure {
+            channel,
+            key: data[0],
+            pressure: data[1],
+        },
+        0xB0 => MidiMessage::ControlChange {
+            channel,
+            controller: data[0],
+            value: data[1],
+        },
+        0xC0 => MidiMessage::ProgramChange {
+            channel,
+            program: data[0],
+        },
+        0xD0 => MidiMessage::ChannelPressure {
+            channel,
+            pressure: data[0],
+        },
+        _ => MidiMessage::PitchBend {
+            channel,
+            value: data[0] as u16 | (data[1] as u16) << 7,
+        },
+    })
+}
+
+/// Returns the Standard MIDI File encoding of a MIDI file.
+///
+/// Channel messages are written with running status, an end of track event is added to the tracks
+/// that do not end with one.
+pub fn write_midi(file: &MidiFile) -> Vec<u8> {
+    let mut buf = vec![];
+
+    buf.extend(b"MThd");
+    buf.extend(6u32.to_be_bytes());
+    buf.extend(file.format.to_be_bytes());
+    buf.extend((file.tracks.len() as u16).to_be_bytes());
+    buf.extend(file.division.to_be_bytes());
+
+    for track in &file.tracks {
+        let mut chunk = vec![];
+        let mut running_status = None;
+
+        for event in track {
+            write_vlq(&mut chunk, event.delta);
+            write_message(&mut chunk, &event.message, &mut running_status);
+        }
+
+        if !track
+            .last()
+            .is_some_and(|event| event.message.is_meta(META_END_OF_TRACK))
+        {
+            write_vlq(&mut chunk, 0);
+            write_message(
+                &mut chunk,
+                &MidiMessage::Meta {
+                    kind: META_END_OF_TRACK,
+                    data: vec![],
+                },
+                &mut running_status,
+            );
+        }
+
+        buf.extend(b"MTrk");
+        buf.extend((chunk.len() as u32).to_be_bytes());
+        buf.extend(chunk);
+    }
+
+    buf
+}
+
+fn write_message(buf: &mut Vec<u8>, message: &MidiMessage, running_status: &mut Option<u8>) {
+    let (status, data) = match message {
+        MidiMessage::NoteOff {
+            channel,
+            key,
+            velocity,
+        } => (0x80 | channel, vec![*key, *velocity]),
+        MidiMessage::NoteOn {
+            channel,
+            key,
+            velocity,
+        } => (0x90 | channel, vec![*key, *velocity]),
+        MidiMessage::KeyPressure {
+            channel,
+            key,
+            pressure,
+        } => (0xA0 | channel, vec![*key, *pressure]),
+        MidiMessage::ControlChange {
+            channel,
+            controller,
+            value,
+        } => (0xB0 | channel, vec![*controller, *value]),
+        MidiMessage::ProgramChange { channel, program } => (0xC0 | channel, vec![*program]),
+        MidiMessage::ChannelPressure { channel, pressure } => (0xD0 | channel, vec![*pressure]),
+        MidiMessage::PitchBend { channel, value } => (
+            0xE0 | channel,
+            vec![(value & 0x7F) as u8, (value >> 7 & 0x7F) as u8],
+        ),
+        MidiMessage::SysEx(data) | MidiMessage::Escape(data) => {
+            *running_status = None;
+            buf.push(if matches!(message, MidiMessage::SysEx(_)) {
+                0xF0
+            } else {
+                0xF7
+            });
+            write_vlq(buf, data.len() as u32);
+            buf.extend(data);
+            return;
+        }
+        MidiMessage::Meta { kind, data } => {
+            *running_status = None;
+            buf.extend([0xFF, *kind]);
+            write_vlq(buf, data.len() as u32);
+            buf.extend(data);
+            return;
+        }
+    };
+
+    if *running_status != Some(status) {
+        buf.push(status);
+        *running_status = Some(status);
+    }
+
+    buf.extend(data);
+}
+
+/// Writes a variable-length quantity, 7 bits per byte with the most significant first.
+fn write_vlq(buf: &mut Vec<u8>, value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut value = value >> 7;
+
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+
+    buf.extend(bytes.iter().rev());
+}
+
+/// Reads a variable-length quantity of at most 4 bytes.
+fn take_vlq(cursor: &mut &[u8]) -> Result<u32, Error> {
+    let mut value = 0;
+
+    for _ in 0..4 {
+        let byte = take(cursor, 1)?[0];
+        value = value << 7 | (byte & 0x7F) as u32;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(corrupt_midi())
+}
+
+/// Returns the next n bytes of the cursor and advances it.
+fn take<'a>(cursor: &mut &'a [u8], n: usize) -> Result<&'a [u8], Error> {
+    if cursor.len() < n {
+        return Err(corrupt_midi());
+    }
+
+    let (head, tail) = cursor.split_at(n);
+    *cursor = tail;
+
+    Ok(head)
+}
+
+fn take_u16(cursor: &mut &[u8]) -> Result<u16, Error> {
+    Ok(u16::from_be_bytes(take(cursor, 2)?.try_into().unwrap()))
+}
+
+fn take_u32(cursor: &mut &[u8]) -> Result<u32, Error> {
+    Ok(u32::from_be_bytes(take(cursor, 4)?.try_into().unwrap()))
+}
+
+fn corrupt_midi() -> Error {
+    Error::new(ErrorCode::EINVALID, "Corrupt MIDI file".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) parse a format 0 file written with running status and an unknown chunk.
+    /// 2) write it back, the bytes should be identical.
+    /// 3) write and parse a format 1 file with every kind of message, it should be unchanged.
+    /// 4) parse truncated, format 2 and SMPTE files, error should be EINVALID.
+    #[test]
+    fn test_parse_write_midi() {
+        // 1) parse a format 0 file written with running status and an unknown chunk.
+        #[rustfmt::skip]
+        let track = [
+            0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20,
+            0x00, 0x90, 0x3C, 0x64,
+            0x00, 0x40, 0x64,
+            0x83, 0x60, 0x3C, 0x00,
+            0x00, 0x40, 0x00,
+            0x00, 0xFF, 0x2F, 0x00,
+        ];
+        let mut content = vec![];
+        content.extend(b"MThd");
+        content.extend([0, 0, 0, 6, 0, 0, 0, 1, 0x01, 0xE0]);
+        content.extend(b"XFIH");
+        content.extend([0, 0, 0, 2, 0xAA, 0xBB]);
+        content.extend(b"MTrk");
+        content.extend([0, 0, 0, track.len() as u8]);
+        content.extend(track);
+
+        let file = parse_midi(&content).unwrap();
+        assert_eq!(file.format, 0);
+        assert_eq!(file.division, 480);
+        assert_eq!(file.tracks.len(), 1);
+        assert_eq!(file.tracks[0].len(), 6);
+        assert_eq!(
+            file.tracks[0][0].message,
+            MidiMessage::Meta {
+                kind: META_TEMPO,
+                data: vec![0x07, 0xA1, 0x20]
+            }
+        );
+        assert_eq!(
+            file.tracks[0][2].message,
+            MidiMessage::NoteOn {
+                channel: 0,
+                key: 0x40,
+                velocity: 0x64
+            }
+        );
+        assert_eq!(file.tracks[0][3].delta, 480);
+
+        // 2) write it back, the bytes should be identical.
+        let written = write_midi(&file);
+        assert_eq!(&written[0..14], &content[0..14]);
+        assert_eq!(&written[14..], &content[24..]);
+
+        // 3) write and parse a format 1 file with every kind of message, it should be unchanged.
+        let event = |delta, message| MidiEvent { delta, message };
+        let file = MidiFile {
+            format: 1,
+            division: 96,
+            tracks: vec![
+                vec![
+                    event(
+                        0,
+                        MidiMessage::Meta {
+                            kind: META_TIME_SIGNATURE,
+                            data: vec![6, 3, 24, 8],
+                        },
+                    ),
+                    event(
+                        0,
+                        MidiMessage::Meta {
+                            kind: META_END_OF_TRACK,
+                            data: vec![],
+                        },
+                    ),
+                ],
+                vec![
+                    event(0, MidiMessage::SysEx(vec![0x7E, 0x7F, 0x09, 0x01, 0xF7])),
+                    event(
+                        0,
+                        MidiMessage::ProgramChange {
+                            channel: 9,
+                            program: 0,
+                        },
+                    ),
+                    event(
+                        0,
+                        MidiMessage::ControlChange {
+                            channel: 9,
+                            controller: 7,
+                            value: 100,
+                        },
+                    ),
+                    event(
+                        200_000,
+                        MidiMessage::PitchBend {
+                            channel: 1,
+                            value: 16383,
+                        },
+                    ),
+                    event(
+                        1,
+                        MidiMessage::KeyPressure {
+                            channel: 1,
+                            key: 60,
+                            pressure: 3,
+                        },
+                    ),
+                    event(
+                        1,
+                        MidiMessage::ChannelPressure {
+                            channel: 1,
+                            pressure: 4,
+                        },
+                    ),
+                    event(2, MidiMessage::Escape(vec![0xF8])),
+                    event(
+                        3,
+                        MidiMessage::NoteOff {
+                            channel: 1,
+                            key: 60,
+                            velocity: 64,
+                        },
+                    ),
+                    event(
+                        0,
+                        MidiMessage::Meta {
+                            kind: META_END_OF_TRACK,
+                            data: vec![],
+                        },
+                    ),
+                ],
+            ],
+        };
+        assert_eq!(parse_midi(&write_midi(&file)).unwrap(), file);
+
+        let mut unterminated = file.clone();
+        unterminated.tracks[1].pop();
+        assert_eq!(parse_midi(&write_midi(&unterminated)).unwrap(), file);
+
+        // 4) parse truncated, format 2 and SMPTE files, error should be EINVALID.
+        let res = parse_midi(&content[..content.len() - 3]);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let mut format_2 = content.clone();
+        format_2[9] = 2;
+        let res = parse_midi(&format_2);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().message, "Unsupported MIDI format 2");
+
+        let mut smpte = content.clone();
+        smpte[12] = 0xE7;
+        let res = parse_midi(&smpte);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let res = parse_midi(b"RIFF");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+    }
+}
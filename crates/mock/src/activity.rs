@@ -0,0 +1,21 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::activity::FeedPage;
+use openmusicgang_err::error::Error;
+use openmusicgang_service::activity_service::{
+    ActivityService as ActivityServiceTrait, FeedFilter,
+};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct ActivityService {
+    pub find_feed_fn: Option<fn(AppContext, FeedFilter) -> Result<FeedPage, Error>>,
+}
+
+impl ActivityServiceTrait for ActivityService {
+    fn find_feed(&self, ctx: AppContext, filters: FeedFilter) -> Result<FeedPage, Error> {
+        if let Some(f) = self.find_feed_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_feed_fn not set");
+    }
+}
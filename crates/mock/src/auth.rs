@@ -0,0 +1,44 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::session::Session;
+use openmusicgang_entity::user::User;
+use openmusicgang_err::error::Error;
+use openmusicgang_service::auth_service::AuthService as AuthServiceTrait;
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct AuthService {
+    pub login_fn: Option<fn(AppContext, String, String) -> Result<Session, Error>>,
+    pub logout_fn: Option<fn(AppContext, String) -> Result<(), Error>>,
+    pub resolve_session_fn: Option<fn(AppContext, String) -> Result<(Session, User), Error>>,
+    pub find_sessions_fn: Option<fn(AppContext, i64) -> Result<Vec<Session>, Error>>,
+}
+
+impl AuthServiceTrait for AuthService {
+    fn login(&self, ctx: AppContext, email: String, password: String) -> Result<Session, Error> {
+        if let Some(f) = self.login_fn {
+            return f(ctx, email, password);
+        }
+        panic!("login_fn not set");
+    }
+
+    fn logout(&self, ctx: AppContext, token: String) -> Result<(), Error> {
+        if let Some(f) = self.logout_fn {
+            return f(ctx, token);
+        }
+        panic!("logout_fn not set");
+    }
+
+    fn resolve_session(&self, ctx: AppContext, token: String) -> Result<(Session, User), Error> {
+        if let Some(f) = self.resolve_session_fn {
+            return f(ctx, token);
+        }
+        panic!("resolve_session_fn not set");
+    }
+
+    fn find_sessions(&self, ctx: AppContext, user_id: i64) -> Result<Vec<Session>, Error> {
+        if let Some(f) = self.find_sessions_fn {
+            return f(ctx, user_id);
+        }
+        panic!("find_sessions_fn not set");
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::Read;
+
+use openmusicgang_entity::blob::Blob;
+use openmusicgang_err::error::Error;
+use openmusicgang_service::blob_store::{BlobStore as BlobStoreTrait, ByteRange};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct BlobStore {
+    pub put_blob_fn: Option<fn(&mut dyn Read) -> Result<Blob, Error>>,
+    pub get_blob_fn:
+        Option<fn(&str, Option<ByteRange>) -> Result<Box<dyn Read + Send + Sync>, Error>>,
+    pub delete_blob_fn: Option<fn(&str) -> Result<(), Error>>,
+    pub stat_blob_fn: Option<fn(&str) -> Result<Blob, Error>>,
+    pub list_blobs_fn: Option<fn(&str) -> Result<Vec<Blob>, Error>>,
+}
+
+impl BlobStoreTrait for BlobStore {
+    fn put_blob(&self, reader: &mut dyn Read) -> Result<Blob, Error> {
+        if let Some(f) = self.put_blob_fn {
+            return f(reader);
+        }
+        panic!("put_blob_fn not set");
+    }
+
+    fn get_blob(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<Box<dyn Read + Send + Sync>, Error> {
+        if let Some(f) = self.get_blob_fn {
+            return f(key, range);
+        }
+        panic!("get_blob_fn not set");
+    }
+
+    fn delete_blob(&self, key: &str) -> Result<(), Error> {
+        if let Some(f) = self.delete_blob_fn {
+            return f(key);
+        }
+        panic!("delete_blob_fn not set");
+    }
+
+    fn stat_blob(&self, key: &str) -> Result<Blob, Error> {
+        if let Some(f) = self.stat_blob_fn {
+            return f(key);
+        }
+        panic!("stat_blob_fn not set");
+    }
+
+    fn list_blobs(&self, prefix: &str) -> Result<Vec<Blob>, Error> {
+        if let Some(f) = self.list_blobs_fn {
+            return f(prefix);
+        }
+        panic!("list_blobs_fn not set");
+    }
+}
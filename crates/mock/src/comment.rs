@@ -0,0 +1,87 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::comment::{Comment, CommentEdit};
+use openmusicgang_err::error::Error;
+use openmusicgang_service::comment_service::{
+    CommentFilter, CommentService as CommentServiceTrait, CommentUpdate,
+};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct CommentService {
+    pub create_comment_fn: Option<fn(AppContext, &mut Comment) -> Result<(), Error>>,
+    pub update_comment_fn: Option<fn(AppContext, i64, CommentUpdate) -> Result<Comment, Error>>,
+    pub delete_comment_fn: Option<fn(AppContext, i64) -> Result<(), Error>>,
+    pub resolve_comment_fn: Option<fn(AppContext, i64) -> Result<Comment, Error>>,
+    pub unresolve_comment_fn: Option<fn(AppContext, i64) -> Result<Comment, Error>>,
+    pub find_comment_by_id_fn: Option<fn(AppContext, i64) -> Result<Comment, Error>>,
+    pub find_comment_edits_fn: Option<fn(AppContext, i64) -> Result<Vec<CommentEdit>, Error>>,
+    pub find_comments_fn:
+        Option<fn(AppContext, CommentFilter) -> Result<(Vec<Comment>, i64), Error>>,
+}
+
+impl CommentServiceTrait for CommentService {
+    fn create_comment(&self, ctx: AppContext, comment: &mut Comment) -> Result<(), Error> {
+        if let Some(f) = self.create_comment_fn {
+            return f(ctx, comment);
+        }
+        panic!("create_comment_fn not set");
+    }
+
+    fn update_comment(
+        &self,
+        ctx: AppContext,
+        id: i64,
+        comment: CommentUpdate,
+    ) -> Result<Comment, Error> {
+        if let Some(f) = self.update_comment_fn {
+            return f(ctx, id, comment);
+        }
+        panic!("update_comment_fn not set");
+    }
+
+    fn delete_comment(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        if let Some(f) = self.delete_comment_fn {
+            return f(ctx, id);
+        }
+        panic!("delete_comment_fn not set");
+    }
+
+    fn resolve_comment(&self, ctx: AppContext, id: i64) -> Result<Comment, Error> {
+        if let Some(f) = self.resolve_comment_fn {
+            return f(ctx, id);
+        }
+        panic!("resolve_comment_fn not set");
+    }
+
+    fn unresolve_comment(&self, ctx: AppContext, id: i64) -> Result<Comment, Error> {
+        if let Some(f) = self.unresolve_comment_fn {
+            return f(ctx, id);
+        }
+        panic!("unresolve_comment_fn not set");
+    }
+
+    fn find_comment_by_id(&self, ctx: AppContext, id: i64) -> Result<Comment, Error> {
+        if let Some(f) = self.find_comment_by_id_fn {
+            return f(ctx, id);
+        }
+        panic!("find_comment_by_id_fn not set");
+    }
+
+    fn find_comment_edits(&self, ctx: AppContext, id: i64) -> Result<Vec<CommentEdit>, Error> {
+        if let Some(f) = self.find_comment_edits_fn {
+            return f(ctx, id);
+        }
+        panic!("find_comment_edits_fn not set");
+    }
+
+    fn find_comments(
+        &self,
+        ctx: AppContext,
+        filters: CommentFilter,
+    ) -> Result<(Vec<Comment>, i64), Error> {
+        if let Some(f) = self.find_comments_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_comments_fn not set");
+    }
+}
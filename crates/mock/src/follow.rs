@@ -0,0 +1,64 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::follow::{Follow, FollowCounts, FollowTarget};
+use openmusicgang_err::error::Error;
+use openmusicgang_service::follow_service::{FollowFilter, FollowService as FollowServiceTrait};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct FollowService {
+    pub follow_fn: Option<fn(AppContext, FollowTarget) -> Result<Follow, Error>>,
+    pub unfollow_fn: Option<fn(AppContext, FollowTarget) -> Result<(), Error>>,
+    pub find_follows_fn: Option<fn(AppContext, FollowFilter) -> Result<(Vec<Follow>, i64), Error>>,
+    pub is_mutual_follow_fn: Option<fn(AppContext, i64, i64) -> Result<bool, Error>>,
+    pub find_follow_counts_fn: Option<fn(AppContext, FollowTarget) -> Result<FollowCounts, Error>>,
+}
+
+impl FollowServiceTrait for FollowService {
+    fn follow(&self, ctx: AppContext, target: FollowTarget) -> Result<Follow, Error> {
+        if let Some(f) = self.follow_fn {
+            return f(ctx, target);
+        }
+        panic!("follow_fn not set");
+    }
+
+    fn unfollow(&self, ctx: AppContext, target: FollowTarget) -> Result<(), Error> {
+        if let Some(f) = self.unfollow_fn {
+            return f(ctx, target);
+        }
+        panic!("unfollow_fn not set");
+    }
+
+    fn find_follows(
+        &self,
+        ctx: AppContext,
+        filters: FollowFilter,
+    ) -> Result<(Vec<Follow>, i64), Error> {
+        if let Some(f) = self.find_follows_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_follows_fn not set");
+    }
+
+    fn is_mutual_follow(
+        &self,
+        ctx: AppContext,
+        user_id: i64,
+        other_id: i64,
+    ) -> Result<bool, Error> {
+        if let Some(f) = self.is_mutual_follow_fn {
+            return f(ctx, user_id, other_id);
+        }
+        panic!("is_mutual_follow_fn not set");
+    }
+
+    fn find_follow_counts(
+        &self,
+        ctx: AppContext,
+        target: FollowTarget,
+    ) -> Result<FollowCounts, Error> {
+        if let Some(f) = self.find_follow_counts_fn {
+            return f(ctx, target);
+        }
+        panic!("find_follow_counts_fn not set");
+    }
+}
@@ -0,0 +1,61 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::gang::Gang;
+use openmusicgang_err::error::Error;
+use openmusicgang_service::gang_service::{
+    GangFilter, GangService as GangServiceTrait, GangUpdate,
+};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct GangService {
+    pub create_gang_fn: Option<fn(AppContext, &mut Gang) -> Result<(), Error>>,
+    pub delete_gang_fn: Option<fn(AppContext, i64) -> Result<(), Error>>,
+    pub update_gang_fn: Option<fn(AppContext, i64, GangUpdate) -> Result<Gang, Error>>,
+    pub find_gang_by_id_fn: Option<fn(AppContext, i64) -> Result<Gang, Error>>,
+    pub find_gang_by_slug_fn: Option<fn(AppContext, String) -> Result<Gang, Error>>,
+    pub find_gangs_fn: Option<fn(AppContext, GangFilter) -> Result<(Vec<Gang>, i64), Error>>,
+}
+
+impl GangServiceTrait for GangService {
+    fn create_gang(&self, ctx: AppContext, gang: &mut Gang) -> Result<(), Error> {
+        if let Some(f) = self.create_gang_fn {
+            return f(ctx, gang);
+        }
+        panic!("create_gang_fn not set");
+    }
+
+    fn delete_gang(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        if let Some(f) = self.delete_gang_fn {
+            return f(ctx, id);
+        }
+        panic!("delete_gang_fn not set");
+    }
+
+    fn update_gang(&self, ctx: AppContext, id: i64, gang: GangUpdate) -> Result<Gang, Error> {
+        if let Some(f) = self.update_gang_fn {
+            return f(ctx, id, gang);
+        }
+        panic!("update_gang_fn not set");
+    }
+
+    fn find_gang_by_id(&self, ctx: AppContext, id: i64) -> Result<Gang, Error> {
+        if let Some(f) = self.find_gang_by_id_fn {
+            return f(ctx, id);
+        }
+        panic!("find_gang_by_id_fn not set");
+    }
+
+    fn find_gang_by_slug(&self, ctx: AppContext, slug: String) -> Result<Gang, Error> {
+        if let Some(f) = self.find_gang_by_slug_fn {
+            return f(ctx, slug);
+        }
+        panic!("find_gang_by_slug_fn not set");
+    }
+
+    fn find_gangs(&self, ctx: AppContext, filters: GangFilter) -> Result<(Vec<Gang>, i64), Error> {
+        if let Some(f) = self.find_gangs_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_gangs_fn not set");
+    }
+}
@@ -0,0 +1,68 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::lead_sheet::LeadSheet;
+use openmusicgang_err::error::Error;
+use openmusicgang_service::lead_sheet_service::LeadSheetService as LeadSheetServiceTrait;
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct LeadSheetService {
+    pub save_lead_sheet_fn: Option<fn(AppContext, &mut LeadSheet) -> Result<(), Error>>,
+    pub import_lead_sheet_fn: Option<fn(AppContext, i64, i32, &str) -> Result<LeadSheet, Error>>,
+    pub delete_lead_sheet_fn: Option<fn(AppContext, i64) -> Result<(), Error>>,
+    pub find_lead_sheet_fn: Option<fn(AppContext, i64, i32) -> Result<LeadSheet, Error>>,
+    pub render_lead_sheet_fn: Option<fn(AppContext, i64, i32, i32) -> Result<String, Error>>,
+}
+
+impl LeadSheetServiceTrait for LeadSheetService {
+    fn save_lead_sheet(&self, ctx: AppContext, sheet: &mut LeadSheet) -> Result<(), Error> {
+        if let Some(f) = self.save_lead_sheet_fn {
+            return f(ctx, sheet);
+        }
+        panic!("save_lead_sheet_fn not set");
+    }
+
+    fn import_lead_sheet(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        chordpro: &str,
+    ) -> Result<LeadSheet, Error> {
+        if let Some(f) = self.import_lead_sheet_fn {
+            return f(ctx, song_id, revision, chordpro);
+        }
+        panic!("import_lead_sheet_fn not set");
+    }
+
+    fn delete_lead_sheet(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        if let Some(f) = self.delete_lead_sheet_fn {
+            return f(ctx, id);
+        }
+        panic!("delete_lead_sheet_fn not set");
+    }
+
+    fn find_lead_sheet(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+    ) -> Result<LeadSheet, Error> {
+        if let Some(f) = self.find_lead_sheet_fn {
+            return f(ctx, song_id, revision);
+        }
+        panic!("find_lead_sheet_fn not set");
+    }
+
+    fn render_lead_sheet(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        semitones: i32,
+    ) -> Result<String, Error> {
+        if let Some(f) = self.render_lead_sheet_fn {
+            return f(ctx, song_id, revision, semitones);
+        }
+        panic!("render_lead_sheet_fn not set");
+    }
+}
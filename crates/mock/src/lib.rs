@@ -1 +1,14 @@
+pub mod activity;
+pub mod auth;
+pub mod blob_store;
+pub mod comment;
+pub mod follow;
+pub mod gang;
+pub mod lead_sheet;
+pub mod membership;
+pub mod midi_clip;
+pub mod notification;
+pub mod song;
+pub mod token;
+pub mod track;
 pub mod user;
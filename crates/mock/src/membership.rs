@@ -0,0 +1,111 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::invitation::Invitation;
+use openmusicgang_entity::membership::{GangRole, Membership};
+use openmusicgang_err::error::Error;
+use openmusicgang_service::membership_service::{
+    InvitationFilter, MembershipFilter, MembershipService as MembershipServiceTrait,
+};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct MembershipService {
+    pub invite_member_fn: Option<fn(AppContext, &mut Invitation) -> Result<(), Error>>,
+    pub accept_invitation_fn: Option<fn(AppContext, i64) -> Result<Membership, Error>>,
+    pub decline_invitation_fn: Option<fn(AppContext, i64) -> Result<Invitation, Error>>,
+    pub expire_invitations_fn: Option<fn(AppContext) -> Result<i64, Error>>,
+    pub find_invitations_fn:
+        Option<fn(AppContext, InvitationFilter) -> Result<(Vec<Invitation>, i64), Error>>,
+    pub find_memberships_fn:
+        Option<fn(AppContext, MembershipFilter) -> Result<(Vec<Membership>, i64), Error>>,
+    pub update_member_role_fn:
+        Option<fn(AppContext, i64, i64, GangRole) -> Result<Membership, Error>>,
+    pub remove_member_fn: Option<fn(AppContext, i64, i64) -> Result<(), Error>>,
+    pub leave_gang_fn: Option<fn(AppContext, i64) -> Result<(), Error>>,
+    pub transfer_ownership_fn: Option<fn(AppContext, i64, i64) -> Result<(), Error>>,
+}
+
+impl MembershipServiceTrait for MembershipService {
+    fn invite_member(&self, ctx: AppContext, invitation: &mut Invitation) -> Result<(), Error> {
+        if let Some(f) = self.invite_member_fn {
+            return f(ctx, invitation);
+        }
+        panic!("invite_member_fn not set");
+    }
+
+    fn accept_invitation(&self, ctx: AppContext, id: i64) -> Result<Membership, Error> {
+        if let Some(f) = self.accept_invitation_fn {
+            return f(ctx, id);
+        }
+        panic!("accept_invitation_fn not set");
+    }
+
+    fn decline_invitation(&self, ctx: AppContext, id: i64) -> Result<Invitation, Error> {
+        if let Some(f) = self.decline_invitation_fn {
+            return f(ctx, id);
+        }
+        panic!("decline_invitation_fn not set");
+    }
+
+    fn expire_invitations(&self, ctx: AppContext) -> Result<i64, Error> {
+        if let Some(f) = self.expire_invitations_fn {
+            return f(ctx);
+        }
+        panic!("expire_invitations_fn not set");
+    }
+
+    fn find_invitations(
+        &self,
+        ctx: AppContext,
+        filters: InvitationFilter,
+    ) -> Result<(Vec<Invitation>, i64), Error> {
+        if let Some(f) = self.find_invitations_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_invitations_fn not set");
+    }
+
+    fn find_memberships(
+        &self,
+        ctx: AppContext,
+        filters: MembershipFilter,
+    ) -> Result<(Vec<Membership>, i64), Error> {
+        if let Some(f) = self.find_memberships_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_memberships_fn not set");
+    }
+
+    fn update_member_role(
+        &self,
+        ctx: AppContext,
+        gang_id: i64,
+        user_id: i64,
+        role: GangRole,
+    ) -> Result<Membership, Error> {
+        if let Some(f) = self.update_member_role_fn {
+            return f(ctx, gang_id, user_id, role);
+        }
+        panic!("update_member_role_fn not set");
+    }
+
+    fn remove_member(&self, ctx: AppContext, gang_id: i64, user_id: i64) -> Result<(), Error> {
+        if let Some(f) = self.remove_member_fn {
+            return f(ctx, gang_id, user_id);
+        }
+        panic!("remove_member_fn not set");
+    }
+
+    fn leave_gang(&self, ctx: AppContext, gang_id: i64) -> Result<(), Error> {
+        if let Some(f) = self.leave_gang_fn {
+            return f(ctx, gang_id);
+        }
+        panic!("leave_gang_fn not set");
+    }
+
+    fn transfer_ownership(&self, ctx: AppContext, gang_id: i64, user_id: i64) -> Result<(), Error> {
+        if let Some(f) = self.transfer_ownership_fn {
+            return f(ctx, gang_id, user_id);
+        }
+        panic!("transfer_ownership_fn not set");
+    }
+}
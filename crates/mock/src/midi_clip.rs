@@ -0,0 +1,73 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::blob::Blob;
+use openmusicgang_entity::midi_clip::MidiClip;
+use openmusicgang_entity::song::Song;
+use openmusicgang_err::error::Error;
+use openmusicgang_service::midi_clip_service::{
+    MidiClipFilter, MidiClipService as MidiClipServiceTrait,
+};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct MidiClipService {
+    pub create_midi_clip_fn: Option<fn(AppContext, &mut MidiClip) -> Result<(), Error>>,
+    pub delete_midi_clip_fn: Option<fn(AppContext, i64) -> Result<(), Error>>,
+    pub find_midi_clip_by_id_fn: Option<fn(AppContext, i64) -> Result<MidiClip, Error>>,
+    pub import_midi_clip_metadata_fn: Option<fn(AppContext, i64) -> Result<Song, Error>>,
+    pub export_song_midi_fn: Option<fn(AppContext, i64, i32) -> Result<Blob, Error>>,
+    pub find_midi_clips_fn:
+        Option<fn(AppContext, MidiClipFilter) -> Result<(Vec<MidiClip>, i64), Error>>,
+}
+
+impl MidiClipServiceTrait for MidiClipService {
+    fn create_midi_clip(&self, ctx: AppContext, clip: &mut MidiClip) -> Result<(), Error> {
+        if let Some(f) = self.create_midi_clip_fn {
+            return f(ctx, clip);
+        }
+        panic!("create_midi_clip_fn not set");
+    }
+
+    fn delete_midi_clip(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        if let Some(f) = self.delete_midi_clip_fn {
+            return f(ctx, id);
+        }
+        panic!("delete_midi_clip_fn not set");
+    }
+
+    fn find_midi_clip_by_id(&self, ctx: AppContext, id: i64) -> Result<MidiClip, Error> {
+        if let Some(f) = self.find_midi_clip_by_id_fn {
+            return f(ctx, id);
+        }
+        panic!("find_midi_clip_by_id_fn not set");
+    }
+
+    fn import_midi_clip_metadata(&self, ctx: AppContext, id: i64) -> Result<Song, Error> {
+        if let Some(f) = self.import_midi_clip_metadata_fn {
+            return f(ctx, id);
+        }
+        panic!("import_midi_clip_metadata_fn not set");
+    }
+
+    fn export_song_midi(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+    ) -> Result<Blob, Error> {
+        if let Some(f) = self.export_song_midi_fn {
+            return f(ctx, song_id, revision);
+        }
+        panic!("export_song_midi_fn not set");
+    }
+
+    fn find_midi_clips(
+        &self,
+        ctx: AppContext,
+        filters: MidiClipFilter,
+    ) -> Result<(Vec<MidiClip>, i64), Error> {
+        if let Some(f) = self.find_midi_clips_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_midi_clips_fn not set");
+    }
+}
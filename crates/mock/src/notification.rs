@@ -0,0 +1,69 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::notification::{Notification, NotificationPreferences};
+use openmusicgang_err::error::Error;
+use openmusicgang_service::notification_service::{
+    NotificationFilter, NotificationPreferencesUpdate,
+    NotificationService as NotificationServiceTrait,
+};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct NotificationService {
+    pub find_notifications_fn:
+        Option<fn(AppContext, NotificationFilter) -> Result<(Vec<Notification>, i64), Error>>,
+    pub mark_notification_read_fn: Option<fn(AppContext, i64) -> Result<Notification, Error>>,
+    pub mark_all_notifications_read_fn: Option<fn(AppContext) -> Result<i64, Error>>,
+    pub find_notification_preferences_fn:
+        Option<fn(AppContext) -> Result<NotificationPreferences, Error>>,
+    pub update_notification_preferences_fn: Option<
+        fn(AppContext, NotificationPreferencesUpdate) -> Result<NotificationPreferences, Error>,
+    >,
+}
+
+impl NotificationServiceTrait for NotificationService {
+    fn find_notifications(
+        &self,
+        ctx: AppContext,
+        filters: NotificationFilter,
+    ) -> Result<(Vec<Notification>, i64), Error> {
+        if let Some(f) = self.find_notifications_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_notifications_fn not set");
+    }
+
+    fn mark_notification_read(&self, ctx: AppContext, id: i64) -> Result<Notification, Error> {
+        if let Some(f) = self.mark_notification_read_fn {
+            return f(ctx, id);
+        }
+        panic!("mark_notification_read_fn not set");
+    }
+
+    fn mark_all_notifications_read(&self, ctx: AppContext) -> Result<i64, Error> {
+        if let Some(f) = self.mark_all_notifications_read_fn {
+            return f(ctx);
+        }
+        panic!("mark_all_notifications_read_fn not set");
+    }
+
+    fn find_notification_preferences(
+        &self,
+        ctx: AppContext,
+    ) -> Result<NotificationPreferences, Error> {
+        if let Some(f) = self.find_notification_preferences_fn {
+            return f(ctx);
+        }
+        panic!("find_notification_preferences_fn not set");
+    }
+
+    fn update_notification_preferences(
+        &self,
+        ctx: AppContext,
+        preferences: NotificationPreferencesUpdate,
+    ) -> Result<NotificationPreferences, Error> {
+        if let Some(f) = self.update_notification_preferences_fn {
+            return f(ctx, preferences);
+        }
+        panic!("update_notification_preferences_fn not set");
+    }
+}
@@ -0,0 +1,87 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::song::{Song, SongRevision};
+use openmusicgang_err::error::Error;
+use openmusicgang_service::song_service::{
+    SongFilter, SongRevisionFilter, SongService as SongServiceTrait, SongUpdate,
+};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct SongService {
+    pub create_song_fn: Option<fn(AppContext, &mut Song) -> Result<(), Error>>,
+    pub delete_song_fn: Option<fn(AppContext, i64) -> Result<(), Error>>,
+    pub update_song_fn: Option<fn(AppContext, i64, SongUpdate) -> Result<Song, Error>>,
+    pub find_song_by_id_fn: Option<fn(AppContext, i64) -> Result<Song, Error>>,
+    pub find_songs_fn: Option<fn(AppContext, SongFilter) -> Result<(Vec<Song>, i64), Error>>,
+    pub find_song_revision_fn: Option<fn(AppContext, i64, i32) -> Result<SongRevision, Error>>,
+    pub find_song_revisions_fn:
+        Option<fn(AppContext, SongRevisionFilter) -> Result<(Vec<SongRevision>, i64), Error>>,
+    pub revert_song_fn: Option<fn(AppContext, i64, i32) -> Result<Song, Error>>,
+}
+
+impl SongServiceTrait for SongService {
+    fn create_song(&self, ctx: AppContext, song: &mut Song) -> Result<(), Error> {
+        if let Some(f) = self.create_song_fn {
+            return f(ctx, song);
+        }
+        panic!("create_song_fn not set");
+    }
+
+    fn delete_song(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        if let Some(f) = self.delete_song_fn {
+            return f(ctx, id);
+        }
+        panic!("delete_song_fn not set");
+    }
+
+    fn update_song(&self, ctx: AppContext, id: i64, song: SongUpdate) -> Result<Song, Error> {
+        if let Some(f) = self.update_song_fn {
+            return f(ctx, id, song);
+        }
+        panic!("update_song_fn not set");
+    }
+
+    fn find_song_by_id(&self, ctx: AppContext, id: i64) -> Result<Song, Error> {
+        if let Some(f) = self.find_song_by_id_fn {
+            return f(ctx, id);
+        }
+        panic!("find_song_by_id_fn not set");
+    }
+
+    fn find_songs(&self, ctx: AppContext, filters: SongFilter) -> Result<(Vec<Song>, i64), Error> {
+        if let Some(f) = self.find_songs_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_songs_fn not set");
+    }
+
+    fn find_song_revision(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+    ) -> Result<SongRevision, Error> {
+        if let Some(f) = self.find_song_revision_fn {
+            return f(ctx, song_id, revision);
+        }
+        panic!("find_song_revision_fn not set");
+    }
+
+    fn find_song_revisions(
+        &self,
+        ctx: AppContext,
+        filters: SongRevisionFilter,
+    ) -> Result<(Vec<SongRevision>, i64), Error> {
+        if let Some(f) = self.find_song_revisions_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_song_revisions_fn not set");
+    }
+
+    fn revert_song(&self, ctx: AppContext, id: i64, revision: i32) -> Result<Song, Error> {
+        if let Some(f) = self.revert_song_fn {
+            return f(ctx, id, revision);
+        }
+        panic!("revert_song_fn not set");
+    }
+}
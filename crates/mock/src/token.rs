@@ -0,0 +1,56 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::token::{AccessToken, TokenPair};
+use openmusicgang_entity::user::User;
+use openmusicgang_err::error::Error;
+use openmusicgang_service::token_service::TokenService as TokenServiceTrait;
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct TokenService {
+    pub issue_tokens_fn:
+        Option<fn(AppContext, String, String, Vec<String>) -> Result<TokenPair, Error>>,
+    pub refresh_tokens_fn: Option<fn(AppContext, String) -> Result<TokenPair, Error>>,
+    pub revoke_tokens_fn: Option<fn(AppContext, String) -> Result<(), Error>>,
+    pub verify_access_token_fn:
+        Option<fn(AppContext, String) -> Result<(AccessToken, User), Error>>,
+}
+
+impl TokenServiceTrait for TokenService {
+    fn issue_tokens(
+        &self,
+        ctx: AppContext,
+        email: String,
+        password: String,
+        scopes: Vec<String>,
+    ) -> Result<TokenPair, Error> {
+        if let Some(f) = self.issue_tokens_fn {
+            return f(ctx, email, password, scopes);
+        }
+        panic!("issue_tokens_fn not set");
+    }
+
+    fn refresh_tokens(&self, ctx: AppContext, refresh_token: String) -> Result<TokenPair, Error> {
+        if let Some(f) = self.refresh_tokens_fn {
+            return f(ctx, refresh_token);
+        }
+        panic!("refresh_tokens_fn not set");
+    }
+
+    fn revoke_tokens(&self, ctx: AppContext, refresh_token: String) -> Result<(), Error> {
+        if let Some(f) = self.revoke_tokens_fn {
+            return f(ctx, refresh_token);
+        }
+        panic!("revoke_tokens_fn not set");
+    }
+
+    fn verify_access_token(
+        &self,
+        ctx: AppContext,
+        access_token: String,
+    ) -> Result<(AccessToken, User), Error> {
+        if let Some(f) = self.verify_access_token_fn {
+            return f(ctx, access_token);
+        }
+        panic!("verify_access_token_fn not set");
+    }
+}
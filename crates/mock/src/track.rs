@@ -0,0 +1,117 @@
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::analysis::AudioAnalysis;
+use openmusicgang_entity::mixdown::Mixdown;
+use openmusicgang_entity::track::Track;
+use openmusicgang_entity::waveform::Waveform;
+use openmusicgang_err::error::Error;
+use openmusicgang_service::track_service::{
+    TrackFilter, TrackService as TrackServiceTrait, TrackUpdate,
+};
+
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
+pub struct TrackService {
+    pub create_track_fn: Option<fn(AppContext, &mut Track) -> Result<(), Error>>,
+    pub delete_track_fn: Option<fn(AppContext, i64) -> Result<(), Error>>,
+    pub update_track_fn: Option<fn(AppContext, i64, TrackUpdate) -> Result<Track, Error>>,
+    pub replace_track_blob_fn: Option<fn(AppContext, i64, String) -> Result<Track, Error>>,
+    pub reorder_tracks_fn: Option<fn(AppContext, i64, i32, Vec<i64>) -> Result<Vec<Track>, Error>>,
+    pub find_track_by_id_fn: Option<fn(AppContext, i64) -> Result<Track, Error>>,
+    pub find_track_waveform_fn: Option<fn(AppContext, i64) -> Result<Waveform, Error>>,
+    pub analyze_track_fn: Option<fn(AppContext, i64) -> Result<AudioAnalysis, Error>>,
+    pub render_mixdown_fn: Option<fn(AppContext, i64, i32, Option<f64>) -> Result<Mixdown, Error>>,
+    pub find_tracks_fn: Option<fn(AppContext, TrackFilter) -> Result<(Vec<Track>, i64), Error>>,
+}
+
+impl TrackServiceTrait for TrackService {
+    fn create_track(&self, ctx: AppContext, track: &mut Track) -> Result<(), Error> {
+        if let Some(f) = self.create_track_fn {
+            return f(ctx, track);
+        }
+        panic!("create_track_fn not set");
+    }
+
+    fn delete_track(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        if let Some(f) = self.delete_track_fn {
+            return f(ctx, id);
+        }
+        panic!("delete_track_fn not set");
+    }
+
+    fn update_track(&self, ctx: AppContext, id: i64, track: TrackUpdate) -> Result<Track, Error> {
+        if let Some(f) = self.update_track_fn {
+            return f(ctx, id, track);
+        }
+        panic!("update_track_fn not set");
+    }
+
+    fn replace_track_blob(
+        &self,
+        ctx: AppContext,
+        id: i64,
+        blob_key: String,
+    ) -> Result<Track, Error> {
+        if let Some(f) = self.replace_track_blob_fn {
+            return f(ctx, id, blob_key);
+        }
+        panic!("replace_track_blob_fn not set");
+    }
+
+    fn reorder_tracks(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        track_ids: Vec<i64>,
+    ) -> Result<Vec<Track>, Error> {
+        if let Some(f) = self.reorder_tracks_fn {
+            return f(ctx, song_id, revision, track_ids);
+        }
+        panic!("reorder_tracks_fn not set");
+    }
+
+    fn find_track_by_id(&self, ctx: AppContext, id: i64) -> Result<Track, Error> {
+        if let Some(f) = self.find_track_by_id_fn {
+            return f(ctx, id);
+        }
+        panic!("find_track_by_id_fn not set");
+    }
+
+    fn find_track_waveform(&self, ctx: AppContext, id: i64) -> Result<Waveform, Error> {
+        if let Some(f) = self.find_track_waveform_fn {
+            return f(ctx, id);
+        }
+        panic!("find_track_waveform_fn not set");
+    }
+
+    fn analyze_track(&self, ctx: AppContext, id: i64) -> Result<AudioAnalysis, Error> {
+        if let Some(f) = self.analyze_track_fn {
+            return f(ctx, id);
+        }
+        panic!("analyze_track_fn not set");
+    }
+
+    fn render_mixdown(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        target_loudness: Option<f64>,
+    ) -> Result<Mixdown, Error> {
+        if let Some(f) = self.render_mixdown_fn {
+            return f(ctx, song_id, revision, target_loudness);
+        }
+        panic!("render_mixdown_fn not set");
+    }
+
+    fn find_tracks(
+        &self,
+        ctx: AppContext,
+        filters: TrackFilter,
+    ) -> Result<(Vec<Track>, i64), Error> {
+        if let Some(f) = self.find_tracks_fn {
+            return f(ctx, filters);
+        }
+        panic!("find_tracks_fn not set");
+    }
+}
@@ -5,6 +5,8 @@ use openmusicgang_service::user_service::{
     UserFilter, UserService as UserServiceTrait, UserUpdate,
 };
 
+#[allow(clippy::type_complexity)]
+#[derive(Default)]
 pub struct UserService {
     pub create_user_fn: Option<fn(AppContext, &mut User) -> Result<(), Error>>,
     pub delete_user_fn: Option<fn(AppContext, i64) -> Result<(), Error>>,
@@ -12,6 +14,13 @@ pub struct UserService {
     pub find_user_by_id_fn: Option<fn(AppContext, i64) -> Result<User, Error>>,
     pub find_user_by_email_fn: Option<fn(AppContext, String) -> Result<User, Error>>,
     pub find_users_fn: Option<fn(AppContext, UserFilter) -> Result<(Vec<User>, i64), Error>>,
+    pub verify_password_fn: Option<fn(AppContext, String, String) -> Result<User, Error>>,
+    pub confirm_email_fn: Option<fn(AppContext, String) -> Result<User, Error>>,
+    pub resend_verification_email_fn: Option<fn(AppContext) -> Result<(), Error>>,
+    pub change_email_fn: Option<fn(AppContext, String) -> Result<User, Error>>,
+    pub change_password_fn: Option<fn(AppContext, String, String) -> Result<(), Error>>,
+    pub request_password_reset_fn: Option<fn(AppContext, String) -> Result<(), Error>>,
+    pub reset_password_fn: Option<fn(AppContext, String, String) -> Result<User, Error>>,
 }
 
 impl UserServiceTrait for UserService {
@@ -56,4 +65,68 @@ impl UserServiceTrait for UserService {
         }
         panic!("find_users_fn not set");
     }
+
+    fn verify_password(
+        &self,
+        ctx: AppContext,
+        email: String,
+        password: String,
+    ) -> Result<User, Error> {
+        if let Some(f) = self.verify_password_fn {
+            return f(ctx, email, password);
+        }
+        panic!("verify_password_fn not set");
+    }
+
+    fn confirm_email(&self, ctx: AppContext, token: String) -> Result<User, Error> {
+        if let Some(f) = self.confirm_email_fn {
+            return f(ctx, token);
+        }
+        panic!("confirm_email_fn not set");
+    }
+
+    fn resend_verification_email(&self, ctx: AppContext) -> Result<(), Error> {
+        if let Some(f) = self.resend_verification_email_fn {
+            return f(ctx);
+        }
+        panic!("resend_verification_email_fn not set");
+    }
+
+    fn change_email(&self, ctx: AppContext, email: String) -> Result<User, Error> {
+        if let Some(f) = self.change_email_fn {
+            return f(ctx, email);
+        }
+        panic!("change_email_fn not set");
+    }
+
+    fn change_password(
+        &self,
+        ctx: AppContext,
+        current_password: String,
+        new_password: String,
+    ) -> Result<(), Error> {
+        if let Some(f) = self.change_password_fn {
+            return f(ctx, current_password, new_password);
+        }
+        panic!("change_password_fn not set");
+    }
+
+    fn request_password_reset(&self, ctx: AppContext, email: String) -> Result<(), Error> {
+        if let Some(f) = self.request_password_reset_fn {
+            return f(ctx, email);
+        }
+        panic!("request_password_reset_fn not set");
+    }
+
+    fn reset_password(
+        &self,
+        ctx: AppContext,
+        token: String,
+        new_password: String,
+    ) -> Result<User, Error> {
+        if let Some(f) = self.reset_password_fn {
+            return f(ctx, token, new_password);
+        }
+        panic!("reset_password_fn not set");
+    }
 }
@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::PathBuf;
+
+use openmusicgang_entity::notification::Delivery;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::notification_transport::NotificationTransport as NotificationTransportTrait;
+use serde_json::json;
+
+/// NotificationTransport is a struct that implements the NotificationTransportTrait on the local filesystem.
+///
+/// Deliveries are written as JSON to `channel/id.json` under the root directory, so local environments
+/// can read what would have been sent without outside services.
+pub struct NotificationTransport {
+    root: PathBuf,
+}
+
+impl NotificationTransport {
+    /// Create a new NotificationTransport struct, creating the root directory if it does not exist.
+    pub fn new(root: impl Into<PathBuf>) -> Result<NotificationTransport, Error> {
+        let root = root.into();
+
+        fs::create_dir_all(&root).map_err(io_error)?;
+
+        Ok(NotificationTransport { root })
+    }
+
+    /// Returns the path of the file of a delivery.
+    fn delivery_path(&self, delivery: &Delivery) -> PathBuf {
+        self.root
+            .join(delivery.channel.as_str())
+            .join(format!("{}.json", delivery.id))
+    }
+}
+
+impl NotificationTransportTrait for NotificationTransport {
+    /// Writes the delivery to its file, replacing a previous sending of it.
+    ///
+    /// The file is written next to its path first and renamed once complete, so it is never partially visible.
+    fn send(&self, delivery: &Delivery) -> Result<(), Error> {
+        let path = self.delivery_path(delivery);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+
+        let content = json!({
+            "id": delivery.id,
+            "user_id": delivery.user_id,
+            "channel": delivery.channel.as_str(),
+            "address": delivery.address,
+            "kind": delivery.kind.as_str(),
+            "message": delivery.message,
+            "created_at": delivery.created_at.to_rfc3339(),
+        });
+
+        let part = path.with_extension("json.part");
+        fs::write(&part, content.to_string()).map_err(io_error)?;
+        fs::rename(&part, &path).map_err(io_error)?;
+
+        Ok(())
+    }
+}
+
+/// Returns an EINTERNAL error of an IO error.
+fn io_error(error: std::io::Error) -> Error {
+    Error::new(ErrorCode::EINTERNAL, error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use chrono::prelude::*;
+    use openmusicgang_crypto::random::random_token;
+    use openmusicgang_entity::notification::{Channel, NotificationKind};
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) create a transport in a new directory.
+    /// 2) send a delivery, it should be written to the directory of its channel.
+    /// 3) send it again after a failure, the file should be replaced.
+    #[test]
+    fn test_file_notification_transport() {
+        // 1) create a transport in a new directory.
+        let root = std::env::temp_dir().join(format!("openmusicgang-{}", random_token(8)));
+        let transport = NotificationTransport::new(&root).unwrap();
+
+        // 2) send a delivery, it should be written to the directory of its channel.
+        let mut delivery = Delivery {
+            id: 7,
+            user_id: 1,
+            channel: Channel::Webhook,
+            address: "https://example.com/hook".to_string(),
+            kind: NotificationKind::Mention,
+            message: "Bob Smith mentioned you in a comment on Segfault Blues".to_string(),
+            attempts: 1,
+            last_error: None,
+            delivered_at: None,
+            created_at: Utc::now(),
+        };
+        transport.send(&delivery).unwrap();
+
+        let path = root.join("webhook").join("7.json");
+        let content: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(content["address"], "https://example.com/hook");
+        assert_eq!(content["kind"], "mention");
+        assert_eq!(content["message"], delivery.message);
+
+        // 3) send it again after a failure, the file should be replaced.
+        delivery.attempts = 2;
+        delivery.message = "Bob Smith mentioned you again".to_string();
+        transport.send(&delivery).unwrap();
+
+        let content: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(content["message"], "Bob Smith mentioned you again");
+        assert_eq!(fs::read_dir(root.join("webhook")).unwrap().count(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Mutex;
+
+use openmusicgang_entity::notification::Delivery;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::notification_transport::NotificationTransport as NotificationTransportTrait;
+
+/// NotificationTransport is a struct that implements the NotificationTransportTrait in memory, the sent
+/// deliveries are kept to be inspected, e.g. by tests.
+#[derive(Default)]
+pub struct NotificationTransport {
+    deliveries: Mutex<Vec<Delivery>>,
+}
+
+impl NotificationTransport {
+    /// Create a new NotificationTransport struct
+    pub fn new() -> NotificationTransport {
+        NotificationTransport::default()
+    }
+
+    /// Returns the sent deliveries in order of sending.
+    pub fn deliveries(&self) -> Vec<Delivery> {
+        self.deliveries
+            .lock()
+            .map(|deliveries| deliveries.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl NotificationTransportTrait for NotificationTransport {
+    /// Keeps the delivery, replacing a previous sending of it.
+    fn send(&self, delivery: &Delivery) -> Result<(), Error> {
+        let mut deliveries = self.deliveries.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on deliveries".to_string(),
+            )
+        })?;
+
+        deliveries.retain(|sent| sent.id != delivery.id);
+        deliveries.push(delivery.clone());
+
+        Ok(())
+    }
+}
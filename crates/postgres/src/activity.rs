@@ -0,0 +1,408 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::activity::{Activity, FeedPage};
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::activity_service::{
+    ActivityService as ActivityServiceTrait, FeedFilter,
+};
+use postgres::types::ToSql;
+use postgres::{Row, Transaction};
+
+use crate::postgres::DB;
+use crate::user::find_user_by_id;
+use crate::{
+    insert_activity_params, insert_activity_sql, insert_feed_entries_params,
+    insert_feed_entries_sql, select_feed_sql,
+};
+
+/// FEED_PAGE_SIZE is the number of activities of a page of a feed when no limit is given.
+static FEED_PAGE_SIZE: i64 = 20;
+
+/// MAX_FEED_PAGE_SIZE is the maximum number of activities of a page of a feed.
+static MAX_FEED_PAGE_SIZE: i64 = 100;
+
+/// ActivityService is a struct that implements the ActivityServiceTrait for the postgres crate.
+pub struct ActivityService {
+    db: Arc<Mutex<DB>>,
+}
+
+impl ActivityService {
+    /// Create a new ActivityService struct
+    pub fn new(db: Arc<Mutex<DB>>) -> ActivityService {
+        ActivityService { db }
+    }
+}
+
+impl ActivityServiceTrait for ActivityService {
+    /// Returns a page of the feed of the user of the context.
+    fn find_feed(&self, ctx: AppContext, filters: FeedFilter) -> Result<FeedPage, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_feed(ctx, &mut tx, filters)
+    }
+}
+
+/// record_activity inserts an activity, summarized by the name of its actor followed by the description,
+/// and adds it to the feeds of the members of the gang and, unless it is about a song, of the followers of
+/// the gang and of the actor. The songs of a gang are only visible to its members.
+///
+/// Returns the errors of find_user_by_id.
+///
+/// Returns EINVALID if the activity is invalid.
+pub(crate) fn record_activity(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    activity: &mut Activity,
+    description: &str,
+) -> Result<(), Error> {
+    let actor = match activity.actor_id {
+        Some(actor_id) => find_user_by_id(ctx, tx, actor_id)?.name,
+        None => "Someone".to_string(),
+    };
+
+    activity.summary = format!("{} {}", actor, description);
+    activity.created_at = Utc::now();
+
+    activity.validate()?;
+
+    let row = tx
+        .query_one(insert_activity_sql!(), insert_activity_params!(activity))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    activity.id = row.get(0);
+
+    tx.execute(
+        insert_feed_entries_sql!(),
+        insert_feed_entries_params!(activity),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// find_feed returns a page of the feed of the user of the context, newest first.
+///
+/// Handles the find_feed Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+fn find_feed(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    filters: FeedFilter,
+) -> Result<FeedPage, Error> {
+    let user_id = Context::user_id_from_context(ctx);
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in to read your feed".to_string(),
+        ));
+    }
+
+    let limit = match filters.limit {
+        limit if limit <= 0 => FEED_PAGE_SIZE,
+        limit => limit.min(MAX_FEED_PAGE_SIZE),
+    };
+
+    let mut where_conditions = vec!["feed_entries.user_id = $1".to_string()];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![&user_id];
+
+    if filters.before.is_some() {
+        where_conditions.push(format!("feed_entries.activity_id < ${}", args.len() + 1));
+        args.push(&filters.before);
+    }
+
+    if filters.after.is_some() {
+        where_conditions.push(format!("feed_entries.activity_id > ${}", args.len() + 1));
+        args.push(&filters.after);
+    }
+
+    // One more activity than the page is selected to know if there is a next page.
+    let query = select_feed_sql!(where_conditions, limit + 1);
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut activities = rows
+        .iter()
+        .map(activity_from_row)
+        .collect::<Result<Vec<Activity>, Error>>()?;
+
+    let next_cursor = match activities.len() as i64 > limit {
+        true => {
+            activities.truncate(limit as usize);
+            activities.last().map(|activity| activity.id)
+        }
+        false => None,
+    };
+
+    Ok(FeedPage {
+        activities,
+        next_cursor,
+    })
+}
+
+/// Returns the activity of a row selected with select_feed_sql.
+///
+/// Returns EINVALID if the kind of the activity is unknown.
+fn activity_from_row(row: &Row) -> Result<Activity, Error> {
+    Ok(Activity {
+        id: row.get(0),
+        kind: row.get::<_, String>(1).parse()?,
+        actor_id: row.get(2),
+        gang_id: row.get(3),
+        song_id: row.get(4),
+        revision: row.get(5),
+        subject_id: row.get(6),
+        summary: row.get(7),
+        created_at: row.get(8),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_crypto::random::random_token;
+    use openmusicgang_entity::activity::ActivityKind;
+    use openmusicgang_entity::comment::Comment;
+    use openmusicgang_entity::follow::FollowTarget;
+    use openmusicgang_entity::gang::Gang;
+    use openmusicgang_entity::invitation::Invitation;
+    use openmusicgang_entity::membership::GangRole;
+    use openmusicgang_entity::song::Song;
+    use openmusicgang_entity::track::Track;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_service::comment_service::CommentService as CommentServiceTrait;
+    use openmusicgang_service::follow_service::FollowService as FollowServiceTrait;
+    use openmusicgang_service::gang_service::GangService as GangServiceTrait;
+    use openmusicgang_service::membership_service::MembershipService as MembershipServiceTrait;
+    use openmusicgang_service::song_service::{SongService as SongServiceTrait, SongUpdate};
+    use openmusicgang_service::track_service::TrackService as TrackServiceTrait;
+    use openmusicgang_storage::local::BlobStore as LocalBlobStore;
+
+    use crate::comment::CommentService;
+    use crate::follow::FollowService;
+    use crate::gang::GangService;
+    use crate::membership::MembershipService;
+    use crate::song::SongService;
+    use crate::test_utils::{
+        must_create_user, must_lock_db, must_open_db, must_put_wav, must_truncate_table,
+    };
+    use crate::track::TrackService;
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) find a feed without a user in the context, error should be EUNAUTHORIZED.
+    /// 4) join a gang, the members and the followers of the user should see it.
+    /// 5) upload a track and save a revision, the members should see them but not the actor nor the followers who are not members.
+    /// 6) comment a song, the members should see it but not the followers who are not members.
+    /// 7) read a feed page by page with cursors.
+    /// 8) read the activities newer than a cursor.
+    #[test]
+    fn test_activity_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "feed_entries");
+        must_truncate_table(&mut db, "activities");
+        must_truncate_table(&mut db, "follows");
+        must_truncate_table(&mut db, "comments");
+        must_truncate_table(&mut db, "tracks");
+        must_truncate_table(&mut db, "song_revisions");
+        must_truncate_table(&mut db, "songs");
+        must_truncate_table(&mut db, "gang_invitations");
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+        let mark = must_create_user(&mut db, "Mark Smith", "mark.smith@test.com");
+        let steve = must_create_user(&mut db, "Steve Smith", "steve.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+        let membership_service = MembershipService::new(Arc::clone(&db), 3600);
+        let song_service = SongService::new(Arc::clone(&db));
+        let root = std::env::temp_dir().join(format!("openmusicgang-{}", random_token(8)));
+        let blob_store = Arc::new(LocalBlobStore::new(&root).unwrap());
+        let track_service = TrackService::new(Arc::clone(&db), blob_store.clone());
+        let comment_service = CommentService::new(Arc::clone(&db));
+        let follow_service = FollowService::new(Arc::clone(&db));
+        let activity_service = ActivityService::new(Arc::clone(&db));
+
+        let ctx = |user: &User| Context::with_user(Context::background(), user.clone());
+        let feed = |user: &User| {
+            activity_service
+                .find_feed(ctx(user), FeedFilter::default())
+                .unwrap()
+                .activities
+        };
+
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang_service.create_gang(ctx(&bob), &mut gang).unwrap();
+
+        follow_service
+            .follow(ctx(&mark), FollowTarget::User(john.id))
+            .unwrap();
+        follow_service
+            .follow(ctx(&steve), FollowTarget::Gang(gang.id))
+            .unwrap();
+
+        // 3) find a feed without a user in the context, error should be EUNAUTHORIZED.
+        let res = activity_service.find_feed(Context::background(), FeedFilter::default());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 4) join a gang, the members and the followers of the user should see it.
+        let mut invitation = Invitation {
+            gang_id: gang.id,
+            user_id: Some(john.id),
+            role: GangRole::Member,
+            ..Default::default()
+        };
+        membership_service
+            .invite_member(ctx(&bob), &mut invitation)
+            .unwrap();
+        membership_service
+            .accept_invitation(ctx(&john), invitation.id)
+            .unwrap();
+
+        for user in [&bob, &mark, &steve] {
+            let activities = feed(user);
+            assert_eq!(activities.len(), 1);
+            assert_eq!(activities[0].kind, ActivityKind::MemberJoined);
+            assert_eq!(activities[0].actor_id, Some(john.id));
+            assert_eq!(activities[0].summary, "John Smith joined The Rolling Bytes");
+        }
+        assert!(feed(&john).is_empty());
+
+        // 5) upload a track and save a revision, the members should see them but not the actor nor the followers who are not members.
+        let mut song = Song {
+            gang_id: gang.id,
+            title: "Segfault Blues".to_string(),
+            ..Default::default()
+        };
+        song_service.create_song(ctx(&john), &mut song).unwrap();
+
+        let mut bass = Track {
+            song_id: song.id,
+            blob_key: must_put_wav(&blob_store, 44100, 1, 4410, 8192),
+            instrument: "Bass".to_string(),
+            ..Default::default()
+        };
+        track_service.create_track(ctx(&john), &mut bass).unwrap();
+
+        let update = SongUpdate {
+            tempo: Some(120.0),
+            ..Default::default()
+        };
+        song_service
+            .update_song(ctx(&john), song.id, update)
+            .unwrap();
+
+        let activities = feed(&bob);
+        assert_eq!(activities.len(), 3);
+        assert_eq!(activities[0].kind, ActivityKind::RevisionCreated);
+        assert_eq!(activities[0].revision, Some(2));
+        assert_eq!(
+            activities[0].summary,
+            "John Smith saved revision 2 of Segfault Blues"
+        );
+        assert_eq!(activities[1].kind, ActivityKind::TrackUploaded);
+        assert_eq!(activities[1].subject_id, Some(bass.id));
+        assert_eq!(
+            activities[1].summary,
+            "John Smith uploaded a new bass take to Segfault Blues"
+        );
+
+        assert!(feed(&john).is_empty());
+
+        for user in [&mark, &steve] {
+            let activities = feed(user);
+            assert_eq!(activities.len(), 1);
+            assert_eq!(activities[0].kind, ActivityKind::MemberJoined);
+        }
+
+        // 6) comment a song, the members should see it but not the followers who are not members.
+        let mut comment = Comment {
+            song_id: song.id,
+            body: "Love the groove".to_string(),
+            ..Default::default()
+        };
+        comment_service
+            .create_comment(ctx(&bob), &mut comment)
+            .unwrap();
+
+        let activities = feed(&john);
+        assert_eq!(activities.len(), 1);
+        assert_eq!(activities[0].kind, ActivityKind::CommentAdded);
+        assert_eq!(activities[0].subject_id, Some(comment.id));
+        assert_eq!(
+            activities[0].summary,
+            "Bob Smith commented on Segfault Blues"
+        );
+
+        assert_eq!(feed(&mark).len(), 1);
+        assert_eq!(feed(&steve).len(), 1);
+
+        // 7) read a feed page by page with cursors.
+        let filters = FeedFilter {
+            limit: 2,
+            ..Default::default()
+        };
+
+        let page = activity_service
+            .find_feed(ctx(&bob), filters.clone())
+            .unwrap();
+        assert_eq!(page.activities.len(), 2);
+        assert_eq!(page.activities[0].kind, ActivityKind::RevisionCreated);
+        assert_eq!(page.next_cursor, Some(page.activities[1].id));
+
+        let page = activity_service
+            .find_feed(
+                ctx(&bob),
+                FeedFilter {
+                    before: page.next_cursor,
+                    ..filters.clone()
+                },
+            )
+            .unwrap();
+        assert_eq!(page.activities.len(), 1);
+        assert_eq!(page.activities[0].kind, ActivityKind::MemberJoined);
+        assert_eq!(page.next_cursor, None);
+
+        // 8) read the activities newer than a cursor.
+        let page = activity_service
+            .find_feed(
+                ctx(&bob),
+                FeedFilter {
+                    after: Some(page.activities[0].id),
+                    ..filters
+                },
+            )
+            .unwrap();
+        assert_eq!(page.activities.len(), 2);
+        assert_eq!(page.activities[0].kind, ActivityKind::RevisionCreated);
+        assert_eq!(page.activities[1].kind, ActivityKind::TrackUploaded);
+    }
+}
@@ -0,0 +1,908 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::activity::{Activity, ActivityKind};
+use openmusicgang_entity::comment::{Comment, CommentEdit};
+use openmusicgang_entity::membership::{GangRole, Membership};
+use openmusicgang_entity::notification::{Notification, NotificationKind};
+use openmusicgang_entity::song::Song;
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::comment_service::{
+    CommentFilter, CommentService as CommentServiceTrait, CommentUpdate,
+};
+use postgres::types::ToSql;
+use postgres::{Row, Transaction};
+
+use crate::activity::record_activity;
+use crate::membership::require_role;
+use crate::notification::record_notification;
+use crate::postgres::DB;
+use crate::song::{find_song_by_id, song_revision_number};
+use crate::track::find_track_by_id;
+use crate::{
+    delete_comment_params, delete_comment_sql, format_limit_offset, insert_comment_edit_params,
+    insert_comment_edit_sql, insert_comment_params, insert_comment_sql, select_comment_edits_sql,
+    select_comments_sql, select_gang_member_names_sql, update_comment_params, update_comment_sql,
+    where_condition_eq,
+};
+
+/// CommentService is a struct that implements the CommentServiceTrait for the postgres crate.
+pub struct CommentService {
+    db: Arc<Mutex<DB>>,
+}
+
+impl CommentService {
+    /// Create a new CommentService struct
+    pub fn new(db: Arc<Mutex<DB>>) -> CommentService {
+        CommentService { db }
+    }
+}
+
+impl CommentServiceTrait for CommentService {
+    /// Create a new comment.
+    fn create_comment(&self, ctx: AppContext, comment: &mut Comment) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        create_comment(ctx, &mut tx, comment)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Updates the body of a comment.
+    fn update_comment(
+        &self,
+        ctx: AppContext,
+        id: i64,
+        comment: CommentUpdate,
+    ) -> Result<Comment, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let comment = update_comment(ctx, &mut tx, id, comment)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(comment)
+    }
+
+    /// Deletes a comment.
+    fn delete_comment(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        delete_comment(ctx, &mut tx, id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Resolves the thread of a comment.
+    fn resolve_comment(&self, ctx: AppContext, id: i64) -> Result<Comment, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let comment = set_comment_resolved(ctx, &mut tx, id, true)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(comment)
+    }
+
+    /// Opens again the thread of a comment.
+    fn unresolve_comment(&self, ctx: AppContext, id: i64) -> Result<Comment, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let comment = set_comment_resolved(ctx, &mut tx, id, false)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(comment)
+    }
+
+    /// Get a comment by id.
+    fn find_comment_by_id(&self, ctx: AppContext, id: i64) -> Result<Comment, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_comment_by_id(ctx, &mut tx, id).map(|(comment, _)| comment)
+    }
+
+    /// Returns the edit history of a comment.
+    fn find_comment_edits(&self, ctx: AppContext, id: i64) -> Result<Vec<CommentEdit>, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_comment_edits(ctx, &mut tx, id)
+    }
+
+    /// Returns a vector of comments based on passed filters, also returns the total number of comments.
+    fn find_comments(
+        &self,
+        ctx: AppContext,
+        filters: CommentFilter,
+    ) -> Result<(Vec<Comment>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_comments(ctx, &mut tx, filters)
+    }
+}
+
+/// create_comment inserts a new comment, anchored to the song revision and track of its thread,
+/// of its track or to a song revision. The members of the gang mentioned in it are notified.
+///
+/// Handles the create_comment Business Logic.
+///
+/// Returns ENOTFOUND if the song, the track or the comment replied to does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang.
+///
+/// Returns EINVALID if the comment is invalid, the revision does not exist, or the comment replies
+/// to a reply or with a time range.
+fn create_comment(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    comment: &mut Comment,
+) -> Result<(), Error> {
+    let member = if let Some(parent_id) = comment.parent_id {
+        let (parent, member) = find_comment_by_id(ctx.clone(), tx, parent_id)?;
+
+        if parent.parent_id.is_some() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "Cannot reply to a reply".to_string(),
+            ));
+        }
+
+        if comment.range_start.is_some() || comment.range_end.is_some() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "A reply cannot have a time range".to_string(),
+            ));
+        }
+
+        comment.song_id = parent.song_id;
+        comment.revision = parent.revision;
+        comment.track_id = parent.track_id;
+
+        member
+    } else if let Some(track_id) = comment.track_id {
+        let (track, song) = find_track_by_id(ctx.clone(), tx, track_id)?;
+
+        comment.song_id = track.song_id;
+        comment.revision = track.revision;
+
+        require_role(ctx.clone(), tx, song.gang_id, GangRole::Guest)?
+    } else {
+        let song = find_song_by_id(ctx.clone(), tx, comment.song_id)?;
+
+        comment.revision = song_revision_number(&song, comment.revision)?;
+
+        require_role(ctx.clone(), tx, song.gang_id, GangRole::Guest)?
+    };
+
+    comment.author_id = Some(member.user_id);
+    comment.resolved_at = None;
+    comment.resolved_by = None;
+    comment.created_at = Utc::now();
+    comment.updated_at = comment.created_at;
+
+    comment.validate()?;
+
+    let row = tx
+        .query_one(insert_comment_sql!(), insert_comment_params!(comment))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    comment.id = row.get(0);
+
+    let song = find_song_by_id(ctx.clone(), tx, comment.song_id)?;
+    let mut activity = Activity::new(ActivityKind::CommentAdded, member.user_id, song.gang_id);
+    activity.song_id = Some(song.id);
+    activity.revision = Some(comment.revision);
+    activity.subject_id = Some(comment.id);
+
+    record_activity(
+        ctx.clone(),
+        tx,
+        &mut activity,
+        &format!("commented on {}", song.title),
+    )?;
+
+    notify_mentions(ctx, tx, comment, &song)
+}
+
+/// notify_mentions notifies the members of the gang of a song mentioned in a comment, except its author.
+fn notify_mentions(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    comment: &Comment,
+    song: &Song,
+) -> Result<(), Error> {
+    let rows = tx
+        .query(select_gang_member_names_sql!(), &[&song.gang_id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let author_id = match comment.author_id {
+        Some(author_id) => author_id,
+        None => return Ok(()),
+    };
+
+    for row in rows {
+        let (user_id, name): (i64, String) = (row.get(0), row.get(1));
+
+        if user_id == author_id || !comment.mentions(&name) {
+            continue;
+        }
+
+        let mut notification = Notification::new(NotificationKind::Mention, user_id, author_id);
+        notification.gang_id = Some(song.gang_id);
+        notification.subject_id = Some(comment.id);
+
+        record_notification(
+            ctx.clone(),
+            tx,
+            &mut notification,
+            &format!("mentioned you in a comment on {}", song.title),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// update_comment changes the body of a comment, its previous body is inserted in its edit history.
+///
+/// Handles the update_comment Business Logic.
+///
+/// Returns the errors of find_comment_by_id.
+///
+/// Returns EFORBIDDEN unless the user of the context is the author of the comment.
+///
+/// Returns EINVALID if the comment is invalid.
+fn update_comment(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+    update: CommentUpdate,
+) -> Result<Comment, Error> {
+    let (mut comment, member) = find_comment_by_id(ctx, tx, id)?;
+
+    if comment.author_id != Some(member.user_id) {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            "You do not have permission to edit this comment".to_string(),
+        ));
+    }
+
+    let body = match update.body {
+        Some(body) if body != comment.body => body,
+        _ => return Ok(comment),
+    };
+
+    let edit = CommentEdit {
+        id: 0,
+        comment_id: comment.id,
+        body: std::mem::replace(&mut comment.body, body),
+        created_at: Utc::now(),
+    };
+
+    comment.updated_at = edit.created_at;
+
+    comment.validate()?;
+
+    tx.execute(
+        insert_comment_edit_sql!(),
+        insert_comment_edit_params!(edit),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    tx.execute(update_comment_sql!(), update_comment_params!(comment))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(comment)
+}
+
+/// delete_comment deletes a comment from the database, its replies are deleted with it.
+///
+/// Handles the delete_comment Business Logic.
+///
+/// Returns the errors of find_comment_by_id.
+///
+/// Returns EFORBIDDEN unless the user of the context is an admin of the gang, or the author of the comment.
+fn delete_comment(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<(), Error> {
+    let (comment, member) = find_comment_by_id(ctx, tx, id)?;
+
+    if member.role < GangRole::Admin && comment.author_id != Some(member.user_id) {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            "You do not have permission to delete this comment".to_string(),
+        ));
+    }
+
+    tx.execute(delete_comment_sql!(), delete_comment_params!(id))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// set_comment_resolved resolves or opens again the thread started by a comment.
+///
+/// Handles the resolve_comment and unresolve_comment Business Logic.
+///
+/// Returns the errors of find_comment_by_id.
+///
+/// Returns EFORBIDDEN if the user of the context is a guest of the gang who did not write the comment.
+///
+/// Returns EINVALID if the comment is a reply.
+///
+/// Returns ECONFLICT if the thread is already resolved, or already open.
+fn set_comment_resolved(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+    resolved: bool,
+) -> Result<Comment, Error> {
+    let (mut comment, member) = find_comment_by_id(ctx, tx, id)?;
+
+    if member.role < GangRole::Member && comment.author_id != Some(member.user_id) {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            "You do not have permission to resolve this comment".to_string(),
+        ));
+    }
+
+    if comment.parent_id.is_some() {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "Only the comment starting a thread can be resolved".to_string(),
+        ));
+    }
+
+    if comment.is_resolved() == resolved {
+        return Err(Error::new(
+            ErrorCode::ECONFLICT,
+            match resolved {
+                true => "Comment is already resolved".to_string(),
+                false => "Comment is not resolved".to_string(),
+            },
+        ));
+    }
+
+    comment.updated_at = Utc::now();
+
+    if resolved {
+        comment.resolved_at = Some(comment.updated_at);
+        comment.resolved_by = Some(member.user_id);
+    } else {
+        comment.resolved_at = None;
+        comment.resolved_by = None;
+    }
+
+    tx.execute(update_comment_sql!(), update_comment_params!(comment))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(comment)
+}
+
+/// find_comment_by_id returns a comment by id, with the membership of the user of the context.
+///
+/// Handles the find_comment_by_id Business Logic.
+///
+/// Returns ENOTFOUND if the comment does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang of the song.
+fn find_comment_by_id(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<(Comment, Membership), Error> {
+    let query = select_comments_sql!([where_condition_eq!("id", 1)], "");
+
+    let row = tx
+        .query_opt(query.as_str(), &[&id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let comment = match row {
+        Some(row) => comment_from_row(&row),
+        None => {
+            return Err(Error::new(
+                ErrorCode::ENOTFOUND,
+                "Comment not found".to_string(),
+            ))
+        }
+    };
+
+    let song = find_song_by_id(ctx.clone(), tx, comment.song_id)?;
+    let member = require_role(ctx, tx, song.gang_id, GangRole::Guest)?;
+
+    Ok((comment, member))
+}
+
+/// find_comment_edits returns the previous bodies of a comment, oldest first.
+///
+/// Handles the find_comment_edits Business Logic.
+///
+/// Returns the errors of find_comment_by_id.
+fn find_comment_edits(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<Vec<CommentEdit>, Error> {
+    find_comment_by_id(ctx, tx, id)?;
+
+    let rows = tx
+        .query(select_comment_edits_sql!(), &[&id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| CommentEdit {
+            id: row.get(0),
+            comment_id: row.get(1),
+            body: row.get(2),
+            created_at: row.get(3),
+        })
+        .collect())
+}
+
+/// find_comments finds the comments of a song based on the filters.
+///
+/// Handles the find_comments Business Logic.
+///
+/// Returns the errors of find_song_by_id.
+fn find_comments(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    filters: CommentFilter,
+) -> Result<(Vec<Comment>, i64), Error> {
+    find_song_by_id(ctx, tx, filters.song_id)?;
+
+    let mut where_conditions = vec![where_condition_eq!("song_id", 1)];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![&filters.song_id];
+
+    if filters.revision.is_some() {
+        where_conditions.push(where_condition_eq!("revision", args.len() + 1));
+        args.push(&filters.revision);
+    }
+
+    if filters.track_id.is_some() {
+        where_conditions.push(where_condition_eq!("track_id", args.len() + 1));
+        args.push(&filters.track_id);
+    }
+
+    if filters.parent_id.is_some() {
+        where_conditions.push(where_condition_eq!("parent_id", args.len() + 1));
+        args.push(&filters.parent_id);
+    }
+
+    match filters.resolved {
+        Some(true) => where_conditions.push("resolved_at IS NOT NULL".to_string()),
+        Some(false) => where_conditions.push("resolved_at IS NULL".to_string()),
+        None => {}
+    }
+
+    let query = select_comments_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut comments: Vec<Comment> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        comments.push(comment_from_row(&row));
+        tot_results = row.get(13);
+    }
+
+    Ok((comments, tot_results))
+}
+
+/// Returns the comment of a row selected with select_comments_sql.
+fn comment_from_row(row: &Row) -> Comment {
+    Comment {
+        id: row.get(0),
+        song_id: row.get(1),
+        revision: row.get(2),
+        track_id: row.get(3),
+        parent_id: row.get(4),
+        range_start: row.get(5),
+        range_end: row.get(6),
+        body: row.get(7),
+        author_id: row.get(8),
+        resolved_at: row.get(9),
+        resolved_by: row.get(10),
+        created_at: row.get(11),
+        updated_at: row.get(12),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_app::context::Context;
+    use openmusicgang_crypto::random::random_token;
+    use openmusicgang_entity::gang::Gang;
+    use openmusicgang_entity::invitation::Invitation;
+    use openmusicgang_entity::song::Song;
+    use openmusicgang_entity::track::Track;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_service::gang_service::GangService as GangServiceTrait;
+    use openmusicgang_service::membership_service::MembershipService as MembershipServiceTrait;
+    use openmusicgang_service::song_service::{SongService as SongServiceTrait, SongUpdate};
+    use openmusicgang_service::track_service::TrackService as TrackServiceTrait;
+    use openmusicgang_storage::local::BlobStore as LocalBlobStore;
+
+    use crate::gang::GangService;
+    use crate::membership::MembershipService;
+    use crate::song::SongService;
+    use crate::test_utils::{
+        must_create_user, must_lock_db, must_open_db, must_put_wav, must_truncate_table,
+    };
+    use crate::track::TrackService;
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) comment a song as a non member, error should be EFORBIDDEN.
+    /// 4) comment the latest revision of the song and a time range of a track as a guest.
+    /// 5) comment with an invalid range, error should be EINVALID.
+    /// 6) reply to a comment, the reply should belong to the track of the comment, replies to replies should be EINVALID.
+    /// 7) edit a comment as another user, error should be EFORBIDDEN.
+    /// 8) edit a comment twice, its edit history should have its previous bodies.
+    /// 9) resolve and unresolve a thread, resolving it twice should be ECONFLICT.
+    /// 10) find the comments of the song, of the track and the open ones.
+    /// 11) delete the thread as an admin, its replies should be deleted with it.
+    #[test]
+    fn test_comment_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "comment_edits");
+        must_truncate_table(&mut db, "comments");
+        must_truncate_table(&mut db, "tracks");
+        must_truncate_table(&mut db, "song_revisions");
+        must_truncate_table(&mut db, "songs");
+        must_truncate_table(&mut db, "gang_invitations");
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+        let mark = must_create_user(&mut db, "Mark Smith", "mark.smith@test.com");
+        let steve = must_create_user(&mut db, "Steve Smith", "steve.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+        let membership_service = MembershipService::new(Arc::clone(&db), 3600);
+        let song_service = SongService::new(Arc::clone(&db));
+        let root = std::env::temp_dir().join(format!("openmusicgang-{}", random_token(8)));
+        let blob_store = Arc::new(LocalBlobStore::new(&root).unwrap());
+        let track_service = TrackService::new(Arc::clone(&db), blob_store.clone());
+        let comment_service = CommentService::new(Arc::clone(&db));
+
+        let ctx = |user: &User| Context::with_user(Context::background(), user.clone());
+
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang_service.create_gang(ctx(&bob), &mut gang).unwrap();
+
+        for (user, role) in [(&john, GangRole::Member), (&mark, GangRole::Guest)] {
+            let mut invitation = Invitation {
+                gang_id: gang.id,
+                user_id: Some(user.id),
+                role,
+                ..Default::default()
+            };
+            membership_service
+                .invite_member(ctx(&bob), &mut invitation)
+                .unwrap();
+            membership_service
+                .accept_invitation(ctx(user), invitation.id)
+                .unwrap();
+        }
+
+        let mut song = Song {
+            gang_id: gang.id,
+            title: "Segfault Blues".to_string(),
+            ..Default::default()
+        };
+        song_service.create_song(ctx(&john), &mut song).unwrap();
+
+        let mut bass = Track {
+            song_id: song.id,
+            blob_key: must_put_wav(&blob_store, 44100, 1, 4410, 8192),
+            instrument: "Bass".to_string(),
+            ..Default::default()
+        };
+        track_service.create_track(ctx(&john), &mut bass).unwrap();
+
+        let update = SongUpdate {
+            tempo: Some(120.0),
+            ..Default::default()
+        };
+        let song = song_service
+            .update_song(ctx(&john), song.id, update)
+            .unwrap();
+
+        // 3) comment a song as a non member, error should be EFORBIDDEN.
+        let mut comment = Comment {
+            song_id: song.id,
+            body: "Love the groove".to_string(),
+            ..Default::default()
+        };
+
+        let res = comment_service.create_comment(ctx(&steve), &mut comment.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 4) comment the latest revision of the song and a time range of a track as a guest.
+        comment_service
+            .create_comment(ctx(&mark), &mut comment)
+            .unwrap();
+        assert_eq!(comment.revision, 2);
+        assert_eq!(comment.author_id, Some(mark.id));
+
+        let mut loud = Comment {
+            track_id: Some(bass.id),
+            range_start: Some(4_224_000),
+            range_end: Some(4_410_000),
+            body: "At 1:36 the bass is too loud".to_string(),
+            ..Default::default()
+        };
+        comment_service
+            .create_comment(ctx(&mark), &mut loud)
+            .unwrap();
+        assert_eq!(loud.song_id, song.id);
+        assert_eq!(loud.revision, 1);
+
+        // 5) comment with an invalid range, error should be EINVALID.
+        for (range_start, range_end) in [(Some(10), Some(5)), (None, Some(5)), (Some(-1), None)] {
+            let res = comment_service.create_comment(
+                ctx(&john),
+                &mut Comment {
+                    id: 0,
+                    range_start,
+                    range_end,
+                    ..loud.clone()
+                },
+            );
+            assert!(res.is_err());
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+
+        // 6) reply to a comment, the reply should belong to the track of the comment, replies to replies should be EINVALID.
+        let mut reply = Comment {
+            parent_id: Some(loud.id),
+            body: "Turned it down 3 dB".to_string(),
+            ..Default::default()
+        };
+        comment_service
+            .create_comment(ctx(&john), &mut reply)
+            .unwrap();
+        assert_eq!(reply.song_id, song.id);
+        assert_eq!(reply.revision, 1);
+        assert_eq!(reply.track_id, Some(bass.id));
+
+        let res = comment_service.create_comment(
+            ctx(&mark),
+            &mut Comment {
+                parent_id: Some(reply.id),
+                body: "Thanks".to_string(),
+                ..Default::default()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 7) edit a comment as another user, error should be EFORBIDDEN.
+        let update = CommentUpdate {
+            body: Some("At 1:36 the bass is way too loud".to_string()),
+        };
+
+        let res = comment_service.update_comment(ctx(&bob), loud.id, update.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 8) edit a comment twice, its edit history should have its previous bodies.
+        let edited = comment_service
+            .update_comment(ctx(&mark), loud.id, update)
+            .unwrap();
+        assert_eq!(edited.body, "At 1:36 the bass is way too loud");
+
+        comment_service
+            .update_comment(
+                ctx(&mark),
+                loud.id,
+                CommentUpdate {
+                    body: Some("At 1:36 the bass clips".to_string()),
+                },
+            )
+            .unwrap();
+
+        let edits = comment_service
+            .find_comment_edits(ctx(&john), loud.id)
+            .unwrap();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].body, "At 1:36 the bass is too loud");
+        assert_eq!(edits[1].body, "At 1:36 the bass is way too loud");
+
+        // 9) resolve and unresolve a thread, resolving it twice should be ECONFLICT.
+        let res = comment_service.resolve_comment(ctx(&john), reply.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let res = comment_service.resolve_comment(ctx(&mark), comment.id);
+        assert!(res.is_ok());
+
+        let resolved = comment_service
+            .resolve_comment(ctx(&john), loud.id)
+            .unwrap();
+        assert!(resolved.is_resolved());
+        assert_eq!(resolved.resolved_by, Some(john.id));
+
+        let res = comment_service.resolve_comment(ctx(&john), loud.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        let opened = comment_service
+            .unresolve_comment(ctx(&john), comment.id)
+            .unwrap();
+        assert!(!opened.is_resolved());
+        assert_eq!(opened.resolved_by, None);
+
+        // 10) find the comments of the song, of the track and the open ones.
+        let filters = CommentFilter {
+            song_id: song.id,
+            ..Default::default()
+        };
+
+        let (comments, total) = comment_service
+            .find_comments(ctx(&mark), filters.clone())
+            .unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(comments[0].id, comment.id);
+
+        let (_, total) = comment_service
+            .find_comments(
+                ctx(&mark),
+                CommentFilter {
+                    track_id: Some(bass.id),
+                    ..filters.clone()
+                },
+            )
+            .unwrap();
+        assert_eq!(total, 2);
+
+        let (comments, total) = comment_service
+            .find_comments(
+                ctx(&mark),
+                CommentFilter {
+                    resolved: Some(false),
+                    ..filters.clone()
+                },
+            )
+            .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(comments[1].id, reply.id);
+
+        let (comments, _) = comment_service
+            .find_comments(
+                ctx(&mark),
+                CommentFilter {
+                    parent_id: Some(loud.id),
+                    ..filters.clone()
+                },
+            )
+            .unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].id, reply.id);
+
+        let res = comment_service.find_comments(ctx(&steve), filters.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 11) delete the thread as an admin, its replies should be deleted with it.
+        let res = comment_service.delete_comment(ctx(&john), loud.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        comment_service.delete_comment(ctx(&bob), loud.id).unwrap();
+
+        let res = comment_service.find_comment_by_id(ctx(&mark), reply.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        let (_, total) = comment_service.find_comments(ctx(&mark), filters).unwrap();
+        assert_eq!(total, 1);
+    }
+}
@@ -0,0 +1,347 @@
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chrono::prelude::*;
+
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::email::Email;
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::email_sender::EmailSender;
+use postgres::{Row, Transaction};
+
+use crate::postgres::DB;
+use crate::{
+    claim_emails_sql, insert_email_params, insert_email_sql, update_email_failed_sql,
+    update_email_sent_sql,
+};
+
+/// EMAIL_BATCH_SIZE is the maximum number of emails claimed at once by the dispatcher.
+static EMAIL_BATCH_SIZE: i64 = 100;
+
+/// EmailDispatcher sends the pending emails of the outbox with a sender.
+///
+/// Emails are queued in the outbox by the transaction of the operation sending them, so they are only
+/// sent once it is committed. They are claimed in a transaction and sent once it is committed, the
+/// database is not locked while the sender sends them. A failed email is tried again after a delay
+/// doubling at every attempt, until it was tried max_attempts times.
+pub struct EmailDispatcher {
+    db: Arc<Mutex<DB>>,
+    sender: Arc<dyn EmailSender + Send + Sync>,
+    max_attempts: i32,
+    /// Seconds before a failed email is tried again the first time.
+    retry_delay: u64,
+}
+
+impl EmailDispatcher {
+    /// Create a new EmailDispatcher struct
+    pub fn new(
+        db: Arc<Mutex<DB>>,
+        sender: Arc<dyn EmailSender + Send + Sync>,
+        max_attempts: i32,
+        retry_delay: u64,
+    ) -> EmailDispatcher {
+        EmailDispatcher {
+            db,
+            sender,
+            max_attempts,
+            retry_delay,
+        }
+    }
+
+    /// Sends the pending emails due now and returns how many were sent.
+    pub fn dispatch_emails(&self) -> Result<i64, Error> {
+        let emails = {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on database".to_string(),
+                )
+            })?;
+
+            let mut tx = mutex_db.begin_tx()?;
+
+            let emails = claim_emails(&mut tx, self.max_attempts, self.retry_delay)?;
+
+            tx.commit().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not commit transaction to database".to_string(),
+                )
+            })?;
+
+            emails
+        };
+
+        if emails.is_empty() {
+            return Ok(0);
+        }
+
+        let results: Vec<(i64, Result<(), Error>)> = emails
+            .iter()
+            .map(|email| (email.id, self.sender.send_email(email)))
+            .collect();
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let sent = complete_emails(&mut tx, results)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(sent)
+    }
+
+    /// Starts a thread dispatching the emails every interval seconds.
+    pub fn start(self: Arc<Self>, interval: u64) -> JoinHandle<()> {
+        thread::spawn(move || loop {
+            if let Err(error) = self.dispatch_emails() {
+                eprintln!("could not dispatch emails: {}", error);
+            }
+
+            thread::sleep(Duration::from_secs(interval));
+        })
+    }
+}
+
+/// queue_email inserts an email in the outbox, it is sent by the EmailDispatcher once the transaction is
+/// committed and never if it is rolled back.
+///
+/// Returns EINVALID if the email is invalid.
+pub(crate) fn queue_email(
+    _ctx: AppContext,
+    tx: &mut Transaction,
+    email: &mut Email,
+) -> Result<(), Error> {
+    email.attempts = 0;
+    email.last_error = None;
+    email.sent_at = None;
+    email.created_at = Utc::now();
+
+    email.validate()?;
+
+    let row = tx
+        .query_one(insert_email_sql!(), insert_email_params!(email))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    email.id = row.get(0);
+
+    Ok(())
+}
+
+/// claim_emails claims the pending emails due now, oldest first, their attempts are counted.
+fn claim_emails(
+    tx: &mut Transaction,
+    max_attempts: i32,
+    retry_delay: u64,
+) -> Result<Vec<Email>, Error> {
+    let rows = tx
+        .query(
+            claim_emails_sql!(),
+            &[
+                &Utc::now(),
+                &(retry_delay as f64),
+                &max_attempts,
+                &EMAIL_BATCH_SIZE,
+            ],
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut emails: Vec<Email> = rows.iter().map(email_from_row).collect();
+
+    // The rows returned by an UPDATE are not ordered.
+    emails.sort_by_key(|email| email.id);
+
+    Ok(emails)
+}
+
+/// complete_emails stores the results of the sending of claimed emails and returns how many were sent.
+fn complete_emails(
+    tx: &mut Transaction,
+    results: Vec<(i64, Result<(), Error>)>,
+) -> Result<i64, Error> {
+    let mut sent = 0;
+
+    for (id, result) in results {
+        match result {
+            Ok(()) => {
+                tx.execute(update_email_sent_sql!(), &[&id, &Utc::now()])
+                    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+                sent += 1;
+            }
+            Err(error) => {
+                tx.execute(update_email_failed_sql!(), &[&id, &error.message])
+                    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+            }
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Returns the email of a row returned by claim_emails_sql.
+fn email_from_row(row: &Row) -> Email {
+    Email {
+        id: row.get(0),
+        to: row.get(1),
+        subject: row.get(2),
+        text: row.get(3),
+        html: row.get(4),
+        attempts: row.get(5),
+        last_error: row.get(6),
+        sent_at: row.get(7),
+        created_at: row.get(8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_app::context::Context;
+    use openmusicgang_entity::email::EmailTemplate;
+
+    use crate::test_utils::{must_lock_db, must_open_db, must_truncate_table};
+
+    use super::*;
+
+    /// WELCOME_EMAIL is the template of the emails of the test.
+    static WELCOME_EMAIL: EmailTemplate = EmailTemplate {
+        subject: "Welcome to {{gang}}",
+        text: "Hi {{name}},\n\nyou joined {{gang}}.",
+        html: "<p>Hi {{name}},</p><p>you joined <b>{{gang}}</b>.</p>",
+    };
+
+    /// Sender is an email sender keeping the sent emails, or failing while fail is true.
+    #[derive(Default)]
+    struct Sender {
+        sent: Mutex<Vec<Email>>,
+        fail: Mutex<bool>,
+    }
+
+    impl EmailSender for Sender {
+        fn send_email(&self, email: &Email) -> Result<(), Error> {
+            if *self.fail.lock().unwrap() {
+                return Err(Error::new(
+                    ErrorCode::EINTERNAL,
+                    "connection refused".to_string(),
+                ));
+            }
+
+            self.sent.lock().unwrap().push(email.clone());
+            Ok(())
+        }
+    }
+
+    /// Queues an email rendered from the welcome template in a transaction, committed if commit is true.
+    fn must_queue_email(db: &Arc<Mutex<DB>>, to: &str, commit: bool) -> Email {
+        let mut email =
+            WELCOME_EMAIL.render(to, &[("name", "Bob <3"), ("gang", "The Rolling Bytes")]);
+
+        let mut mutex_db = db.lock().unwrap();
+        let mut tx = mutex_db.begin_tx().unwrap();
+        queue_email(Context::background(), &mut tx, &mut email).unwrap();
+
+        if commit {
+            tx.commit().unwrap();
+        }
+
+        email
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) queue an invalid email, error should be EINVALID.
+    /// 4) queue an email in a transaction rolled back, it should never be sent.
+    /// 5) queue an email and fail to send it, it should be tried again later.
+    /// 6) send it again, it should be sent once with its rendered parts.
+    #[test]
+    fn test_email_dispatcher() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "email_outbox");
+
+        let db = Arc::new(Mutex::new(db));
+        let sender = Arc::new(Sender::default());
+        let dispatcher = EmailDispatcher::new(Arc::clone(&db), sender.clone(), 5, 0);
+
+        // 3) queue an invalid email, error should be EINVALID.
+        {
+            let mut email = WELCOME_EMAIL.render("bob.smith", &[]);
+            let mut mutex_db = db.lock().unwrap();
+            let mut tx = mutex_db.begin_tx().unwrap();
+            let res = queue_email(Context::background(), &mut tx, &mut email);
+            assert!(res.is_err());
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+
+        // 4) queue an email in a transaction rolled back, it should never be sent.
+        must_queue_email(&db, "john.smith@test.com", false);
+        assert_eq!(dispatcher.dispatch_emails().unwrap(), 0);
+
+        // 5) queue an email and fail to send it, it should be tried again later.
+        let email = must_queue_email(&db, "bob.smith@test.com", true);
+
+        *sender.fail.lock().unwrap() = true;
+        let failing_dispatcher = EmailDispatcher::new(Arc::clone(&db), sender.clone(), 5, 3600);
+        assert_eq!(failing_dispatcher.dispatch_emails().unwrap(), 0);
+        assert_eq!(failing_dispatcher.dispatch_emails().unwrap(), 0);
+
+        {
+            let mut mutex_db = db.lock().unwrap();
+            let mut tx = mutex_db.begin_tx().unwrap();
+            let row = tx
+                .query_one(
+                    "SELECT attempts, last_error FROM email_outbox WHERE id = $1",
+                    &[&email.id],
+                )
+                .unwrap();
+            assert_eq!(row.get::<_, i32>(0), 1);
+            assert_eq!(
+                row.get::<_, Option<String>>(1),
+                Some("connection refused".to_string())
+            );
+
+            tx.execute(
+                "UPDATE email_outbox SET next_attempt_at = $1",
+                &[&Utc::now()],
+            )
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        // 6) send it again, it should be sent once with its rendered parts.
+        *sender.fail.lock().unwrap() = false;
+        assert_eq!(dispatcher.dispatch_emails().unwrap(), 1);
+        assert_eq!(dispatcher.dispatch_emails().unwrap(), 0);
+
+        let sent = sender.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].id, email.id);
+        assert_eq!(sent[0].to, "bob.smith@test.com");
+        assert_eq!(sent[0].subject, "Welcome to The Rolling Bytes");
+        assert_eq!(sent[0].text, "Hi Bob <3,\n\nyou joined The Rolling Bytes.");
+        assert_eq!(
+            sent[0].html,
+            "<p>Hi Bob &lt;3,</p><p>you joined <b>The Rolling Bytes</b>.</p>"
+        );
+        assert_eq!(sent[0].attempts, 2);
+    }
+}
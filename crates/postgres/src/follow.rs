@@ -0,0 +1,523 @@
+use std::sync::{Arc, Mutex};
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::follow::{Follow, FollowCounts, FollowTarget};
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::follow_service::{FollowFilter, FollowService as FollowServiceTrait};
+use postgres::types::ToSql;
+use postgres::{Row, Transaction};
+
+use crate::gang::find_gang_by_id;
+use crate::postgres::DB;
+use crate::user::find_user_by_id;
+use crate::{
+    delete_follow_params, delete_follow_sql, format_limit_offset, insert_follow_params,
+    insert_follow_sql, mutual_follow_condition, select_follow_counts_params,
+    select_follow_counts_sql, select_follows_sql, select_mutual_follow_sql, where_condition_eq,
+};
+
+/// FollowService is a struct that implements the FollowServiceTrait for the postgres crate.
+pub struct FollowService {
+    db: Arc<Mutex<DB>>,
+}
+
+impl FollowService {
+    /// Create a new FollowService struct
+    pub fn new(db: Arc<Mutex<DB>>) -> FollowService {
+        FollowService { db }
+    }
+}
+
+impl FollowServiceTrait for FollowService {
+    /// Follows a user or a gang.
+    fn follow(&self, ctx: AppContext, target: FollowTarget) -> Result<Follow, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let follow = create_follow(ctx, &mut tx, target)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(follow)
+    }
+
+    /// Unfollows a user or a gang.
+    fn unfollow(&self, ctx: AppContext, target: FollowTarget) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        delete_follow(ctx, &mut tx, target)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Returns a vector of follows based on passed filters, also returns the total number of follows.
+    fn find_follows(
+        &self,
+        ctx: AppContext,
+        filters: FollowFilter,
+    ) -> Result<(Vec<Follow>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_follows(ctx, &mut tx, filters)
+    }
+
+    /// Returns true if two users follow each other.
+    fn is_mutual_follow(
+        &self,
+        ctx: AppContext,
+        user_id: i64,
+        other_id: i64,
+    ) -> Result<bool, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        is_mutual_follow(ctx, &mut tx, user_id, other_id)
+    }
+
+    /// Returns the follow counts of a user or a gang.
+    fn find_follow_counts(
+        &self,
+        ctx: AppContext,
+        target: FollowTarget,
+    ) -> Result<FollowCounts, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_follow_counts(ctx, &mut tx, target)
+    }
+}
+
+/// create_follow inserts a follow of a user or a gang by the user of the context.
+///
+/// Handles the follow Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EINVALID if the follow is invalid, e.g. the user follows themselves.
+///
+/// Returns ENOTFOUND if the user or the gang does not exist.
+///
+/// Returns ECONFLICT if the user already follows the target.
+fn create_follow(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    target: FollowTarget,
+) -> Result<Follow, Error> {
+    let user_id = Context::user_id_from_context(ctx.clone());
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in to follow".to_string(),
+        ));
+    }
+
+    let mut follow = Follow::new(user_id, target);
+
+    follow.validate()?;
+
+    ensure_target_exists(ctx, tx, target)?;
+
+    let row = tx
+        .query_opt(insert_follow_sql!(), insert_follow_params!(follow))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    follow.id = match row {
+        Some(row) => row.get(0),
+        None => {
+            return Err(Error::new(
+                ErrorCode::ECONFLICT,
+                format!("You already follow this {}", target.kind()),
+            ))
+        }
+    };
+
+    Ok(follow)
+}
+
+/// delete_follow deletes the follow of a user or a gang by the user of the context.
+///
+/// Handles the unfollow Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns ENOTFOUND if the user of the context does not follow the target.
+fn delete_follow(ctx: AppContext, tx: &mut Transaction, target: FollowTarget) -> Result<(), Error> {
+    let user_id = Context::user_id_from_context(ctx);
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in to unfollow".to_string(),
+        ));
+    }
+
+    let deleted = tx
+        .execute(delete_follow_sql!(), delete_follow_params!(user_id, target))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    if deleted == 0 {
+        return Err(Error::new(
+            ErrorCode::ENOTFOUND,
+            format!("You do not follow this {}", target.kind()),
+        ));
+    }
+
+    Ok(())
+}
+
+/// ensure_target_exists checks that the followed user or gang exists.
+///
+/// Returns ENOTFOUND if the user or the gang does not exist.
+fn ensure_target_exists(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    target: FollowTarget,
+) -> Result<(), Error> {
+    match target {
+        FollowTarget::User(id) => find_user_by_id(ctx, tx, id).map(|_| ()),
+        FollowTarget::Gang(id) => find_gang_by_id(ctx, tx, id).map(|_| ()),
+    }
+}
+
+/// find_follows finds follows in the database based on the filters.
+///
+/// Handles the find_follows Business Logic.
+fn find_follows(
+    _ctx: AppContext,
+    tx: &mut Transaction,
+    filters: FollowFilter,
+) -> Result<(Vec<Follow>, i64), Error> {
+    let mut where_conditions = vec!["1 = 1".to_string()];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![];
+
+    if filters.follower_id.is_some() {
+        where_conditions.push(where_condition_eq!("follower_id", args.len() + 1));
+        args.push(&filters.follower_id);
+    }
+
+    let user_id = filters.target.and_then(|target| target.user_id());
+    let gang_id = filters.target.and_then(|target| target.gang_id());
+
+    if user_id.is_some() {
+        where_conditions.push(where_condition_eq!("followed_user_id", args.len() + 1));
+        args.push(&user_id);
+    }
+
+    if gang_id.is_some() {
+        where_conditions.push(where_condition_eq!("followed_gang_id", args.len() + 1));
+        args.push(&gang_id);
+    }
+
+    if filters.mutual {
+        where_conditions.push(mutual_follow_condition!().to_string());
+    }
+
+    let query = select_follows_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut follows: Vec<Follow> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        follows.push(follow_from_row(&row));
+        tot_results = row.get(5);
+    }
+
+    Ok((follows, tot_results))
+}
+
+/// is_mutual_follow checks if two users follow each other.
+///
+/// Handles the is_mutual_follow Business Logic.
+fn is_mutual_follow(
+    _ctx: AppContext,
+    tx: &mut Transaction,
+    user_id: i64,
+    other_id: i64,
+) -> Result<bool, Error> {
+    let row = tx
+        .query_one(select_mutual_follow_sql!(), &[&user_id, &other_id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(row.get(0))
+}
+
+/// find_follow_counts counts the followers of a user or a gang, and the follows of a user.
+///
+/// Handles the find_follow_counts Business Logic.
+///
+/// Returns ENOTFOUND if the user or the gang does not exist.
+fn find_follow_counts(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    target: FollowTarget,
+) -> Result<FollowCounts, Error> {
+    ensure_target_exists(ctx, tx, target)?;
+
+    let row = tx
+        .query_one(
+            select_follow_counts_sql!(),
+            select_follow_counts_params!(target),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(FollowCounts {
+        followers: row.get(0),
+        following: row.get(1),
+    })
+}
+
+/// Returns the follow of a row selected with select_follows_sql.
+fn follow_from_row(row: &Row) -> Follow {
+    let target = match row.get::<_, Option<i64>>(3) {
+        Some(gang_id) => FollowTarget::Gang(gang_id),
+        None => FollowTarget::User(row.get(2)),
+    };
+
+    Follow {
+        id: row.get(0),
+        follower_id: row.get(1),
+        target,
+        created_at: row.get(4),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_entity::gang::Gang;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_service::gang_service::GangService as GangServiceTrait;
+
+    use crate::gang::GangService;
+    use crate::test_utils::{must_create_user, must_lock_db, must_open_db, must_truncate_table};
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) follow without a user in the context, error should be EUNAUTHORIZED.
+    /// 4) follow yourself or a missing user, error should be EINVALID and ENOTFOUND.
+    /// 5) follow users and a gang, following twice should be ECONFLICT.
+    /// 6) find the followers of a user page by page, newest first.
+    /// 7) find the follows of a user, and the mutual ones.
+    /// 8) check if users follow each other.
+    /// 9) count the followers and follows of a user and a gang.
+    /// 10) unfollow, counts should be updated and unfollowing twice should be ENOTFOUND.
+    #[test]
+    fn test_follow_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "follows");
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+        let mark = must_create_user(&mut db, "Mark Smith", "mark.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+        let follow_service = FollowService::new(Arc::clone(&db));
+
+        let ctx = |user: &User| Context::with_user(Context::background(), user.clone());
+
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang_service.create_gang(ctx(&bob), &mut gang).unwrap();
+
+        // 3) follow without a user in the context, error should be EUNAUTHORIZED.
+        let res = follow_service.follow(Context::background(), FollowTarget::User(bob.id));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 4) follow yourself or a missing user, error should be EINVALID and ENOTFOUND.
+        let res = follow_service.follow(ctx(&bob), FollowTarget::User(bob.id));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let res = follow_service.follow(ctx(&bob), FollowTarget::User(mark.id + 100));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        // 5) follow users and a gang, following twice should be ECONFLICT.
+        for (follower, target) in [
+            (&bob, FollowTarget::User(john.id)),
+            (&john, FollowTarget::User(bob.id)),
+            (&mark, FollowTarget::Gang(gang.id)),
+            (&mark, FollowTarget::User(bob.id)),
+        ] {
+            let follow = follow_service.follow(ctx(follower), target).unwrap();
+            assert_eq!(follow.follower_id, follower.id);
+            assert_eq!(follow.target, target);
+        }
+
+        let res = follow_service.follow(ctx(&mark), FollowTarget::Gang(gang.id));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        // 6) find the followers of a user page by page, newest first.
+        let filters = FollowFilter {
+            target: Some(FollowTarget::User(bob.id)),
+            limit: 1,
+            ..Default::default()
+        };
+
+        let (follows, total) = follow_service
+            .find_follows(Context::background(), filters.clone())
+            .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(follows.len(), 1);
+        assert_eq!(follows[0].follower_id, mark.id);
+
+        let (follows, _) = follow_service
+            .find_follows(
+                Context::background(),
+                FollowFilter {
+                    offset: 1,
+                    ..filters
+                },
+            )
+            .unwrap();
+        assert_eq!(follows.len(), 1);
+        assert_eq!(follows[0].follower_id, john.id);
+
+        // 7) find the follows of a user, and the mutual ones.
+        let (follows, total) = follow_service
+            .find_follows(
+                Context::background(),
+                FollowFilter {
+                    follower_id: Some(mark.id),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(follows[0].target, FollowTarget::User(bob.id));
+        assert_eq!(follows[1].target, FollowTarget::Gang(gang.id));
+
+        let (follows, total) = follow_service
+            .find_follows(
+                Context::background(),
+                FollowFilter {
+                    target: Some(FollowTarget::User(bob.id)),
+                    mutual: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(follows[0].follower_id, john.id);
+
+        // 8) check if users follow each other.
+        assert!(follow_service
+            .is_mutual_follow(Context::background(), bob.id, john.id)
+            .unwrap());
+        assert!(!follow_service
+            .is_mutual_follow(Context::background(), bob.id, mark.id)
+            .unwrap());
+
+        // 9) count the followers and follows of a user and a gang.
+        let counts = follow_service
+            .find_follow_counts(Context::background(), FollowTarget::User(bob.id))
+            .unwrap();
+        assert_eq!(
+            counts,
+            FollowCounts {
+                followers: 2,
+                following: 1
+            }
+        );
+
+        let counts = follow_service
+            .find_follow_counts(Context::background(), FollowTarget::Gang(gang.id))
+            .unwrap();
+        assert_eq!(
+            counts,
+            FollowCounts {
+                followers: 1,
+                following: 0
+            }
+        );
+
+        let res = follow_service
+            .find_follow_counts(Context::background(), FollowTarget::Gang(gang.id + 1));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        // 10) unfollow, counts should be updated and unfollowing twice should be ENOTFOUND.
+        follow_service
+            .unfollow(ctx(&john), FollowTarget::User(bob.id))
+            .unwrap();
+
+        let counts = follow_service
+            .find_follow_counts(Context::background(), FollowTarget::User(bob.id))
+            .unwrap();
+        assert_eq!(counts.followers, 1);
+        assert!(!follow_service
+            .is_mutual_follow(Context::background(), bob.id, john.id)
+            .unwrap());
+
+        let res = follow_service.unfollow(ctx(&john), FollowTarget::User(bob.id));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+    }
+}
@@ -0,0 +1,611 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+
+use openmusicgang_app::context::AppContext;
+use openmusicgang_crypto::random::random_token;
+use openmusicgang_entity::gang::{slugify, Gang};
+use openmusicgang_entity::membership::{GangRole, Membership};
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::gang_service::{
+    GangFilter, GangService as GangServiceTrait, GangUpdate,
+};
+use postgres::types::ToSql;
+use postgres::Transaction;
+
+use crate::membership::{create_membership, require_role};
+use crate::postgres::DB;
+use crate::user::require_verified_user;
+use crate::{
+    delete_gang_params, delete_gang_sql, format_limit_offset, insert_gang_params, insert_gang_sql,
+    select_gangs_sql, update_gang_params, update_gang_sql, where_condition_eq,
+};
+
+/// GENERATED_SLUG_BYTES is the number of random bytes of the slug of a gang whose name has none.
+static GENERATED_SLUG_BYTES: usize = 4;
+
+/// GangService is a struct that implements the GangServiceTrait for the postgres crate.
+pub struct GangService {
+    db: Arc<Mutex<DB>>,
+}
+
+impl GangService {
+    /// Create a new GangService struct
+    pub fn new(db: Arc<Mutex<DB>>) -> GangService {
+        GangService { db }
+    }
+}
+
+impl GangServiceTrait for GangService {
+    /// Create a new gang.
+    fn create_gang(&self, ctx: AppContext, gang: &mut Gang) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        create_gang(ctx, &mut tx, gang)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Deletes a gang.
+    fn delete_gang(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        delete_gang(ctx, &mut tx, id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Updates a gang.
+    fn update_gang(&self, ctx: AppContext, id: i64, gang: GangUpdate) -> Result<Gang, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let gang = update_gang(ctx, &mut tx, id, gang)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(gang)
+    }
+
+    /// Get a gang by id.
+    fn find_gang_by_id(&self, ctx: AppContext, id: i64) -> Result<Gang, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_gang_by_id(ctx, &mut tx, id)
+    }
+
+    /// Get a gang by slug.
+    fn find_gang_by_slug(&self, ctx: AppContext, slug: String) -> Result<Gang, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_gang_by_slug(ctx, &mut tx, slug)
+    }
+
+    /// Returns a vector of gangs based on passed filters, also returns the total number of gangs.
+    fn find_gangs(&self, ctx: AppContext, filters: GangFilter) -> Result<(Vec<Gang>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_gangs(ctx, &mut tx, filters)
+    }
+}
+
+/// create_gang inserts a new gang into the database.
+///
+/// Handles the create_gang Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EFORBIDDEN if the email address of the user of the context is not verified.
+///
+/// Returns EINVALID if the gang is invalid.
+///
+/// Returns ECONFLICT if the slug is already taken.
+///
+/// The slug is derived from the name if not provided, a name without Latin letters or digits gets a generated
+/// slug. The user of the context becomes the owner of the gang.
+fn create_gang(ctx: AppContext, tx: &mut Transaction, gang: &mut Gang) -> Result<(), Error> {
+    let user_id = require_verified_user(ctx.clone(), tx, "create a gang")?.id;
+
+    if gang.slug.is_empty() {
+        gang.slug = match slugify(&gang.name) {
+            slug if slug.is_empty() => format!("gang-{}", random_token(GENERATED_SLUG_BYTES)),
+            slug => slug,
+        };
+    }
+
+    gang.created_by = Some(user_id);
+    gang.created_at = Utc::now();
+    gang.updated_at = Utc::now();
+
+    gang.validate()?;
+
+    ensure_slug_available(ctx, tx, &gang.slug, 0)?;
+
+    let row = tx
+        .query_one(insert_gang_sql!().as_str(), insert_gang_params!(gang))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    gang.id = row.get(0);
+
+    create_membership(tx, &mut Membership::new(gang.id, user_id, GangRole::Owner))?;
+
+    Ok(())
+}
+
+/// delete_gang deletes a gang from the database.
+/// Handles the delete_gang Business Logic.
+/// Returns EFORBIDDEN if the user of the context is not an owner of the gang.
+fn delete_gang(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<(), Error> {
+    find_gang_by_id(ctx.clone(), tx, id)?;
+    require_role(ctx, tx, id, GangRole::Owner)?;
+
+    tx.execute(delete_gang_sql!(), delete_gang_params!(id))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// ensure_slug_available checks that no gang other than the given one uses the slug.
+/// Returns ECONFLICT if the slug is already taken.
+fn ensure_slug_available(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    slug: &str,
+    id: i64,
+) -> Result<(), Error> {
+    match find_gang_by_slug(ctx, tx, slug.to_string()) {
+        Ok(gang) if gang.id != id => Err(Error::new(
+            ErrorCode::ECONFLICT,
+            "Slug already taken".to_string(),
+        )),
+        Ok(_) => Ok(()),
+        Err(error) if error.code == ErrorCode::ENOTFOUND => Ok(()),
+        Err(error) => Err(error),
+    }
+}
+
+/// find_gang_by_slug finds a gang by slug.
+/// Handles the find_gang_by_slug Business Logic.
+/// Returns ENOTFOUND if the gang is not found.
+fn find_gang_by_slug(ctx: AppContext, tx: &mut Transaction, slug: String) -> Result<Gang, Error> {
+    let filters = GangFilter {
+        slug: Some(slug),
+        ..Default::default()
+    };
+
+    let result = find_gangs(ctx, tx, filters)?;
+
+    if result.1 == 0 {
+        return Err(Error::new(
+            ErrorCode::ENOTFOUND,
+            "Gang not found".to_string(),
+        ));
+    }
+
+    Ok(result.0[0].clone())
+}
+
+/// find_gang_by_id returns a gang by id.
+/// Returns ENOTFOUND if the gang does not exist.
+/// Handles the find_gang_by_id Business Logic.
+pub(crate) fn find_gang_by_id(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<Gang, Error> {
+    let filters = GangFilter {
+        id: Some(id),
+        ..Default::default()
+    };
+
+    let result = find_gangs(ctx, tx, filters)?;
+
+    if result.1 == 0 {
+        return Err(Error::new(
+            ErrorCode::ENOTFOUND,
+            "Gang not found".to_string(),
+        ));
+    }
+
+    Ok(result.0[0].clone())
+}
+
+/// find_gangs finds gangs in the database based on the filters.
+/// Handles the find_gangs Business Logic.
+fn find_gangs(
+    _ctx: AppContext,
+    tx: &mut Transaction,
+    filters: GangFilter,
+) -> Result<(Vec<Gang>, i64), Error> {
+    let mut where_conditions = vec!["1 = 1".to_string()];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![];
+    let mut args_counter = 1;
+
+    if filters.id.is_some() {
+        where_conditions.push(where_condition_eq!("id", args_counter));
+        args_counter += 1;
+        args.push(&filters.id);
+    }
+
+    if filters.name.is_some() {
+        where_conditions.push(where_condition_eq!("name", args_counter));
+        args_counter += 1;
+        args.push(&filters.name);
+    }
+
+    if filters.slug.is_some() {
+        where_conditions.push(where_condition_eq!("slug", args_counter));
+        args_counter += 1;
+        args.push(&filters.slug);
+    }
+
+    if filters.genre.is_some() {
+        where_conditions.push(where_condition_eq!("genre", args_counter));
+        args_counter += 1;
+        args.push(&filters.genre);
+    }
+
+    if filters.created_by.is_some() {
+        where_conditions.push(where_condition_eq!("created_by", args_counter));
+        args.push(&filters.created_by);
+    }
+
+    let query = select_gangs_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut gangs: Vec<Gang> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        let mut gang = Gang::new();
+
+        gang.id = row.get(0);
+        gang.name = row.get(1);
+        gang.slug = row.get(2);
+        gang.description = row.get(3);
+        gang.genre = row.get(4);
+        gang.created_by = row.get(5);
+        gang.created_at = row.get(6);
+        gang.updated_at = row.get(7);
+        tot_results = row.get(8);
+
+        gangs.push(gang);
+    }
+
+    Ok((gangs, tot_results))
+}
+
+/// update_gang updates a gang in the database.
+///
+/// Handles the update_gang Business Logic.
+///
+/// Returns ENOTFOUND if the gang is not found.
+///
+/// Returns EFORBIDDEN if the user of the context is not an admin of the gang.
+///
+/// Returns EINVALID if the gang is invalid.
+///
+/// Returns ECONFLICT if the new slug is already taken.
+fn update_gang(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+    update: GangUpdate,
+) -> Result<Gang, Error> {
+    let mut gang = find_gang_by_id(ctx.clone(), tx, id)?;
+    require_role(ctx.clone(), tx, id, GangRole::Admin)?;
+
+    if let Some(name) = update.name {
+        gang.name = name;
+    }
+
+    if let Some(slug) = update.slug {
+        gang.slug = slug;
+    }
+
+    if let Some(description) = update.description {
+        gang.description = description;
+    }
+
+    if let Some(genre) = update.genre {
+        gang.genre = Some(genre);
+    }
+
+    gang.updated_at = Utc::now();
+
+    gang.validate()?;
+
+    ensure_slug_available(ctx, tx, &gang.slug, gang.id)?;
+
+    tx.execute(update_gang_sql!(), update_gang_params!(gang))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(gang)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_app::context::Context;
+
+    use crate::test_utils::{
+        must_create_user, must_exec, must_lock_db, must_open_db, must_truncate_table,
+    };
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) create a gang without a user in the context, error should be EUNAUTHORIZED.
+    /// 4) create a gang, the slug should be derived from the name.
+    /// 5) create a gang with the same slug, error should be ECONFLICT.
+    /// 6) create a gang with an invalid slug, error should be EINVALID.
+    /// 7) find the gang by id and by slug.
+    /// 8) find gangs by genre and by creator.
+    /// 9) update the gang with a user who is not a member, error should be EFORBIDDEN.
+    /// 10) update the gang and check that the update was successful.
+    /// 11) delete the gang with a user who is not a member, error should be EFORBIDDEN.
+    /// 12) delete the gang and check that the delete was successful.
+    /// 13) create a gang with a user whose email is not verified, error should be EFORBIDDEN.
+    /// 14) create gangs whose names only differ by an accented letter, their slugs should differ.
+    /// 15) create gangs whose names have no Latin letters, their slugs should be generated.
+    #[test]
+    fn test_gang_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+
+        let bob_ctx = || Context::with_user(Context::background(), bob.clone());
+        let john_ctx = || Context::with_user(Context::background(), john.clone());
+
+        // 3) create a gang without a user in the context, error should be EUNAUTHORIZED.
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang.description = "Garage rock since 2022".to_string();
+        gang.genre = Some("rock".to_string());
+
+        let res = gang_service.create_gang(Context::background(), &mut gang);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 4) create a gang, the slug should be derived from the name.
+        let res = gang_service.create_gang(bob_ctx(), &mut gang);
+        if let Err(error) = res {
+            panic!("{}", error);
+        }
+        assert_eq!(gang.id, 1);
+        assert_eq!(gang.slug, "the-rolling-bytes");
+        assert_eq!(gang.created_by, Some(bob.id));
+
+        // 5) create a gang with the same slug, error should be ECONFLICT.
+        let mut another = Gang::new();
+        another.name = "The Rolling Bytes!".to_string();
+
+        let res = gang_service.create_gang(john_ctx(), &mut another);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        // 6) create a gang with an invalid slug, error should be EINVALID.
+        another.slug = "Rolling Bytes".to_string();
+
+        let res = gang_service.create_gang(john_ctx(), &mut another);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        another.slug = "".to_string();
+        another.name = "Jazz Crabs".to_string();
+        another.genre = Some("jazz".to_string());
+
+        let res = gang_service.create_gang(john_ctx(), &mut another);
+        assert!(res.is_ok());
+
+        // 7) find the gang by id and by slug.
+        let res = gang_service.find_gang_by_id(Context::background(), gang.id);
+        assert!(res.is_ok());
+        let found = res.unwrap();
+        assert_eq!(found.name, "The Rolling Bytes");
+        assert_eq!(found.description, "Garage rock since 2022");
+        assert_eq!(found.created_by, Some(bob.id));
+
+        let res = gang_service.find_gang_by_slug(Context::background(), "jazz-crabs".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap().id, another.id);
+
+        let res = gang_service.find_gang_by_id(Context::background(), 3);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        // 8) find gangs by genre and by creator.
+        let filters = GangFilter {
+            genre: Some("rock".to_string()),
+            ..Default::default()
+        };
+        let (gangs, total) = gang_service
+            .find_gangs(Context::background(), filters)
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(gangs[0].id, gang.id);
+
+        let filters = GangFilter {
+            created_by: Some(john.id),
+            ..Default::default()
+        };
+        let (gangs, total) = gang_service
+            .find_gangs(Context::background(), filters)
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(gangs[0].id, another.id);
+
+        // 9) update the gang with a user who is not a member, error should be EFORBIDDEN.
+        let update = GangUpdate {
+            name: Some("The Rolling Bits".to_string()),
+            ..Default::default()
+        };
+
+        let res = gang_service.update_gang(john_ctx(), gang.id, update.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 10) update the gang and check that the update was successful.
+        let res = gang_service.update_gang(
+            bob_ctx(),
+            gang.id,
+            GangUpdate {
+                slug: Some("jazz-crabs".to_string()),
+                ..update.clone()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        let res = gang_service.update_gang(
+            bob_ctx(),
+            gang.id,
+            GangUpdate {
+                slug: Some("the-rolling-bits".to_string()),
+                ..update
+            },
+        );
+        assert!(res.is_ok());
+
+        let found = gang_service
+            .find_gang_by_slug(Context::background(), "the-rolling-bits".to_string())
+            .unwrap();
+        assert_eq!(found.id, gang.id);
+        assert_eq!(found.name, "The Rolling Bits");
+        assert_eq!(found.genre, Some("rock".to_string()));
+
+        // 11) delete the gang with a user who is not a member, error should be EFORBIDDEN.
+        let res = gang_service.delete_gang(john_ctx(), gang.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 12) delete the gang and check that the delete was successful.
+        let res = gang_service.delete_gang(bob_ctx(), gang.id);
+        assert!(res.is_ok());
+
+        let res = gang_service.find_gang_by_id(Context::background(), gang.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        // 13) create a gang with a user whose email is not verified, error should be EFORBIDDEN.
+        must_exec(
+            &mut db.lock().unwrap(),
+            "UPDATE users SET email_verified_at = NULL WHERE id = $1",
+            &[&john.id],
+        );
+
+        let mut gang = Gang::new();
+        gang.name = "The Jazz Crabs".to_string();
+
+        let res = gang_service.create_gang(john_ctx(), &mut gang);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 14) create gangs whose names only differ by an accented letter, their slugs should differ.
+        let mut uber = Gang::new();
+        uber.name = "Über".to_string();
+        gang_service.create_gang(bob_ctx(), &mut uber).unwrap();
+        assert_eq!(uber.slug, "uber");
+
+        let mut ber = Gang::new();
+        ber.name = "Ber".to_string();
+        gang_service.create_gang(bob_ctx(), &mut ber).unwrap();
+        assert_eq!(ber.slug, "ber");
+
+        // 15) create gangs whose names have no Latin letters, their slugs should be generated.
+        let mut first = Gang::new();
+        first.name = "東京事変".to_string();
+        gang_service.create_gang(bob_ctx(), &mut first).unwrap();
+        assert!(first.slug.starts_with("gang-"));
+        assert_eq!(first.slug.len(), "gang-".len() + 2 * GENERATED_SLUG_BYTES);
+
+        let mut second = Gang::new();
+        second.name = "東京事変".to_string();
+        gang_service.create_gang(bob_ctx(), &mut second).unwrap();
+        assert_ne!(second.slug, first.slug);
+
+        let res = gang_service.find_gang_by_slug(Context::background(), first.slug.clone());
+        assert_eq!(res.unwrap().name, "東京事変");
+    }
+}
@@ -0,0 +1,531 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+
+use openmusicgang_app::context::AppContext;
+use openmusicgang_chordpro::parse::parse_chordpro;
+use openmusicgang_chordpro::render::render_chordpro;
+use openmusicgang_entity::lead_sheet::{ChordPosition, LeadSheet, Line, Section};
+use openmusicgang_entity::membership::GangRole;
+use openmusicgang_entity::song::Song;
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::lead_sheet_service::LeadSheetService as LeadSheetServiceTrait;
+use openmusicgang_theory::chord::Chord;
+use postgres::{Row, Transaction};
+
+use crate::membership::require_role;
+use crate::postgres::DB;
+use crate::song::{find_song_by_id, find_song_revision, lock_song, song_revision_number};
+use crate::{
+    delete_lead_sheet_params, delete_lead_sheet_sql, select_lead_sheets_sql,
+    upsert_lead_sheet_params, upsert_lead_sheet_sql, where_condition_eq,
+};
+
+/// LeadSheetService is a struct that implements the LeadSheetServiceTrait for the postgres crate.
+pub struct LeadSheetService {
+    db: Arc<Mutex<DB>>,
+}
+
+impl LeadSheetService {
+    /// Create a new LeadSheetService struct
+    pub fn new(db: Arc<Mutex<DB>>) -> LeadSheetService {
+        LeadSheetService { db }
+    }
+}
+
+impl LeadSheetServiceTrait for LeadSheetService {
+    /// Saves the lead sheet of a song revision.
+    fn save_lead_sheet(&self, ctx: AppContext, sheet: &mut LeadSheet) -> Result<(), Error> {
+        sheet.validate()?;
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        save_lead_sheet(ctx, &mut tx, sheet)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Saves the lead sheet of a song revision parsed from a ChordPro text.
+    fn import_lead_sheet(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        chordpro: &str,
+    ) -> Result<LeadSheet, Error> {
+        let mut sheet = LeadSheet {
+            song_id,
+            revision,
+            sections: parse_chordpro(chordpro)?,
+            ..Default::default()
+        };
+
+        self.save_lead_sheet(ctx, &mut sheet)?;
+
+        Ok(sheet)
+    }
+
+    /// Deletes a lead sheet.
+    fn delete_lead_sheet(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        delete_lead_sheet(ctx, &mut tx, id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Get the lead sheet of a song revision.
+    fn find_lead_sheet(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+    ) -> Result<LeadSheet, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_lead_sheet(ctx, &mut tx, song_id, revision).map(|(sheet, _)| sheet)
+    }
+
+    /// Renders the lead sheet of a song revision as a ChordPro text.
+    fn render_lead_sheet(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        semitones: i32,
+    ) -> Result<String, Error> {
+        if !(-11..=11).contains(&semitones) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "semitones must be between -11 and 11".to_string(),
+            ));
+        }
+
+        let (sheet, song) = {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on database".to_string(),
+                )
+            })?;
+
+            let mut tx = mutex_db.begin_tx()?;
+
+            find_lead_sheet(ctx, &mut tx, song_id, revision)?
+        };
+
+        Ok(render_chordpro(&song, &sheet, semitones))
+    }
+}
+
+/// save_lead_sheet inserts the lead sheet of a song revision, or replaces the sections of its existing one.
+///
+/// Handles the save_lead_sheet Business Logic.
+///
+/// Returns ENOTFOUND if the song does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot write lead sheets.
+///
+/// Returns EINVALID if the lead sheet is invalid or the revision does not exist.
+fn save_lead_sheet(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    sheet: &mut LeadSheet,
+) -> Result<(), Error> {
+    lock_song(tx, sheet.song_id)?;
+
+    let song = find_song_by_id(ctx.clone(), tx, sheet.song_id)?;
+    let member = require_role(ctx, tx, song.gang_id, GangRole::Member)?;
+
+    sheet.revision = song_revision_number(&song, sheet.revision)?;
+    sheet.created_by = Some(member.user_id);
+    sheet.created_at = Utc::now();
+    sheet.updated_at = sheet.created_at;
+
+    sheet.validate()?;
+
+    let row = tx
+        .query_one(
+            upsert_lead_sheet_sql!(),
+            upsert_lead_sheet_params!(sheet, sections_json(&sheet.sections)),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    sheet.id = row.get(0);
+    sheet.created_by = row.get(1);
+    sheet.created_at = row.get(2);
+
+    Ok(())
+}
+
+/// delete_lead_sheet deletes a lead sheet from the database.
+///
+/// Handles the delete_lead_sheet Business Logic.
+///
+/// Returns ENOTFOUND if the lead sheet does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot delete lead sheets.
+fn delete_lead_sheet(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<(), Error> {
+    let query = select_lead_sheets_sql!([where_condition_eq!("id", 1)]);
+
+    let row = tx
+        .query_opt(query.as_str(), &[&id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let sheet = match row {
+        Some(row) => lead_sheet_from_row(&row),
+        None => {
+            return Err(Error::new(
+                ErrorCode::ENOTFOUND,
+                "Lead sheet not found".to_string(),
+            ))
+        }
+    };
+
+    let song = find_song_by_id(ctx.clone(), tx, sheet.song_id)?;
+    require_role(ctx, tx, song.gang_id, GangRole::Member)?;
+
+    tx.execute(delete_lead_sheet_sql!(), delete_lead_sheet_params!(id))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// find_lead_sheet returns the lead sheet of a song revision, with the song as it was at the revision.
+///
+/// Handles the find_lead_sheet Business Logic.
+///
+/// Returns ENOTFOUND if the song or its lead sheet does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang of the song.
+///
+/// Returns EINVALID if the revision does not exist.
+fn find_lead_sheet(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    song_id: i64,
+    revision: i32,
+) -> Result<(LeadSheet, Song), Error> {
+    let mut song = find_song_by_id(ctx.clone(), tx, song_id)?;
+    let revision = song_revision_number(&song, revision)?;
+
+    let query = select_lead_sheets_sql!([
+        where_condition_eq!("song_id", 1),
+        where_condition_eq!("revision", 2)
+    ]);
+
+    let row = tx
+        .query_opt(query.as_str(), &[&song_id, &revision])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let sheet = match row {
+        Some(row) => lead_sheet_from_row(&row),
+        None => {
+            return Err(Error::new(
+                ErrorCode::ENOTFOUND,
+                "Lead sheet not found".to_string(),
+            ))
+        }
+    };
+
+    if revision != song.revision {
+        song.restore(&find_song_revision(ctx, tx, song_id, revision)?);
+    }
+
+    Ok((sheet, song))
+}
+
+/// Returns the lead sheet of a row selected with select_lead_sheets_sql.
+fn lead_sheet_from_row(row: &Row) -> LeadSheet {
+    LeadSheet {
+        id: row.get(0),
+        song_id: row.get(1),
+        revision: row.get(2),
+        sections: sections_from_json(row.get(3)),
+        created_by: row.get(4),
+        created_at: row.get(5),
+        updated_at: row.get(6),
+    }
+}
+
+/// Returns the JSON of the sections of a lead sheet, an array of `{"kind", "label", "lines"}` objects
+/// whose lines are `{"lyrics", "chords"}` objects with `{"position", "chord"}` chords.
+fn sections_json(sections: &[Section]) -> serde_json::Value {
+    sections
+        .iter()
+        .map(|section| {
+            let lines: serde_json::Value = section
+                .lines
+                .iter()
+                .map(|line| {
+                    let chords: serde_json::Value = line
+                        .chords
+                        .iter()
+                        .map(|chord| {
+                            serde_json::json!({
+                                "position": chord.position,
+                                "chord": chord.chord.to_string(),
+                            })
+                        })
+                        .collect();
+
+                    serde_json::json!({ "lyrics": line.lyrics, "chords": chords })
+                })
+                .collect();
+
+            serde_json::json!({
+                "kind": section.kind.as_str(),
+                "label": section.label,
+                "lines": lines,
+            })
+        })
+        .collect()
+}
+
+/// Returns the sections of a lead sheet of their JSON, the sections, lines and chords that cannot be read are skipped.
+fn sections_from_json(json: serde_json::Value) -> Vec<Section> {
+    let array = |value: &serde_json::Value| value.as_array().cloned().unwrap_or_default();
+
+    array(&json)
+        .iter()
+        .filter_map(|section| {
+            Some(Section {
+                kind: section["kind"].as_str()?.parse().ok()?,
+                label: section["label"].as_str()?.to_string(),
+                lines: array(&section["lines"])
+                    .iter()
+                    .filter_map(|line| {
+                        Some(Line {
+                            lyrics: line["lyrics"].as_str()?.to_string(),
+                            chords: array(&line["chords"])
+                                .iter()
+                                .filter_map(|chord| {
+                                    Some(ChordPosition {
+                                        position: chord["position"].as_u64()? as usize,
+                                        chord: chord["chord"].as_str()?.parse::<Chord>().ok()?,
+                                    })
+                                })
+                                .collect(),
+                        })
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_app::context::Context;
+    use openmusicgang_entity::gang::Gang;
+    use openmusicgang_entity::invitation::Invitation;
+    use openmusicgang_entity::lead_sheet::SectionKind;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_service::gang_service::GangService as GangServiceTrait;
+    use openmusicgang_service::membership_service::MembershipService as MembershipServiceTrait;
+    use openmusicgang_service::song_service::{SongService as SongServiceTrait, SongUpdate};
+
+    use crate::gang::GangService;
+    use crate::membership::MembershipService;
+    use crate::song::SongService;
+    use crate::test_utils::{must_create_user, must_lock_db, must_open_db, must_truncate_table};
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) import a lead sheet as a guest, error should be EFORBIDDEN.
+    /// 4) import a lead sheet, it should belong to the latest revision.
+    /// 5) import an invalid ChordPro text, error should be EINVALID.
+    /// 6) save the lead sheet again, its sections should be replaced.
+    /// 7) render the lead sheet of the first revision transposed, with the key of the revision.
+    /// 8) delete the lead sheet as a guest, error should be EFORBIDDEN.
+    /// 9) delete the lead sheet, it should not be found anymore.
+    #[test]
+    fn test_lead_sheet_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "lead_sheets");
+        must_truncate_table(&mut db, "song_revisions");
+        must_truncate_table(&mut db, "songs");
+        must_truncate_table(&mut db, "gang_invitations");
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+        let mark = must_create_user(&mut db, "Mark Smith", "mark.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+        let membership_service = MembershipService::new(Arc::clone(&db), 3600);
+        let song_service = SongService::new(Arc::clone(&db));
+        let lead_sheet_service = LeadSheetService::new(Arc::clone(&db));
+
+        let ctx = |user: &User| Context::with_user(Context::background(), user.clone());
+
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang_service.create_gang(ctx(&bob), &mut gang).unwrap();
+
+        for (user, role) in [(&john, GangRole::Member), (&mark, GangRole::Guest)] {
+            let mut invitation = Invitation {
+                gang_id: gang.id,
+                user_id: Some(user.id),
+                role,
+                ..Default::default()
+            };
+            membership_service
+                .invite_member(ctx(&bob), &mut invitation)
+                .unwrap();
+            membership_service
+                .accept_invitation(ctx(user), invitation.id)
+                .unwrap();
+        }
+
+        let mut song = Song {
+            gang_id: gang.id,
+            title: "Segfault Blues".to_string(),
+            key: Some("E".to_string()),
+            ..Default::default()
+        };
+        song_service.create_song(ctx(&john), &mut song).unwrap();
+
+        let update = SongUpdate {
+            key: Some("G".to_string()),
+            ..Default::default()
+        };
+        let song = song_service
+            .update_song(ctx(&john), song.id, update)
+            .unwrap();
+
+        let chordpro = "{title: Segfault Blues}
+[E]Pointers [A]everywhere
+{soc: Chorus}
+[A]Core [B7/D#]dumped
+{eoc}";
+
+        // 3) import a lead sheet as a guest, error should be EFORBIDDEN.
+        let res = lead_sheet_service.import_lead_sheet(ctx(&mark), song.id, 1, chordpro);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 4) import a lead sheet, it should belong to the latest revision.
+        let sheet = lead_sheet_service
+            .import_lead_sheet(ctx(&john), song.id, 0, chordpro)
+            .unwrap();
+        assert_eq!(sheet.revision, 2);
+        assert_eq!(sheet.created_by, Some(john.id));
+        assert_eq!(sheet.sections.len(), 2);
+
+        let found = lead_sheet_service
+            .find_lead_sheet(ctx(&mark), song.id, 0)
+            .unwrap();
+        assert_eq!(found.id, sheet.id);
+        assert_eq!(found.sections, sheet.sections);
+        assert_eq!(found.sections[1].kind, SectionKind::Chorus);
+        assert_eq!(found.sections[1].label, "Chorus");
+
+        let res = lead_sheet_service.find_lead_sheet(ctx(&mark), song.id, 1);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        // 5) import an invalid ChordPro text, error should be EINVALID.
+        for (chordpro, revision) in [("[Hm]Pointers", 0), ("{soc}\n[Am]Pointers", 0), ("", 3)] {
+            let res = lead_sheet_service.import_lead_sheet(ctx(&john), song.id, revision, chordpro);
+            assert!(res.is_err());
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+
+        // 6) save the lead sheet again, its sections should be replaced.
+        let mut replaced = LeadSheet {
+            song_id: song.id,
+            sections: vec![sheet.sections[1].clone()],
+            ..Default::default()
+        };
+        lead_sheet_service
+            .save_lead_sheet(ctx(&bob), &mut replaced)
+            .unwrap();
+        assert_eq!(replaced.id, sheet.id);
+        assert_eq!(replaced.created_by, Some(john.id));
+
+        let found = lead_sheet_service
+            .find_lead_sheet(ctx(&mark), song.id, 2)
+            .unwrap();
+        assert_eq!(found.sections.len(), 1);
+
+        // 7) render the lead sheet of the first revision transposed, with the key of the revision.
+        let first = lead_sheet_service
+            .import_lead_sheet(ctx(&john), song.id, 1, chordpro)
+            .unwrap();
+        assert_ne!(first.id, sheet.id);
+
+        let res = lead_sheet_service.render_lead_sheet(ctx(&mark), song.id, 1, 12);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let text = lead_sheet_service
+            .render_lead_sheet(ctx(&mark), song.id, 1, -2)
+            .unwrap();
+        assert!(text.contains("{key: D}"));
+        assert!(text.contains("[D]Pointers [G]everywhere"));
+        assert!(text.contains("[G]Core [A7/C#]dumped"));
+
+        // 8) delete the lead sheet as a guest, error should be EFORBIDDEN.
+        let res = lead_sheet_service.delete_lead_sheet(ctx(&mark), first.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 9) delete the lead sheet, it should not be found anymore.
+        lead_sheet_service
+            .delete_lead_sheet(ctx(&john), first.id)
+            .unwrap();
+
+        let res = lead_sheet_service.find_lead_sheet(ctx(&john), song.id, 1);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+    }
+}
@@ -1,13 +1,40 @@
+pub mod activity;
+pub mod comment;
+pub mod email;
+pub mod follow;
+pub mod gang;
+pub mod lead_sheet;
+pub mod membership;
+pub mod midi_clip;
 pub mod migrations;
+pub mod notification;
 pub mod postgres;
 pub mod query;
+pub mod song;
+pub mod token;
+pub mod track;
 pub mod user;
 
 #[cfg(test)]
 pub mod test_utils {
+    use std::sync::{Mutex, MutexGuard};
+
+    use once_cell::sync::Lazy;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_service::blob_store::BlobStore;
+    use openmusicgang_storage::local::BlobStore as LocalBlobStore;
     use postgres::types::ToSql;
 
     use crate::postgres::DB;
+    use crate::{insert_user_params, insert_user_sql};
+
+    /// TEST_LOCK serializes the tests that truncate shared tables.
+    static TEST_LOCK: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
+
+    #[allow(dead_code)]
+    pub fn must_lock_db() -> MutexGuard<'static, ()> {
+        TEST_LOCK.lock().unwrap_or_else(|error| error.into_inner())
+    }
 
     #[allow(dead_code)]
     pub fn must_open_db() -> DB {
@@ -31,6 +58,23 @@ pub mod test_utils {
         }
     }
 
+    #[allow(dead_code)]
+    pub fn must_create_user(db: &mut DB, name: &str, email: &str) -> User {
+        let mut user = User::new();
+        user.name = name.to_string();
+        user.email = email.to_string();
+        user.email_verified_at = Some(user.created_at);
+
+        let mut tx = db.begin_tx().unwrap();
+        let row = tx
+            .query_one(insert_user_sql!().as_str(), insert_user_params!(user))
+            .unwrap();
+        tx.commit().unwrap();
+
+        user.id = row.get(0);
+        user
+    }
+
     #[allow(dead_code)]
     pub fn must_truncate_table(db: &mut DB, table: &str) {
         let query = format!("TRUNCATE TABLE {} RESTART IDENTITY CASCADE", table);
@@ -42,4 +86,46 @@ pub mod test_utils {
         let query = format!("DROP TABLE IF EXISTS {}", table);
         must_exec(db, &query, &[]);
     }
+
+    /// Stores a 16 bits PCM WAV file of a 1 kHz square wave of the given amplitude and returns its key,
+    /// silence if the amplitude is 0.
+    #[allow(dead_code)]
+    pub fn must_put_wav(
+        blob_store: &LocalBlobStore,
+        sample_rate: u32,
+        channels: u16,
+        frames: u32,
+        amplitude: i16,
+    ) -> String {
+        let block_align = channels * 2;
+        let data_size = frames * block_align as u32;
+
+        let mut wav = vec![];
+        wav.extend(b"RIFF");
+        wav.extend((36 + data_size).to_le_bytes());
+        wav.extend(b"WAVEfmt ");
+        wav.extend(16u32.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(channels.to_le_bytes());
+        wav.extend(sample_rate.to_le_bytes());
+        wav.extend((sample_rate * block_align as u32).to_le_bytes());
+        wav.extend(block_align.to_le_bytes());
+        wav.extend(16u16.to_le_bytes());
+        wav.extend(b"data");
+        wav.extend(data_size.to_le_bytes());
+
+        let half_period = sample_rate / 2000;
+        for frame in 0..frames {
+            let sample = if (frame / half_period).is_multiple_of(2) {
+                amplitude
+            } else {
+                -amplitude
+            };
+            for _ in 0..channels {
+                wav.extend(sample.to_le_bytes());
+            }
+        }
+
+        blob_store.put_blob(&mut wav.as_slice()).unwrap().key
+    }
 }
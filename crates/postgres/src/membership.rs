@@ -0,0 +1,1314 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+use chrono::Duration;
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::activity::{Activity, ActivityKind};
+use openmusicgang_entity::invitation::{Invitation, InvitationStatus};
+use openmusicgang_entity::membership::{GangRole, Membership};
+use openmusicgang_entity::notification::{Notification, NotificationKind};
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::membership_service::{
+    InvitationFilter, MembershipFilter, MembershipService as MembershipServiceTrait,
+};
+use postgres::types::ToSql;
+use postgres::{Row, Transaction};
+
+use crate::activity::record_activity;
+use crate::gang::find_gang_by_id;
+use crate::notification::record_notification;
+use crate::postgres::DB;
+use crate::user::{find_user_by_id, require_verified_user};
+use crate::{
+    delete_membership_params, delete_membership_sql, expire_invitations_sql, format_limit_offset,
+    insert_invitation_params, insert_invitation_sql, insert_membership_params,
+    insert_membership_sql, select_invitations_sql, select_memberships_sql,
+    select_pending_invitation_id_sql, select_user_id_by_email_sql, update_invitation_status_params,
+    update_invitation_status_sql, update_membership_role_params, update_membership_role_sql,
+    where_condition_eq,
+};
+
+/// MembershipService is a struct that implements the MembershipServiceTrait for the postgres crate.
+pub struct MembershipService {
+    db: Arc<Mutex<DB>>,
+    /// Seconds after which an invitation expires.
+    invitation_ttl: u64,
+}
+
+impl MembershipService {
+    /// Create a new MembershipService struct
+    pub fn new(db: Arc<Mutex<DB>>, invitation_ttl: u64) -> MembershipService {
+        MembershipService { db, invitation_ttl }
+    }
+}
+
+impl MembershipServiceTrait for MembershipService {
+    /// Invites a user to a gang.
+    fn invite_member(&self, ctx: AppContext, invitation: &mut Invitation) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        invite_member(ctx, &mut tx, self.invitation_ttl, invitation)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Accepts an invitation.
+    fn accept_invitation(&self, ctx: AppContext, id: i64) -> Result<Membership, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let res = accept_invitation(ctx, &mut tx, id);
+
+        // The expiration of an invitation must be stored even if the acceptance fails.
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        res
+    }
+
+    /// Declines an invitation.
+    fn decline_invitation(&self, ctx: AppContext, id: i64) -> Result<Invitation, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let res = decline_invitation(ctx, &mut tx, id);
+
+        // The expiration of an invitation must be stored even if the decline fails.
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        res
+    }
+
+    /// Expires the pending invitations past their expiration.
+    fn expire_invitations(&self, ctx: AppContext) -> Result<i64, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let expired = expire_invitations(ctx, &mut tx)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(expired)
+    }
+
+    /// Returns a vector of invitations based on passed filters, also returns the total number of invitations.
+    fn find_invitations(
+        &self,
+        ctx: AppContext,
+        filters: InvitationFilter,
+    ) -> Result<(Vec<Invitation>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_invitations(ctx, &mut tx, filters)
+    }
+
+    /// Returns a vector of memberships based on passed filters, also returns the total number of memberships.
+    fn find_memberships(
+        &self,
+        ctx: AppContext,
+        filters: MembershipFilter,
+    ) -> Result<(Vec<Membership>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_memberships(ctx, &mut tx, filters)
+    }
+
+    /// Updates the role of a member.
+    fn update_member_role(
+        &self,
+        ctx: AppContext,
+        gang_id: i64,
+        user_id: i64,
+        role: GangRole,
+    ) -> Result<Membership, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let membership = update_member_role(ctx, &mut tx, gang_id, user_id, role)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(membership)
+    }
+
+    /// Removes a member from a gang.
+    fn remove_member(&self, ctx: AppContext, gang_id: i64, user_id: i64) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        remove_member(ctx, &mut tx, gang_id, user_id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Leaves a gang.
+    fn leave_gang(&self, ctx: AppContext, gang_id: i64) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        leave_gang(ctx, &mut tx, gang_id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Transfers the ownership of a gang.
+    fn transfer_ownership(&self, ctx: AppContext, gang_id: i64, user_id: i64) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        transfer_ownership(ctx, &mut tx, gang_id, user_id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+}
+
+/// require_role returns the membership of the user of the context in the gang.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EFORBIDDEN if the user is not a member of the gang or its role is lower than the given one.
+pub(crate) fn require_role(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    gang_id: i64,
+    role: GangRole,
+) -> Result<Membership, Error> {
+    let user_id = Context::user_id_from_context(ctx.clone());
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in".to_string(),
+        ));
+    }
+
+    let membership = match find_membership(ctx, tx, gang_id, user_id) {
+        Ok(membership) => membership,
+        Err(error) if error.code == ErrorCode::ENOTFOUND => {
+            return Err(Error::new(
+                ErrorCode::EFORBIDDEN,
+                "You are not a member of this gang".to_string(),
+            ))
+        }
+        Err(error) => return Err(error),
+    };
+
+    if membership.role < role {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            format!("This action requires the {} role", role),
+        ));
+    }
+
+    Ok(membership)
+}
+
+/// create_membership inserts a new membership into the database.
+pub(crate) fn create_membership(
+    tx: &mut Transaction,
+    membership: &mut Membership,
+) -> Result<(), Error> {
+    membership.created_at = Utc::now();
+    membership.updated_at = Utc::now();
+
+    let row = tx
+        .query_one(
+            insert_membership_sql!(),
+            insert_membership_params!(membership),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    membership.id = row.get(0);
+
+    Ok(())
+}
+
+/// find_membership returns the membership of a user in a gang.
+/// Returns ENOTFOUND if the user is not a member of the gang.
+pub(crate) fn find_membership(
+    _ctx: AppContext,
+    tx: &mut Transaction,
+    gang_id: i64,
+    user_id: i64,
+) -> Result<Membership, Error> {
+    let filters = MembershipFilter {
+        gang_id: Some(gang_id),
+        user_id: Some(user_id),
+        ..Default::default()
+    };
+
+    let result = select_memberships(tx, filters)?;
+
+    if result.1 == 0 {
+        return Err(Error::new(
+            ErrorCode::ENOTFOUND,
+            "Membership not found".to_string(),
+        ));
+    }
+
+    Ok(result.0[0].clone())
+}
+
+/// find_memberships finds memberships in the database based on the filters.
+///
+/// Handles the find_memberships Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EFORBIDDEN if the memberships of a gang are requested by someone who is not a member of the gang.
+///
+/// Without a gang in the filters only the memberships of the user of the context are returned.
+fn find_memberships(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    mut filters: MembershipFilter,
+) -> Result<(Vec<Membership>, i64), Error> {
+    if let Some(gang_id) = filters.gang_id {
+        require_role(ctx, tx, gang_id, GangRole::Guest)?;
+        return select_memberships(tx, filters);
+    }
+
+    let user_id = Context::user_id_from_context(ctx);
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in".to_string(),
+        ));
+    }
+
+    filters.user_id = Some(user_id);
+
+    select_memberships(tx, filters)
+}
+
+/// select_memberships selects the memberships matching the filters.
+fn select_memberships(
+    tx: &mut Transaction,
+    filters: MembershipFilter,
+) -> Result<(Vec<Membership>, i64), Error> {
+    let role = filters.role.map(|role| role.as_str());
+
+    let mut where_conditions = vec!["1 = 1".to_string()];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![];
+    let mut args_counter = 1;
+
+    if filters.gang_id.is_some() {
+        where_conditions.push(where_condition_eq!("gang_id", args_counter));
+        args_counter += 1;
+        args.push(&filters.gang_id);
+    }
+
+    if filters.user_id.is_some() {
+        where_conditions.push(where_condition_eq!("user_id", args_counter));
+        args_counter += 1;
+        args.push(&filters.user_id);
+    }
+
+    if role.is_some() {
+        where_conditions.push(where_condition_eq!("role", args_counter));
+        args.push(&role);
+    }
+
+    let query = select_memberships_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut memberships: Vec<Membership> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        let role: String = row.get(3);
+        let mut membership = Membership::new(row.get(1), row.get(2), role.parse()?);
+
+        membership.id = row.get(0);
+        membership.created_at = row.get(4);
+        membership.updated_at = row.get(5);
+        tot_results = row.get(6);
+
+        memberships.push(membership);
+    }
+
+    Ok((memberships, tot_results))
+}
+
+/// invite_member inserts a new pending invitation into the database, the invited user is notified if they
+/// have an account.
+///
+/// Handles the invite_member Business Logic.
+///
+/// Returns EFORBIDDEN if the user of the context is not an admin of the gang or invites with a role higher than its own.
+///
+/// Returns EINVALID if the invitation is invalid.
+///
+/// Returns ENOTFOUND if the invited user id does not exist.
+///
+/// Returns ECONFLICT if the invited user is already a member or has a pending invitation.
+fn invite_member(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    ttl: u64,
+    invitation: &mut Invitation,
+) -> Result<(), Error> {
+    let inviter = require_role(ctx.clone(), tx, invitation.gang_id, GangRole::Admin)?;
+
+    invitation.validate()?;
+
+    if invitation.role > inviter.role {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            "You cannot invite with a role higher than your own".to_string(),
+        ));
+    }
+
+    let (user_id, email) = match (invitation.user_id, &invitation.email) {
+        (Some(user_id), _) => {
+            let user = find_user_by_id(ctx.clone(), tx, user_id)?;
+            (Some(user.id), Some(user.email))
+        }
+        (None, Some(email)) => (find_user_id_by_email(tx, email)?, Some(email.clone())),
+        (None, None) => (None, None),
+    };
+
+    if let Some(user_id) = user_id {
+        match find_membership(ctx.clone(), tx, invitation.gang_id, user_id) {
+            Ok(_) => {
+                return Err(Error::new(
+                    ErrorCode::ECONFLICT,
+                    "User is already a member of this gang".to_string(),
+                ))
+            }
+            Err(error) if error.code == ErrorCode::ENOTFOUND => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    let now = Utc::now();
+
+    let pending = tx
+        .query_opt(
+            select_pending_invitation_id_sql!(),
+            &[&invitation.gang_id, &now, &user_id, &email],
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    if pending.is_some() {
+        return Err(Error::new(
+            ErrorCode::ECONFLICT,
+            "User already has a pending invitation to this gang".to_string(),
+        ));
+    }
+
+    invitation.inviter_id = Some(inviter.user_id);
+    invitation.status = InvitationStatus::Pending;
+    invitation.created_at = now;
+    invitation.expires_at = now + Duration::seconds(ttl as i64);
+    invitation.responded_at = None;
+
+    let row = tx
+        .query_one(
+            insert_invitation_sql!(),
+            insert_invitation_params!(invitation),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    invitation.id = row.get(0);
+
+    if let Some(user_id) = user_id {
+        let gang = find_gang_by_id(ctx.clone(), tx, invitation.gang_id)?;
+        let mut notification =
+            Notification::new(NotificationKind::Invitation, user_id, inviter.user_id);
+        notification.gang_id = Some(gang.id);
+        notification.subject_id = Some(invitation.id);
+
+        record_notification(
+            ctx,
+            tx,
+            &mut notification,
+            &format!("invited you to join {}", gang.name),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// find_user_id_by_email returns the id of the user with the given email, compared case-insensitively.
+fn find_user_id_by_email(tx: &mut Transaction, email: &str) -> Result<Option<i64>, Error> {
+    let row = tx
+        .query_opt(select_user_id_by_email_sql!(), &[&email])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(row.map(|row| row.get(0)))
+}
+
+/// find_received_invitation returns a pending invitation addressed to the user of the context.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns ENOTFOUND if the invitation does not exist.
+///
+/// Returns EFORBIDDEN if the invitation is addressed to another user or is expired, the expiration is stored.
+/// An invitation addressed to an email address is only received once the user has verified it.
+///
+/// Returns ECONFLICT if the invitation was already answered.
+fn find_received_invitation(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<Invitation, Error> {
+    let user = match Context::user_from_context(ctx.clone()) {
+        Some(user) => user,
+        None => {
+            return Err(Error::new(
+                ErrorCode::EUNAUTHORIZED,
+                "You must be logged in".to_string(),
+            ))
+        }
+    };
+
+    let filters = InvitationFilter {
+        id: Some(id),
+        ..Default::default()
+    };
+
+    let mut invitation = match select_invitations(tx, filters, None)? {
+        (invitations, 1) => invitations[0].clone(),
+        _ => {
+            return Err(Error::new(
+                ErrorCode::ENOTFOUND,
+                "Invitation not found".to_string(),
+            ))
+        }
+    };
+
+    if invitation.user_id != Some(user.id) {
+        let addressed_to_email = invitation
+            .email
+            .as_ref()
+            .is_some_and(|email| email.eq_ignore_ascii_case(&user.email));
+
+        if !addressed_to_email {
+            return Err(Error::new(
+                ErrorCode::EFORBIDDEN,
+                "This invitation is not addressed to you".to_string(),
+            ));
+        }
+
+        require_verified_user(ctx, tx, "answer an invitation sent to it")?;
+    }
+
+    if invitation.status != InvitationStatus::Pending {
+        return Err(Error::new(
+            ErrorCode::ECONFLICT,
+            format!("Invitation already {}", invitation.status),
+        ));
+    }
+
+    if invitation.expires_at <= Utc::now() {
+        invitation.status = InvitationStatus::Expired;
+
+        tx.execute(
+            update_invitation_status_sql!(),
+            update_invitation_status_params!(invitation),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            "Invitation expired".to_string(),
+        ));
+    }
+
+    Ok(invitation)
+}
+
+/// accept_invitation accepts an invitation and creates the membership of the invited user.
+///
+/// Handles the accept_invitation Business Logic.
+///
+/// Returns the errors of find_received_invitation.
+///
+/// Returns ECONFLICT if the user is already a member of the gang.
+fn accept_invitation(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<Membership, Error> {
+    let mut invitation = find_received_invitation(ctx.clone(), tx, id)?;
+    let user_id = Context::user_id_from_context(ctx.clone());
+
+    match find_membership(ctx.clone(), tx, invitation.gang_id, user_id) {
+        Ok(_) => {
+            return Err(Error::new(
+                ErrorCode::ECONFLICT,
+                "You are already a member of this gang".to_string(),
+            ))
+        }
+        Err(error) if error.code == ErrorCode::ENOTFOUND => {}
+        Err(error) => return Err(error),
+    }
+
+    let mut membership = Membership::new(invitation.gang_id, user_id, invitation.role);
+    create_membership(tx, &mut membership)?;
+
+    invitation.status = InvitationStatus::Accepted;
+    invitation.responded_at = Some(Utc::now());
+
+    tx.execute(
+        update_invitation_status_sql!(),
+        update_invitation_status_params!(invitation),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let gang = find_gang_by_id(ctx.clone(), tx, invitation.gang_id)?;
+    let mut activity = Activity::new(ActivityKind::MemberJoined, user_id, gang.id);
+
+    record_activity(ctx, tx, &mut activity, &format!("joined {}", gang.name))?;
+
+    Ok(membership)
+}
+
+/// decline_invitation declines an invitation.
+///
+/// Handles the decline_invitation Business Logic.
+///
+/// Returns the errors of find_received_invitation.
+fn decline_invitation(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<Invitation, Error> {
+    let mut invitation = find_received_invitation(ctx, tx, id)?;
+
+    invitation.status = InvitationStatus::Declined;
+    invitation.responded_at = Some(Utc::now());
+
+    tx.execute(
+        update_invitation_status_sql!(),
+        update_invitation_status_params!(invitation),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(invitation)
+}
+
+/// expire_invitations marks the pending invitations past their expiration as expired.
+/// Handles the expire_invitations Business Logic.
+fn expire_invitations(_ctx: AppContext, tx: &mut Transaction) -> Result<i64, Error> {
+    let expired = tx
+        .execute(expire_invitations_sql!(), &[&Utc::now()])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(expired as i64)
+}
+
+/// find_invitations finds invitations in the database based on the filters.
+///
+/// Handles the find_invitations Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EFORBIDDEN if the invitations of a gang are requested by someone who is not an admin of the gang.
+///
+/// Without a gang in the filters only the invitations addressed to the user of the context are returned, those
+/// addressed to their email address only once it is verified.
+fn find_invitations(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    filters: InvitationFilter,
+) -> Result<(Vec<Invitation>, i64), Error> {
+    if let Some(gang_id) = filters.gang_id {
+        require_role(ctx, tx, gang_id, GangRole::Admin)?;
+        return select_invitations(tx, filters, None);
+    }
+
+    let user_id = Context::user_id_from_context(ctx.clone());
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in".to_string(),
+        ));
+    }
+
+    let user = find_user_by_id(ctx, tx, user_id)?;
+    let email = user.is_verified().then_some(user.email);
+
+    select_invitations(tx, filters, Some((user.id, email)))
+}
+
+/// select_invitations selects the invitations matching the filters.
+///
+/// If a recipient is given only the invitations addressed to its user id or its email, if any, are selected.
+fn select_invitations(
+    tx: &mut Transaction,
+    filters: InvitationFilter,
+    recipient: Option<(i64, Option<String>)>,
+) -> Result<(Vec<Invitation>, i64), Error> {
+    let status = filters.status.map(|status| status.as_str());
+
+    let mut where_conditions = vec!["1 = 1".to_string()];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![];
+    let mut args_counter = 1;
+
+    if filters.id.is_some() {
+        where_conditions.push(where_condition_eq!("id", args_counter));
+        args_counter += 1;
+        args.push(&filters.id);
+    }
+
+    if filters.gang_id.is_some() {
+        where_conditions.push(where_condition_eq!("gang_id", args_counter));
+        args_counter += 1;
+        args.push(&filters.gang_id);
+    }
+
+    if status.is_some() {
+        where_conditions.push(where_condition_eq!("status", args_counter));
+        args_counter += 1;
+        args.push(&status);
+    }
+
+    if let Some((user_id, email)) = &recipient {
+        // Without an email, LOWER(NULL) matches no invitation.
+        where_conditions.push(format!(
+            "(user_id = ${} OR LOWER(email) = LOWER(${}))",
+            args_counter,
+            args_counter + 1
+        ));
+        args.push(user_id);
+        args.push(email);
+    }
+
+    let query = select_invitations_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut invitations: Vec<Invitation> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        invitations.push(invitation_from_row(&row)?);
+        tot_results = row.get(10);
+    }
+
+    Ok((invitations, tot_results))
+}
+
+/// Returns the invitation of a row selected with select_invitations_sql.
+fn invitation_from_row(row: &Row) -> Result<Invitation, Error> {
+    let role: String = row.get(5);
+    let status: String = row.get(6);
+
+    Ok(Invitation {
+        id: row.get(0),
+        gang_id: row.get(1),
+        inviter_id: row.get(2),
+        user_id: row.get(3),
+        email: row.get(4),
+        role: role.parse()?,
+        status: status.parse()?,
+        created_at: row.get(7),
+        expires_at: row.get(8),
+        responded_at: row.get(9),
+    })
+}
+
+/// update_member_role updates the role of a member of a gang.
+///
+/// Handles the update_member_role Business Logic.
+///
+/// Returns EINVALID if the role is owner, the ownership is transferred with transfer_ownership.
+///
+/// Returns EFORBIDDEN if the user of the context is not an admin of the gang,
+/// if the member has a role not lower than its own or if the role is higher than its own.
+///
+/// Returns ENOTFOUND if the user is not a member of the gang.
+fn update_member_role(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    gang_id: i64,
+    user_id: i64,
+    role: GangRole,
+) -> Result<Membership, Error> {
+    if role == GangRole::Owner {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "Cannot grant the owner role, transfer the ownership instead".to_string(),
+        ));
+    }
+
+    let actor = require_role(ctx.clone(), tx, gang_id, GangRole::Admin)?;
+    let mut membership = find_membership(ctx, tx, gang_id, user_id)?;
+
+    if membership.role >= actor.role || role > actor.role {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            "You do not have permission to change the role of this member".to_string(),
+        ));
+    }
+
+    membership.role = role;
+    membership.updated_at = Utc::now();
+
+    tx.execute(
+        update_membership_role_sql!(),
+        update_membership_role_params!(membership),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(membership)
+}
+
+/// remove_member removes a member from a gang.
+///
+/// Handles the remove_member Business Logic.
+///
+/// Returns EFORBIDDEN if the user of the context is not an admin of the gang or the member has a role not lower than its own.
+///
+/// Returns ENOTFOUND if the user is not a member of the gang.
+fn remove_member(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    gang_id: i64,
+    user_id: i64,
+) -> Result<(), Error> {
+    let actor = require_role(ctx.clone(), tx, gang_id, GangRole::Admin)?;
+    let membership = find_membership(ctx, tx, gang_id, user_id)?;
+
+    if membership.role >= actor.role {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            "You do not have permission to remove this member".to_string(),
+        ));
+    }
+
+    tx.execute(
+        delete_membership_sql!(),
+        delete_membership_params!(membership.id),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// leave_gang removes the user of the context from a gang.
+///
+/// Handles the leave_gang Business Logic.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang.
+///
+/// Returns ECONFLICT if the user is the last owner of the gang.
+fn leave_gang(ctx: AppContext, tx: &mut Transaction, gang_id: i64) -> Result<(), Error> {
+    let membership = require_role(ctx.clone(), tx, gang_id, GangRole::Guest)?;
+
+    if membership.role == GangRole::Owner {
+        let filters = MembershipFilter {
+            gang_id: Some(gang_id),
+            role: Some(GangRole::Owner),
+            ..Default::default()
+        };
+
+        if select_memberships(tx, filters)?.1 <= 1 {
+            return Err(Error::new(
+                ErrorCode::ECONFLICT,
+                "The last owner cannot leave the gang, transfer the ownership first".to_string(),
+            ));
+        }
+    }
+
+    tx.execute(
+        delete_membership_sql!(),
+        delete_membership_params!(membership.id),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// transfer_ownership makes a member the owner of a gang, the user of the context becomes an admin.
+///
+/// Handles the transfer_ownership Business Logic.
+///
+/// Returns EFORBIDDEN if the user of the context is not an owner of the gang.
+///
+/// Returns ENOTFOUND if the user is not a member of the gang.
+///
+/// Returns ECONFLICT if the user is already an owner of the gang.
+fn transfer_ownership(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    gang_id: i64,
+    user_id: i64,
+) -> Result<(), Error> {
+    let mut owner = require_role(ctx.clone(), tx, gang_id, GangRole::Owner)?;
+    let mut membership = find_membership(ctx, tx, gang_id, user_id)?;
+
+    if membership.role == GangRole::Owner {
+        return Err(Error::new(
+            ErrorCode::ECONFLICT,
+            "User is already an owner of this gang".to_string(),
+        ));
+    }
+
+    for (member, role) in [
+        (&mut membership, GangRole::Owner),
+        (&mut owner, GangRole::Admin),
+    ] {
+        member.role = role;
+        member.updated_at = Utc::now();
+
+        tx.execute(
+            update_membership_role_sql!(),
+            update_membership_role_params!(member),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_entity::gang::Gang;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_service::gang_service::GangService as GangServiceTrait;
+
+    use crate::gang::GangService;
+    use crate::test_utils::{
+        must_create_user, must_exec, must_lock_db, must_open_db, must_truncate_table,
+    };
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) create a gang, the creator should be its owner and only members should list the members.
+    /// 4) invite a user by id and a user by email, a duplicate invite should be ECONFLICT.
+    /// 5) invite as a non admin, error should be EFORBIDDEN.
+    /// 6) find the invitations of the gang and the invitations of a user.
+    /// 7) accept an invitation addressed to another user or to an unverified email, error should be EFORBIDDEN.
+    /// 8) accept the invitations, accepting twice should be ECONFLICT.
+    /// 9) decline an invitation.
+    /// 10) accept an expired invitation, error should be EFORBIDDEN and the invitation should be expired.
+    /// 11) update the role of a member, as an admin an owner cannot be demoted.
+    /// 12) the last owner leaves the gang, error should be ECONFLICT.
+    /// 13) transfer the ownership, the previous owner should be an admin and able to leave.
+    /// 14) remove a member.
+    #[test]
+    fn test_membership_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "gang_invitations");
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+        let steve = must_create_user(&mut db, "Steve Smith", "steve.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+        let membership_service = MembershipService::new(Arc::clone(&db), 3600);
+
+        let ctx = |user: &User| Context::with_user(Context::background(), user.clone());
+
+        // 3) create a gang, the creator should be its owner and only members should list the members.
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang_service.create_gang(ctx(&bob), &mut gang).unwrap();
+
+        let filters = MembershipFilter {
+            gang_id: Some(gang.id),
+            ..Default::default()
+        };
+        let (memberships, total) = membership_service
+            .find_memberships(ctx(&bob), filters.clone())
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(memberships[0].user_id, bob.id);
+        assert_eq!(memberships[0].role, GangRole::Owner);
+
+        let res = membership_service.find_memberships(ctx(&john), filters.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let res = membership_service.find_memberships(Context::background(), filters.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let (_, total) = membership_service
+            .find_memberships(ctx(&john), MembershipFilter::default())
+            .unwrap();
+        assert_eq!(total, 0);
+
+        // 4) invite a user by id and a user by email, a duplicate invite should be ECONFLICT.
+        let mut john_invitation = Invitation {
+            gang_id: gang.id,
+            user_id: Some(john.id),
+            role: GangRole::Admin,
+            ..Default::default()
+        };
+        let res = membership_service.invite_member(ctx(&bob), &mut john_invitation);
+        if let Err(error) = res {
+            panic!("{}", error);
+        }
+        assert_eq!(john_invitation.inviter_id, Some(bob.id));
+        assert_eq!(john_invitation.status, InvitationStatus::Pending);
+
+        let mut steve_invitation = Invitation {
+            gang_id: gang.id,
+            email: Some("Steve.Smith@test.com".to_string()),
+            ..Default::default()
+        };
+        let res = membership_service.invite_member(ctx(&bob), &mut steve_invitation);
+        assert!(res.is_ok());
+
+        let mut duplicate = Invitation {
+            gang_id: gang.id,
+            user_id: Some(steve.id),
+            ..Default::default()
+        };
+        let res = membership_service.invite_member(ctx(&bob), &mut duplicate);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        duplicate.user_id = Some(bob.id);
+        let res = membership_service.invite_member(ctx(&bob), &mut duplicate);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        // 5) invite as a non admin, error should be EFORBIDDEN.
+        let mut invitation = Invitation {
+            gang_id: gang.id,
+            email: Some("mark.smith@test.com".to_string()),
+            ..Default::default()
+        };
+        let res = membership_service.invite_member(ctx(&john), &mut invitation);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let res = membership_service.invite_member(Context::background(), &mut invitation);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 6) find the invitations of the gang and the invitations of a user.
+        let gang_filters = InvitationFilter {
+            gang_id: Some(gang.id),
+            ..Default::default()
+        };
+        let (_, total) = membership_service
+            .find_invitations(ctx(&bob), gang_filters.clone())
+            .unwrap();
+        assert_eq!(total, 2);
+
+        let res = membership_service.find_invitations(ctx(&steve), gang_filters);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let (invitations, total) = membership_service
+            .find_invitations(ctx(&steve), InvitationFilter::default())
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(invitations[0].id, steve_invitation.id);
+
+        // 7) accept an invitation addressed to another user or to an unverified email, error should be EFORBIDDEN.
+        let res = membership_service.accept_invitation(ctx(&steve), john_invitation.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        must_exec(
+            &mut db.lock().unwrap(),
+            "UPDATE users SET email_verified_at = NULL WHERE id = $1",
+            &[&steve.id],
+        );
+
+        let (_, total) = membership_service
+            .find_invitations(ctx(&steve), InvitationFilter::default())
+            .unwrap();
+        assert_eq!(total, 0);
+
+        let res = membership_service.accept_invitation(ctx(&steve), steve_invitation.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        must_exec(
+            &mut db.lock().unwrap(),
+            "UPDATE users SET email_verified_at = created_at WHERE id = $1",
+            &[&steve.id],
+        );
+
+        // 8) accept the invitations, accepting twice should be ECONFLICT.
+        let membership = membership_service
+            .accept_invitation(ctx(&john), john_invitation.id)
+            .unwrap();
+        assert_eq!(membership.user_id, john.id);
+        assert_eq!(membership.role, GangRole::Admin);
+
+        let res = membership_service.accept_invitation(ctx(&john), john_invitation.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        let membership = membership_service
+            .accept_invitation(ctx(&steve), steve_invitation.id)
+            .unwrap();
+        assert_eq!(membership.role, GangRole::Member);
+
+        // 9) decline an invitation.
+        let mut another = Gang::new();
+        another.name = "Jazz Crabs".to_string();
+        gang_service.create_gang(ctx(&john), &mut another).unwrap();
+
+        let mut invitation = Invitation {
+            gang_id: another.id,
+            user_id: Some(steve.id),
+            ..Default::default()
+        };
+        membership_service
+            .invite_member(ctx(&john), &mut invitation)
+            .unwrap();
+
+        let invitation = membership_service
+            .decline_invitation(ctx(&steve), invitation.id)
+            .unwrap();
+        assert_eq!(invitation.status, InvitationStatus::Declined);
+        assert!(invitation.responded_at.is_some());
+
+        // 10) accept an expired invitation, error should be EFORBIDDEN and the invitation should be expired.
+        let mut invitation = Invitation {
+            gang_id: another.id,
+            user_id: Some(steve.id),
+            ..Default::default()
+        };
+        membership_service
+            .invite_member(ctx(&john), &mut invitation)
+            .unwrap();
+
+        must_exec(
+            &mut db.lock().unwrap(),
+            "UPDATE gang_invitations SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1",
+            &[&invitation.id],
+        );
+
+        let res = membership_service.accept_invitation(ctx(&steve), invitation.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let filters_expired = InvitationFilter {
+            status: Some(InvitationStatus::Expired),
+            ..Default::default()
+        };
+        let (invitations, total) = membership_service
+            .find_invitations(ctx(&steve), filters_expired)
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(invitations[0].id, invitation.id);
+
+        let mut invitation = Invitation {
+            gang_id: another.id,
+            user_id: Some(steve.id),
+            ..Default::default()
+        };
+        membership_service
+            .invite_member(ctx(&john), &mut invitation)
+            .unwrap();
+
+        must_exec(
+            &mut db.lock().unwrap(),
+            "UPDATE gang_invitations SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1",
+            &[&invitation.id],
+        );
+
+        let expired = membership_service
+            .expire_invitations(Context::background())
+            .unwrap();
+        assert_eq!(expired, 1);
+
+        // 11) update the role of a member, as an admin an owner cannot be demoted.
+        let membership = membership_service
+            .update_member_role(ctx(&john), gang.id, steve.id, GangRole::Guest)
+            .unwrap();
+        assert_eq!(membership.role, GangRole::Guest);
+
+        let res =
+            membership_service.update_member_role(ctx(&john), gang.id, bob.id, GangRole::Member);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let res =
+            membership_service.update_member_role(ctx(&bob), gang.id, john.id, GangRole::Owner);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 12) the last owner leaves the gang, error should be ECONFLICT.
+        let res = membership_service.leave_gang(ctx(&bob), gang.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        // 13) transfer the ownership, the previous owner should be an admin and able to leave.
+        let res = membership_service.transfer_ownership(ctx(&john), gang.id, steve.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        membership_service
+            .transfer_ownership(ctx(&bob), gang.id, john.id)
+            .unwrap();
+
+        let (memberships, _) = membership_service
+            .find_memberships(ctx(&bob), filters.clone())
+            .unwrap();
+        let roles: Vec<(i64, GangRole)> = memberships.iter().map(|m| (m.user_id, m.role)).collect();
+        assert_eq!(
+            roles,
+            vec![
+                (bob.id, GangRole::Admin),
+                (john.id, GangRole::Owner),
+                (steve.id, GangRole::Guest)
+            ]
+        );
+
+        membership_service.leave_gang(ctx(&bob), gang.id).unwrap();
+
+        // 14) remove a member.
+        let res = membership_service.remove_member(ctx(&steve), gang.id, john.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        membership_service
+            .remove_member(ctx(&john), gang.id, steve.id)
+            .unwrap();
+
+        let (memberships, total) = membership_service
+            .find_memberships(ctx(&john), filters)
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(memberships[0].user_id, john.id);
+
+        let (memberships, total) = membership_service
+            .find_memberships(ctx(&steve), MembershipFilter::default())
+            .unwrap();
+        assert_eq!(total, 0);
+        assert!(memberships.is_empty());
+    }
+}
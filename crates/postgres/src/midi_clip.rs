@@ -0,0 +1,795 @@
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+
+use openmusicgang_app::context::AppContext;
+use openmusicgang_entity::blob::Blob;
+use openmusicgang_entity::membership::GangRole;
+use openmusicgang_entity::midi_clip::MidiClip;
+use openmusicgang_entity::song::Song;
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_midi::export::song_midi_file;
+use openmusicgang_midi::metadata::{duration, import_song_metadata, note_count};
+use openmusicgang_midi::smf::{parse_midi, write_midi, MidiFile};
+use openmusicgang_service::blob_store::BlobStore;
+use openmusicgang_service::midi_clip_service::{
+    MidiClipFilter, MidiClipService as MidiClipServiceTrait,
+};
+use postgres::types::ToSql;
+use postgres::{Row, Transaction};
+
+use crate::membership::require_role;
+use crate::postgres::DB;
+use crate::song::{find_song_by_id, find_song_revision, lock_song, save_song_revision};
+use crate::{
+    delete_midi_clip_params, delete_midi_clip_sql, format_limit_offset, insert_midi_clip_params,
+    insert_midi_clip_sql, select_midi_clips_sql, where_condition_eq,
+};
+
+/// MidiClipService is a struct that implements the MidiClipServiceTrait for the postgres crate.
+///
+/// The MIDI files of the clips are read from the blob store to be parsed, exported files are stored in it.
+pub struct MidiClipService {
+    db: Arc<Mutex<DB>>,
+    blob_store: Arc<dyn BlobStore + Send + Sync>,
+}
+
+impl MidiClipService {
+    /// Create a new MidiClipService struct
+    pub fn new(
+        db: Arc<Mutex<DB>>,
+        blob_store: Arc<dyn BlobStore + Send + Sync>,
+    ) -> MidiClipService {
+        MidiClipService { db, blob_store }
+    }
+
+    /// Returns the MIDI file of the blob with the given key.
+    ///
+    /// Returns EINVALID if the blob does not exist or is not a supported MIDI file.
+    fn parse_blob(&self, blob_key: &str) -> Result<MidiFile, Error> {
+        let mut reader = match self.blob_store.get_blob(blob_key, None) {
+            Ok(reader) => reader,
+            Err(error) if error.code == ErrorCode::ENOTFOUND => {
+                return Err(Error::new(
+                    ErrorCode::EINVALID,
+                    "blob_key must reference an uploaded blob".to_string(),
+                ))
+            }
+            Err(error) => return Err(error),
+        };
+
+        let mut buf = vec![];
+
+        reader
+            .read_to_end(&mut buf)
+            .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+        parse_midi(&buf)
+    }
+}
+
+impl MidiClipServiceTrait for MidiClipService {
+    /// Create a new MIDI clip.
+    fn create_midi_clip(&self, ctx: AppContext, clip: &mut MidiClip) -> Result<(), Error> {
+        clip.validate()?;
+
+        let file = self.parse_blob(&clip.blob_key)?;
+        clip.format = file.format as i32;
+        clip.tracks = file.tracks.len() as i32;
+        clip.ticks_per_quarter = file.division as i32;
+        clip.notes = note_count(&file);
+        clip.duration = duration(&file);
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        create_midi_clip(ctx, &mut tx, clip)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Deletes a MIDI clip.
+    fn delete_midi_clip(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        delete_midi_clip(ctx, &mut tx, id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Get a MIDI clip by id.
+    fn find_midi_clip_by_id(&self, ctx: AppContext, id: i64) -> Result<MidiClip, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_midi_clip_by_id(ctx, &mut tx, id).map(|(clip, _)| clip)
+    }
+
+    /// Imports the metadata of the MIDI file of a clip into its song.
+    fn import_midi_clip_metadata(&self, ctx: AppContext, id: i64) -> Result<Song, Error> {
+        let clip = self.find_midi_clip_by_id(ctx.clone(), id)?;
+        let file = self.parse_blob(&clip.blob_key)?;
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let song = import_midi_clip_metadata(ctx, &mut tx, &clip, &file)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(song)
+    }
+
+    /// Exports a song revision and its clips as a MIDI file stored in the blob store.
+    fn export_song_midi(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+    ) -> Result<Blob, Error> {
+        let (song, clips) = {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on database".to_string(),
+                )
+            })?;
+
+            let mut tx = mutex_db.begin_tx()?;
+
+            find_export_clips(ctx, &mut tx, song_id, revision)?
+        };
+
+        let mut files = vec![];
+
+        for clip in clips {
+            files.push(self.parse_blob(&clip.blob_key)?);
+        }
+
+        let buf = write_midi(&song_midi_file(&song, &files));
+
+        self.blob_store.put_blob(&mut buf.as_slice())
+    }
+
+    /// Returns a vector of MIDI clips based on passed filters, also returns the total number of clips.
+    fn find_midi_clips(
+        &self,
+        ctx: AppContext,
+        filters: MidiClipFilter,
+    ) -> Result<(Vec<MidiClip>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_midi_clips(ctx, &mut tx, filters)
+    }
+}
+
+/// create_midi_clip inserts a new MIDI clip in a song revision, with the properties of its file already parsed.
+///
+/// Handles the create_midi_clip Business Logic.
+///
+/// Returns ENOTFOUND if the song does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot add clips.
+///
+/// Returns EINVALID if the clip is invalid or the revision does not exist.
+fn create_midi_clip(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    clip: &mut MidiClip,
+) -> Result<(), Error> {
+    lock_song(tx, clip.song_id)?;
+
+    let song = find_song_by_id(ctx.clone(), tx, clip.song_id)?;
+    let member = require_role(ctx, tx, song.gang_id, GangRole::Member)?;
+
+    if clip.revision == 0 {
+        clip.revision = song.revision;
+    }
+
+    if clip.revision < 1 || clip.revision > song.revision {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            format!("Song has no revision {}", clip.revision),
+        ));
+    }
+
+    clip.created_by = Some(member.user_id);
+    clip.created_at = Utc::now();
+    clip.updated_at = clip.created_at;
+
+    clip.validate()?;
+
+    let row = tx
+        .query_one(insert_midi_clip_sql!(), insert_midi_clip_params!(clip))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    clip.id = row.get(0);
+
+    Ok(())
+}
+
+/// delete_midi_clip deletes a MIDI clip from the database.
+///
+/// Handles the delete_midi_clip Business Logic.
+///
+/// Returns ENOTFOUND if the clip does not exist.
+///
+/// Returns EFORBIDDEN unless the user of the context is an admin of the gang, or the member who added the clip.
+fn delete_midi_clip(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<(), Error> {
+    let (clip, song) = find_midi_clip_by_id(ctx.clone(), tx, id)?;
+    let member = require_role(ctx, tx, song.gang_id, GangRole::Member)?;
+
+    if member.role < GangRole::Admin && clip.created_by != Some(member.user_id) {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            "You do not have permission to delete this MIDI clip".to_string(),
+        ));
+    }
+
+    tx.execute(delete_midi_clip_sql!(), delete_midi_clip_params!(id))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// import_midi_clip_metadata sets the tempo map, time signature and key of the song of a clip to the ones of its file,
+/// the change is recorded as a new revision.
+///
+/// Handles the import_midi_clip_metadata Business Logic.
+///
+/// Returns ENOTFOUND if the song does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot change songs.
+///
+/// Returns EINVALID if the imported metadata is invalid.
+fn import_midi_clip_metadata(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    clip: &MidiClip,
+    file: &MidiFile,
+) -> Result<Song, Error> {
+    lock_song(tx, clip.song_id)?;
+
+    let mut song = find_song_by_id(ctx.clone(), tx, clip.song_id)?;
+    let member = require_role(ctx.clone(), tx, song.gang_id, GangRole::Member)?;
+
+    import_song_metadata(file, &mut song);
+
+    song.validate()?;
+
+    let source_revision = song.revision;
+
+    save_song_revision(
+        ctx,
+        tx,
+        &mut song,
+        member.user_id,
+        format!("Import metadata of MIDI clip {}", clip.name),
+        source_revision,
+    )?;
+
+    Ok(song)
+}
+
+/// find_export_clips returns the content of a song revision and its clips to export as a MIDI file.
+///
+/// Handles the export_song_midi Business Logic.
+///
+/// Returns ENOTFOUND if the song does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot export songs.
+///
+/// Returns EINVALID if the revision does not exist.
+fn find_export_clips(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    song_id: i64,
+    revision: i32,
+) -> Result<(Song, Vec<MidiClip>), Error> {
+    let mut song = find_song_by_id(ctx.clone(), tx, song_id)?;
+    require_role(ctx.clone(), tx, song.gang_id, GangRole::Member)?;
+
+    let revision = if revision == 0 {
+        song.revision
+    } else {
+        revision
+    };
+
+    if revision < 1 || revision > song.revision {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            format!("Song has no revision {}", revision),
+        ));
+    }
+
+    if revision != song.revision {
+        song.restore(&find_song_revision(ctx, tx, song_id, revision)?);
+    }
+
+    let filters = MidiClipFilter {
+        song_id,
+        revision: Some(revision),
+        ..Default::default()
+    };
+
+    let (mut clips, _) = select_midi_clips(tx, filters)?;
+    clips.sort_by_key(|clip| clip.id);
+
+    Ok((song, clips))
+}
+
+/// find_midi_clip_by_id returns a MIDI clip by id, with its song.
+///
+/// Handles the find_midi_clip_by_id Business Logic.
+///
+/// Returns ENOTFOUND if the clip does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang of the song.
+fn find_midi_clip_by_id(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<(MidiClip, Song), Error> {
+    let query = select_midi_clips_sql!([where_condition_eq!("id", 1)], "");
+
+    let row = tx
+        .query_opt(query.as_str(), &[&id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let clip = match row {
+        Some(row) => midi_clip_from_row(&row),
+        None => {
+            return Err(Error::new(
+                ErrorCode::ENOTFOUND,
+                "MIDI clip not found".to_string(),
+            ))
+        }
+    };
+
+    let song = find_song_by_id(ctx, tx, clip.song_id)?;
+
+    Ok((clip, song))
+}
+
+/// find_midi_clips finds the MIDI clips of a song based on the filters.
+///
+/// Handles the find_midi_clips Business Logic.
+///
+/// Returns the errors of find_song_by_id.
+fn find_midi_clips(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    filters: MidiClipFilter,
+) -> Result<(Vec<MidiClip>, i64), Error> {
+    find_song_by_id(ctx, tx, filters.song_id)?;
+
+    select_midi_clips(tx, filters)
+}
+
+/// select_midi_clips selects the MIDI clips matching the filters.
+fn select_midi_clips(
+    tx: &mut Transaction,
+    filters: MidiClipFilter,
+) -> Result<(Vec<MidiClip>, i64), Error> {
+    let mut where_conditions = vec![where_condition_eq!("song_id", 1)];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![&filters.song_id];
+
+    if filters.revision.is_some() {
+        where_conditions.push(where_condition_eq!("revision", 2));
+        args.push(&filters.revision);
+    }
+
+    let query = select_midi_clips_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut clips: Vec<MidiClip> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        clips.push(midi_clip_from_row(&row));
+        tot_results = row.get(13);
+    }
+
+    Ok((clips, tot_results))
+}
+
+/// Returns the MIDI clip of a row selected with select_midi_clips_sql.
+fn midi_clip_from_row(row: &Row) -> MidiClip {
+    MidiClip {
+        id: row.get(0),
+        song_id: row.get(1),
+        revision: row.get(2),
+        blob_key: row.get(3),
+        name: row.get(4),
+        format: row.get(5),
+        tracks: row.get(6),
+        ticks_per_quarter: row.get(7),
+        notes: row.get(8),
+        duration: row.get(9),
+        created_by: row.get(10),
+        created_at: row.get(11),
+        updated_at: row.get(12),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_app::context::Context;
+    use openmusicgang_crypto::random::random_token;
+    use openmusicgang_entity::gang::Gang;
+    use openmusicgang_entity::invitation::Invitation;
+    use openmusicgang_entity::song::TempoChange;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_midi::metadata::{key_signature, tempo_map, time_signature};
+    use openmusicgang_midi::smf::{
+        MidiEvent, MidiMessage, META_KEY_SIGNATURE, META_TEMPO, META_TIME_SIGNATURE,
+    };
+    use openmusicgang_service::gang_service::GangService as GangServiceTrait;
+    use openmusicgang_service::membership_service::MembershipService as MembershipServiceTrait;
+    use openmusicgang_service::song_service::{SongService as SongServiceTrait, SongUpdate};
+    use openmusicgang_storage::local::BlobStore as LocalBlobStore;
+
+    use crate::gang::GangService;
+    use crate::membership::MembershipService;
+    use crate::song::SongService;
+    use crate::test_utils::{must_create_user, must_lock_db, must_open_db, must_truncate_table};
+
+    use super::*;
+
+    /// Stores a format 0 MIDI file of 4 quarter notes at 100 bpm, in 3/4 and in D major, and returns its key.
+    fn must_put_midi(blob_store: &LocalBlobStore) -> String {
+        let meta = |delta, kind, data| MidiEvent {
+            delta,
+            message: MidiMessage::Meta { kind, data },
+        };
+        let note = |delta, velocity| MidiEvent {
+            delta,
+            message: MidiMessage::NoteOn {
+                channel: 0,
+                key: 50,
+                velocity,
+            },
+        };
+
+        let mut track = vec![
+            meta(0, META_TEMPO, vec![0x09, 0x27, 0xC0]),
+            meta(0, META_TIME_SIGNATURE, vec![3, 2, 24, 8]),
+            meta(0, META_KEY_SIGNATURE, vec![2, 0]),
+        ];
+        for _ in 0..4 {
+            track.push(note(0, 100));
+            track.push(note(96, 0));
+        }
+
+        let file = MidiFile {
+            format: 0,
+            division: 96,
+            tracks: vec![track],
+        };
+
+        blob_store
+            .put_blob(&mut write_midi(&file).as_slice())
+            .unwrap()
+            .key
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) add a clip as a guest, error should be EFORBIDDEN.
+    /// 4) add a clip, it should belong to the latest revision with the properties of its file.
+    /// 5) add a clip whose blob is missing or not MIDI, error should be EINVALID.
+    /// 6) import the metadata of the clip, the song should have a new revision with them and with the clip.
+    /// 7) find the clips of the song and of a revision.
+    /// 8) export the revision of the clip, the file should have the metadata of the song and the tracks of the clip.
+    /// 9) delete the clip as another member, error should be EFORBIDDEN.
+    /// 10) delete the clip as an admin.
+    #[test]
+    fn test_midi_clip_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "midi_clips");
+        must_truncate_table(&mut db, "song_revisions");
+        must_truncate_table(&mut db, "songs");
+        must_truncate_table(&mut db, "gang_invitations");
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+        let steve = must_create_user(&mut db, "Steve Smith", "steve.smith@test.com");
+        let mark = must_create_user(&mut db, "Mark Smith", "mark.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+        let membership_service = MembershipService::new(Arc::clone(&db), 3600);
+        let song_service = SongService::new(Arc::clone(&db));
+        let root = std::env::temp_dir().join(format!("openmusicgang-{}", random_token(8)));
+        let blob_store = Arc::new(LocalBlobStore::new(&root).unwrap());
+        let midi_clip_service = MidiClipService::new(Arc::clone(&db), blob_store.clone());
+
+        let piano_key = must_put_midi(&blob_store);
+
+        let ctx = |user: &User| Context::with_user(Context::background(), user.clone());
+
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang_service.create_gang(ctx(&bob), &mut gang).unwrap();
+
+        for (user, role) in [
+            (&john, GangRole::Member),
+            (&steve, GangRole::Member),
+            (&mark, GangRole::Guest),
+        ] {
+            let mut invitation = Invitation {
+                gang_id: gang.id,
+                user_id: Some(user.id),
+                role,
+                ..Default::default()
+            };
+            membership_service
+                .invite_member(ctx(&bob), &mut invitation)
+                .unwrap();
+            membership_service
+                .accept_invitation(ctx(user), invitation.id)
+                .unwrap();
+        }
+
+        let mut song = Song {
+            gang_id: gang.id,
+            title: "Segfault Blues".to_string(),
+            ..Default::default()
+        };
+        song_service.create_song(ctx(&john), &mut song).unwrap();
+
+        let update = SongUpdate {
+            tempo: Some(120.0),
+            key: Some("E".to_string()),
+            ..Default::default()
+        };
+        let song = song_service
+            .update_song(ctx(&john), song.id, update)
+            .unwrap();
+
+        // 3) add a clip as a guest, error should be EFORBIDDEN.
+        let mut piano = MidiClip {
+            song_id: song.id,
+            blob_key: piano_key.clone(),
+            name: "Piano".to_string(),
+            ..Default::default()
+        };
+
+        let res = midi_clip_service.create_midi_clip(ctx(&mark), &mut piano.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 4) add a clip, it should belong to the latest revision with the properties of its file.
+        let res = midi_clip_service.create_midi_clip(ctx(&john), &mut piano);
+        if let Err(error) = res {
+            panic!("{}", error);
+        }
+        assert_eq!(piano.revision, 2);
+        assert_eq!(piano.created_by, Some(john.id));
+        assert_eq!(piano.format, 0);
+        assert_eq!(piano.tracks, 1);
+        assert_eq!(piano.ticks_per_quarter, 96);
+        assert_eq!(piano.notes, 4);
+        assert_eq!(piano.duration, 2.4);
+
+        let clip = midi_clip_service
+            .find_midi_clip_by_id(ctx(&mark), piano.id)
+            .unwrap();
+        assert_eq!(clip.notes, piano.notes);
+        assert_eq!(clip.duration, piano.duration);
+
+        // 5) add a clip whose blob is missing or not MIDI, error should be EINVALID.
+        let missing = MidiClip {
+            blob_key: "0".repeat(64),
+            ..piano.clone()
+        };
+        let res = midi_clip_service.create_midi_clip(ctx(&john), &mut missing.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let not_midi = MidiClip {
+            blob_key: blob_store
+                .put_blob(&mut &b"not a MIDI file"[..])
+                .unwrap()
+                .key,
+            ..piano.clone()
+        };
+        let res = midi_clip_service.create_midi_clip(ctx(&john), &mut not_midi.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 6) import the metadata of the clip, the song should have a new revision with them and with the clip.
+        let res = midi_clip_service.import_midi_clip_metadata(ctx(&mark), piano.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let song = midi_clip_service
+            .import_midi_clip_metadata(ctx(&steve), piano.id)
+            .unwrap();
+        assert_eq!(song.revision, 3);
+        assert_eq!(song.tempo, Some(100.0));
+        assert_eq!(song.tempo_map, vec![]);
+        assert_eq!(song.time_signature, "3/4");
+        assert_eq!(song.key, Some("D".to_string()));
+
+        let revision = song_service
+            .find_song_revision(ctx(&steve), song.id, 3)
+            .unwrap();
+        assert_eq!(revision.author_id, Some(steve.id));
+        assert_eq!(revision.message, "Import metadata of MIDI clip Piano");
+
+        let (clips, total) = midi_clip_service
+            .find_midi_clips(
+                ctx(&steve),
+                MidiClipFilter {
+                    song_id: song.id,
+                    revision: Some(3),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(clips[0].blob_key, piano.blob_key);
+        assert_eq!(clips[0].name, piano.name);
+        assert_eq!(clips[0].notes, piano.notes);
+
+        // 7) find the clips of the song and of a revision.
+        let mut organ = MidiClip {
+            name: "Organ".to_string(),
+            revision: 0,
+            ..piano.clone()
+        };
+        midi_clip_service
+            .create_midi_clip(ctx(&steve), &mut organ)
+            .unwrap();
+        assert_eq!(organ.revision, 3);
+
+        let filters = MidiClipFilter {
+            song_id: song.id,
+            ..Default::default()
+        };
+        let (clips, total) = midi_clip_service
+            .find_midi_clips(ctx(&mark), filters.clone())
+            .unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(clips[0].name, piano.name);
+        assert_eq!(clips[0].revision, 3);
+        assert_eq!(clips[1].id, organ.id);
+        assert_eq!(clips[2].id, piano.id);
+
+        let (clips, total) = midi_clip_service
+            .find_midi_clips(
+                ctx(&mark),
+                MidiClipFilter {
+                    revision: Some(2),
+                    ..filters.clone()
+                },
+            )
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(clips[0].id, piano.id);
+
+        let res = midi_clip_service.find_midi_clips(Context::background(), filters);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 8) export the revision of the clip, the file should have the metadata of the song and the tracks of the clip.
+        let res = midi_clip_service.export_song_midi(ctx(&mark), song.id, 2);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let res = midi_clip_service.export_song_midi(ctx(&john), song.id, 4);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let blob = midi_clip_service
+            .export_song_midi(ctx(&john), song.id, 2)
+            .unwrap();
+
+        let mut buf = vec![];
+        blob_store
+            .get_blob(&blob.key, None)
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+
+        let file = parse_midi(&buf).unwrap();
+        assert_eq!(file.format, 1);
+        assert_eq!(file.division, 96);
+        assert_eq!(file.tracks.len(), 2);
+        assert_eq!(
+            tempo_map(&file),
+            vec![TempoChange {
+                beat: 0.0,
+                tempo: 120.0
+            }]
+        );
+        assert_eq!(time_signature(&file), Some("4/4".to_string()));
+        assert_eq!(key_signature(&file), Some("E".to_string()));
+        assert_eq!(note_count(&file), 4);
+
+        // 9) delete the clip as another member, error should be EFORBIDDEN.
+        let res = midi_clip_service.delete_midi_clip(ctx(&steve), piano.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 10) delete the clip as an admin.
+        let res = midi_clip_service.delete_midi_clip(ctx(&bob), piano.id);
+        assert!(res.is_ok());
+
+        let res = midi_clip_service.find_midi_clip_by_id(ctx(&bob), piano.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
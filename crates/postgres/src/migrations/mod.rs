@@ -6,9 +6,10 @@ pub struct Migration<'a> {
 
 #[allow(dead_code)]
 pub fn get_migrations_list<'a>() -> Vec<Migration<'a>> {
-    vec![Migration {
-        name: "000-create_users_table",
-        query: "CREATE TABLE users(
+    vec![
+        Migration {
+            name: "000-create_users_table",
+            query: "CREATE TABLE users(
                     id BIGSERIAL PRIMARY KEY,
                     name VARCHAR(255) NOT NULL,
                     email VARCHAR(255) UNIQUE NULL,
@@ -16,5 +17,419 @@ pub fn get_migrations_list<'a>() -> Vec<Migration<'a>> {
                     created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
                     updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
                 );",
-    }]
+        },
+        Migration {
+            name: "001-create_refresh_tokens_table",
+            query: "CREATE TABLE refresh_tokens(
+                    id BIGSERIAL PRIMARY KEY,
+                    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    family VARCHAR(64) NOT NULL,
+                    token_hash VARCHAR(64) UNIQUE NOT NULL,
+                    scopes TEXT[] NOT NULL DEFAULT '{}',
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    expires_at TIMESTAMPTZ NOT NULL,
+                    used_at TIMESTAMPTZ NULL,
+                    revoked_at TIMESTAMPTZ NULL
+                );
+                CREATE INDEX refresh_tokens_family_idx ON refresh_tokens(family);",
+        },
+        Migration {
+            name: "002-create_gangs_table",
+            query: "CREATE TABLE gangs(
+                    id BIGSERIAL PRIMARY KEY,
+                    name VARCHAR(255) NOT NULL,
+                    slug VARCHAR(255) UNIQUE NOT NULL,
+                    description TEXT NOT NULL DEFAULT '',
+                    genre VARCHAR(255) NULL,
+                    created_by BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX gangs_created_by_idx ON gangs(created_by);",
+        },
+        Migration {
+            name: "003-create_gang_members_tables",
+            query: "CREATE TABLE gang_members(
+                    id BIGSERIAL PRIMARY KEY,
+                    gang_id BIGINT NOT NULL REFERENCES gangs(id) ON DELETE CASCADE,
+                    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    role VARCHAR(16) NOT NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    UNIQUE (gang_id, user_id)
+                );
+                CREATE INDEX gang_members_user_id_idx ON gang_members(user_id);
+                CREATE TABLE gang_invitations(
+                    id BIGSERIAL PRIMARY KEY,
+                    gang_id BIGINT NOT NULL REFERENCES gangs(id) ON DELETE CASCADE,
+                    inviter_id BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    user_id BIGINT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    email VARCHAR(255) NULL,
+                    role VARCHAR(16) NOT NULL,
+                    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    expires_at TIMESTAMPTZ NOT NULL,
+                    responded_at TIMESTAMPTZ NULL
+                );
+                CREATE INDEX gang_invitations_gang_id_idx ON gang_invitations(gang_id);",
+        },
+        Migration {
+            name: "004-create_songs_tables",
+            query: "CREATE TABLE songs(
+                    id BIGSERIAL PRIMARY KEY,
+                    gang_id BIGINT NOT NULL REFERENCES gangs(id) ON DELETE CASCADE,
+                    title VARCHAR(255) NOT NULL,
+                    key VARCHAR(16) NULL,
+                    tempo DOUBLE PRECISION NULL,
+                    time_signature VARCHAR(8) NOT NULL DEFAULT '4/4',
+                    revision INTEGER NOT NULL,
+                    created_by BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX songs_gang_id_idx ON songs(gang_id);
+                CREATE TABLE song_revisions(
+                    id BIGSERIAL PRIMARY KEY,
+                    song_id BIGINT NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+                    revision INTEGER NOT NULL,
+                    title VARCHAR(255) NOT NULL,
+                    key VARCHAR(16) NULL,
+                    tempo DOUBLE PRECISION NULL,
+                    time_signature VARCHAR(8) NOT NULL,
+                    author_id BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    message TEXT NOT NULL DEFAULT '',
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    UNIQUE (song_id, revision)
+                );",
+        },
+        Migration {
+            name: "005-create_tracks_table",
+            query: "CREATE TABLE tracks(
+                    id BIGSERIAL PRIMARY KEY,
+                    song_id BIGINT NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+                    revision INTEGER NOT NULL,
+                    blob_key VARCHAR(255) NOT NULL,
+                    instrument VARCHAR(64) NOT NULL,
+                    performer_id BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    gain DOUBLE PRECISION NOT NULL DEFAULT 0,
+                    pan DOUBLE PRECISION NOT NULL DEFAULT 0,
+                    muted BOOLEAN NOT NULL DEFAULT FALSE,
+                    solo BOOLEAN NOT NULL DEFAULT FALSE,
+                    offset_samples BIGINT NOT NULL DEFAULT 0,
+                    position INTEGER NOT NULL,
+                    created_by BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX tracks_song_id_revision_idx ON tracks(song_id, revision);",
+        },
+        Migration {
+            name: "006-add_audio_to_tracks",
+            query: "ALTER TABLE tracks
+                    ADD COLUMN audio_format VARCHAR(8) NULL,
+                    ADD COLUMN audio_frames BIGINT NULL,
+                    ADD COLUMN audio_sample_rate INTEGER NULL,
+                    ADD COLUMN audio_bit_depth INTEGER NULL,
+                    ADD COLUMN audio_channels INTEGER NULL,
+                    ADD COLUMN audio_tags JSONB NULL;",
+        },
+        Migration {
+            name: "007-add_waveform_key_to_tracks",
+            query: "ALTER TABLE tracks ADD COLUMN waveform_key VARCHAR(255) NULL;",
+        },
+        Migration {
+            name: "008-add_loudness_to_tracks",
+            query: "ALTER TABLE tracks
+                    ADD COLUMN loudness_integrated DOUBLE PRECISION NULL,
+                    ADD COLUMN loudness_range DOUBLE PRECISION NULL,
+                    ADD COLUMN loudness_true_peak DOUBLE PRECISION NULL;",
+        },
+        Migration {
+            name: "009-add_tempo_map_to_songs",
+            query: "ALTER TABLE songs ADD COLUMN tempo_map JSONB NOT NULL DEFAULT '[]';
+                    ALTER TABLE song_revisions ADD COLUMN tempo_map JSONB NOT NULL DEFAULT '[]';",
+        },
+        Migration {
+            name: "010-create_midi_clips_table",
+            query: "CREATE TABLE midi_clips(
+                    id BIGSERIAL PRIMARY KEY,
+                    song_id BIGINT NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+                    revision INTEGER NOT NULL,
+                    blob_key VARCHAR(255) NOT NULL,
+                    name VARCHAR(255) NOT NULL,
+                    format INTEGER NOT NULL,
+                    tracks INTEGER NOT NULL,
+                    ticks_per_quarter INTEGER NOT NULL,
+                    notes INTEGER NOT NULL,
+                    duration DOUBLE PRECISION NOT NULL,
+                    created_by BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX midi_clips_song_id_revision_idx ON midi_clips(song_id, revision);",
+        },
+        Migration {
+            name: "011-create_lead_sheets_table",
+            query: "CREATE TABLE lead_sheets(
+                    id BIGSERIAL PRIMARY KEY,
+                    song_id BIGINT NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+                    revision INTEGER NOT NULL,
+                    sections JSONB NOT NULL DEFAULT '[]',
+                    created_by BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    UNIQUE (song_id, revision)
+                );",
+        },
+        Migration {
+            name: "012-create_comments_tables",
+            query: "CREATE TABLE comments(
+                    id BIGSERIAL PRIMARY KEY,
+                    song_id BIGINT NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+                    revision INTEGER NOT NULL,
+                    track_id BIGINT NULL REFERENCES tracks(id) ON DELETE CASCADE,
+                    parent_id BIGINT NULL REFERENCES comments(id) ON DELETE CASCADE,
+                    range_start BIGINT NULL,
+                    range_end BIGINT NULL,
+                    body TEXT NOT NULL,
+                    author_id BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    resolved_at TIMESTAMPTZ NULL,
+                    resolved_by BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX comments_song_id_revision_idx ON comments(song_id, revision);
+                CREATE INDEX comments_parent_id_idx ON comments(parent_id);
+                CREATE TABLE comment_edits(
+                    id BIGSERIAL PRIMARY KEY,
+                    comment_id BIGINT NOT NULL REFERENCES comments(id) ON DELETE CASCADE,
+                    body TEXT NOT NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX comment_edits_comment_id_idx ON comment_edits(comment_id);",
+        },
+        Migration {
+            name: "013-create_follows_table",
+            query: "CREATE TABLE follows(
+                    id BIGSERIAL PRIMARY KEY,
+                    follower_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    followed_user_id BIGINT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    followed_gang_id BIGINT NULL REFERENCES gangs(id) ON DELETE CASCADE,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    CHECK ((followed_user_id IS NULL) <> (followed_gang_id IS NULL)),
+                    UNIQUE (follower_id, followed_user_id),
+                    UNIQUE (follower_id, followed_gang_id)
+                );
+                CREATE INDEX follows_followed_user_id_idx ON follows(followed_user_id);
+                CREATE INDEX follows_followed_gang_id_idx ON follows(followed_gang_id);",
+        },
+        Migration {
+            name: "014-create_activities_tables",
+            query: "CREATE TABLE activities(
+                    id BIGSERIAL PRIMARY KEY,
+                    kind VARCHAR(32) NOT NULL,
+                    actor_id BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    gang_id BIGINT NOT NULL REFERENCES gangs(id) ON DELETE CASCADE,
+                    song_id BIGINT NULL REFERENCES songs(id) ON DELETE CASCADE,
+                    revision INTEGER NULL,
+                    subject_id BIGINT NULL,
+                    summary TEXT NOT NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX activities_gang_id_idx ON activities(gang_id);
+                CREATE TABLE feed_entries(
+                    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    activity_id BIGINT NOT NULL REFERENCES activities(id) ON DELETE CASCADE,
+                    PRIMARY KEY (user_id, activity_id)
+                );
+                CREATE INDEX feed_entries_activity_id_idx ON feed_entries(activity_id);",
+        },
+        Migration {
+            name: "015-create_notifications_tables",
+            query: "CREATE TABLE notifications(
+                    id BIGSERIAL PRIMARY KEY,
+                    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    kind VARCHAR(32) NOT NULL,
+                    actor_id BIGINT NULL REFERENCES users(id) ON DELETE SET NULL,
+                    gang_id BIGINT NULL REFERENCES gangs(id) ON DELETE CASCADE,
+                    subject_id BIGINT NULL,
+                    message TEXT NOT NULL,
+                    read_at TIMESTAMPTZ NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX notifications_user_id_idx ON notifications(user_id);
+                CREATE TABLE notification_preferences(
+                    user_id BIGINT PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+                    in_app BOOLEAN NOT NULL DEFAULT TRUE,
+                    email BOOLEAN NOT NULL DEFAULT TRUE,
+                    webhook_url TEXT NULL,
+                    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE TABLE notification_deliveries(
+                    id BIGSERIAL PRIMARY KEY,
+                    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    channel VARCHAR(32) NOT NULL,
+                    address TEXT NOT NULL,
+                    kind VARCHAR(32) NOT NULL,
+                    message TEXT NOT NULL,
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    last_error TEXT NULL,
+                    delivered_at TIMESTAMPTZ NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX notification_deliveries_pending_idx
+                    ON notification_deliveries(next_attempt_at) WHERE delivered_at IS NULL;",
+        },
+        Migration {
+            name: "016-create_email_outbox_table",
+            query: "CREATE TABLE email_outbox(
+                    id BIGSERIAL PRIMARY KEY,
+                    recipient VARCHAR(255) NOT NULL,
+                    subject TEXT NOT NULL,
+                    text_body TEXT NOT NULL,
+                    html_body TEXT NOT NULL,
+                    attempts INTEGER NOT NULL DEFAULT 0,
+                    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    last_error TEXT NULL,
+                    sent_at TIMESTAMPTZ NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+                );
+                CREATE INDEX email_outbox_pending_idx
+                    ON email_outbox(next_attempt_at) WHERE sent_at IS NULL;",
+        },
+        Migration {
+            name: "017-create_email_verifications_table",
+            query: "ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMPTZ NULL;
+                    CREATE TABLE email_verifications(
+                    id BIGSERIAL PRIMARY KEY,
+                    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    email VARCHAR(255) NOT NULL,
+                    token_hash VARCHAR(64) UNIQUE NOT NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    expires_at TIMESTAMPTZ NOT NULL,
+                    used_at TIMESTAMPTZ NULL
+                );
+                CREATE INDEX email_verifications_user_id_idx ON email_verifications(user_id);",
+        },
+        Migration {
+            name: "018-create_password_resets_table",
+            query: "ALTER TABLE users ADD COLUMN sessions_revoked_at TIMESTAMPTZ NULL;
+                    CREATE TABLE password_resets(
+                    id BIGSERIAL PRIMARY KEY,
+                    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                    token_hash VARCHAR(64) UNIQUE NOT NULL,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                    expires_at TIMESTAMPTZ NOT NULL,
+                    used_at TIMESTAMPTZ NULL
+                );
+                CREATE INDEX password_resets_user_id_idx ON password_resets(user_id);",
+        },
+        Migration {
+            name: "019-add_users_pending_email",
+            query: "DO $$
+                    DECLARE duplicates TEXT;
+                    BEGIN
+                        SELECT string_agg(email, ', ' ORDER BY email) INTO duplicates FROM (
+                            SELECT LOWER(TRIM(email)) AS email FROM users
+                            WHERE email IS NOT NULL
+                            GROUP BY LOWER(TRIM(email))
+                            HAVING COUNT(*) > 1
+                        ) AS duplicated;
+
+                        IF duplicates IS NOT NULL THEN
+                            RAISE EXCEPTION 'Users share email addresses that only differ by case or spaces: %. Merge or rename them before migrating.', duplicates;
+                        END IF;
+                    END $$;
+                    ALTER TABLE users ADD COLUMN pending_email VARCHAR(255) NULL;
+                    UPDATE users SET email = LOWER(TRIM(email));
+                    ALTER TABLE users DROP CONSTRAINT users_email_key;
+                    CREATE UNIQUE INDEX users_email_lower_idx ON users(LOWER(email));",
+        },
+        Migration {
+            name: "020-add_users_kept_session_id",
+            query: "ALTER TABLE users ADD COLUMN kept_session_id VARCHAR(64) NULL;",
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::test_utils::must_open_db;
+
+    use super::*;
+
+    /// Runs the migration with the given name on a users table holding the given emails, in a schema of its own
+    /// that is dropped with the transaction.
+    fn must_migrate_users(name: &str, emails: &[&str]) -> Result<Vec<String>, String> {
+        let mut db = must_open_db();
+        let mut tx = db.begin_tx().unwrap();
+
+        tx.batch_execute(
+            "CREATE SCHEMA migration_test;
+            SET LOCAL search_path TO migration_test;
+            CREATE TABLE users(
+                id BIGSERIAL PRIMARY KEY,
+                email VARCHAR(255) UNIQUE NULL
+            );",
+        )
+        .unwrap();
+
+        for email in emails {
+            tx.execute("INSERT INTO users (email) VALUES ($1)", &[email])
+                .unwrap();
+        }
+
+        let migration = get_migrations_list()
+            .into_iter()
+            .find(|migration| migration.name == name)
+            .unwrap();
+
+        tx.batch_execute(migration.query)
+            .map_err(|error| error.as_db_error().unwrap().message().to_string())?;
+
+        Ok(tx
+            .query("SELECT email FROM users ORDER BY id", &[])
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect())
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) migrate users with distinct emails, they should be normalized.
+    /// 2) migrate users whose emails only differ by case or spaces, error should name the duplicates.
+    #[test]
+    fn test_migrate_users_email_lower() {
+        // 1) migrate users with distinct emails, they should be normalized.
+        let emails = must_migrate_users(
+            "019-add_users_pending_email",
+            &["Bob.Smith@test.com", " john.smith@test.com"],
+        )
+        .unwrap();
+        assert_eq!(emails, vec!["bob.smith@test.com", "john.smith@test.com"]);
+
+        // 2) migrate users whose emails only differ by case or spaces, error should name the duplicates.
+        let res = must_migrate_users(
+            "019-add_users_pending_email",
+            &[
+                "Bob.Smith@test.com",
+                "bob.smith@test.com ",
+                "john.smith@test.com",
+                "Steve@test.com",
+                "steve@test.com",
+            ],
+        );
+        assert!(res.is_err());
+
+        let error = res.unwrap_err();
+        assert!(
+            error.contains("bob.smith@test.com, steve@test.com."),
+            "{}",
+            error
+        );
+        assert!(!error.contains("john.smith@test.com"));
+    }
 }
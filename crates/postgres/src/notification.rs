@@ -0,0 +1,899 @@
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use chrono::prelude::*;
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::email::EmailTemplate;
+use openmusicgang_entity::notification::{
+    Channel, Delivery, Notification, NotificationPreferences,
+};
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::notification_service::{
+    NotificationFilter, NotificationPreferencesUpdate,
+    NotificationService as NotificationServiceTrait,
+};
+use openmusicgang_service::notification_transport::NotificationTransport;
+use postgres::types::ToSql;
+use postgres::{Row, Transaction};
+
+use crate::email::queue_email;
+use crate::postgres::DB;
+use crate::user::find_user_by_id;
+use crate::{
+    claim_deliveries_sql, format_limit_offset, insert_delivery_params, insert_delivery_sql,
+    insert_notification_params, insert_notification_sql, select_notification_preferences_sql,
+    select_notifications_sql, update_delivery_failed_sql, update_delivery_sent_sql,
+    update_notification_read_sql, update_notifications_read_sql,
+    upsert_notification_preferences_params, upsert_notification_preferences_sql,
+    where_condition_eq,
+};
+
+/// NOTIFICATION_EMAIL is the template of the emails of the notifications.
+static NOTIFICATION_EMAIL: EmailTemplate = EmailTemplate {
+    subject: "{{message}}",
+    text: "Hi {{name}},\n\n{{message}}.\n\nThe OpenMusicGang team",
+    html: "<p>Hi {{name}},</p><p>{{message}}.</p><p>The OpenMusicGang team</p>",
+};
+
+/// DELIVERY_BATCH_SIZE is the maximum number of deliveries claimed at once by the dispatcher.
+static DELIVERY_BATCH_SIZE: i64 = 100;
+
+/// NotificationService is a struct that implements the NotificationServiceTrait for the postgres crate.
+pub struct NotificationService {
+    db: Arc<Mutex<DB>>,
+}
+
+impl NotificationService {
+    /// Create a new NotificationService struct
+    pub fn new(db: Arc<Mutex<DB>>) -> NotificationService {
+        NotificationService { db }
+    }
+}
+
+impl NotificationServiceTrait for NotificationService {
+    /// Returns the notifications of the user of the context.
+    fn find_notifications(
+        &self,
+        ctx: AppContext,
+        filters: NotificationFilter,
+    ) -> Result<(Vec<Notification>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_notifications(ctx, &mut tx, filters)
+    }
+
+    /// Marks a notification as read.
+    fn mark_notification_read(&self, ctx: AppContext, id: i64) -> Result<Notification, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let notification = mark_notification_read(ctx, &mut tx, id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(notification)
+    }
+
+    /// Marks all the notifications as read.
+    fn mark_all_notifications_read(&self, ctx: AppContext) -> Result<i64, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let count = mark_all_notifications_read(ctx, &mut tx)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(count)
+    }
+
+    /// Returns the notification preferences of the user of the context.
+    fn find_notification_preferences(
+        &self,
+        ctx: AppContext,
+    ) -> Result<NotificationPreferences, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let user_id = require_user_id(ctx)?;
+
+        find_notification_preferences(&mut tx, user_id)
+    }
+
+    /// Updates the notification preferences of the user of the context.
+    fn update_notification_preferences(
+        &self,
+        ctx: AppContext,
+        preferences: NotificationPreferencesUpdate,
+    ) -> Result<NotificationPreferences, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let preferences = update_notification_preferences(ctx, &mut tx, preferences)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(preferences)
+    }
+}
+
+/// NotificationDispatcher sends the pending deliveries of the notifications with a transport.
+///
+/// Deliveries are claimed in a transaction and sent once it is committed, the database is not locked while
+/// the transport sends them. A failed delivery is tried again after a delay doubling at every attempt,
+/// until it was tried max_attempts times.
+pub struct NotificationDispatcher {
+    db: Arc<Mutex<DB>>,
+    transport: Arc<dyn NotificationTransport + Send + Sync>,
+    max_attempts: i32,
+    /// Seconds before a failed delivery is tried again the first time.
+    retry_delay: u64,
+}
+
+impl NotificationDispatcher {
+    /// Create a new NotificationDispatcher struct
+    pub fn new(
+        db: Arc<Mutex<DB>>,
+        transport: Arc<dyn NotificationTransport + Send + Sync>,
+        max_attempts: i32,
+        retry_delay: u64,
+    ) -> NotificationDispatcher {
+        NotificationDispatcher {
+            db,
+            transport,
+            max_attempts,
+            retry_delay,
+        }
+    }
+
+    /// Sends the pending deliveries due now and returns how many were sent.
+    pub fn dispatch_deliveries(&self) -> Result<i64, Error> {
+        let deliveries = {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on database".to_string(),
+                )
+            })?;
+
+            let mut tx = mutex_db.begin_tx()?;
+
+            let deliveries = claim_deliveries(&mut tx, self.max_attempts, self.retry_delay)?;
+
+            tx.commit().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not commit transaction to database".to_string(),
+                )
+            })?;
+
+            deliveries
+        };
+
+        if deliveries.is_empty() {
+            return Ok(0);
+        }
+
+        let results: Vec<(i64, Result<(), Error>)> = deliveries
+            .iter()
+            .map(|delivery| (delivery.id, self.transport.send(delivery)))
+            .collect();
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let sent = complete_deliveries(&mut tx, results)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(sent)
+    }
+
+    /// Starts a thread dispatching the deliveries every interval seconds.
+    pub fn start(self: Arc<Self>, interval: u64) -> JoinHandle<()> {
+        thread::spawn(move || loop {
+            if let Err(error) = self.dispatch_deliveries() {
+                eprintln!("could not dispatch notifications: {}", error);
+            }
+
+            thread::sleep(Duration::from_secs(interval));
+        })
+    }
+}
+
+/// record_notification notifies a user on the channels enabled in their preferences, the message is the
+/// name of the actor followed by the description.
+///
+/// The notification is inserted if the in-app channel is enabled, an email is queued in the outbox if the
+/// email channel is, and a pending delivery is inserted to be sent by the NotificationDispatcher if the
+/// webhook channel is. Nothing is sent before the transaction is committed.
+///
+/// Returns the errors of find_user_by_id.
+///
+/// Returns EINVALID if the notification or its email is invalid.
+pub(crate) fn record_notification(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    notification: &mut Notification,
+    description: &str,
+) -> Result<(), Error> {
+    let actor = match notification.actor_id {
+        Some(actor_id) => find_user_by_id(ctx.clone(), tx, actor_id)?.name,
+        None => "Someone".to_string(),
+    };
+
+    notification.message = format!("{} {}", actor, description);
+    notification.read_at = None;
+    notification.created_at = Utc::now();
+
+    notification.validate()?;
+
+    let user = find_user_by_id(ctx.clone(), tx, notification.user_id)?;
+    let preferences = find_notification_preferences(tx, user.id)?;
+
+    for channel in preferences.channels() {
+        match channel {
+            Channel::InApp => {
+                let row = tx
+                    .query_one(
+                        insert_notification_sql!(),
+                        insert_notification_params!(notification),
+                    )
+                    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+                notification.id = row.get(0);
+            }
+            Channel::Email => {
+                let mut email = NOTIFICATION_EMAIL.render(
+                    &user.email,
+                    &[("name", &user.name), ("message", &notification.message)],
+                );
+
+                queue_email(ctx.clone(), tx, &mut email)?;
+            }
+            Channel::Webhook => {
+                let delivery = Delivery {
+                    id: 0,
+                    user_id: user.id,
+                    channel,
+                    address: preferences.webhook_url.clone().unwrap_or_default(),
+                    kind: notification.kind,
+                    message: notification.message.clone(),
+                    attempts: 0,
+                    last_error: None,
+                    delivered_at: None,
+                    created_at: notification.created_at,
+                };
+
+                tx.execute(insert_delivery_sql!(), insert_delivery_params!(delivery))
+                    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the id of the user of the context.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+fn require_user_id(ctx: AppContext) -> Result<i64, Error> {
+    let user_id = Context::user_id_from_context(ctx);
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in to manage your notifications".to_string(),
+        ));
+    }
+
+    Ok(user_id)
+}
+
+/// find_notifications finds the notifications of the user of the context based on the filters.
+///
+/// Handles the find_notifications Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+fn find_notifications(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    filters: NotificationFilter,
+) -> Result<(Vec<Notification>, i64), Error> {
+    let user_id = require_user_id(ctx)?;
+
+    let mut where_conditions = vec![where_condition_eq!("user_id", 1)];
+    let args: Vec<&(dyn ToSql + Sync)> = vec![&user_id];
+
+    match filters.read {
+        Some(true) => where_conditions.push("read_at IS NOT NULL".to_string()),
+        Some(false) => where_conditions.push("read_at IS NULL".to_string()),
+        None => {}
+    }
+
+    let query = select_notifications_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut notifications: Vec<Notification> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        notifications.push(notification_from_row(&row)?);
+        tot_results = row.get(9);
+    }
+
+    Ok((notifications, tot_results))
+}
+
+/// mark_notification_read marks a notification of the user of the context as read.
+///
+/// Handles the mark_notification_read Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns ENOTFOUND if the notification does not exist or belongs to another user.
+fn mark_notification_read(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<Notification, Error> {
+    let user_id = require_user_id(ctx)?;
+
+    let row = tx
+        .query_opt(
+            update_notification_read_sql!(),
+            &[&id, &user_id, &Utc::now()],
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    if row.is_none() {
+        return Err(Error::new(
+            ErrorCode::ENOTFOUND,
+            "Notification not found".to_string(),
+        ));
+    }
+
+    let query =
+        select_notifications_sql!([where_condition_eq!("id", 1)], format_limit_offset!(1, 0));
+
+    let row = tx
+        .query_one(query.as_str(), &[&id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    notification_from_row(&row)
+}
+
+/// mark_all_notifications_read marks all the unread notifications of the user of the context as read.
+///
+/// Handles the mark_all_notifications_read Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+fn mark_all_notifications_read(ctx: AppContext, tx: &mut Transaction) -> Result<i64, Error> {
+    let user_id = require_user_id(ctx)?;
+
+    let count = tx
+        .execute(update_notifications_read_sql!(), &[&user_id, &Utc::now()])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(count as i64)
+}
+
+/// find_notification_preferences returns the notification preferences of a user, the default ones if the
+/// user never changed them.
+fn find_notification_preferences(
+    tx: &mut Transaction,
+    user_id: i64,
+) -> Result<NotificationPreferences, Error> {
+    let row = tx
+        .query_opt(select_notification_preferences_sql!(), &[&user_id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(match row {
+        Some(row) => NotificationPreferences {
+            user_id: row.get(0),
+            in_app: row.get(1),
+            email: row.get(2),
+            webhook_url: row.get(3),
+            updated_at: row.get(4),
+        },
+        None => NotificationPreferences::new(user_id),
+    })
+}
+
+/// update_notification_preferences updates the notification preferences of the user of the context.
+///
+/// Handles the update_notification_preferences Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EINVALID if the preferences are invalid.
+fn update_notification_preferences(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    update: NotificationPreferencesUpdate,
+) -> Result<NotificationPreferences, Error> {
+    let user_id = require_user_id(ctx)?;
+
+    let mut preferences = find_notification_preferences(tx, user_id)?;
+
+    if let Some(in_app) = update.in_app {
+        preferences.in_app = in_app;
+    }
+
+    if let Some(email) = update.email {
+        preferences.email = email;
+    }
+
+    if let Some(webhook_url) = update.webhook_url {
+        preferences.webhook_url = match webhook_url.trim() {
+            "" => None,
+            webhook_url => Some(webhook_url.to_string()),
+        };
+    }
+
+    preferences.updated_at = Utc::now();
+
+    preferences.validate()?;
+
+    tx.execute(
+        upsert_notification_preferences_sql!(),
+        upsert_notification_preferences_params!(preferences),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(preferences)
+}
+
+/// claim_deliveries claims the pending deliveries due now, oldest first, their attempts are counted.
+fn claim_deliveries(
+    tx: &mut Transaction,
+    max_attempts: i32,
+    retry_delay: u64,
+) -> Result<Vec<Delivery>, Error> {
+    let rows = tx
+        .query(
+            claim_deliveries_sql!(),
+            &[
+                &Utc::now(),
+                &(retry_delay as f64),
+                &max_attempts,
+                &DELIVERY_BATCH_SIZE,
+            ],
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut deliveries = rows
+        .iter()
+        .map(delivery_from_row)
+        .collect::<Result<Vec<Delivery>, Error>>()?;
+
+    // The rows returned by an UPDATE are not ordered.
+    deliveries.sort_by_key(|delivery| delivery.id);
+
+    Ok(deliveries)
+}
+
+/// complete_deliveries stores the results of the sending of claimed deliveries and returns how many were sent.
+fn complete_deliveries(
+    tx: &mut Transaction,
+    results: Vec<(i64, Result<(), Error>)>,
+) -> Result<i64, Error> {
+    let mut sent = 0;
+
+    for (id, result) in results {
+        match result {
+            Ok(()) => {
+                tx.execute(update_delivery_sent_sql!(), &[&id, &Utc::now()])
+                    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+                sent += 1;
+            }
+            Err(error) => {
+                tx.execute(update_delivery_failed_sql!(), &[&id, &error.message])
+                    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+            }
+        }
+    }
+
+    Ok(sent)
+}
+
+/// Returns the notification of a row selected with select_notifications_sql.
+///
+/// Returns EINVALID if the kind of the notification is unknown.
+fn notification_from_row(row: &Row) -> Result<Notification, Error> {
+    Ok(Notification {
+        id: row.get(0),
+        user_id: row.get(1),
+        kind: row.get::<_, String>(2).parse()?,
+        actor_id: row.get(3),
+        gang_id: row.get(4),
+        subject_id: row.get(5),
+        message: row.get(6),
+        read_at: row.get(7),
+        created_at: row.get(8),
+    })
+}
+
+/// Returns the delivery of a row returned by claim_deliveries_sql.
+///
+/// Returns EINVALID if the channel or the kind of the delivery is unknown.
+fn delivery_from_row(row: &Row) -> Result<Delivery, Error> {
+    Ok(Delivery {
+        id: row.get(0),
+        user_id: row.get(1),
+        channel: row.get::<_, String>(2).parse()?,
+        address: row.get(3),
+        kind: row.get::<_, String>(4).parse()?,
+        message: row.get(5),
+        attempts: row.get(6),
+        last_error: row.get(7),
+        delivered_at: row.get(8),
+        created_at: row.get(9),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_entity::comment::Comment;
+    use openmusicgang_entity::gang::Gang;
+    use openmusicgang_entity::invitation::Invitation;
+    use openmusicgang_entity::membership::GangRole;
+    use openmusicgang_entity::notification::NotificationKind;
+    use openmusicgang_entity::song::Song;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_notify::memory::NotificationTransport as MemoryNotificationTransport;
+    use openmusicgang_service::comment_service::CommentService as CommentServiceTrait;
+    use openmusicgang_service::gang_service::GangService as GangServiceTrait;
+    use openmusicgang_service::membership_service::MembershipService as MembershipServiceTrait;
+    use openmusicgang_service::song_service::SongService as SongServiceTrait;
+
+    use crate::comment::CommentService;
+    use crate::gang::GangService;
+    use crate::membership::MembershipService;
+    use crate::song::SongService;
+    use crate::test_utils::{must_create_user, must_lock_db, must_open_db, must_truncate_table};
+
+    use super::*;
+
+    /// FailingTransport is a transport whose every sending fails.
+    struct FailingTransport;
+
+    impl NotificationTransport for FailingTransport {
+        fn send(&self, _delivery: &Delivery) -> Result<(), Error> {
+            Err(Error::new(
+                ErrorCode::EINTERNAL,
+                "connection refused".to_string(),
+            ))
+        }
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) find notifications without a user in the context, error should be EUNAUTHORIZED.
+    /// 4) invite a user, they should be notified in-app and have an email queued in the outbox.
+    /// 5) update preferences with an invalid webhook URL, error should be EINVALID.
+    /// 6) mention users in a comment, the author should not be notified and the preferences should be respected.
+    /// 7) dispatch the deliveries with a failing transport, they should be tried again later.
+    /// 8) dispatch the deliveries, they should be sent once.
+    /// 9) mark a notification as read, then all of them.
+    /// 10) mark the notification of another user as read, error should be ENOTFOUND.
+    #[test]
+    fn test_notification_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "email_outbox");
+        must_truncate_table(&mut db, "notification_deliveries");
+        must_truncate_table(&mut db, "notification_preferences");
+        must_truncate_table(&mut db, "notifications");
+        must_truncate_table(&mut db, "feed_entries");
+        must_truncate_table(&mut db, "activities");
+        must_truncate_table(&mut db, "comments");
+        must_truncate_table(&mut db, "song_revisions");
+        must_truncate_table(&mut db, "songs");
+        must_truncate_table(&mut db, "gang_invitations");
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+        let mark = must_create_user(&mut db, "Mark Smith", "mark.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+        let membership_service = MembershipService::new(Arc::clone(&db), 3600);
+        let song_service = SongService::new(Arc::clone(&db));
+        let comment_service = CommentService::new(Arc::clone(&db));
+        let notification_service = NotificationService::new(Arc::clone(&db));
+
+        let ctx = |user: &User| Context::with_user(Context::background(), user.clone());
+        let notifications = |user: &User, read: Option<bool>| {
+            notification_service
+                .find_notifications(
+                    ctx(user),
+                    NotificationFilter {
+                        read,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+        };
+
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang_service.create_gang(ctx(&bob), &mut gang).unwrap();
+
+        // 3) find notifications without a user in the context, error should be EUNAUTHORIZED.
+        let res =
+            notification_service.find_notifications(Context::background(), Default::default());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 4) invite a user, they should be notified in-app and have an email queued in the outbox.
+        for user in [&john, &mark] {
+            let mut invitation = Invitation {
+                gang_id: gang.id,
+                user_id: Some(user.id),
+                role: GangRole::Member,
+                ..Default::default()
+            };
+            membership_service
+                .invite_member(ctx(&bob), &mut invitation)
+                .unwrap();
+
+            let (notifications, count) = notifications(user, None);
+            assert_eq!(count, 1);
+            assert_eq!(notifications[0].kind, NotificationKind::Invitation);
+            assert_eq!(notifications[0].actor_id, Some(bob.id));
+            assert_eq!(notifications[0].gang_id, Some(gang.id));
+            assert_eq!(notifications[0].subject_id, Some(invitation.id));
+            assert_eq!(
+                notifications[0].message,
+                "Bob Smith invited you to join The Rolling Bytes"
+            );
+            assert!(!notifications[0].is_read());
+
+            membership_service
+                .accept_invitation(ctx(user), invitation.id)
+                .unwrap();
+        }
+        assert_eq!(notifications(&bob, None).1, 0);
+
+        // 5) update preferences with an invalid webhook URL, error should be EINVALID.
+        let update = NotificationPreferencesUpdate {
+            webhook_url: Some("ftp://example.com/hook".to_string()),
+            ..Default::default()
+        };
+        let res = notification_service.update_notification_preferences(ctx(&john), update);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 6) mention users in a comment, the author should not be notified and the preferences should be respected.
+        let update = NotificationPreferencesUpdate {
+            email: Some(false),
+            webhook_url: Some("https://example.com/hook".to_string()),
+            ..Default::default()
+        };
+        let preferences = notification_service
+            .update_notification_preferences(ctx(&john), update)
+            .unwrap();
+        assert_eq!(
+            preferences.channels(),
+            vec![Channel::InApp, Channel::Webhook]
+        );
+        let found = notification_service
+            .find_notification_preferences(ctx(&john))
+            .unwrap();
+        assert_eq!(found.channels(), preferences.channels());
+        assert_eq!(found.webhook_url, preferences.webhook_url);
+
+        let update = NotificationPreferencesUpdate {
+            in_app: Some(false),
+            ..Default::default()
+        };
+        notification_service
+            .update_notification_preferences(ctx(&mark), update)
+            .unwrap();
+
+        let mut song = Song {
+            gang_id: gang.id,
+            title: "Segfault Blues".to_string(),
+            ..Default::default()
+        };
+        song_service.create_song(ctx(&bob), &mut song).unwrap();
+
+        let mut comment = Comment {
+            song_id: song.id,
+            body: "@john smith @Mark Smith the bass is too loud, right @Bob Smith?".to_string(),
+            ..Default::default()
+        };
+        comment_service
+            .create_comment(ctx(&bob), &mut comment)
+            .unwrap();
+
+        let (notifications_of_john, count) = notifications(&john, Some(false));
+        assert_eq!(count, 2);
+        assert_eq!(notifications_of_john[0].kind, NotificationKind::Mention);
+        assert_eq!(notifications_of_john[0].subject_id, Some(comment.id));
+        assert_eq!(
+            notifications_of_john[0].message,
+            "Bob Smith mentioned you in a comment on Segfault Blues"
+        );
+        assert_eq!(notifications(&mark, None).1, 1);
+        assert_eq!(notifications(&bob, None).1, 0);
+
+        {
+            let mut mutex_db = db.lock().unwrap();
+            let mut tx = mutex_db.begin_tx().unwrap();
+            let emails: Vec<(String, String)> = tx
+                .query(
+                    "SELECT recipient, subject FROM email_outbox ORDER BY id ASC",
+                    &[],
+                )
+                .unwrap()
+                .iter()
+                .map(|row| (row.get(0), row.get(1)))
+                .collect();
+
+            let invitation = "Bob Smith invited you to join The Rolling Bytes".to_string();
+            let mention = "Bob Smith mentioned you in a comment on Segfault Blues".to_string();
+            assert_eq!(
+                emails,
+                vec![
+                    ("john.smith@test.com".to_string(), invitation.clone()),
+                    ("mark.smith@test.com".to_string(), invitation),
+                    ("mark.smith@test.com".to_string(), mention),
+                ]
+            );
+        }
+
+        // 7) dispatch the deliveries with a failing transport, they should be tried again later.
+        let dispatcher =
+            NotificationDispatcher::new(Arc::clone(&db), Arc::new(FailingTransport), 5, 3600);
+        assert_eq!(dispatcher.dispatch_deliveries().unwrap(), 0);
+        assert_eq!(dispatcher.dispatch_deliveries().unwrap(), 0);
+
+        {
+            let mut mutex_db = db.lock().unwrap();
+            let mut tx = mutex_db.begin_tx().unwrap();
+            let rows = tx
+                .query(
+                    "SELECT attempts, last_error FROM notification_deliveries",
+                    &[],
+                )
+                .unwrap();
+            assert_eq!(rows.len(), 1);
+            for row in rows {
+                assert_eq!(row.get::<_, i32>(0), 1);
+                assert_eq!(
+                    row.get::<_, Option<String>>(1),
+                    Some("connection refused".to_string())
+                );
+            }
+        }
+
+        // 8) dispatch the deliveries, they should be sent once.
+        let transport = Arc::new(MemoryNotificationTransport::new());
+        let dispatcher = NotificationDispatcher::new(Arc::clone(&db), transport.clone(), 5, 0);
+
+        {
+            let mut mutex_db = db.lock().unwrap();
+            let mut tx = mutex_db.begin_tx().unwrap();
+            tx.execute(
+                "UPDATE notification_deliveries SET next_attempt_at = $1",
+                &[&Utc::now()],
+            )
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        assert_eq!(dispatcher.dispatch_deliveries().unwrap(), 1);
+        assert_eq!(dispatcher.dispatch_deliveries().unwrap(), 0);
+
+        let deliveries = transport.deliveries();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].channel, Channel::Webhook);
+        assert_eq!(deliveries[0].address, "https://example.com/hook");
+        assert_eq!(deliveries[0].kind, NotificationKind::Mention);
+        assert_eq!(deliveries[0].user_id, john.id);
+        assert_eq!(deliveries[0].attempts, 2);
+
+        // 9) mark a notification as read, then all of them.
+        let notification = notification_service
+            .mark_notification_read(ctx(&john), notifications_of_john[0].id)
+            .unwrap();
+        assert!(notification.is_read());
+        assert_eq!(notifications(&john, Some(true)).1, 1);
+
+        let again = notification_service
+            .mark_notification_read(ctx(&john), notification.id)
+            .unwrap();
+        assert_eq!(again.read_at, notification.read_at);
+
+        assert_eq!(
+            notification_service
+                .mark_all_notifications_read(ctx(&john))
+                .unwrap(),
+            1
+        );
+        assert_eq!(notifications(&john, Some(false)).1, 0);
+        assert_eq!(notifications(&john, Some(true)).1, 2);
+
+        // 10) mark the notification of another user as read, error should be ENOTFOUND.
+        let res = notification_service.mark_notification_read(ctx(&mark), notification.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+    }
+}
@@ -17,28 +17,21 @@ pub struct DB {
 impl DB {
     /// Create a new DB struct
     pub fn new(dsn: String) -> DB {
-        DB {
-            client: None,
-            dsn: dsn,
-        }
+        DB { client: None, dsn }
     }
 
     /// Begin a new transaction
-    pub fn begin_tx(&mut self) -> Result<Transaction, Error> {
+    pub fn begin_tx(&mut self) -> Result<Transaction<'_>, Error> {
         if let Some(ref mut client) = self.client {
             match client.transaction() {
-                Ok(tx) => {
-                    return Ok(tx);
-                }
-                Err(error) => {
-                    return Err(Error::new(ErrorCode::EINTERNAL, error.to_string()));
-                }
+                Ok(tx) => Ok(tx),
+                Err(error) => Err(Error::new(ErrorCode::EINTERNAL, error.to_string())),
             }
         } else {
-            return Err(Error::new(
+            Err(Error::new(
                 ErrorCode::EINTERNAL,
                 "No connection to database".to_string(),
-            ));
+            ))
         }
     }
 
@@ -61,16 +54,14 @@ impl DB {
 
     /// Migrate the database to the latest version.
     pub fn migrate(&mut self) -> Result<(), Error> {
-        if let Err(err) = self.create_migrations_table() {
-            return Err(err);
-        }
+        self.create_migrations_table()?;
 
         let mut tx = self.begin_tx()?;
 
         for migration in migrations::get_migrations_list() {
-            let query = format!("SELECT COUNT(*) FROM migrations WHERE name = $1");
+            let query = "SELECT COUNT(*) FROM migrations WHERE name = $1";
             let row = tx
-                .query_one(&query, &[&migration.name])
+                .query_one(query, &[&migration.name])
                 .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
 
             let count: i64 = row.get(0);
@@ -78,12 +69,21 @@ impl DB {
                 continue;
             }
 
-            tx.execute(migration.query, &[])
-                .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+            tx.batch_execute(migration.query).map_err(|error| {
+                let message = match error.as_db_error() {
+                    Some(db_error) => db_error.message().to_string(),
+                    None => error.to_string(),
+                };
+
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    format!("Migration {} failed: {}", migration.name, message),
+                )
+            })?;
 
-            let query = format!("INSERT INTO migrations (name) VALUES ($1)");
+            let query = "INSERT INTO migrations (name) VALUES ($1)";
 
-            tx.execute(&query, &[&migration.name])
+            tx.execute(query, &[&migration.name])
                 .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
         }
 
@@ -93,8 +93,11 @@ impl DB {
 
     /// Connect to the database
     pub fn open(&mut self) -> Result<(), Error> {
-        if self.dsn == "" {
-            return Err(Error::new(ErrorCode::EINVALID, format!("No DSN provided")));
+        if self.dsn.is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "No DSN provided".to_string(),
+            ));
         }
 
         let client = connection(&self.dsn);
@@ -112,7 +115,7 @@ impl DB {
     }
 
     /// Close the connection to the database, simply drops self
-    pub fn close(&self) {
+    pub fn close(self) {
         drop(self);
     }
 }
@@ -134,7 +137,8 @@ mod tests {
 
     #[test]
     fn test_connection() {
-        let dsn = openmusicgang_config::app_config::AppConfig::new("../../config.toml").get_postgres_dsn();
+        let dsn = openmusicgang_config::app_config::AppConfig::new("../../config.toml")
+            .get_postgres_dsn();
         let mut db = DB::new(dsn);
         if let Err(error) = db.open() {
             panic!("{}", error);
@@ -0,0 +1,87 @@
+/// insert_activity_sql is a macro that generates the SQL to insert an activity.
+#[macro_export]
+macro_rules! insert_activity_sql {
+    () => {
+        "INSERT INTO activities (
+            kind,
+            actor_id,
+            gang_id,
+            song_id,
+            revision,
+            subject_id,
+            summary,
+            created_at
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8 ) RETURNING id"
+    };
+}
+
+/// insert_activity_params returns the parameters for an INSERT statement in activities table.
+#[macro_export]
+macro_rules! insert_activity_params {
+    ($activity:expr) => {
+        &[
+            &$activity.kind.as_str(),
+            &$activity.actor_id,
+            &$activity.gang_id,
+            &$activity.song_id,
+            &$activity.revision,
+            &$activity.subject_id,
+            &$activity.summary,
+            &$activity.created_at,
+        ]
+    };
+}
+
+/// insert_feed_entries_sql is a macro that generates the SQL to add an activity to the feeds of the members
+/// of its gang and, unless it is about a song, of the followers of its gang and actor, except the actor.
+#[macro_export]
+macro_rules! insert_feed_entries_sql {
+    () => {
+        "INSERT INTO feed_entries (user_id, activity_id)
+        SELECT audience.user_id, $1::BIGINT FROM (
+            SELECT user_id FROM gang_members WHERE gang_id = $2
+            UNION
+            SELECT follower_id FROM follows
+            WHERE (followed_gang_id = $2 OR followed_user_id = $3) AND $4::BIGINT IS NULL
+        ) AS audience
+        WHERE audience.user_id IS DISTINCT FROM $3"
+    };
+}
+
+/// insert_feed_entries_params is a macro that returns a tuple of the parameters to be used in the insert_feed_entries_sql macro.
+#[macro_export]
+macro_rules! insert_feed_entries_params {
+    ($activity:expr) => {
+        &[
+            &$activity.id,
+            &$activity.gang_id,
+            &$activity.actor_id,
+            &$activity.song_id,
+        ]
+    };
+}
+
+/// select_feed_sql is a macro that generates the SQL to select the activities of the feed of a user, newest first.
+#[macro_export]
+macro_rules! select_feed_sql {
+    ($whereConditions:expr,$limit:expr) => {
+        format!("
+        SELECT 
+            activities.id,
+            activities.kind,
+            activities.actor_id,
+            activities.gang_id,
+            activities.song_id,
+            activities.revision,
+            activities.subject_id,
+            activities.summary,
+            activities.created_at
+        FROM feed_entries
+        JOIN activities ON activities.id = feed_entries.activity_id
+        WHERE
+        {}
+        ORDER BY feed_entries.activity_id DESC
+        LIMIT {}
+        ", $whereConditions.join("\nAND "), $limit)
+    }
+}
@@ -0,0 +1,148 @@
+/// delete_comment_sql is a macro that generates a SQL query to delete a comment.
+#[macro_export]
+macro_rules! delete_comment_sql {
+    () => {
+        "DELETE FROM comments WHERE id = $1"
+    };
+}
+
+/// delete_comment_params is a macro that returns a tuple of the parameters to be used in the delete_comment_sql macro.
+#[macro_export]
+macro_rules! delete_comment_params {
+    ($id:expr) => {
+        &[&$id]
+    };
+}
+
+/// insert_comment_sql is a macro that generates the SQL to insert a comment.
+#[macro_export]
+macro_rules! insert_comment_sql {
+    () => {
+        "INSERT INTO comments (
+            song_id,
+            revision,
+            track_id,
+            parent_id,
+            range_start,
+            range_end,
+            body,
+            author_id,
+            resolved_at,
+            resolved_by,
+            created_at,
+            updated_at
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12 ) RETURNING id"
+    };
+}
+
+/// insert_comment_params returns the parameters for an INSERT statement in comments table.
+#[macro_export]
+macro_rules! insert_comment_params {
+    ($comment:expr) => {
+        &[
+            &$comment.song_id,
+            &$comment.revision,
+            &$comment.track_id,
+            &$comment.parent_id,
+            &$comment.range_start,
+            &$comment.range_end,
+            &$comment.body,
+            &$comment.author_id,
+            &$comment.resolved_at,
+            &$comment.resolved_by,
+            &$comment.created_at,
+            &$comment.updated_at,
+        ]
+    };
+}
+
+/// update_comment_sql is a macro that generates the SQL to update a comment in the database.
+#[macro_export]
+macro_rules! update_comment_sql {
+    () => {
+        "UPDATE comments SET
+            body = $1,
+            resolved_at = $2,
+            resolved_by = $3,
+            updated_at = $4
+        WHERE id = $5"
+    };
+}
+
+/// update_comment_params is a macro that returns the parameters for an UPDATE statement in comments table.
+#[macro_export]
+macro_rules! update_comment_params {
+    ($comment:expr) => {
+        &[
+            &$comment.body,
+            &$comment.resolved_at,
+            &$comment.resolved_by,
+            &$comment.updated_at,
+            &$comment.id,
+        ]
+    };
+}
+
+/// select_comments_sql is a macro that generates the SQL to select comments from the database, in order of creation.
+#[macro_export]
+macro_rules! select_comments_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            song_id,
+            revision,
+            track_id,
+            parent_id,
+            range_start,
+            range_end,
+            body,
+            author_id,
+            resolved_at,
+            resolved_by,
+            created_at,
+            updated_at,
+            COUNT(*) OVER() as count
+        FROM comments
+        WHERE
+        {}
+        ORDER BY created_at ASC, id ASC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
+
+/// insert_comment_edit_sql is a macro that generates the SQL to insert a previous body of a comment.
+#[macro_export]
+macro_rules! insert_comment_edit_sql {
+    () => {
+        "INSERT INTO comment_edits (
+            comment_id,
+            body,
+            created_at
+        ) VALUES ( $1, $2, $3 ) RETURNING id"
+    };
+}
+
+/// insert_comment_edit_params returns the parameters for an INSERT statement in comment_edits table.
+#[macro_export]
+macro_rules! insert_comment_edit_params {
+    ($edit:expr) => {
+        &[&$edit.comment_id, &$edit.body, &$edit.created_at]
+    };
+}
+
+/// select_comment_edits_sql is a macro that generates the SQL to select the previous bodies of a comment, oldest first.
+#[macro_export]
+macro_rules! select_comment_edits_sql {
+    () => {
+        "SELECT 
+            id,
+            comment_id,
+            body,
+            created_at
+        FROM comment_edits
+        WHERE comment_id = $1
+        ORDER BY created_at ASC, id ASC"
+    };
+}
@@ -0,0 +1,76 @@
+/// insert_email_sql is a macro that generates the SQL to queue an email in the outbox.
+#[macro_export]
+macro_rules! insert_email_sql {
+    () => {
+        "INSERT INTO email_outbox (
+            recipient,
+            subject,
+            text_body,
+            html_body,
+            created_at,
+            next_attempt_at
+        ) VALUES ( $1, $2, $3, $4, $5, $5 ) RETURNING id"
+    };
+}
+
+/// insert_email_params returns the parameters for an INSERT statement in email_outbox table.
+#[macro_export]
+macro_rules! insert_email_params {
+    ($email:expr) => {
+        &[
+            &$email.to,
+            &$email.subject,
+            &$email.text,
+            &$email.html,
+            &$email.created_at,
+        ]
+    };
+}
+
+/// claim_emails_sql is a macro that generates the SQL to claim the pending emails of the outbox due at a time.
+///
+/// The attempts of the claimed emails are counted and their next attempt is delayed exponentially,
+/// so an email whose sending is interrupted is tried again later.
+#[macro_export]
+macro_rules! claim_emails_sql {
+    () => {
+        "UPDATE email_outbox SET
+            attempts = attempts + 1,
+            next_attempt_at = $1 + make_interval(secs => $2::DOUBLE PRECISION * POWER(2, attempts))
+        WHERE id IN (
+            SELECT id FROM email_outbox
+            WHERE sent_at IS NULL
+            AND attempts < $3
+            AND next_attempt_at <= $1
+            ORDER BY id ASC
+            LIMIT $4
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING
+            id,
+            recipient,
+            subject,
+            text_body,
+            html_body,
+            attempts,
+            last_error,
+            sent_at,
+            created_at"
+    };
+}
+
+/// update_email_sent_sql is a macro that generates the SQL to mark an email of the outbox as sent.
+#[macro_export]
+macro_rules! update_email_sent_sql {
+    () => {
+        "UPDATE email_outbox SET sent_at = $2, last_error = NULL WHERE id = $1"
+    };
+}
+
+/// update_email_failed_sql is a macro that generates the SQL to store the error of an email that could not be sent.
+#[macro_export]
+macro_rules! update_email_failed_sql {
+    () => {
+        "UPDATE email_outbox SET last_error = $2 WHERE id = $1"
+    };
+}
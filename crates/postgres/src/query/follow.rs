@@ -0,0 +1,108 @@
+/// insert_follow_sql is a macro that generates the SQL to insert a follow, nothing is inserted if it already exists.
+#[macro_export]
+macro_rules! insert_follow_sql {
+    () => {
+        "INSERT INTO follows (
+            follower_id,
+            followed_user_id,
+            followed_gang_id,
+            created_at
+        ) VALUES ( $1, $2, $3, $4 ) ON CONFLICT DO NOTHING RETURNING id"
+    };
+}
+
+/// insert_follow_params returns the parameters for an INSERT statement in follows table.
+#[macro_export]
+macro_rules! insert_follow_params {
+    ($follow:expr) => {
+        &[
+            &$follow.follower_id,
+            &$follow.target.user_id(),
+            &$follow.target.gang_id(),
+            &$follow.created_at,
+        ]
+    };
+}
+
+/// delete_follow_sql is a macro that generates a SQL query to delete the follow of a user or a gang.
+#[macro_export]
+macro_rules! delete_follow_sql {
+    () => {
+        "DELETE FROM follows
+        WHERE follower_id = $1
+        AND followed_user_id IS NOT DISTINCT FROM $2
+        AND followed_gang_id IS NOT DISTINCT FROM $3"
+    };
+}
+
+/// delete_follow_params is a macro that returns a tuple of the parameters to be used in the delete_follow_sql macro.
+#[macro_export]
+macro_rules! delete_follow_params {
+    ($follower_id:expr, $target:expr) => {
+        &[&$follower_id, &$target.user_id(), &$target.gang_id()]
+    };
+}
+
+/// select_follows_sql is a macro that generates the SQL to select follows from the database, newest first.
+#[macro_export]
+macro_rules! select_follows_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            follower_id,
+            followed_user_id,
+            followed_gang_id,
+            created_at,
+            COUNT(*) OVER() as count
+        FROM follows
+        WHERE
+        {}
+        ORDER BY created_at DESC, id DESC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
+
+/// mutual_follow_condition is a macro that returns the where condition of the follows whose follower is followed back.
+#[macro_export]
+macro_rules! mutual_follow_condition {
+    () => {
+        "EXISTS (
+            SELECT 1 FROM follows AS back
+            WHERE back.follower_id = follows.followed_user_id
+            AND back.followed_user_id = follows.follower_id
+        )"
+    };
+}
+
+/// select_mutual_follow_sql is a macro that generates the SQL to check if two users follow each other.
+#[macro_export]
+macro_rules! select_mutual_follow_sql {
+    () => {
+        "SELECT COUNT(*) = 2 FROM follows
+        WHERE (follower_id = $1 AND followed_user_id = $2)
+        OR (follower_id = $2 AND followed_user_id = $1)"
+    };
+}
+
+/// select_follow_counts_sql is a macro that generates the SQL to count the followers of a user or a gang,
+/// and the follows of a user.
+#[macro_export]
+macro_rules! select_follow_counts_sql {
+    () => {
+        "SELECT
+            COUNT(*) FILTER (WHERE followed_user_id = $1 OR followed_gang_id = $2),
+            COUNT(*) FILTER (WHERE follower_id = $1)
+        FROM follows
+        WHERE followed_user_id = $1 OR followed_gang_id = $2 OR follower_id = $1"
+    };
+}
+
+/// select_follow_counts_params is a macro that returns a tuple of the parameters to be used in the select_follow_counts_sql macro.
+#[macro_export]
+macro_rules! select_follow_counts_params {
+    ($target:expr) => {
+        &[&$target.user_id(), &$target.gang_id()]
+    };
+}
@@ -0,0 +1,101 @@
+/// delete_gang_sql is a macro that generates a SQL query to delete a gang.
+#[macro_export]
+macro_rules! delete_gang_sql {
+    () => {
+        "DELETE FROM gangs WHERE id = $1"
+    };
+}
+
+/// delete_gang_params is a macro that returns a tuple of the parameters to be used in the delete_gang_sql macro.
+#[macro_export]
+macro_rules! delete_gang_params {
+    ($id:expr) => {
+        &[&$id]
+    };
+}
+
+/// insert_gang_sql is a macro that generates the SQL to insert a gang into the database.
+#[macro_export]
+macro_rules! insert_gang_sql {
+    () => {
+        "INSERT INTO gangs (
+            name,
+            slug,
+            description,
+            genre,
+            created_by,
+            created_at,
+            updated_at
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7 ) RETURNING id"
+            .to_string()
+    };
+}
+
+/// insert_gang_params returns the parameters for an INSERT statement in gangs table.
+#[macro_export]
+macro_rules! insert_gang_params {
+    ($gang:expr) => {
+        &[
+            &$gang.name,
+            &$gang.slug,
+            &$gang.description,
+            &$gang.genre,
+            &$gang.created_by,
+            &$gang.created_at,
+            &$gang.updated_at,
+        ]
+    };
+}
+
+/// select_gangs_sql is a macro that generates the SQL to select gangs from the database.
+#[macro_export]
+macro_rules! select_gangs_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            name,
+            slug,
+            description,
+            genre,
+            created_by,
+            created_at,
+            updated_at,
+            COUNT(*) OVER() as count
+        FROM gangs
+        WHERE
+        {}
+        ORDER BY id ASC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
+
+/// update_gang_sql is a macro that generates the SQL to update a gang in the database.
+#[macro_export]
+macro_rules! update_gang_sql {
+    () => {
+        "UPDATE gangs SET
+            name = $1,
+            slug = $2,
+            description = $3,
+            genre = $4,
+            updated_at = $5
+        WHERE id = $6"
+    };
+}
+
+/// update_gang_params is a macro that returns the parameters for an UPDATE statement in gangs table.
+#[macro_export]
+macro_rules! update_gang_params {
+    ($gang:expr) => {
+        &[
+            &$gang.name,
+            &$gang.slug,
+            &$gang.description,
+            &$gang.genre,
+            &$gang.updated_at,
+            &$gang.id,
+        ]
+    };
+}
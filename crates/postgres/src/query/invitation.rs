@@ -0,0 +1,106 @@
+/// insert_invitation_sql is a macro that generates the SQL to insert an invitation into the database.
+#[macro_export]
+macro_rules! insert_invitation_sql {
+    () => {
+        "INSERT INTO gang_invitations (
+            gang_id,
+            inviter_id,
+            user_id,
+            email,
+            role,
+            status,
+            created_at,
+            expires_at
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8 ) RETURNING id"
+    };
+}
+
+/// insert_invitation_params returns the parameters for an INSERT statement in gang_invitations table.
+#[macro_export]
+macro_rules! insert_invitation_params {
+    ($invitation:expr) => {
+        &[
+            &$invitation.gang_id,
+            &$invitation.inviter_id,
+            &$invitation.user_id,
+            &$invitation.email,
+            &$invitation.role.as_str(),
+            &$invitation.status.as_str(),
+            &$invitation.created_at,
+            &$invitation.expires_at,
+        ]
+    };
+}
+
+/// select_invitations_sql is a macro that generates the SQL to select invitations from the database.
+#[macro_export]
+macro_rules! select_invitations_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            gang_id,
+            inviter_id,
+            user_id,
+            email,
+            role,
+            status,
+            created_at,
+            expires_at,
+            responded_at,
+            COUNT(*) OVER() as count
+        FROM gang_invitations
+        WHERE
+        {}
+        ORDER BY id ASC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
+
+/// select_pending_invitation_id_sql is a macro that generates the SQL to select the pending invitation of a gang
+/// addressed to a user id or an email, the email is compared case-insensitively.
+#[macro_export]
+macro_rules! select_pending_invitation_id_sql {
+    () => {
+        "SELECT id FROM gang_invitations
+        WHERE gang_id = $1
+        AND status = 'pending'
+        AND expires_at > $2
+        AND (user_id = $3 OR LOWER(email) = LOWER($4))
+        LIMIT 1"
+    };
+}
+
+/// update_invitation_status_sql is a macro that generates the SQL to update the status of an invitation in the database.
+#[macro_export]
+macro_rules! update_invitation_status_sql {
+    () => {
+        "UPDATE gang_invitations SET
+            status = $1,
+            responded_at = $2
+        WHERE id = $3"
+    };
+}
+
+/// update_invitation_status_params is a macro that returns the parameters for an UPDATE statement of the status in gang_invitations table.
+#[macro_export]
+macro_rules! update_invitation_status_params {
+    ($invitation:expr) => {
+        &[
+            &$invitation.status.as_str(),
+            &$invitation.responded_at,
+            &$invitation.id,
+        ]
+    };
+}
+
+/// expire_invitations_sql is a macro that generates the SQL to expire the pending invitations past their expiration.
+#[macro_export]
+macro_rules! expire_invitations_sql {
+    () => {
+        "UPDATE gang_invitations SET status = 'expired'
+        WHERE status = 'pending'
+        AND expires_at <= $1"
+    };
+}
@@ -0,0 +1,70 @@
+/// delete_lead_sheet_sql is a macro that generates a SQL query to delete a lead sheet.
+#[macro_export]
+macro_rules! delete_lead_sheet_sql {
+    () => {
+        "DELETE FROM lead_sheets WHERE id = $1"
+    };
+}
+
+/// delete_lead_sheet_params is a macro that returns a tuple of the parameters to be used in the delete_lead_sheet_sql macro.
+#[macro_export]
+macro_rules! delete_lead_sheet_params {
+    ($id:expr) => {
+        &[&$id]
+    };
+}
+
+/// upsert_lead_sheet_sql is a macro that generates the SQL to insert a lead sheet,
+/// or to replace the sections of the lead sheet of the same song revision.
+#[macro_export]
+macro_rules! upsert_lead_sheet_sql {
+    () => {
+        "INSERT INTO lead_sheets (
+            song_id,
+            revision,
+            sections,
+            created_by,
+            created_at,
+            updated_at
+        ) VALUES ( $1, $2, $3, $4, $5, $6 )
+        ON CONFLICT (song_id, revision) DO UPDATE SET
+            sections = EXCLUDED.sections,
+            updated_at = EXCLUDED.updated_at
+        RETURNING id, created_by, created_at"
+    };
+}
+
+/// upsert_lead_sheet_params returns the parameters for an upsert statement in lead_sheets table.
+#[macro_export]
+macro_rules! upsert_lead_sheet_params {
+    ($sheet:expr, $sections:expr) => {
+        &[
+            &$sheet.song_id,
+            &$sheet.revision,
+            &$sections,
+            &$sheet.created_by,
+            &$sheet.created_at,
+            &$sheet.updated_at,
+        ]
+    };
+}
+
+/// select_lead_sheets_sql is a macro that generates the SQL to select lead sheets from the database.
+#[macro_export]
+macro_rules! select_lead_sheets_sql {
+    ($whereConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            song_id,
+            revision,
+            sections,
+            created_by,
+            created_at,
+            updated_at
+        FROM lead_sheets
+        WHERE
+        {}
+        ", $whereConditions.join("\nAND "))
+    }
+}
@@ -0,0 +1,88 @@
+/// delete_membership_sql is a macro that generates a SQL query to delete a membership.
+#[macro_export]
+macro_rules! delete_membership_sql {
+    () => {
+        "DELETE FROM gang_members WHERE id = $1"
+    };
+}
+
+/// delete_membership_params is a macro that returns a tuple of the parameters to be used in the delete_membership_sql macro.
+#[macro_export]
+macro_rules! delete_membership_params {
+    ($id:expr) => {
+        &[&$id]
+    };
+}
+
+/// insert_membership_sql is a macro that generates the SQL to insert a membership into the database.
+#[macro_export]
+macro_rules! insert_membership_sql {
+    () => {
+        "INSERT INTO gang_members (
+            gang_id,
+            user_id,
+            role,
+            created_at,
+            updated_at
+        ) VALUES ( $1, $2, $3, $4, $5 ) RETURNING id"
+    };
+}
+
+/// insert_membership_params returns the parameters for an INSERT statement in gang_members table.
+#[macro_export]
+macro_rules! insert_membership_params {
+    ($membership:expr) => {
+        &[
+            &$membership.gang_id,
+            &$membership.user_id,
+            &$membership.role.as_str(),
+            &$membership.created_at,
+            &$membership.updated_at,
+        ]
+    };
+}
+
+/// select_memberships_sql is a macro that generates the SQL to select memberships from the database.
+#[macro_export]
+macro_rules! select_memberships_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            gang_id,
+            user_id,
+            role,
+            created_at,
+            updated_at,
+            COUNT(*) OVER() as count
+        FROM gang_members
+        WHERE
+        {}
+        ORDER BY id ASC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
+
+/// update_membership_role_sql is a macro that generates the SQL to update the role of a membership in the database.
+#[macro_export]
+macro_rules! update_membership_role_sql {
+    () => {
+        "UPDATE gang_members SET
+            role = $1,
+            updated_at = $2
+        WHERE id = $3"
+    };
+}
+
+/// update_membership_role_params is a macro that returns the parameters for an UPDATE statement of the role in gang_members table.
+#[macro_export]
+macro_rules! update_membership_role_params {
+    ($membership:expr) => {
+        &[
+            &$membership.role.as_str(),
+            &$membership.updated_at,
+            &$membership.id,
+        ]
+    };
+}
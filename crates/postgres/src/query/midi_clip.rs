@@ -0,0 +1,128 @@
+/// delete_midi_clip_sql is a macro that generates a SQL query to delete a MIDI clip.
+#[macro_export]
+macro_rules! delete_midi_clip_sql {
+    () => {
+        "DELETE FROM midi_clips WHERE id = $1"
+    };
+}
+
+/// delete_midi_clip_params is a macro that returns a tuple of the parameters to be used in the delete_midi_clip_sql macro.
+#[macro_export]
+macro_rules! delete_midi_clip_params {
+    ($id:expr) => {
+        &[&$id]
+    };
+}
+
+/// insert_midi_clip_sql is a macro that generates the SQL to insert a MIDI clip.
+#[macro_export]
+macro_rules! insert_midi_clip_sql {
+    () => {
+        "INSERT INTO midi_clips (
+            song_id,
+            revision,
+            blob_key,
+            name,
+            format,
+            tracks,
+            ticks_per_quarter,
+            notes,
+            duration,
+            created_by,
+            created_at,
+            updated_at
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12 ) RETURNING id"
+    };
+}
+
+/// copy_midi_clips_sql is a macro that generates the SQL to copy the MIDI clips of a song revision ($2) into another revision ($3).
+#[macro_export]
+macro_rules! copy_midi_clips_sql {
+    () => {
+        "INSERT INTO midi_clips (
+            song_id,
+            revision,
+            blob_key,
+            name,
+            format,
+            tracks,
+            ticks_per_quarter,
+            notes,
+            duration,
+            created_by,
+            created_at,
+            updated_at
+        ) SELECT
+            song_id,
+            $3,
+            blob_key,
+            name,
+            format,
+            tracks,
+            ticks_per_quarter,
+            notes,
+            duration,
+            created_by,
+            created_at,
+            updated_at
+        FROM midi_clips WHERE song_id = $1 AND revision = $2 ORDER BY id"
+    };
+}
+
+/// copy_midi_clips_params is a macro that returns the parameters to be used in the copy_midi_clips_sql macro.
+#[macro_export]
+macro_rules! copy_midi_clips_params {
+    ($song_id:expr, $from:expr, $to:expr) => {
+        &[&$song_id, &$from, &$to]
+    };
+}
+
+/// insert_midi_clip_params returns the parameters for an INSERT statement in midi_clips table.
+#[macro_export]
+macro_rules! insert_midi_clip_params {
+    ($clip:expr) => {
+        &[
+            &$clip.song_id,
+            &$clip.revision,
+            &$clip.blob_key,
+            &$clip.name,
+            &$clip.format,
+            &$clip.tracks,
+            &$clip.ticks_per_quarter,
+            &$clip.notes,
+            &$clip.duration,
+            &$clip.created_by,
+            &$clip.created_at,
+            &$clip.updated_at,
+        ]
+    };
+}
+
+/// select_midi_clips_sql is a macro that generates the SQL to select MIDI clips from the database, in order of creation.
+#[macro_export]
+macro_rules! select_midi_clips_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            song_id,
+            revision,
+            blob_key,
+            name,
+            format,
+            tracks,
+            ticks_per_quarter,
+            notes,
+            duration,
+            created_by,
+            created_at,
+            updated_at,
+            COUNT(*) OVER() as count
+        FROM midi_clips
+        WHERE
+        {}
+        ORDER BY revision DESC, id ASC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
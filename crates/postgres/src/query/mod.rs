@@ -1,4 +1,19 @@
+pub mod activity;
+pub mod comment;
+pub mod email;
+pub mod follow;
+pub mod gang;
+pub mod invitation;
+pub mod lead_sheet;
+pub mod membership;
+pub mod midi_clip;
+pub mod notification;
+pub mod password_reset;
+pub mod refresh_token;
+pub mod song;
+pub mod track;
 pub mod user;
+pub mod verification;
 
 /// Returns a a string as single where condition for an equality comparison.
 ///
@@ -0,0 +1,215 @@
+/// insert_notification_sql is a macro that generates the SQL to insert a notification.
+#[macro_export]
+macro_rules! insert_notification_sql {
+    () => {
+        "INSERT INTO notifications (
+            user_id,
+            kind,
+            actor_id,
+            gang_id,
+            subject_id,
+            message,
+            created_at
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7 ) RETURNING id"
+    };
+}
+
+/// insert_notification_params returns the parameters for an INSERT statement in notifications table.
+#[macro_export]
+macro_rules! insert_notification_params {
+    ($notification:expr) => {
+        &[
+            &$notification.user_id,
+            &$notification.kind.as_str(),
+            &$notification.actor_id,
+            &$notification.gang_id,
+            &$notification.subject_id,
+            &$notification.message,
+            &$notification.created_at,
+        ]
+    };
+}
+
+/// select_notifications_sql is a macro that generates the SQL to select notifications from the database, newest first.
+#[macro_export]
+macro_rules! select_notifications_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            user_id,
+            kind,
+            actor_id,
+            gang_id,
+            subject_id,
+            message,
+            read_at,
+            created_at,
+            COUNT(*) OVER() as count
+        FROM notifications
+        WHERE
+        {}
+        ORDER BY id DESC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
+
+/// update_notification_read_sql is a macro that generates the SQL to mark a notification of a user as read,
+/// the time it was first read is kept.
+#[macro_export]
+macro_rules! update_notification_read_sql {
+    () => {
+        "UPDATE notifications SET read_at = COALESCE(read_at, $3)
+        WHERE id = $1 AND user_id = $2
+        RETURNING id"
+    };
+}
+
+/// update_notifications_read_sql is a macro that generates the SQL to mark all the unread notifications
+/// of a user as read.
+#[macro_export]
+macro_rules! update_notifications_read_sql {
+    () => {
+        "UPDATE notifications SET read_at = $2 WHERE user_id = $1 AND read_at IS NULL"
+    };
+}
+
+/// select_notification_preferences_sql is a macro that generates the SQL to select the notification
+/// preferences of a user.
+#[macro_export]
+macro_rules! select_notification_preferences_sql {
+    () => {
+        "SELECT user_id, in_app, email, webhook_url, updated_at
+        FROM notification_preferences
+        WHERE user_id = $1"
+    };
+}
+
+/// upsert_notification_preferences_sql is a macro that generates the SQL to insert or replace the
+/// notification preferences of a user.
+#[macro_export]
+macro_rules! upsert_notification_preferences_sql {
+    () => {
+        "INSERT INTO notification_preferences (
+            user_id,
+            in_app,
+            email,
+            webhook_url,
+            updated_at
+        ) VALUES ( $1, $2, $3, $4, $5 )
+        ON CONFLICT (user_id) DO UPDATE SET
+            in_app = EXCLUDED.in_app,
+            email = EXCLUDED.email,
+            webhook_url = EXCLUDED.webhook_url,
+            updated_at = EXCLUDED.updated_at"
+    };
+}
+
+/// upsert_notification_preferences_params returns the parameters for the upsert_notification_preferences_sql macro.
+#[macro_export]
+macro_rules! upsert_notification_preferences_params {
+    ($preferences:expr) => {
+        &[
+            &$preferences.user_id,
+            &$preferences.in_app,
+            &$preferences.email,
+            &$preferences.webhook_url,
+            &$preferences.updated_at,
+        ]
+    };
+}
+
+/// select_gang_member_names_sql is a macro that generates the SQL to select the ids and names of the
+/// members of a gang.
+#[macro_export]
+macro_rules! select_gang_member_names_sql {
+    () => {
+        "SELECT users.id, users.name
+        FROM gang_members
+        JOIN users ON users.id = gang_members.user_id
+        WHERE gang_members.gang_id = $1
+        ORDER BY gang_members.id ASC"
+    };
+}
+
+/// insert_delivery_sql is a macro that generates the SQL to insert a pending delivery.
+#[macro_export]
+macro_rules! insert_delivery_sql {
+    () => {
+        "INSERT INTO notification_deliveries (
+            user_id,
+            channel,
+            address,
+            kind,
+            message,
+            created_at,
+            next_attempt_at
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $6 ) RETURNING id"
+    };
+}
+
+/// insert_delivery_params returns the parameters for an INSERT statement in notification_deliveries table.
+#[macro_export]
+macro_rules! insert_delivery_params {
+    ($delivery:expr) => {
+        &[
+            &$delivery.user_id,
+            &$delivery.channel.as_str(),
+            &$delivery.address,
+            &$delivery.kind.as_str(),
+            &$delivery.message,
+            &$delivery.created_at,
+        ]
+    };
+}
+
+/// claim_deliveries_sql is a macro that generates the SQL to claim the pending deliveries due at a time,
+/// oldest first.
+///
+/// The attempts of the claimed deliveries are counted and their next attempt is delayed exponentially,
+/// so a delivery whose sending is interrupted is tried again later.
+#[macro_export]
+macro_rules! claim_deliveries_sql {
+    () => {
+        "UPDATE notification_deliveries SET
+            attempts = attempts + 1,
+            next_attempt_at = $1 + make_interval(secs => $2::DOUBLE PRECISION * POWER(2, attempts))
+        WHERE id IN (
+            SELECT id FROM notification_deliveries
+            WHERE delivered_at IS NULL
+            AND attempts < $3
+            AND next_attempt_at <= $1
+            ORDER BY id ASC
+            LIMIT $4
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING
+            id,
+            user_id,
+            channel,
+            address,
+            kind,
+            message,
+            attempts,
+            last_error,
+            delivered_at,
+            created_at"
+    };
+}
+
+/// update_delivery_sent_sql is a macro that generates the SQL to mark a delivery as sent.
+#[macro_export]
+macro_rules! update_delivery_sent_sql {
+    () => {
+        "UPDATE notification_deliveries SET delivered_at = $2, last_error = NULL WHERE id = $1"
+    };
+}
+
+/// update_delivery_failed_sql is a macro that generates the SQL to store the error of a failed delivery.
+#[macro_export]
+macro_rules! update_delivery_failed_sql {
+    () => {
+        "UPDATE notification_deliveries SET last_error = $2 WHERE id = $1"
+    };
+}
@@ -0,0 +1,70 @@
+/// insert_password_reset_sql is a macro that generates the SQL to insert a password reset token.
+#[macro_export]
+macro_rules! insert_password_reset_sql {
+    () => {
+        "INSERT INTO password_resets (
+            user_id,
+            token_hash,
+            created_at,
+            expires_at
+        ) VALUES ( $1, $2, $3, $4 ) RETURNING id"
+    };
+}
+
+/// insert_password_reset_params returns the parameters for an INSERT statement in password_resets table.
+#[macro_export]
+macro_rules! insert_password_reset_params {
+    ($reset:expr) => {
+        &[
+            &$reset.user_id,
+            &$reset.token_hash,
+            &$reset.created_at,
+            &$reset.expires_at,
+        ]
+    };
+}
+
+/// select_password_reset_by_hash_sql is a macro that generates the SQL to select and lock a password reset
+/// token by its hash.
+#[macro_export]
+macro_rules! select_password_reset_by_hash_sql {
+    () => {
+        "SELECT
+            id,
+            user_id,
+            token_hash,
+            created_at,
+            expires_at,
+            used_at
+        FROM password_resets
+        WHERE token_hash = $1
+        FOR UPDATE"
+    };
+}
+
+/// select_last_password_reset_at_sql is a macro that generates the SQL to select the time the last
+/// password reset token of a user was sent.
+#[macro_export]
+macro_rules! select_last_password_reset_at_sql {
+    () => {
+        "SELECT MAX(created_at) FROM password_resets WHERE user_id = $1"
+    };
+}
+
+/// update_password_reset_used_sql is a macro that generates the SQL to mark a password reset token as used.
+#[macro_export]
+macro_rules! update_password_reset_used_sql {
+    () => {
+        "UPDATE password_resets SET used_at = $1 WHERE id = $2"
+    };
+}
+
+/// expire_password_resets_sql is a macro that generates the SQL to expire the unused password reset tokens
+/// of a user.
+#[macro_export]
+macro_rules! expire_password_resets_sql {
+    () => {
+        "UPDATE password_resets SET expires_at = $2
+        WHERE user_id = $1 AND used_at IS NULL AND expires_at > $2"
+    };
+}
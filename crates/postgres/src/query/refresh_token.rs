@@ -0,0 +1,82 @@
+/// insert_refresh_token_sql is a macro that generates the SQL to insert a refresh token into the database.
+#[macro_export]
+macro_rules! insert_refresh_token_sql {
+    () => {
+        "INSERT INTO refresh_tokens (
+            user_id,
+            family,
+            token_hash,
+            scopes,
+            created_at,
+            expires_at
+        ) VALUES ( $1, $2, $3, $4, $5, $6 ) RETURNING id"
+    };
+}
+
+/// insert_refresh_token_params returns the parameters for an INSERT statement in refresh_tokens table.
+#[macro_export]
+macro_rules! insert_refresh_token_params {
+    ($token:expr) => {
+        &[
+            &$token.user_id,
+            &$token.family,
+            &$token.token_hash,
+            &$token.scopes,
+            &$token.created_at,
+            &$token.expires_at,
+        ]
+    };
+}
+
+/// select_refresh_token_by_hash_sql is a macro that generates the SQL to select and lock a refresh token by its hash.
+#[macro_export]
+macro_rules! select_refresh_token_by_hash_sql {
+    () => {
+        "SELECT
+            id,
+            user_id,
+            family,
+            token_hash,
+            scopes,
+            created_at,
+            expires_at,
+            used_at,
+            revoked_at
+        FROM refresh_tokens
+        WHERE token_hash = $1
+        FOR UPDATE"
+    };
+}
+
+/// update_refresh_token_used_sql is a macro that generates the SQL to mark a refresh token as used.
+#[macro_export]
+macro_rules! update_refresh_token_used_sql {
+    () => {
+        "UPDATE refresh_tokens SET used_at = $1 WHERE id = $2"
+    };
+}
+
+/// revoke_refresh_token_family_sql is a macro that generates the SQL to revoke every refresh token of a family.
+#[macro_export]
+macro_rules! revoke_refresh_token_family_sql {
+    () => {
+        "UPDATE refresh_tokens SET revoked_at = $1 WHERE family = $2 AND revoked_at IS NULL"
+    };
+}
+
+/// revoke_other_refresh_tokens_sql is a macro that generates the SQL to revoke every refresh token of a user
+/// outside of the given family.
+#[macro_export]
+macro_rules! revoke_other_refresh_tokens_sql {
+    () => {
+        "UPDATE refresh_tokens SET revoked_at = $1 WHERE user_id = $2 AND family <> $3 AND revoked_at IS NULL"
+    };
+}
+
+/// revoke_user_refresh_tokens_sql is a macro that generates the SQL to revoke every refresh token of a user.
+#[macro_export]
+macro_rules! revoke_user_refresh_tokens_sql {
+    () => {
+        "UPDATE refresh_tokens SET revoked_at = $1 WHERE user_id = $2 AND revoked_at IS NULL"
+    };
+}
@@ -0,0 +1,189 @@
+/// delete_song_sql is a macro that generates a SQL query to delete a song.
+#[macro_export]
+macro_rules! delete_song_sql {
+    () => {
+        "DELETE FROM songs WHERE id = $1"
+    };
+}
+
+/// delete_song_params is a macro that returns a tuple of the parameters to be used in the delete_song_sql macro.
+#[macro_export]
+macro_rules! delete_song_params {
+    ($id:expr) => {
+        &[&$id]
+    };
+}
+
+/// insert_song_sql is a macro that generates the SQL to insert a song into the database.
+#[macro_export]
+macro_rules! insert_song_sql {
+    () => {
+        "INSERT INTO songs (
+            gang_id,
+            title,
+            key,
+            tempo,
+            time_signature,
+            revision,
+            created_by,
+            created_at,
+            updated_at,
+            tempo_map
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10 ) RETURNING id"
+    };
+}
+
+/// insert_song_params returns the parameters for an INSERT statement in songs table.
+/// $tempo_map is the JSON of the tempo map of the song.
+#[macro_export]
+macro_rules! insert_song_params {
+    ($song:expr, $tempo_map:expr) => {
+        &[
+            &$song.gang_id,
+            &$song.title,
+            &$song.key,
+            &$song.tempo,
+            &$song.time_signature,
+            &$song.revision,
+            &$song.created_by,
+            &$song.created_at,
+            &$song.updated_at,
+            &$tempo_map,
+        ]
+    };
+}
+
+/// lock_song_sql is a macro that generates the SQL to lock a song until the end of the transaction.
+#[macro_export]
+macro_rules! lock_song_sql {
+    () => {
+        "SELECT id FROM songs WHERE id = $1 FOR UPDATE"
+    };
+}
+
+/// select_songs_sql is a macro that generates the SQL to select songs from the database.
+#[macro_export]
+macro_rules! select_songs_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            gang_id,
+            title,
+            key,
+            tempo,
+            time_signature,
+            revision,
+            created_by,
+            created_at,
+            updated_at,
+            tempo_map,
+            COUNT(*) OVER() as count
+        FROM songs
+        WHERE
+        {}
+        ORDER BY id ASC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
+
+/// update_song_sql is a macro that generates the SQL to update a song in the database.
+#[macro_export]
+macro_rules! update_song_sql {
+    () => {
+        "UPDATE songs SET
+            title = $1,
+            key = $2,
+            tempo = $3,
+            time_signature = $4,
+            revision = $5,
+            updated_at = $6,
+            tempo_map = $7
+        WHERE id = $8"
+    };
+}
+
+/// update_song_params is a macro that returns the parameters for an UPDATE statement in songs table.
+/// $tempo_map is the JSON of the tempo map of the song.
+#[macro_export]
+macro_rules! update_song_params {
+    ($song:expr, $tempo_map:expr) => {
+        &[
+            &$song.title,
+            &$song.key,
+            &$song.tempo,
+            &$song.time_signature,
+            &$song.revision,
+            &$song.updated_at,
+            &$tempo_map,
+            &$song.id,
+        ]
+    };
+}
+
+/// insert_song_revision_sql is a macro that generates the SQL to insert a song revision into the database.
+#[macro_export]
+macro_rules! insert_song_revision_sql {
+    () => {
+        "INSERT INTO song_revisions (
+            song_id,
+            revision,
+            title,
+            key,
+            tempo,
+            time_signature,
+            author_id,
+            message,
+            created_at,
+            tempo_map
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10 ) RETURNING id"
+    };
+}
+
+/// insert_song_revision_params returns the parameters for an INSERT statement in song_revisions table.
+/// $tempo_map is the JSON of the tempo map of the revision.
+#[macro_export]
+macro_rules! insert_song_revision_params {
+    ($revision:expr, $tempo_map:expr) => {
+        &[
+            &$revision.song_id,
+            &$revision.revision,
+            &$revision.title,
+            &$revision.key,
+            &$revision.tempo,
+            &$revision.time_signature,
+            &$revision.author_id,
+            &$revision.message,
+            &$revision.created_at,
+            &$tempo_map,
+        ]
+    };
+}
+
+/// select_song_revisions_sql is a macro that generates the SQL to select song revisions from the database, latest first.
+#[macro_export]
+macro_rules! select_song_revisions_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            song_id,
+            revision,
+            title,
+            key,
+            tempo,
+            time_signature,
+            author_id,
+            message,
+            created_at,
+            tempo_map,
+            COUNT(*) OVER() as count
+        FROM song_revisions
+        WHERE
+        {}
+        ORDER BY revision DESC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
@@ -0,0 +1,250 @@
+/// delete_track_sql is a macro that generates a SQL query to delete a track.
+#[macro_export]
+macro_rules! delete_track_sql {
+    () => {
+        "DELETE FROM tracks WHERE id = $1"
+    };
+}
+
+/// delete_track_params is a macro that returns a tuple of the parameters to be used in the delete_track_sql macro.
+#[macro_export]
+macro_rules! delete_track_params {
+    ($id:expr) => {
+        &[&$id]
+    };
+}
+
+/// insert_track_sql is a macro that generates the SQL to insert a track at the end of a song revision.
+#[macro_export]
+macro_rules! insert_track_sql {
+    () => {
+        "INSERT INTO tracks (
+            song_id,
+            revision,
+            blob_key,
+            instrument,
+            performer_id,
+            gain,
+            pan,
+            muted,
+            solo,
+            offset_samples,
+            created_by,
+            created_at,
+            updated_at,
+            audio_format,
+            audio_frames,
+            audio_sample_rate,
+            audio_bit_depth,
+            audio_channels,
+            audio_tags,
+            waveform_key,
+            loudness_integrated,
+            loudness_range,
+            loudness_true_peak,
+            position
+        ) VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, (
+            SELECT COALESCE(MAX(position), 0) + 1 FROM tracks WHERE song_id = $1 AND revision = $2
+        )) RETURNING id, position"
+    };
+}
+
+/// copy_tracks_sql is a macro that generates the SQL to copy the tracks of a song revision ($2) into another revision ($3).
+#[macro_export]
+macro_rules! copy_tracks_sql {
+    () => {
+        "INSERT INTO tracks (
+            song_id,
+            revision,
+            blob_key,
+            instrument,
+            performer_id,
+            gain,
+            pan,
+            muted,
+            solo,
+            offset_samples,
+            created_by,
+            created_at,
+            updated_at,
+            audio_format,
+            audio_frames,
+            audio_sample_rate,
+            audio_bit_depth,
+            audio_channels,
+            audio_tags,
+            waveform_key,
+            loudness_integrated,
+            loudness_range,
+            loudness_true_peak,
+            position
+        ) SELECT
+            song_id,
+            $3,
+            blob_key,
+            instrument,
+            performer_id,
+            gain,
+            pan,
+            muted,
+            solo,
+            offset_samples,
+            created_by,
+            created_at,
+            updated_at,
+            audio_format,
+            audio_frames,
+            audio_sample_rate,
+            audio_bit_depth,
+            audio_channels,
+            audio_tags,
+            waveform_key,
+            loudness_integrated,
+            loudness_range,
+            loudness_true_peak,
+            position
+        FROM tracks WHERE song_id = $1 AND revision = $2 ORDER BY position"
+    };
+}
+
+/// copy_tracks_params is a macro that returns the parameters to be used in the copy_tracks_sql macro.
+#[macro_export]
+macro_rules! copy_tracks_params {
+    ($song_id:expr, $from:expr, $to:expr) => {
+        &[&$song_id, &$from, &$to]
+    };
+}
+
+/// insert_track_params returns the parameters for an INSERT statement in tracks table.
+/// $audio are the AudioColumns of the track.
+#[macro_export]
+macro_rules! insert_track_params {
+    ($track:expr, $audio:expr) => {
+        &[
+            &$track.song_id,
+            &$track.revision,
+            &$track.blob_key,
+            &$track.instrument,
+            &$track.performer_id,
+            &$track.gain,
+            &$track.pan,
+            &$track.muted,
+            &$track.solo,
+            &$track.offset,
+            &$track.created_by,
+            &$track.created_at,
+            &$track.updated_at,
+            &$audio.format,
+            &$audio.frames,
+            &$audio.sample_rate,
+            &$audio.bit_depth,
+            &$audio.channels,
+            &$audio.tags,
+            &$track.waveform_key,
+            &$audio.loudness_integrated,
+            &$audio.loudness_range,
+            &$audio.loudness_true_peak,
+        ]
+    };
+}
+
+/// select_tracks_sql is a macro that generates the SQL to select tracks from the database, in order.
+#[macro_export]
+macro_rules! select_tracks_sql {
+    ($whereConditions:expr,$limitOffsetConditions:expr) => {
+        format!("
+        SELECT 
+            id,
+            song_id,
+            revision,
+            blob_key,
+            instrument,
+            performer_id,
+            gain,
+            pan,
+            muted,
+            solo,
+            offset_samples,
+            position,
+            created_by,
+            created_at,
+            updated_at,
+            audio_format,
+            audio_frames,
+            audio_sample_rate,
+            audio_bit_depth,
+            audio_channels,
+            audio_tags,
+            waveform_key,
+            loudness_integrated,
+            loudness_range,
+            loudness_true_peak,
+            COUNT(*) OVER() as count
+        FROM tracks
+        WHERE
+        {}
+        ORDER BY revision DESC, position ASC
+        {}
+        ", $whereConditions.join("\nAND "), $limitOffsetConditions)
+    }
+}
+
+/// update_track_sql is a macro that generates the SQL to update a track in the database.
+#[macro_export]
+macro_rules! update_track_sql {
+    () => {
+        "UPDATE tracks SET
+            blob_key = $1,
+            instrument = $2,
+            performer_id = $3,
+            gain = $4,
+            pan = $5,
+            muted = $6,
+            solo = $7,
+            offset_samples = $8,
+            position = $9,
+            updated_at = $10,
+            audio_format = $11,
+            audio_frames = $12,
+            audio_sample_rate = $13,
+            audio_bit_depth = $14,
+            audio_channels = $15,
+            audio_tags = $16,
+            waveform_key = $17,
+            loudness_integrated = $18,
+            loudness_range = $19,
+            loudness_true_peak = $20
+        WHERE id = $21"
+    };
+}
+
+/// update_track_params is a macro that returns the parameters for an UPDATE statement in tracks table.
+/// $audio are the AudioColumns of the track.
+#[macro_export]
+macro_rules! update_track_params {
+    ($track:expr, $audio:expr) => {
+        &[
+            &$track.blob_key,
+            &$track.instrument,
+            &$track.performer_id,
+            &$track.gain,
+            &$track.pan,
+            &$track.muted,
+            &$track.solo,
+            &$track.offset,
+            &$track.position,
+            &$track.updated_at,
+            &$audio.format,
+            &$audio.frames,
+            &$audio.sample_rate,
+            &$audio.bit_depth,
+            &$audio.channels,
+            &$audio.tags,
+            &$track.waveform_key,
+            &$audio.loudness_integrated,
+            &$audio.loudness_range,
+            &$audio.loudness_true_peak,
+            &$track.id,
+        ]
+    };
+}
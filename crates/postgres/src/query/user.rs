@@ -22,9 +22,10 @@ macro_rules! insert_user_sql {
             name,
             email,
             password,
+            email_verified_at,
             created_at,
             updated_at
-        ) VALUES ( $1, $2, $3, $4, $5 ) RETURNING id"
+        ) VALUES ( $1, $2, $3, $4, $5, $6 ) RETURNING id"
             .to_string()
     };
 }
@@ -37,6 +38,7 @@ macro_rules! insert_user_params {
             &$user.name,
             &$user.email,
             &$user.password,
+            &$user.email_verified_at,
             &$user.created_at,
             &$user.updated_at,
         ]
@@ -55,6 +57,10 @@ macro_rules! select_users_sql {
             password,
             created_at,
             updated_at,
+            email_verified_at,
+            sessions_revoked_at,
+            pending_email,
+            kept_session_id,
             COUNT(*) OVER() as count
         FROM users
         WHERE
@@ -83,3 +89,102 @@ macro_rules! update_users_params {
         &[&$user.name, &$user.updated_at, &$user.id]
     };
 }
+
+/// update_user_password_sql is a macro that generates the SQL to update the password of a user in the database.
+#[macro_export]
+macro_rules! update_user_password_sql {
+    () => {
+        "UPDATE users SET
+            password = $1,
+            updated_at = $2
+        WHERE id = $3"
+    };
+}
+
+/// update_user_password_params is a macro that returns the parameters for an UPDATE statement of the password in users table.
+#[macro_export]
+macro_rules! update_user_password_params {
+    ($user:expr) => {
+        &[&$user.password, &$user.updated_at, &$user.id]
+    };
+}
+
+/// update_user_password_sessions_sql is a macro that generates the SQL to set the password of a user in the
+/// database, revoking their sessions but the kept one.
+#[macro_export]
+macro_rules! update_user_password_sessions_sql {
+    () => {
+        "UPDATE users SET
+            password = $1,
+            sessions_revoked_at = $2,
+            kept_session_id = $3,
+            updated_at = $4
+        WHERE id = $5"
+    };
+}
+
+/// update_user_password_sessions_params is a macro that returns the parameters for the update_user_password_sessions_sql macro.
+#[macro_export]
+macro_rules! update_user_password_sessions_params {
+    ($user:expr) => {
+        &[
+            &$user.password,
+            &$user.sessions_revoked_at,
+            &$user.kept_session_id,
+            &$user.updated_at,
+            &$user.id,
+        ]
+    };
+}
+
+/// update_user_email_verified_sql is a macro that generates the SQL to mark the email address of a user as verified.
+#[macro_export]
+macro_rules! update_user_email_verified_sql {
+    () => {
+        "UPDATE users SET
+            email_verified_at = $1,
+            updated_at = $1
+        WHERE id = $2"
+    };
+}
+
+/// update_user_pending_email_sql is a macro that generates the SQL to store the address a user asked to change
+/// their email to.
+#[macro_export]
+macro_rules! update_user_pending_email_sql {
+    () => {
+        "UPDATE users SET
+            pending_email = $1,
+            updated_at = $2
+        WHERE id = $3"
+    };
+}
+
+/// update_user_pending_email_params is a macro that returns the parameters for the update_user_pending_email_sql macro.
+#[macro_export]
+macro_rules! update_user_pending_email_params {
+    ($user:expr) => {
+        &[&$user.pending_email, &$user.updated_at, &$user.id]
+    };
+}
+
+/// update_user_email_sql is a macro that generates the SQL to swap in the verified pending email address of a user.
+#[macro_export]
+macro_rules! update_user_email_sql {
+    () => {
+        "UPDATE users SET
+            email = $1,
+            pending_email = NULL,
+            email_verified_at = $2,
+            updated_at = $2
+        WHERE id = $3"
+    };
+}
+
+/// select_user_id_by_email_sql is a macro that generates the SQL to select the id of a user by email, compared case-insensitively.
+#[macro_export]
+macro_rules! select_user_id_by_email_sql {
+    () => {
+        "SELECT id FROM users WHERE LOWER(email) = LOWER($1) LIMIT 1"
+    };
+}
@@ -0,0 +1,73 @@
+/// insert_email_verification_sql is a macro that generates the SQL to insert an email verification token.
+#[macro_export]
+macro_rules! insert_email_verification_sql {
+    () => {
+        "INSERT INTO email_verifications (
+            user_id,
+            email,
+            token_hash,
+            created_at,
+            expires_at
+        ) VALUES ( $1, $2, $3, $4, $5 ) RETURNING id"
+    };
+}
+
+/// insert_email_verification_params returns the parameters for an INSERT statement in email_verifications table.
+#[macro_export]
+macro_rules! insert_email_verification_params {
+    ($verification:expr) => {
+        &[
+            &$verification.user_id,
+            &$verification.email,
+            &$verification.token_hash,
+            &$verification.created_at,
+            &$verification.expires_at,
+        ]
+    };
+}
+
+/// select_email_verification_by_hash_sql is a macro that generates the SQL to select and lock an email
+/// verification token by its hash.
+#[macro_export]
+macro_rules! select_email_verification_by_hash_sql {
+    () => {
+        "SELECT
+            id,
+            user_id,
+            email,
+            token_hash,
+            created_at,
+            expires_at,
+            used_at
+        FROM email_verifications
+        WHERE token_hash = $1
+        FOR UPDATE"
+    };
+}
+
+/// select_last_email_verification_at_sql is a macro that generates the SQL to select the time the last
+/// email verification token of a user was sent.
+#[macro_export]
+macro_rules! select_last_email_verification_at_sql {
+    () => {
+        "SELECT MAX(created_at) FROM email_verifications WHERE user_id = $1"
+    };
+}
+
+/// update_email_verification_used_sql is a macro that generates the SQL to mark an email verification token as used.
+#[macro_export]
+macro_rules! update_email_verification_used_sql {
+    () => {
+        "UPDATE email_verifications SET used_at = $1 WHERE id = $2"
+    };
+}
+
+/// expire_email_verifications_sql is a macro that generates the SQL to expire the unused email verification
+/// tokens of a user.
+#[macro_export]
+macro_rules! expire_email_verifications_sql {
+    () => {
+        "UPDATE email_verifications SET expires_at = $2
+        WHERE user_id = $1 AND used_at IS NULL AND expires_at > $2"
+    };
+}
@@ -0,0 +1,961 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::activity::{Activity, ActivityKind};
+use openmusicgang_entity::membership::GangRole;
+use openmusicgang_entity::song::{Song, SongRevision, TempoChange};
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::song_service::{
+    SongFilter, SongRevisionFilter, SongService as SongServiceTrait, SongUpdate,
+};
+use postgres::types::ToSql;
+use postgres::Transaction;
+
+use crate::activity::record_activity;
+use crate::membership::require_role;
+use crate::postgres::DB;
+use crate::{
+    copy_midi_clips_params, copy_midi_clips_sql, copy_tracks_params, copy_tracks_sql,
+    delete_song_params, delete_song_sql, format_limit_offset, insert_song_params,
+    insert_song_revision_params, insert_song_revision_sql, insert_song_sql, lock_song_sql,
+    select_song_revisions_sql, select_songs_sql, update_song_params, update_song_sql,
+    where_condition_eq,
+};
+
+/// SongService is a struct that implements the SongServiceTrait for the postgres crate.
+pub struct SongService {
+    db: Arc<Mutex<DB>>,
+}
+
+impl SongService {
+    /// Create a new SongService struct
+    pub fn new(db: Arc<Mutex<DB>>) -> SongService {
+        SongService { db }
+    }
+}
+
+impl SongServiceTrait for SongService {
+    /// Create a new song.
+    fn create_song(&self, ctx: AppContext, song: &mut Song) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        create_song(ctx, &mut tx, song)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Deletes a song.
+    fn delete_song(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        delete_song(ctx, &mut tx, id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Updates a song.
+    fn update_song(&self, ctx: AppContext, id: i64, song: SongUpdate) -> Result<Song, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let song = update_song(ctx, &mut tx, id, song)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(song)
+    }
+
+    /// Get a song by id.
+    fn find_song_by_id(&self, ctx: AppContext, id: i64) -> Result<Song, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_song_by_id(ctx, &mut tx, id)
+    }
+
+    /// Returns a vector of songs based on passed filters, also returns the total number of songs.
+    fn find_songs(&self, ctx: AppContext, filters: SongFilter) -> Result<(Vec<Song>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_songs(ctx, &mut tx, filters)
+    }
+
+    /// Get a revision of a song by number.
+    fn find_song_revision(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+    ) -> Result<SongRevision, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_song_revision(ctx, &mut tx, song_id, revision)
+    }
+
+    /// Returns the history of a song, also returns the total number of revisions.
+    fn find_song_revisions(
+        &self,
+        ctx: AppContext,
+        filters: SongRevisionFilter,
+    ) -> Result<(Vec<SongRevision>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_song_revisions(ctx, &mut tx, filters)
+    }
+
+    /// Reverts a song to a revision.
+    fn revert_song(&self, ctx: AppContext, id: i64, revision: i32) -> Result<Song, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let song = revert_song(ctx, &mut tx, id, revision)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(song)
+    }
+}
+
+/// create_song inserts a new song into the database with its first revision.
+///
+/// Handles the create_song Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang.
+///
+/// Returns EINVALID if the song is invalid.
+fn create_song(ctx: AppContext, tx: &mut Transaction, song: &mut Song) -> Result<(), Error> {
+    let member = require_role(ctx, tx, song.gang_id, GangRole::Member)?;
+
+    song.revision = 1;
+    song.created_by = Some(member.user_id);
+    song.created_at = Utc::now();
+    song.updated_at = song.created_at;
+
+    song.validate()?;
+
+    let row = tx
+        .query_one(
+            insert_song_sql!(),
+            insert_song_params!(song, tempo_map_json(&song.tempo_map)),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    song.id = row.get(0);
+
+    create_song_revision(tx, song, member.user_id, "Create song".to_string())?;
+
+    Ok(())
+}
+
+/// create_song_revision records the current content of the song as its latest revision.
+fn create_song_revision(
+    tx: &mut Transaction,
+    song: &Song,
+    author_id: i64,
+    message: String,
+) -> Result<SongRevision, Error> {
+    let mut revision = SongRevision::new(song, author_id, message);
+
+    let row = tx
+        .query_one(
+            insert_song_revision_sql!(),
+            insert_song_revision_params!(revision, tempo_map_json(&revision.tempo_map)),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    revision.id = row.get(0);
+
+    Ok(revision)
+}
+
+/// delete_song deletes a song and its history from the database.
+/// Handles the delete_song Business Logic.
+/// Returns EFORBIDDEN if the user of the context is not an admin of the gang.
+fn delete_song(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<(), Error> {
+    let song = find_song_by_id(ctx.clone(), tx, id)?;
+    require_role(ctx, tx, song.gang_id, GangRole::Admin)?;
+
+    tx.execute(delete_song_sql!(), delete_song_params!(id))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// lock_song locks a song until the end of the transaction, so that revisions are recorded one at a time.
+/// Returns ENOTFOUND if the song does not exist.
+pub(crate) fn lock_song(tx: &mut Transaction, id: i64) -> Result<(), Error> {
+    let row = tx
+        .query_opt(lock_song_sql!(), &[&id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    if row.is_none() {
+        return Err(Error::new(
+            ErrorCode::ENOTFOUND,
+            "Song not found".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// find_song_by_id returns a song by id.
+///
+/// Handles the find_song_by_id Business Logic.
+///
+/// Returns ENOTFOUND if the song does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang of the song.
+pub(crate) fn find_song_by_id(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<Song, Error> {
+    let filters = SongFilter {
+        id: Some(id),
+        ..Default::default()
+    };
+
+    let result = select_songs(tx, filters, None)?;
+
+    if result.1 == 0 {
+        return Err(Error::new(
+            ErrorCode::ENOTFOUND,
+            "Song not found".to_string(),
+        ));
+    }
+
+    let song = result.0[0].clone();
+    require_role(ctx, tx, song.gang_id, GangRole::Guest)?;
+
+    Ok(song)
+}
+
+/// find_songs finds songs in the database based on the filters.
+///
+/// Handles the find_songs Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EFORBIDDEN if the songs of a gang are requested by someone who is not a member of the gang.
+///
+/// Without a gang in the filters only the songs of the gangs of the user of the context are returned.
+fn find_songs(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    filters: SongFilter,
+) -> Result<(Vec<Song>, i64), Error> {
+    if let Some(gang_id) = filters.gang_id {
+        require_role(ctx, tx, gang_id, GangRole::Guest)?;
+        return select_songs(tx, filters, None);
+    }
+
+    match Context::user_id_from_context(ctx) {
+        0 => Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in".to_string(),
+        )),
+        user_id => select_songs(tx, filters, Some(user_id)),
+    }
+}
+
+/// select_songs selects the songs matching the filters.
+///
+/// If a member is given only the songs of the gangs of the user are selected.
+fn select_songs(
+    tx: &mut Transaction,
+    filters: SongFilter,
+    member_id: Option<i64>,
+) -> Result<(Vec<Song>, i64), Error> {
+    let mut where_conditions = vec!["1 = 1".to_string()];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![];
+    let mut args_counter = 1;
+
+    if filters.id.is_some() {
+        where_conditions.push(where_condition_eq!("id", args_counter));
+        args_counter += 1;
+        args.push(&filters.id);
+    }
+
+    if filters.gang_id.is_some() {
+        where_conditions.push(where_condition_eq!("gang_id", args_counter));
+        args_counter += 1;
+        args.push(&filters.gang_id);
+    }
+
+    if filters.title.is_some() {
+        where_conditions.push(where_condition_eq!("title", args_counter));
+        args_counter += 1;
+        args.push(&filters.title);
+    }
+
+    if member_id.is_some() {
+        where_conditions.push(format!(
+            "gang_id IN (SELECT gang_id FROM gang_members WHERE user_id = ${})",
+            args_counter
+        ));
+        args.push(&member_id);
+    }
+
+    let query = select_songs_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut songs: Vec<Song> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        let mut song = Song::new();
+
+        song.id = row.get(0);
+        song.gang_id = row.get(1);
+        song.title = row.get(2);
+        song.key = row.get(3);
+        song.tempo = row.get(4);
+        song.time_signature = row.get(5);
+        song.revision = row.get(6);
+        song.created_by = row.get(7);
+        song.created_at = row.get(8);
+        song.updated_at = row.get(9);
+        song.tempo_map = tempo_map_from_json(row.get(10));
+        tot_results = row.get(11);
+
+        songs.push(song);
+    }
+
+    Ok((songs, tot_results))
+}
+
+/// find_song_revision returns a revision of a song by number.
+///
+/// Handles the find_song_revision Business Logic.
+///
+/// Returns the errors of find_song_by_id.
+///
+/// Returns ENOTFOUND if the revision does not exist.
+pub(crate) fn find_song_revision(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    song_id: i64,
+    revision: i32,
+) -> Result<SongRevision, Error> {
+    find_song_by_id(ctx, tx, song_id)?;
+
+    let filters = SongRevisionFilter {
+        song_id,
+        ..Default::default()
+    };
+
+    let result = select_song_revisions(tx, filters, Some(revision))?;
+
+    if result.1 == 0 {
+        return Err(Error::new(
+            ErrorCode::ENOTFOUND,
+            "Song revision not found".to_string(),
+        ));
+    }
+
+    Ok(result.0[0].clone())
+}
+
+/// find_song_revisions finds the revisions of a song, latest first.
+///
+/// Handles the find_song_revisions Business Logic.
+///
+/// Returns the errors of find_song_by_id.
+fn find_song_revisions(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    filters: SongRevisionFilter,
+) -> Result<(Vec<SongRevision>, i64), Error> {
+    find_song_by_id(ctx, tx, filters.song_id)?;
+
+    select_song_revisions(tx, filters, None)
+}
+
+/// select_song_revisions selects the revisions matching the filters, and the given number if any.
+fn select_song_revisions(
+    tx: &mut Transaction,
+    filters: SongRevisionFilter,
+    revision: Option<i32>,
+) -> Result<(Vec<SongRevision>, i64), Error> {
+    let mut where_conditions = vec![where_condition_eq!("song_id", 1)];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![&filters.song_id];
+    let mut args_counter = 2;
+
+    if filters.author_id.is_some() {
+        where_conditions.push(where_condition_eq!("author_id", args_counter));
+        args_counter += 1;
+        args.push(&filters.author_id);
+    }
+
+    if revision.is_some() {
+        where_conditions.push(where_condition_eq!("revision", args_counter));
+        args.push(&revision);
+    }
+
+    let query = select_song_revisions_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut revisions: Vec<SongRevision> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        revisions.push(SongRevision {
+            id: row.get(0),
+            song_id: row.get(1),
+            revision: row.get(2),
+            title: row.get(3),
+            key: row.get(4),
+            tempo: row.get(5),
+            time_signature: row.get(6),
+            author_id: row.get(7),
+            message: row.get(8),
+            created_at: row.get(9),
+            tempo_map: tempo_map_from_json(row.get(10)),
+        });
+        tot_results = row.get(11);
+    }
+
+    Ok((revisions, tot_results))
+}
+
+/// update_song updates a song in the database and records the change as a new revision.
+///
+/// Handles the update_song Business Logic.
+///
+/// Returns ENOTFOUND if the song is not found.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot change songs.
+///
+/// Returns EINVALID if the song is invalid.
+fn update_song(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+    update: SongUpdate,
+) -> Result<Song, Error> {
+    lock_song(tx, id)?;
+
+    let mut song = find_song_by_id(ctx.clone(), tx, id)?;
+    let member = require_role(ctx.clone(), tx, song.gang_id, GangRole::Member)?;
+
+    if let Some(title) = update.title {
+        song.title = title;
+    }
+
+    if let Some(key) = update.key {
+        song.key = Some(key);
+    }
+
+    if let Some(tempo) = update.tempo {
+        song.tempo = Some(tempo);
+    }
+
+    if let Some(time_signature) = update.time_signature {
+        song.time_signature = time_signature;
+    }
+
+    if let Some(tempo_map) = update.tempo_map {
+        song.tempo_map = tempo_map;
+    }
+
+    song.validate()?;
+
+    let message = match update.message.trim() {
+        "" => "Update song".to_string(),
+        message => message.to_string(),
+    };
+
+    let source_revision = song.revision;
+
+    save_song_revision(ctx, tx, &mut song, member.user_id, message, source_revision)?;
+
+    Ok(song)
+}
+
+/// save_song_revision stores the content of the song as a new revision, and records it in the activity feeds.
+/// The tracks and MIDI clips of source_revision are copied into the new revision.
+pub(crate) fn save_song_revision(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    song: &mut Song,
+    author_id: i64,
+    message: String,
+    source_revision: i32,
+) -> Result<(), Error> {
+    song.revision += 1;
+    song.updated_at = Utc::now();
+
+    tx.execute(
+        update_song_sql!(),
+        update_song_params!(song, tempo_map_json(&song.tempo_map)),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    create_song_revision(tx, song, author_id, message)?;
+
+    tx.execute(
+        copy_tracks_sql!(),
+        copy_tracks_params!(song.id, source_revision, song.revision),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    tx.execute(
+        copy_midi_clips_sql!(),
+        copy_midi_clips_params!(song.id, source_revision, song.revision),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut activity = Activity::new(ActivityKind::RevisionCreated, author_id, song.gang_id);
+    activity.song_id = Some(song.id);
+    activity.revision = Some(song.revision);
+
+    record_activity(
+        ctx,
+        tx,
+        &mut activity,
+        &format!("saved revision {} of {}", song.revision, song.title),
+    )?;
+
+    Ok(())
+}
+
+/// revert_song restores the content of a song to a previous revision, the revert is recorded as a new revision.
+///
+/// Handles the revert_song Business Logic.
+///
+/// Returns ENOTFOUND if the song or the revision is not found.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot change songs.
+///
+/// Returns ECONFLICT if the revision is the latest one.
+fn revert_song(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+    revision: i32,
+) -> Result<Song, Error> {
+    lock_song(tx, id)?;
+
+    let mut song = find_song_by_id(ctx.clone(), tx, id)?;
+    let member = require_role(ctx.clone(), tx, song.gang_id, GangRole::Member)?;
+
+    if revision == song.revision {
+        return Err(Error::new(
+            ErrorCode::ECONFLICT,
+            format!("Song is already at revision {}", revision),
+        ));
+    }
+
+    let target = find_song_revision(ctx.clone(), tx, id, revision)?;
+    song.restore(&target);
+
+    save_song_revision(
+        ctx,
+        tx,
+        &mut song,
+        member.user_id,
+        format!("Revert to revision {}", revision),
+        revision,
+    )?;
+
+    Ok(song)
+}
+
+/// Returns the number of a revision of a song, the latest revision if none is given.
+///
+/// Returns EINVALID if the revision does not exist.
+pub(crate) fn song_revision_number(song: &Song, revision: i32) -> Result<i32, Error> {
+    let revision = if revision == 0 {
+        song.revision
+    } else {
+        revision
+    };
+
+    if revision < 1 || revision > song.revision {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            format!("Song has no revision {}", revision),
+        ));
+    }
+
+    Ok(revision)
+}
+
+/// Returns the JSON of a tempo map, an array of `{"beat", "tempo"}` objects.
+fn tempo_map_json(tempo_map: &[TempoChange]) -> serde_json::Value {
+    tempo_map
+        .iter()
+        .map(|change| serde_json::json!({ "beat": change.beat, "tempo": change.tempo }))
+        .collect()
+}
+
+/// Returns the tempo map of its JSON, the changes that cannot be read are skipped.
+fn tempo_map_from_json(json: serde_json::Value) -> Vec<TempoChange> {
+    json.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|change| {
+            Some(TempoChange {
+                beat: change["beat"].as_f64()?,
+                tempo: change["tempo"].as_f64()?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_entity::gang::Gang;
+    use openmusicgang_entity::invitation::Invitation;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_service::gang_service::GangService as GangServiceTrait;
+    use openmusicgang_service::membership_service::MembershipService as MembershipServiceTrait;
+
+    use crate::gang::GangService;
+    use crate::membership::MembershipService;
+    use crate::test_utils::{must_create_user, must_lock_db, must_open_db, must_truncate_table};
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) create a song as a non member, error should be EFORBIDDEN.
+    /// 4) create a song, it should be at revision 1.
+    /// 5) create songs with an invalid time signature and key, error should be EINVALID.
+    /// 6) update the song as a guest, error should be EFORBIDDEN.
+    /// 7) update the song twice, each update should record a revision, an invalid tempo map should be EINVALID.
+    /// 8) get a revision by number and list the history, latest first.
+    /// 9) revert the song to its first revision, the revert should be a new revision.
+    /// 10) find the songs of the user and of the gang.
+    /// 11) delete the song as a member, error should be EFORBIDDEN.
+    /// 12) delete the song as the owner and check that the delete was successful.
+    #[test]
+    fn test_song_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "song_revisions");
+        must_truncate_table(&mut db, "songs");
+        must_truncate_table(&mut db, "gang_invitations");
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+        let steve = must_create_user(&mut db, "Steve Smith", "steve.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+        let membership_service = MembershipService::new(Arc::clone(&db), 3600);
+        let song_service = SongService::new(Arc::clone(&db));
+
+        let ctx = |user: &User| Context::with_user(Context::background(), user.clone());
+
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang_service.create_gang(ctx(&bob), &mut gang).unwrap();
+
+        for (user, role) in [(&john, GangRole::Member), (&steve, GangRole::Guest)] {
+            let mut invitation = Invitation {
+                gang_id: gang.id,
+                user_id: Some(user.id),
+                role,
+                ..Default::default()
+            };
+            membership_service
+                .invite_member(ctx(&bob), &mut invitation)
+                .unwrap();
+            membership_service
+                .accept_invitation(ctx(user), invitation.id)
+                .unwrap();
+        }
+
+        // 3) create a song as a non member, error should be EFORBIDDEN.
+        let mut song = Song {
+            gang_id: gang.id,
+            title: "Segfault Blues".to_string(),
+            key: Some("Em".to_string()),
+            tempo: Some(92.0),
+            ..Default::default()
+        };
+
+        let mut another_gang = Gang::new();
+        another_gang.name = "Jazz Crabs".to_string();
+        gang_service
+            .create_gang(ctx(&steve), &mut another_gang)
+            .unwrap();
+
+        let res = song_service.create_song(
+            ctx(&john),
+            &mut Song {
+                gang_id: another_gang.id,
+                ..song.clone()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 4) create a song, it should be at revision 1.
+        let res = song_service.create_song(ctx(&john), &mut song);
+        if let Err(error) = res {
+            panic!("{}", error);
+        }
+        assert_eq!(song.id, 1);
+        assert_eq!(song.revision, 1);
+        assert_eq!(song.created_by, Some(john.id));
+
+        // 5) create songs with an invalid time signature and key, error should be EINVALID.
+        let res = song_service.create_song(
+            ctx(&john),
+            &mut Song {
+                time_signature: "4/3".to_string(),
+                ..song.clone()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let res = song_service.create_song(
+            ctx(&john),
+            &mut Song {
+                key: Some("H#m".to_string()),
+                ..song.clone()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 6) update the song as a guest, error should be EFORBIDDEN.
+        let update = SongUpdate {
+            tempo: Some(120.0),
+            message: "Faster".to_string(),
+            ..Default::default()
+        };
+
+        let res = song_service.update_song(ctx(&steve), song.id, update.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 7) update the song twice, each update should record a revision, an invalid tempo map should be EINVALID.
+        let res = song_service.update_song(ctx(&john), song.id, update);
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap().revision, 2);
+
+        let update = SongUpdate {
+            title: Some("Segfault Boogie".to_string()),
+            time_signature: Some("7/8".to_string()),
+            tempo_map: Some(vec![
+                TempoChange {
+                    beat: 0.0,
+                    tempo: 120.0,
+                },
+                TempoChange {
+                    beat: 56.0,
+                    tempo: 96.5,
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let song = song_service
+            .update_song(ctx(&bob), song.id, update)
+            .unwrap();
+        assert_eq!(song.revision, 3);
+        assert_eq!(song.title, "Segfault Boogie");
+        assert_eq!(song.tempo, Some(120.0));
+        assert_eq!(song.tempo_map.len(), 2);
+
+        let res = song_service.update_song(
+            ctx(&bob),
+            song.id,
+            SongUpdate {
+                tempo_map: Some(vec![TempoChange {
+                    beat: 8.0,
+                    tempo: 120.0,
+                }]),
+                ..Default::default()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 8) get a revision by number and list the history, latest first.
+        let revision = song_service
+            .find_song_revision(ctx(&steve), song.id, 2)
+            .unwrap();
+        assert_eq!(revision.title, "Segfault Blues");
+        assert_eq!(revision.tempo, Some(120.0));
+        assert_eq!(revision.author_id, Some(john.id));
+        assert_eq!(revision.message, "Faster");
+
+        let res = song_service.find_song_revision(ctx(&steve), song.id, 4);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        let filters = SongRevisionFilter {
+            song_id: song.id,
+            ..Default::default()
+        };
+        let (revisions, total) = song_service
+            .find_song_revisions(ctx(&steve), filters.clone())
+            .unwrap();
+        assert_eq!(total, 3);
+        let numbers: Vec<i32> = revisions.iter().map(|r| r.revision).collect();
+        assert_eq!(numbers, vec![3, 2, 1]);
+        assert_eq!(revisions[0].message, "Update song");
+        assert_eq!(revisions[0].tempo_map, song.tempo_map);
+        assert_eq!(revisions[2].message, "Create song");
+
+        let res = song_service.find_song_revisions(Context::background(), filters.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 9) revert the song to its first revision, the revert should be a new revision.
+        let res = song_service.revert_song(ctx(&john), song.id, 3);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        let song = song_service.revert_song(ctx(&john), song.id, 1).unwrap();
+        assert_eq!(song.revision, 4);
+        assert_eq!(song.title, "Segfault Blues");
+        assert_eq!(song.tempo, Some(92.0));
+        assert_eq!(song.time_signature, "4/4");
+        assert_eq!(song.tempo_map, vec![]);
+
+        let (revisions, total) = song_service
+            .find_song_revisions(ctx(&john), filters)
+            .unwrap();
+        assert_eq!(total, 4);
+        assert_eq!(revisions[0].message, "Revert to revision 1");
+        assert_eq!(revisions[0].title, "Segfault Blues");
+
+        // 10) find the songs of the user and of the gang.
+        let (songs, total) = song_service
+            .find_songs(ctx(&steve), SongFilter::default())
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(songs[0].id, song.id);
+        assert_eq!(songs[0].revision, 4);
+
+        let filters = SongFilter {
+            gang_id: Some(another_gang.id),
+            ..Default::default()
+        };
+        let res = song_service.find_songs(ctx(&john), filters);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 11) delete the song as a member, error should be EFORBIDDEN.
+        let res = song_service.delete_song(ctx(&john), song.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 12) delete the song as the owner and check that the delete was successful.
+        let res = song_service.delete_song(ctx(&bob), song.id);
+        assert!(res.is_ok());
+
+        let res = song_service.find_song_by_id(ctx(&bob), song.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+    }
+}
@@ -0,0 +1,625 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+use chrono::Duration;
+
+use openmusicgang_app::context::AppContext;
+use openmusicgang_crypto::digest::sha256_hex;
+use openmusicgang_crypto::jwt::{Claims, JwtSigner};
+use openmusicgang_crypto::password::PasswordHasher;
+use openmusicgang_crypto::random::random_token;
+use openmusicgang_entity::token::{AccessToken, RefreshToken, TokenPair};
+use openmusicgang_entity::user::User;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::token_service::TokenService as TokenServiceTrait;
+use postgres::Transaction;
+
+use crate::postgres::DB;
+use crate::user::{find_user_by_id, verify_password};
+use crate::{
+    insert_refresh_token_params, insert_refresh_token_sql, revoke_refresh_token_family_sql,
+    select_refresh_token_by_hash_sql, update_refresh_token_used_sql,
+};
+
+/// REFRESH_TOKEN_BYTES is the number of random bytes of a refresh token.
+static REFRESH_TOKEN_BYTES: usize = 32;
+
+/// TOKEN_ID_BYTES is the number of random bytes of token families and access token ids.
+static TOKEN_ID_BYTES: usize = 16;
+
+/// TokenService is a struct that implements the TokenServiceTrait for the postgres crate.
+///
+/// Access tokens are signed JWTs, refresh tokens are opaque and stored hashed in the refresh_tokens table.
+pub struct TokenService {
+    db: Arc<Mutex<DB>>,
+    hasher: PasswordHasher,
+    signer: JwtSigner,
+    access_token_ttl: u64,
+    refresh_token_ttl: u64,
+}
+
+impl TokenService {
+    /// Create a new TokenService struct
+    pub fn new(
+        db: Arc<Mutex<DB>>,
+        hasher: PasswordHasher,
+        signer: JwtSigner,
+        access_token_ttl: u64,
+        refresh_token_ttl: u64,
+    ) -> TokenService {
+        TokenService {
+            db,
+            hasher,
+            signer,
+            access_token_ttl,
+            refresh_token_ttl,
+        }
+    }
+
+    /// Issues a new token pair for the user, the refresh token is stored in the given family.
+    fn create_token_pair(
+        &self,
+        tx: &mut Transaction,
+        user_id: i64,
+        family: String,
+        scopes: Vec<String>,
+    ) -> Result<TokenPair, Error> {
+        let now = Utc::now();
+        let refresh_token = random_token(REFRESH_TOKEN_BYTES);
+
+        let mut record = RefreshToken {
+            id: 0,
+            user_id,
+            family,
+            token_hash: sha256_hex(refresh_token.as_bytes()),
+            scopes: scopes.clone(),
+            created_at: now,
+            expires_at: now + Duration::seconds(self.refresh_token_ttl as i64),
+            used_at: None,
+            revoked_at: None,
+        };
+
+        insert_refresh_token(tx, &mut record)?;
+
+        let access_token_expires_at = now + Duration::seconds(self.access_token_ttl as i64);
+
+        let access_token = self.signer.sign(&Claims {
+            sub: user_id,
+            scopes,
+            iat: now.timestamp(),
+            iat_ms: now.timestamp_millis(),
+            exp: access_token_expires_at.timestamp(),
+            jti: random_token(TOKEN_ID_BYTES),
+            sid: record.family.clone(),
+        })?;
+
+        Ok(TokenPair {
+            access_token,
+            access_token_expires_at,
+            refresh_token,
+            refresh_token_expires_at: record.expires_at,
+        })
+    }
+}
+
+impl TokenServiceTrait for TokenService {
+    /// Verifies the credentials of the user and issues a token pair in a new family.
+    fn issue_tokens(
+        &self,
+        ctx: AppContext,
+        email: String,
+        password: String,
+        scopes: Vec<String>,
+    ) -> Result<TokenPair, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let user = verify_password(ctx, &mut tx, &self.hasher, email, password)?;
+
+        let pair =
+            self.create_token_pair(&mut tx, user.id, random_token(TOKEN_ID_BYTES), scopes)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(pair)
+    }
+
+    /// Rotates a refresh token.
+    fn refresh_tokens(&self, _ctx: AppContext, refresh_token: String) -> Result<TokenPair, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let token_hash = sha256_hex(refresh_token.as_bytes());
+
+        let res = match find_refresh_token_by_hash(&mut tx, token_hash)? {
+            Some(record) => rotate_refresh_token(&mut tx, &record).and_then(|_| {
+                self.create_token_pair(&mut tx, record.user_id, record.family, record.scopes)
+            }),
+            None => Err(Error::new(
+                ErrorCode::EUNAUTHORIZED,
+                "Invalid refresh token".to_string(),
+            )),
+        };
+
+        // The revocation of a reused token family must be stored even if the rotation fails.
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        res
+    }
+
+    /// Revokes the family of a refresh token.
+    fn revoke_tokens(&self, _ctx: AppContext, refresh_token: String) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        if let Some(record) =
+            find_refresh_token_by_hash(&mut tx, sha256_hex(refresh_token.as_bytes()))?
+        {
+            revoke_refresh_token_family(&mut tx, &record.family)?;
+        }
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Verifies an access token and finds its user.
+    fn verify_access_token(
+        &self,
+        ctx: AppContext,
+        access_token: String,
+    ) -> Result<(AccessToken, User), Error> {
+        let claims = self.signer.verify(&access_token)?;
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let user = match find_user_by_id(ctx, &mut tx, claims.sub) {
+            Ok(user) => user,
+            Err(error) if error.code == ErrorCode::ENOTFOUND => {
+                return Err(Error::new(
+                    ErrorCode::EUNAUTHORIZED,
+                    "Invalid access token".to_string(),
+                ))
+            }
+            Err(error) => return Err(error),
+        };
+
+        let access_token = AccessToken {
+            user_id: claims.sub,
+            session_id: claims.sid,
+            scopes: claims.scopes,
+            issued_at: match claims.iat_ms {
+                0 => Utc.timestamp_opt(claims.iat, 0).single(),
+                iat_ms => Utc.timestamp_millis_opt(iat_ms).single(),
+            }
+            .unwrap_or_default(),
+            expires_at: Utc
+                .timestamp_opt(claims.exp, 0)
+                .single()
+                .unwrap_or_default(),
+        };
+
+        if user.is_session_revoked(&access_token.session_id, access_token.issued_at) {
+            return Err(Error::new(
+                ErrorCode::EUNAUTHORIZED,
+                "Access token revoked".to_string(),
+            ));
+        }
+
+        Ok((access_token, user))
+    }
+}
+
+/// insert_refresh_token inserts a new refresh token into the database.
+fn insert_refresh_token(tx: &mut Transaction, token: &mut RefreshToken) -> Result<(), Error> {
+    let row = tx
+        .query_one(
+            insert_refresh_token_sql!(),
+            insert_refresh_token_params!(token),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    token.id = row.get(0);
+
+    Ok(())
+}
+
+/// find_refresh_token_by_hash returns the refresh token with the given hash, locking it until the end of the transaction.
+fn find_refresh_token_by_hash(
+    tx: &mut Transaction,
+    token_hash: String,
+) -> Result<Option<RefreshToken>, Error> {
+    let row = tx
+        .query_opt(select_refresh_token_by_hash_sql!(), &[&token_hash])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(row.map(|row| RefreshToken {
+        id: row.get(0),
+        user_id: row.get(1),
+        family: row.get(2),
+        token_hash: row.get(3),
+        scopes: row.get(4),
+        created_at: row.get(5),
+        expires_at: row.get(6),
+        used_at: row.get(7),
+        revoked_at: row.get(8),
+    }))
+}
+
+/// rotate_refresh_token marks the refresh token as used.
+///
+/// Returns EUNAUTHORIZED if the token is revoked or expired.
+///
+/// Returns EUNAUTHORIZED if the token was already used, revoking its whole family.
+fn rotate_refresh_token(tx: &mut Transaction, token: &RefreshToken) -> Result<(), Error> {
+    if token.used_at.is_some() {
+        revoke_refresh_token_family(tx, &token.family)?;
+
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "Refresh token reuse detected".to_string(),
+        ));
+    }
+
+    if token.revoked_at.is_some() {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "Refresh token revoked".to_string(),
+        ));
+    }
+
+    if token.expires_at <= Utc::now() {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "Refresh token expired".to_string(),
+        ));
+    }
+
+    tx.execute(update_refresh_token_used_sql!(), &[&Utc::now(), &token.id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// revoke_refresh_token_family revokes every refresh token of the family.
+fn revoke_refresh_token_family(tx: &mut Transaction, family: &str) -> Result<(), Error> {
+    tx.execute(revoke_refresh_token_family_sql!(), &[&Utc::now(), &family])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_app::context::Context;
+    use openmusicgang_service::token_service::with_access_token;
+    use openmusicgang_service::user_service::UserService as UserServiceTrait;
+
+    use crate::test_utils::{must_lock_db, must_open_db, must_truncate_table};
+    use crate::user::UserService;
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection, truncate tables and create a user.
+    /// 2) issue tokens with a wrong password, error should be EUNAUTHORIZED.
+    /// 3) issue tokens and verify the access token, the user should be returned.
+    /// 4) verify an access token signed with another key, error should be EUNAUTHORIZED.
+    /// 5) refresh the tokens, a new pair should be issued.
+    /// 6) reuse the rotated refresh token, error should be EUNAUTHORIZED and the family revoked.
+    /// 7) issue tokens and revoke them, refreshing should be EUNAUTHORIZED.
+    /// 8) refresh an expired refresh token, error should be EUNAUTHORIZED.
+    /// 9) reset the password of the user, the tokens issued before should be EUNAUTHORIZED and the ones issued right after valid.
+    /// 10) change the password from a session, the other sessions should be EUNAUTHORIZED and this one kept.
+    #[test]
+    fn test_token_service() {
+        // 1) open database connection, truncate tables and create a user.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        must_truncate_table(&mut db, "users");
+
+        let db = Arc::new(Mutex::new(db));
+
+        let hasher = PasswordHasher::new(64, 1, 1).unwrap();
+        let signer = JwtSigner::hs256("secret").unwrap();
+
+        let user_service = UserService::new(
+            Arc::clone(&db),
+            hasher.clone(),
+            86400,
+            60,
+            "http://localhost/verify-email?token=".to_string(),
+            3600,
+            "http://localhost/reset-password?token=".to_string(),
+        );
+        let token_service =
+            TokenService::new(Arc::clone(&db), hasher.clone(), signer.clone(), 60, 3600);
+
+        let mut user = User::new();
+        user.name = "Bob Smith".to_string();
+        user.email = "bob.smith@test.com".to_string();
+        user.password = Some("password".to_string());
+
+        user_service
+            .create_user(Context::background(), &mut user)
+            .unwrap();
+
+        // 2) issue tokens with a wrong password, error should be EUNAUTHORIZED.
+        let res = token_service.issue_tokens(
+            Context::background(),
+            "bob.smith@test.com".to_string(),
+            "wrong password".to_string(),
+            vec![],
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 3) issue tokens and verify the access token, the user should be returned.
+        let pair = token_service
+            .issue_tokens(
+                Context::background(),
+                "bob.smith@test.com".to_string(),
+                "password".to_string(),
+                vec!["songs:read".to_string()],
+            )
+            .unwrap();
+
+        let (access_token, found) = token_service
+            .verify_access_token(Context::background(), pair.access_token.clone())
+            .unwrap();
+        assert_eq!(access_token.user_id, user.id);
+        assert_eq!(access_token.scopes, vec!["songs:read".to_string()]);
+        assert_eq!(found.id, user.id);
+
+        let ctx = with_access_token(
+            &token_service,
+            Context::background(),
+            pair.access_token.clone(),
+        );
+        assert!(ctx.is_ok());
+
+        let ctx = ctx.unwrap();
+        assert_eq!(Context::user_id_from_context(ctx.clone()), user.id);
+        assert!(Context::session_id_from_context(ctx).is_some());
+
+        // 4) verify an access token signed with another key, error should be EUNAUTHORIZED.
+        let another = JwtSigner::hs256("another secret").unwrap();
+        let forged = another
+            .sign(&Claims {
+                sub: user.id,
+                scopes: vec![],
+                iat: Utc::now().timestamp(),
+                iat_ms: Utc::now().timestamp_millis(),
+                exp: Utc::now().timestamp() + 60,
+                jti: "jti".to_string(),
+                sid: "sid".to_string(),
+            })
+            .unwrap();
+
+        let res = token_service.verify_access_token(Context::background(), forged);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 5) refresh the tokens, a new pair should be issued.
+        let rotated = token_service
+            .refresh_tokens(Context::background(), pair.refresh_token.clone())
+            .unwrap();
+        assert_ne!(rotated.refresh_token, pair.refresh_token);
+
+        let (access_token, _) = token_service
+            .verify_access_token(Context::background(), rotated.access_token.clone())
+            .unwrap();
+        assert_eq!(access_token.scopes, vec!["songs:read".to_string()]);
+
+        // 6) reuse the rotated refresh token, error should be EUNAUTHORIZED and the family revoked.
+        let res = token_service.refresh_tokens(Context::background(), pair.refresh_token.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let res = token_service.refresh_tokens(Context::background(), rotated.refresh_token);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 7) issue tokens and revoke them, refreshing should be EUNAUTHORIZED.
+        let pair = token_service
+            .issue_tokens(
+                Context::background(),
+                "bob.smith@test.com".to_string(),
+                "password".to_string(),
+                vec![],
+            )
+            .unwrap();
+
+        assert!(token_service
+            .revoke_tokens(Context::background(), pair.refresh_token.clone())
+            .is_ok());
+
+        let res = token_service.refresh_tokens(Context::background(), pair.refresh_token);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 8) refresh an expired refresh token, error should be EUNAUTHORIZED.
+        let expired_token_service =
+            TokenService::new(Arc::clone(&db), hasher.clone(), signer.clone(), 60, 0);
+
+        let pair = expired_token_service
+            .issue_tokens(
+                Context::background(),
+                "bob.smith@test.com".to_string(),
+                "password".to_string(),
+                vec![],
+            )
+            .unwrap();
+
+        let res = expired_token_service.refresh_tokens(Context::background(), pair.refresh_token);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 9) reset the password of the user, the tokens issued before should be EUNAUTHORIZED and the ones issued right after valid.
+        let pair = token_service
+            .issue_tokens(
+                Context::background(),
+                "bob.smith@test.com".to_string(),
+                "password".to_string(),
+                vec![],
+            )
+            .unwrap();
+
+        // Access tokens carry whole seconds, this one is issued in the second of the reset.
+        let access_token = signer
+            .sign(&Claims {
+                sub: user.id,
+                scopes: vec![],
+                iat: Utc::now().timestamp(),
+                iat_ms: Utc::now().timestamp_millis(),
+                exp: Utc::now().timestamp() + 60,
+                jti: "jti".to_string(),
+                sid: "sid".to_string(),
+            })
+            .unwrap();
+
+        assert!(token_service
+            .verify_access_token(Context::background(), access_token.clone())
+            .is_ok());
+
+        user_service
+            .request_password_reset(Context::background(), "bob.smith@test.com".to_string())
+            .unwrap();
+
+        let reset_token: String = {
+            let mut mutex_db = db.lock().unwrap();
+            let mut tx = mutex_db.begin_tx().unwrap();
+            let text: String = tx
+                .query_one(
+                    "SELECT text_body FROM email_outbox WHERE recipient = $1 ORDER BY id DESC LIMIT 1",
+                    &[&"bob.smith@test.com"],
+                )
+                .unwrap()
+                .get(0);
+            let start = text.find("token=").unwrap() + "token=".len();
+            text[start..start + 64].to_string()
+        };
+
+        user_service
+            .reset_password(
+                Context::background(),
+                reset_token,
+                "new password".to_string(),
+            )
+            .unwrap();
+
+        let res = token_service.verify_access_token(Context::background(), access_token);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let res = token_service.refresh_tokens(Context::background(), pair.refresh_token);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let pair = token_service
+            .issue_tokens(
+                Context::background(),
+                "bob.smith@test.com".to_string(),
+                "new password".to_string(),
+                vec![],
+            )
+            .unwrap();
+        assert!(token_service
+            .verify_access_token(Context::background(), pair.access_token)
+            .is_ok());
+
+        // 10) change the password from a session, the other sessions should be EUNAUTHORIZED and this one kept.
+        let mut pairs = vec![];
+        for _ in 0..2 {
+            pairs.push(
+                token_service
+                    .issue_tokens(
+                        Context::background(),
+                        "bob.smith@test.com".to_string(),
+                        "new password".to_string(),
+                        vec![],
+                    )
+                    .unwrap(),
+            );
+        }
+        let (kept, other) = (&pairs[0], &pairs[1]);
+
+        let ctx = with_access_token(
+            &token_service,
+            Context::background(),
+            kept.access_token.clone(),
+        )
+        .unwrap();
+
+        user_service
+            .change_password(
+                ctx,
+                "new password".to_string(),
+                "newer password".to_string(),
+            )
+            .unwrap();
+
+        let res =
+            token_service.verify_access_token(Context::background(), other.access_token.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let res = token_service.refresh_tokens(Context::background(), other.refresh_token.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        assert!(token_service
+            .verify_access_token(Context::background(), kept.access_token.clone())
+            .is_ok());
+
+        let rotated = token_service
+            .refresh_tokens(Context::background(), kept.refresh_token.clone())
+            .unwrap();
+        assert!(token_service
+            .verify_access_token(Context::background(), rotated.access_token)
+            .is_ok());
+    }
+}
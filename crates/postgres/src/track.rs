@@ -0,0 +1,1408 @@
+use std::collections::HashSet;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+
+use openmusicgang_app::context::AppContext;
+use openmusicgang_audio::analysis::analyze_audio;
+use openmusicgang_audio::mixdown::{render_mixdown, MixdownTrack};
+use openmusicgang_audio::probe::probe_audio;
+use openmusicgang_audio::waveform::{
+    decode_waveform, encode_waveform, generate_waveform_and_loudness,
+};
+use openmusicgang_entity::activity::{Activity, ActivityKind};
+use openmusicgang_entity::analysis::AudioAnalysis;
+use openmusicgang_entity::audio::AudioInfo;
+use openmusicgang_entity::loudness::Loudness;
+use openmusicgang_entity::membership::GangRole;
+use openmusicgang_entity::mixdown::Mixdown;
+use openmusicgang_entity::song::Song;
+use openmusicgang_entity::track::Track;
+use openmusicgang_entity::waveform::Waveform;
+use openmusicgang_entity::Validable;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::blob_store::BlobStore;
+use openmusicgang_service::track_service::{
+    TrackFilter, TrackService as TrackServiceTrait, TrackUpdate,
+};
+use postgres::types::ToSql;
+use postgres::{Row, Transaction};
+
+use crate::activity::record_activity;
+use crate::membership::{find_membership, require_role};
+use crate::postgres::DB;
+use crate::song::{find_song_by_id, lock_song, song_revision_number};
+use crate::user::require_verified_user;
+use crate::{
+    delete_track_params, delete_track_sql, format_limit_offset, insert_track_params,
+    insert_track_sql, select_tracks_sql, update_track_params, update_track_sql, where_condition_eq,
+};
+
+/// TrackService is a struct that implements the TrackServiceTrait for the postgres crate.
+///
+/// The audio blobs of the tracks are read from the blob store to be probed when they are set,
+/// and their waveforms are stored in it.
+pub struct TrackService {
+    db: Arc<Mutex<DB>>,
+    blob_store: Arc<dyn BlobStore + Send + Sync>,
+}
+
+impl TrackService {
+    /// Create a new TrackService struct
+    pub fn new(db: Arc<Mutex<DB>>, blob_store: Arc<dyn BlobStore + Send + Sync>) -> TrackService {
+        TrackService { db, blob_store }
+    }
+
+    /// Returns the properties of the audio blob with the given key.
+    ///
+    /// Returns EINVALID if the blob does not exist or is not a supported audio file.
+    fn probe_blob(&self, blob_key: &str) -> Result<AudioInfo, Error> {
+        let reader = match self.blob_store.get_blob(blob_key, None) {
+            Ok(reader) => reader,
+            Err(error) if error.code == ErrorCode::ENOTFOUND => {
+                return Err(Error::new(
+                    ErrorCode::EINVALID,
+                    "blob_key must reference an uploaded blob".to_string(),
+                ))
+            }
+            Err(error) => return Err(error),
+        };
+
+        probe_audio(reader)
+    }
+
+    /// Returns the properties, the key of the stored waveform and the loudness of the audio blob with the given key.
+    ///
+    /// The audio is decoded once to generate its waveform and measure its loudness.
+    ///
+    /// Returns EINVALID if the blob does not exist or if the audio cannot be decoded.
+    fn process_blob(&self, blob_key: &str) -> Result<(AudioInfo, String, Option<Loudness>), Error> {
+        let audio = self.probe_blob(blob_key)?;
+
+        let (waveform, loudness) =
+            generate_waveform_and_loudness(self.blob_store.get_blob(blob_key, None)?)?;
+
+        let blob = self
+            .blob_store
+            .put_blob(&mut encode_waveform(&waveform).as_slice())?;
+
+        Ok((audio, blob.key, loudness))
+    }
+}
+
+impl TrackServiceTrait for TrackService {
+    /// Create a new track.
+    fn create_track(&self, ctx: AppContext, track: &mut Track) -> Result<(), Error> {
+        track.validate()?;
+
+        {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on database".to_string(),
+                )
+            })?;
+
+            let mut tx = mutex_db.begin_tx()?;
+
+            authorize_track_upload(ctx.clone(), &mut tx, track.song_id)?;
+        }
+
+        let (audio, waveform_key, loudness) = self.process_blob(&track.blob_key)?;
+        track.audio = Some(audio);
+        track.waveform_key = Some(waveform_key);
+        track.loudness = loudness;
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        create_track(ctx, &mut tx, track)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Deletes a track.
+    fn delete_track(&self, ctx: AppContext, id: i64) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        delete_track(ctx, &mut tx, id)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Updates a track.
+    fn update_track(&self, ctx: AppContext, id: i64, track: TrackUpdate) -> Result<Track, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let track = update_track(ctx, &mut tx, id, track)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(track)
+    }
+
+    /// Replaces the audio of a track.
+    fn replace_track_blob(
+        &self,
+        ctx: AppContext,
+        id: i64,
+        blob_key: String,
+    ) -> Result<Track, Error> {
+        {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on database".to_string(),
+                )
+            })?;
+
+            let mut tx = mutex_db.begin_tx()?;
+
+            authorize_track_replacement(ctx.clone(), &mut tx, id)?;
+        }
+
+        let (audio, waveform_key, loudness) = self.process_blob(&blob_key)?;
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let track = replace_track_blob(ctx, &mut tx, id, blob_key, audio, waveform_key, loudness)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(track)
+    }
+
+    /// Sets the order of the tracks of a song revision.
+    fn reorder_tracks(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        track_ids: Vec<i64>,
+    ) -> Result<Vec<Track>, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let tracks = reorder_tracks(ctx, &mut tx, song_id, revision, track_ids)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(tracks)
+    }
+
+    /// Get a track by id.
+    fn find_track_by_id(&self, ctx: AppContext, id: i64) -> Result<Track, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_track_by_id(ctx, &mut tx, id).map(|(track, _)| track)
+    }
+
+    /// Returns the waveform of a track, read from the blob store.
+    fn find_track_waveform(&self, ctx: AppContext, id: i64) -> Result<Waveform, Error> {
+        let track = self.find_track_by_id(ctx, id)?;
+
+        let waveform_key = match track.waveform_key {
+            Some(waveform_key) => waveform_key,
+            None => {
+                return Err(Error::new(
+                    ErrorCode::ENOTFOUND,
+                    "Track has no waveform".to_string(),
+                ))
+            }
+        };
+
+        let mut buf = vec![];
+
+        self.blob_store
+            .get_blob(&waveform_key, None)?
+            .read_to_end(&mut buf)
+            .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+        decode_waveform(&buf)
+    }
+
+    /// Estimates the tempo and key of a track, decoded from its blob.
+    fn analyze_track(&self, ctx: AppContext, id: i64) -> Result<AudioAnalysis, Error> {
+        let track = self.find_track_by_id(ctx, id)?;
+
+        analyze_audio(self.blob_store.get_blob(&track.blob_key, None)?)
+    }
+
+    /// Renders the mixdown of a song revision and stores it in the blob store.
+    fn render_mixdown(
+        &self,
+        ctx: AppContext,
+        song_id: i64,
+        revision: i32,
+        target_loudness: Option<f64>,
+    ) -> Result<Mixdown, Error> {
+        let tracks = {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on database".to_string(),
+                )
+            })?;
+
+            let mut tx = mutex_db.begin_tx()?;
+
+            find_mixdown_tracks(ctx, &mut tx, song_id, revision)?
+        };
+
+        let mut mixdown_tracks = vec![];
+
+        for track in tracks {
+            mixdown_tracks.push(MixdownTrack {
+                reader: self.blob_store.get_blob(&track.blob_key, None)?,
+                gain: track.gain,
+                pan: track.pan,
+                muted: track.muted,
+                solo: track.solo,
+                offset: track.offset,
+            });
+        }
+
+        let (wav, loudness) = render_mixdown(mixdown_tracks, target_loudness)?;
+
+        Ok(Mixdown {
+            blob: self.blob_store.put_blob(&mut wav.as_slice())?,
+            loudness,
+        })
+    }
+
+    /// Returns a vector of tracks based on passed filters, also returns the total number of tracks.
+    fn find_tracks(
+        &self,
+        ctx: AppContext,
+        filters: TrackFilter,
+    ) -> Result<(Vec<Track>, i64), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        find_tracks(ctx, &mut tx, filters)
+    }
+}
+
+/// authorize_track_upload checks that the user of the context can add a track to a song, before its audio is processed.
+///
+/// Returns ENOTFOUND if the song does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot add tracks, or if
+/// their email address is not verified.
+fn authorize_track_upload(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    song_id: i64,
+) -> Result<(), Error> {
+    let song = find_song_by_id(ctx.clone(), tx, song_id)?;
+    require_role(ctx.clone(), tx, song.gang_id, GangRole::Member)?;
+    require_verified_user(ctx, tx, "upload audio")?;
+
+    Ok(())
+}
+
+/// create_track inserts a new track at the end of a song revision, with the properties of its audio already probed.
+///
+/// Handles the create_track Business Logic.
+///
+/// Returns ENOTFOUND if the song does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot add tracks, or if
+/// their email address is not verified.
+///
+/// Returns EINVALID if the track is invalid, the revision does not exist or the performer is not a member of the gang.
+///
+/// Returns ECONFLICT if the revision is not the latest revision of the song.
+fn create_track(ctx: AppContext, tx: &mut Transaction, track: &mut Track) -> Result<(), Error> {
+    lock_song(tx, track.song_id)?;
+
+    let song = find_song_by_id(ctx.clone(), tx, track.song_id)?;
+    let member = require_role(ctx.clone(), tx, song.gang_id, GangRole::Member)?;
+    require_verified_user(ctx.clone(), tx, "upload audio")?;
+
+    track.revision = require_latest_revision(&song, track.revision)?;
+
+    track.created_by = Some(member.user_id);
+    track.created_at = Utc::now();
+    track.updated_at = track.created_at;
+
+    track.validate()?;
+
+    validate_performer(ctx.clone(), tx, &song, track.performer_id)?;
+
+    let row = tx
+        .query_one(
+            insert_track_sql!(),
+            insert_track_params!(track, AudioColumns::new(&track.audio, &track.loudness)),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    track.id = row.get(0);
+    track.position = row.get(1);
+
+    let mut activity = Activity::new(ActivityKind::TrackUploaded, member.user_id, song.gang_id);
+    activity.song_id = Some(song.id);
+    activity.revision = Some(track.revision);
+    activity.subject_id = Some(track.id);
+
+    record_activity(
+        ctx,
+        tx,
+        &mut activity,
+        &format!(
+            "uploaded a new {} take to {}",
+            track.instrument.to_lowercase(),
+            song.title
+        ),
+    )?;
+
+    Ok(())
+}
+
+/// require_latest_revision returns the number of a revision of a song if it is the latest one, the latest revision if none is given.
+///
+/// Returns EINVALID if the revision does not exist.
+///
+/// Returns ECONFLICT if the revision is not the latest revision of the song, past revisions cannot be changed.
+fn require_latest_revision(song: &Song, revision: i32) -> Result<i32, Error> {
+    let revision = song_revision_number(song, revision)?;
+
+    if revision != song.revision {
+        return Err(Error::new(
+            ErrorCode::ECONFLICT,
+            format!(
+                "Only the latest revision {} of the song can be changed",
+                song.revision
+            ),
+        ));
+    }
+
+    Ok(revision)
+}
+
+/// validate_performer checks that the performer of a track is a member of the gang of the song.
+/// Returns EINVALID if the performer is not a member of the gang.
+fn validate_performer(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    song: &Song,
+    performer_id: Option<i64>,
+) -> Result<(), Error> {
+    let performer_id = match performer_id {
+        Some(performer_id) => performer_id,
+        None => return Ok(()),
+    };
+
+    match find_membership(ctx, tx, song.gang_id, performer_id) {
+        Ok(_) => Ok(()),
+        Err(error) if error.code == ErrorCode::ENOTFOUND => Err(Error::new(
+            ErrorCode::EINVALID,
+            "performer must be a member of the gang".to_string(),
+        )),
+        Err(error) => Err(error),
+    }
+}
+
+/// find_editable_track returns a track the user of the context is allowed to change, with its song.
+///
+/// Returns ENOTFOUND if the track does not exist.
+///
+/// Returns EFORBIDDEN unless the user of the context is an admin of the gang, or a member who added or performs the track.
+///
+/// Returns ECONFLICT if the track does not belong to the latest revision of its song.
+fn find_editable_track(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<(Track, Song), Error> {
+    let (track, song) = find_track_by_id(ctx.clone(), tx, id)?;
+    let member = require_role(ctx, tx, song.gang_id, GangRole::Member)?;
+
+    let owns_track =
+        track.created_by == Some(member.user_id) || track.performer_id == Some(member.user_id);
+
+    if member.role < GangRole::Admin && !owns_track {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            "You do not have permission to change this track".to_string(),
+        ));
+    }
+
+    require_latest_revision(&song, track.revision)?;
+
+    Ok((track, song))
+}
+
+/// delete_track deletes a track from the database.
+/// Handles the delete_track Business Logic.
+/// Returns the errors of find_editable_track.
+fn delete_track(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<(), Error> {
+    find_editable_track(ctx, tx, id)?;
+
+    tx.execute(delete_track_sql!(), delete_track_params!(id))
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// update_track updates a track in the database.
+///
+/// Handles the update_track Business Logic.
+///
+/// Returns the errors of find_editable_track.
+///
+/// Returns EINVALID if the track is invalid or the performer is not a member of the gang.
+fn update_track(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+    update: TrackUpdate,
+) -> Result<Track, Error> {
+    let (mut track, song) = find_editable_track(ctx.clone(), tx, id)?;
+
+    if let Some(instrument) = update.instrument {
+        track.instrument = instrument;
+    }
+
+    if let Some(performer_id) = update.performer_id {
+        track.performer_id = Some(performer_id);
+    }
+
+    if let Some(gain) = update.gain {
+        track.gain = gain;
+    }
+
+    if let Some(pan) = update.pan {
+        track.pan = pan;
+    }
+
+    if let Some(muted) = update.muted {
+        track.muted = muted;
+    }
+
+    if let Some(solo) = update.solo {
+        track.solo = solo;
+    }
+
+    if let Some(offset) = update.offset {
+        track.offset = offset;
+    }
+
+    track.updated_at = Utc::now();
+
+    track.validate()?;
+
+    validate_performer(ctx, tx, &song, track.performer_id)?;
+
+    tx.execute(
+        update_track_sql!(),
+        update_track_params!(track, AudioColumns::new(&track.audio, &track.loudness)),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(track)
+}
+
+/// authorize_track_replacement checks that the user of the context can replace the audio of a track, before the new
+/// audio is processed.
+///
+/// Returns the errors of find_editable_track.
+///
+/// Returns EFORBIDDEN if the email address of the user of the context is not verified.
+fn authorize_track_replacement(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<(), Error> {
+    find_editable_track(ctx.clone(), tx, id)?;
+    require_verified_user(ctx, tx, "upload audio")?;
+
+    Ok(())
+}
+
+/// replace_track_blob replaces the audio blob of a track, the properties of its audio, its waveform and its loudness.
+///
+/// Handles the replace_track_blob Business Logic.
+///
+/// Returns the errors of find_editable_track.
+///
+/// Returns EFORBIDDEN if the email address of the user of the context is not verified.
+///
+/// Returns EINVALID if the blob key is empty.
+fn replace_track_blob(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+    blob_key: String,
+    audio: AudioInfo,
+    waveform_key: String,
+    loudness: Option<Loudness>,
+) -> Result<Track, Error> {
+    let (mut track, _) = find_editable_track(ctx.clone(), tx, id)?;
+    require_verified_user(ctx, tx, "upload audio")?;
+
+    track.blob_key = blob_key;
+    track.audio = Some(audio);
+    track.waveform_key = Some(waveform_key);
+    track.loudness = loudness;
+    track.updated_at = Utc::now();
+
+    track.validate()?;
+
+    tx.execute(
+        update_track_sql!(),
+        update_track_params!(track, AudioColumns::new(&track.audio, &track.loudness)),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(track)
+}
+
+/// reorder_tracks sets the positions of the tracks of a song revision in the given order.
+///
+/// Handles the reorder_tracks Business Logic.
+///
+/// Returns ENOTFOUND if the song does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot reorder tracks.
+///
+/// Returns EINVALID if the revision does not exist or the ids are not exactly the tracks of the song revision.
+///
+/// Returns ECONFLICT if the revision is not the latest revision of the song.
+fn reorder_tracks(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    song_id: i64,
+    revision: i32,
+    track_ids: Vec<i64>,
+) -> Result<Vec<Track>, Error> {
+    lock_song(tx, song_id)?;
+
+    let song = find_song_by_id(ctx.clone(), tx, song_id)?;
+    require_role(ctx, tx, song.gang_id, GangRole::Member)?;
+
+    let revision = require_latest_revision(&song, revision)?;
+
+    let filters = TrackFilter {
+        song_id,
+        revision: Some(revision),
+        ..Default::default()
+    };
+
+    let (tracks, _) = select_tracks(tx, filters)?;
+
+    let current: HashSet<i64> = tracks.iter().map(|track| track.id).collect();
+    let requested: HashSet<i64> = track_ids.iter().copied().collect();
+
+    if track_ids.len() != tracks.len() || current != requested {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "track_ids must contain every track of the song revision once".to_string(),
+        ));
+    }
+
+    let mut reordered = vec![];
+
+    for (index, id) in track_ids.into_iter().enumerate() {
+        let mut track = match tracks.iter().find(|track| track.id == id) {
+            Some(track) => track.clone(),
+            None => continue,
+        };
+
+        track.position = index as i32 + 1;
+        track.updated_at = Utc::now();
+
+        tx.execute(
+            update_track_sql!(),
+            update_track_params!(track, AudioColumns::new(&track.audio, &track.loudness)),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+        reordered.push(track);
+    }
+
+    Ok(reordered)
+}
+
+/// find_mixdown_tracks returns the tracks of a song revision to render in a mixdown.
+///
+/// Handles the render_mixdown Business Logic.
+///
+/// Returns ENOTFOUND if the song does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang, guests cannot render mixdowns.
+///
+/// Returns EINVALID if the revision does not exist or has no tracks.
+fn find_mixdown_tracks(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    song_id: i64,
+    revision: i32,
+) -> Result<Vec<Track>, Error> {
+    let song = find_song_by_id(ctx.clone(), tx, song_id)?;
+    require_role(ctx, tx, song.gang_id, GangRole::Member)?;
+
+    let revision = if revision == 0 {
+        song.revision
+    } else {
+        revision
+    };
+
+    if revision < 1 || revision > song.revision {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            format!("Song has no revision {}", revision),
+        ));
+    }
+
+    let filters = TrackFilter {
+        song_id,
+        revision: Some(revision),
+        ..Default::default()
+    };
+
+    let (tracks, _) = select_tracks(tx, filters)?;
+
+    if tracks.is_empty() {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            format!("Song revision {} has no tracks", revision),
+        ));
+    }
+
+    Ok(tracks)
+}
+
+/// find_track_by_id returns a track by id, with its song.
+///
+/// Handles the find_track_by_id Business Logic.
+///
+/// Returns ENOTFOUND if the track does not exist.
+///
+/// Returns EFORBIDDEN if the user of the context is not a member of the gang of the song.
+pub(crate) fn find_track_by_id(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<(Track, Song), Error> {
+    let query = select_tracks_sql!([where_condition_eq!("id", 1)], "");
+
+    let row = tx
+        .query_opt(query.as_str(), &[&id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let track = match row {
+        Some(row) => track_from_row(&row),
+        None => {
+            return Err(Error::new(
+                ErrorCode::ENOTFOUND,
+                "Track not found".to_string(),
+            ))
+        }
+    };
+
+    let song = find_song_by_id(ctx, tx, track.song_id)?;
+
+    Ok((track, song))
+}
+
+/// find_tracks finds the tracks of a song based on the filters.
+///
+/// Handles the find_tracks Business Logic.
+///
+/// Returns the errors of find_song_by_id.
+fn find_tracks(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    filters: TrackFilter,
+) -> Result<(Vec<Track>, i64), Error> {
+    find_song_by_id(ctx, tx, filters.song_id)?;
+
+    select_tracks(tx, filters)
+}
+
+/// select_tracks selects the tracks matching the filters.
+fn select_tracks(tx: &mut Transaction, filters: TrackFilter) -> Result<(Vec<Track>, i64), Error> {
+    let mut where_conditions = vec![where_condition_eq!("song_id", 1)];
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![&filters.song_id];
+    let mut args_counter = 2;
+
+    if filters.revision.is_some() {
+        where_conditions.push(where_condition_eq!("revision", args_counter));
+        args_counter += 1;
+        args.push(&filters.revision);
+    }
+
+    if filters.instrument.is_some() {
+        where_conditions.push(where_condition_eq!("instrument", args_counter));
+        args_counter += 1;
+        args.push(&filters.instrument);
+    }
+
+    if filters.performer_id.is_some() {
+        where_conditions.push(where_condition_eq!("performer_id", args_counter));
+        args.push(&filters.performer_id);
+    }
+
+    let query = select_tracks_sql!(
+        where_conditions,
+        format_limit_offset!(filters.limit, filters.offset)
+    );
+
+    let rows = tx
+        .query(query.as_str(), &args)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut tracks: Vec<Track> = vec![];
+    let mut tot_results = 0;
+
+    for row in rows {
+        tracks.push(track_from_row(&row));
+        tot_results = row.get(25);
+    }
+
+    Ok((tracks, tot_results))
+}
+
+/// Returns the track of a row selected with select_tracks_sql.
+fn track_from_row(row: &Row) -> Track {
+    Track {
+        id: row.get(0),
+        song_id: row.get(1),
+        revision: row.get(2),
+        blob_key: row.get(3),
+        instrument: row.get(4),
+        performer_id: row.get(5),
+        gain: row.get(6),
+        pan: row.get(7),
+        muted: row.get(8),
+        solo: row.get(9),
+        offset: row.get(10),
+        position: row.get(11),
+        created_by: row.get(12),
+        created_at: row.get(13),
+        updated_at: row.get(14),
+        audio: audio_from_row(row),
+        waveform_key: row.get(21),
+        loudness: loudness_from_row(row),
+    }
+}
+
+/// Returns the audio properties of a row selected with select_tracks_sql, if the track has any.
+fn audio_from_row(row: &Row) -> Option<AudioInfo> {
+    let format: Option<String> = row.get(15);
+    let tags: Option<serde_json::Value> = row.get(20);
+
+    Some(AudioInfo {
+        format: format?.parse().ok()?,
+        frames: row.get::<_, Option<i64>>(16)?,
+        sample_rate: row.get::<_, Option<i32>>(17)?,
+        bit_depth: row.get(18),
+        channels: row.get::<_, Option<i32>>(19)?,
+        tags: tags
+            .and_then(|tags| serde_json::from_value(tags).ok())
+            .unwrap_or_default(),
+    })
+}
+
+/// Returns the loudness of a row selected with select_tracks_sql, if the track has any.
+fn loudness_from_row(row: &Row) -> Option<Loudness> {
+    Some(Loudness {
+        integrated: row.get::<_, Option<f64>>(22)?,
+        range: row.get::<_, Option<f64>>(23)?,
+        true_peak: row.get::<_, Option<f64>>(24)?,
+    })
+}
+
+/// AudioColumns are the values of the audio and loudness columns of a track row, NULL when they are unknown.
+struct AudioColumns {
+    format: Option<String>,
+    frames: Option<i64>,
+    sample_rate: Option<i32>,
+    bit_depth: Option<i32>,
+    channels: Option<i32>,
+    tags: Option<serde_json::Value>,
+    loudness_integrated: Option<f64>,
+    loudness_range: Option<f64>,
+    loudness_true_peak: Option<f64>,
+}
+
+impl AudioColumns {
+    fn new(audio: &Option<AudioInfo>, loudness: &Option<Loudness>) -> Self {
+        AudioColumns {
+            format: audio.as_ref().map(|audio| audio.format.to_string()),
+            frames: audio.as_ref().map(|audio| audio.frames),
+            sample_rate: audio.as_ref().map(|audio| audio.sample_rate),
+            bit_depth: audio.as_ref().and_then(|audio| audio.bit_depth),
+            channels: audio.as_ref().map(|audio| audio.channels),
+            tags: audio.as_ref().map(|audio| {
+                serde_json::Value::Object(
+                    audio
+                        .tags
+                        .iter()
+                        .map(|(name, value)| (name.clone(), value.clone().into()))
+                        .collect(),
+                )
+            }),
+            loudness_integrated: loudness.as_ref().map(|loudness| loudness.integrated),
+            loudness_range: loudness.as_ref().map(|loudness| loudness.range),
+            loudness_true_peak: loudness.as_ref().map(|loudness| loudness.true_peak),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_app::context::Context;
+    use openmusicgang_crypto::random::random_token;
+    use openmusicgang_entity::audio::AudioFormat;
+    use openmusicgang_entity::gang::Gang;
+    use openmusicgang_entity::invitation::Invitation;
+    use openmusicgang_entity::track::MAX_TRACK_OFFSET;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_service::gang_service::GangService as GangServiceTrait;
+    use openmusicgang_service::membership_service::MembershipService as MembershipServiceTrait;
+    use openmusicgang_service::song_service::{SongService as SongServiceTrait, SongUpdate};
+    use openmusicgang_storage::local::BlobStore as LocalBlobStore;
+
+    use crate::gang::GangService;
+    use crate::membership::MembershipService;
+    use crate::song::SongService;
+    use crate::test_utils::{
+        must_create_user, must_exec, must_lock_db, must_open_db, must_put_wav, must_truncate_table,
+    };
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) open database connection.
+    /// 2) truncate tables to start fresh.
+    /// 3) add a track as a guest, error should be EFORBIDDEN even if its blob is missing.
+    /// 4) add tracks, they should belong to the latest revision in order, with the properties, the waveform and the loudness of their audio.
+    /// 5) add a track whose blob is missing or not audio, error should be EINVALID.
+    /// 6) add a track with a performer who is not a member, error should be EINVALID.
+    /// 7) add a track to a previous revision and to a missing revision, error should be ECONFLICT and EINVALID.
+    /// 8) update or replace the blob of a track added by another member, error should be EFORBIDDEN.
+    /// 9) update and replace the blob of a track as its performer, its waveform should be regenerated.
+    /// 10) reorder the tracks, a partial order should be EINVALID and a previous revision ECONFLICT.
+    /// 11) find the tracks of a revision and by instrument.
+    /// 12) render the mixdown of the latest revision, it should be stored as a 24 bits stereo blob with its loudness.
+    /// 13) update and revert the song, the tracks of the source revision should be copied into the new revision and mixed down, past revisions should be ECONFLICT.
+    /// 14) analyze a silent track, it should have no tempo and no key.
+    /// 15) delete a track as an admin.
+    /// 16) add a track as a member whose email is not verified, error should be EFORBIDDEN.
+    #[test]
+    fn test_track_service() {
+        // 1) open database connection.
+        let _lock = must_lock_db();
+        let mut db = must_open_db();
+
+        // 2) truncate tables to start fresh.
+        must_truncate_table(&mut db, "tracks");
+        must_truncate_table(&mut db, "song_revisions");
+        must_truncate_table(&mut db, "songs");
+        must_truncate_table(&mut db, "gang_invitations");
+        must_truncate_table(&mut db, "gang_members");
+        must_truncate_table(&mut db, "gangs");
+        must_truncate_table(&mut db, "users");
+
+        let bob = must_create_user(&mut db, "Bob Smith", "bob.smith@test.com");
+        let john = must_create_user(&mut db, "John Smith", "john.smith@test.com");
+        let steve = must_create_user(&mut db, "Steve Smith", "steve.smith@test.com");
+        let mark = must_create_user(&mut db, "Mark Smith", "mark.smith@test.com");
+
+        let db = Arc::new(Mutex::new(db));
+        let gang_service = GangService::new(Arc::clone(&db));
+        let membership_service = MembershipService::new(Arc::clone(&db), 3600);
+        let song_service = SongService::new(Arc::clone(&db));
+        let root = std::env::temp_dir().join(format!("openmusicgang-{}", random_token(8)));
+        let blob_store = Arc::new(LocalBlobStore::new(&root).unwrap());
+        let track_service = TrackService::new(Arc::clone(&db), blob_store.clone());
+
+        let drums_key = must_put_wav(&blob_store, 48000, 2, 96000, 8192);
+        let bass_key = must_put_wav(&blob_store, 44100, 1, 44100, 0);
+        let bass_take_2_key = must_put_wav(&blob_store, 44100, 1, 88200, 0);
+
+        let ctx = |user: &User| Context::with_user(Context::background(), user.clone());
+
+        let mut gang = Gang::new();
+        gang.name = "The Rolling Bytes".to_string();
+        gang_service.create_gang(ctx(&bob), &mut gang).unwrap();
+
+        for (user, role) in [
+            (&john, GangRole::Member),
+            (&steve, GangRole::Member),
+            (&mark, GangRole::Guest),
+        ] {
+            let mut invitation = Invitation {
+                gang_id: gang.id,
+                user_id: Some(user.id),
+                role,
+                ..Default::default()
+            };
+            membership_service
+                .invite_member(ctx(&bob), &mut invitation)
+                .unwrap();
+            membership_service
+                .accept_invitation(ctx(user), invitation.id)
+                .unwrap();
+        }
+
+        let mut song = Song {
+            gang_id: gang.id,
+            title: "Segfault Blues".to_string(),
+            ..Default::default()
+        };
+        song_service.create_song(ctx(&john), &mut song).unwrap();
+
+        let update = SongUpdate {
+            tempo: Some(120.0),
+            ..Default::default()
+        };
+        let song = song_service
+            .update_song(ctx(&john), song.id, update)
+            .unwrap();
+
+        // 3) add a track as a guest, error should be EFORBIDDEN even if its blob is missing.
+        let mut drums = Track {
+            song_id: song.id,
+            blob_key: drums_key.clone(),
+            instrument: "drums".to_string(),
+            ..Default::default()
+        };
+
+        let res = track_service.create_track(ctx(&mark), &mut drums.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let res = track_service.create_track(
+            ctx(&mark),
+            &mut Track {
+                blob_key: "0".repeat(64),
+                ..drums.clone()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 4) add tracks, they should belong to the latest revision in order, with the properties, the waveform and the loudness of their audio.
+        let res = track_service.create_track(ctx(&john), &mut drums);
+        if let Err(error) = res {
+            panic!("{}", error);
+        }
+        assert_eq!(drums.revision, 2);
+        assert_eq!(drums.position, 1);
+        assert_eq!(drums.created_by, Some(john.id));
+
+        let audio = drums.audio.clone().unwrap();
+        assert_eq!(audio.format, AudioFormat::Wav);
+        assert_eq!(audio.sample_rate, 48000);
+        assert_eq!(audio.channels, 2);
+        assert_eq!(audio.bit_depth, Some(16));
+        assert_eq!(audio.duration(), 2.0);
+
+        let track = track_service
+            .find_track_by_id(ctx(&john), drums.id)
+            .unwrap();
+        assert_eq!(track.audio, drums.audio);
+        assert!(track.waveform_key.is_some());
+        assert_eq!(track.waveform_key, drums.waveform_key);
+        assert!(drums.loudness.clone().unwrap().integrated > -15.0);
+        assert_eq!(track.loudness, drums.loudness);
+
+        let waveform = track_service
+            .find_track_waveform(ctx(&mark), drums.id)
+            .unwrap();
+        assert_eq!(waveform.sample_rate, 48000);
+        assert_eq!(waveform.frames, 96000);
+        assert_eq!(waveform.levels[0].peaks.len(), 375);
+
+        let mut bass = Track {
+            song_id: song.id,
+            blob_key: bass_key.clone(),
+            instrument: "bass".to_string(),
+            performer_id: Some(steve.id),
+            pan: -0.3,
+            ..Default::default()
+        };
+        track_service.create_track(ctx(&john), &mut bass).unwrap();
+        assert_eq!(bass.position, 2);
+
+        // 5) add a track whose blob is missing or not audio, error should be EINVALID.
+        let missing = Track {
+            blob_key: "0".repeat(64),
+            ..bass.clone()
+        };
+        let res = track_service.create_track(ctx(&john), &mut missing.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let not_audio = Track {
+            blob_key: blob_store
+                .put_blob(&mut &b"not an audio file"[..])
+                .unwrap()
+                .key,
+            ..bass.clone()
+        };
+        let res = track_service.create_track(ctx(&john), &mut not_audio.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 6) add a track with a performer who is not a member, error should be EINVALID.
+        let outsider = Track {
+            performer_id: Some(999),
+            ..bass.clone()
+        };
+        let res = track_service.create_track(ctx(&john), &mut outsider.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 7) add a track to a previous revision and to a missing revision, error should be ECONFLICT and EINVALID.
+        let res = track_service.create_track(
+            ctx(&john),
+            &mut Track {
+                revision: 1,
+                ..drums.clone()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        let res = track_service.create_track(
+            ctx(&john),
+            &mut Track {
+                revision: 3,
+                ..drums.clone()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 8) update or replace the blob of a track added by another member, error should be EFORBIDDEN.
+        let update = TrackUpdate {
+            gain: Some(-6.0),
+            muted: Some(true),
+            ..Default::default()
+        };
+
+        let res = track_service.update_track(ctx(&steve), drums.id, update.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let res = track_service.replace_track_blob(ctx(&steve), drums.id, "0".repeat(64));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        // 9) update and replace the blob of a track as its performer, its waveform should be regenerated.
+        let track = track_service
+            .update_track(ctx(&steve), bass.id, update)
+            .unwrap();
+        assert_eq!(track.gain, -6.0);
+        assert!(track.muted);
+
+        let res = track_service.update_track(
+            ctx(&steve),
+            bass.id,
+            TrackUpdate {
+                pan: Some(2.0),
+                ..Default::default()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let res = track_service.update_track(
+            ctx(&steve),
+            bass.id,
+            TrackUpdate {
+                offset: Some(MAX_TRACK_OFFSET + 1),
+                ..Default::default()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let track = track_service
+            .replace_track_blob(ctx(&steve), bass.id, bass_take_2_key.clone())
+            .unwrap();
+        assert_eq!(track.blob_key, bass_take_2_key);
+        assert_eq!(track.audio.unwrap().duration(), 2.0);
+        assert_ne!(track.waveform_key, bass.waveform_key);
+
+        let waveform = track_service
+            .find_track_waveform(ctx(&steve), bass.id)
+            .unwrap();
+        assert_eq!(waveform.frames, 88200);
+
+        // 10) reorder the tracks, a partial order should be EINVALID and a previous revision ECONFLICT.
+        let res = track_service.reorder_tracks(ctx(&steve), song.id, 2, vec![bass.id]);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let res = track_service.reorder_tracks(ctx(&mark), song.id, 2, vec![bass.id, drums.id]);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let res = track_service.reorder_tracks(ctx(&steve), song.id, 1, vec![]);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        let tracks = track_service
+            .reorder_tracks(ctx(&steve), song.id, 2, vec![bass.id, drums.id])
+            .unwrap();
+        let order: Vec<(i64, i32)> = tracks.iter().map(|t| (t.id, t.position)).collect();
+        assert_eq!(order, vec![(bass.id, 1), (drums.id, 2)]);
+
+        // 11) find the tracks of a revision and by instrument.
+        let filters = TrackFilter {
+            song_id: song.id,
+            revision: Some(2),
+            ..Default::default()
+        };
+        let (tracks, total) = track_service
+            .find_tracks(ctx(&mark), filters.clone())
+            .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(tracks[0].id, bass.id);
+        assert_eq!(tracks[0].blob_key, bass_take_2_key);
+        assert_eq!(tracks[0].audio.as_ref().unwrap().frames, 88200);
+        assert_eq!(tracks[1].id, drums.id);
+
+        let (tracks, total) = track_service
+            .find_tracks(
+                ctx(&mark),
+                TrackFilter {
+                    song_id: song.id,
+                    instrument: Some("drums".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(tracks[0].revision, 2);
+
+        let res = track_service.find_tracks(Context::background(), filters);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 12) render the mixdown of the latest revision, it should be stored as a 24 bits stereo blob with its loudness.
+        let res = track_service.render_mixdown(ctx(&mark), song.id, 0, None);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        let res = track_service.render_mixdown(ctx(&john), song.id, 3, None);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let mixdown = track_service
+            .render_mixdown(ctx(&john), song.id, 0, None)
+            .unwrap();
+        assert_eq!(
+            blob_store.stat_blob(&mixdown.blob.key).unwrap().size,
+            mixdown.blob.size
+        );
+
+        let audio = probe_audio(blob_store.get_blob(&mixdown.blob.key, None).unwrap()).unwrap();
+        assert_eq!(audio.sample_rate, 48000);
+        assert_eq!(audio.channels, 2);
+        assert_eq!(audio.bit_depth, Some(24));
+        assert_eq!(audio.duration(), 2.0);
+
+        let again = track_service
+            .render_mixdown(ctx(&john), song.id, 2, None)
+            .unwrap();
+        assert_eq!(again, mixdown);
+
+        let normalized = track_service
+            .render_mixdown(ctx(&john), song.id, 0, Some(-23.0))
+            .unwrap();
+        assert_ne!(normalized.blob.key, mixdown.blob.key);
+        assert!(mixdown.loudness.unwrap().integrated > -15.0);
+        assert!((normalized.loudness.unwrap().integrated + 23.0).abs() < 0.01);
+
+        // 13) update and revert the song, the tracks of the source revision should be copied into the new revision and mixed down, past revisions should be ECONFLICT.
+        let update = SongUpdate {
+            tempo: Some(96.0),
+            ..Default::default()
+        };
+        let updated = song_service
+            .update_song(ctx(&john), song.id, update)
+            .unwrap();
+        assert_eq!(updated.revision, 3);
+
+        let (tracks, total) = track_service
+            .find_tracks(
+                ctx(&mark),
+                TrackFilter {
+                    song_id: song.id,
+                    revision: Some(3),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(tracks[0].blob_key, bass_take_2_key);
+        assert_eq!(tracks[0].position, 1);
+        assert_eq!(tracks[0].performer_id, Some(steve.id));
+        assert_eq!(tracks[0].waveform_key, track.waveform_key);
+        assert_eq!(tracks[1].blob_key, drums_key);
+        assert_eq!(tracks[1].loudness, drums.loudness);
+
+        let latest = track_service
+            .render_mixdown(ctx(&john), song.id, 0, None)
+            .unwrap();
+        assert_eq!(latest.blob, mixdown.blob);
+
+        let res = track_service.update_track(
+            ctx(&steve),
+            bass.id,
+            TrackUpdate {
+                gain: Some(-3.0),
+                ..Default::default()
+            },
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        let res = track_service.delete_track(ctx(&bob), drums.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        track_service
+            .delete_track(ctx(&john), tracks[1].id)
+            .unwrap();
+
+        let reverted = song_service.revert_song(ctx(&john), song.id, 2).unwrap();
+        assert_eq!(reverted.revision, 4);
+
+        let (tracks, total) = track_service
+            .find_tracks(
+                ctx(&mark),
+                TrackFilter {
+                    song_id: song.id,
+                    revision: Some(4),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(tracks[1].blob_key, drums_key);
+
+        let latest_drums = tracks[1].clone();
+
+        // 14) analyze a silent track, it should have no tempo and no key.
+        let res = track_service.analyze_track(Context::background(), bass.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let analysis = track_service.analyze_track(ctx(&steve), bass.id).unwrap();
+        assert_eq!(analysis.tempo, None);
+        assert_eq!(analysis.key, None);
+
+        // 15) delete a track as an admin.
+        let res = track_service.delete_track(ctx(&bob), latest_drums.id);
+        assert!(res.is_ok());
+
+        let res = track_service.find_track_by_id(ctx(&bob), latest_drums.id);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        // 16) add a track as a member whose email is not verified, error should be EFORBIDDEN.
+        must_exec(
+            &mut db.lock().unwrap(),
+            "UPDATE users SET email_verified_at = NULL WHERE id = $1",
+            &[&steve.id],
+        );
+
+        let mut bass_take_3 = Track {
+            song_id: song.id,
+            blob_key: bass_take_2_key.clone(),
+            instrument: "bass".to_string(),
+            ..Default::default()
+        };
+
+        let res = track_service.create_track(ctx(&steve), &mut bass_take_3);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EFORBIDDEN);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,32 +1,99 @@
 use std::sync::{Arc, Mutex};
 
 use chrono::prelude::*;
+use chrono::Duration;
 
 use openmusicgang_app::context::{AppContext, Context};
-use openmusicgang_entity::user::User;
+use openmusicgang_crypto::digest::sha256_hex;
+use openmusicgang_crypto::password::PasswordHasher;
+use openmusicgang_crypto::random::random_token;
+use openmusicgang_entity::email::EmailTemplate;
+use openmusicgang_entity::password_reset::PasswordReset;
+use openmusicgang_entity::user::{is_email_address, normalize_email, User};
+use openmusicgang_entity::verification::EmailVerification;
 use openmusicgang_entity::Validable;
 use openmusicgang_err::error::{Error, ErrorCode};
 use openmusicgang_service::user_service::{
     UserFilter, UserService as UserServiceTrait, UserUpdate,
 };
+use postgres::error::SqlState;
 use postgres::types::ToSql;
-use postgres::Transaction;
+use postgres::{Row, Transaction};
 
+use crate::email::queue_email;
 use crate::postgres::DB;
 use crate::{
-    delete_user_params, delete_user_sql, format_limit_offset, insert_user_params, insert_user_sql,
-    select_users_sql, update_users_params, update_users_sql, where_condition_eq,
+    delete_user_params, delete_user_sql, expire_email_verifications_sql,
+    expire_password_resets_sql, format_limit_offset, insert_email_verification_params,
+    insert_email_verification_sql, insert_password_reset_params, insert_password_reset_sql,
+    insert_user_params, insert_user_sql, revoke_other_refresh_tokens_sql,
+    revoke_user_refresh_tokens_sql, select_email_verification_by_hash_sql,
+    select_last_email_verification_at_sql, select_last_password_reset_at_sql,
+    select_password_reset_by_hash_sql, select_user_id_by_email_sql, select_users_sql,
+    update_email_verification_used_sql, update_password_reset_used_sql, update_user_email_sql,
+    update_user_email_verified_sql, update_user_password_params,
+    update_user_password_sessions_params, update_user_password_sessions_sql,
+    update_user_password_sql, update_user_pending_email_params, update_user_pending_email_sql,
+    update_users_params, update_users_sql, where_condition_eq,
+};
+
+/// VERIFICATION_TOKEN_BYTES is the number of random bytes of an email verification token.
+static VERIFICATION_TOKEN_BYTES: usize = 32;
+
+/// RESET_TOKEN_BYTES is the number of random bytes of a password reset token.
+static RESET_TOKEN_BYTES: usize = 32;
+
+/// VERIFICATION_EMAIL is the template of the email sent to verify an email address.
+static VERIFICATION_EMAIL: EmailTemplate = EmailTemplate {
+    subject: "Verify your email address",
+    text: "Hi {{name}},\n\nconfirm your email address by opening the link below:\n\n{{url}}\n\nThe link expires in {{expires_in}}.\n\nThe OpenMusicGang team",
+    html: "<p>Hi {{name}},</p><p>confirm your email address by opening the link below:</p><p><a href=\"{{url}}\">{{url}}</a></p><p>The link expires in {{expires_in}}.</p><p>The OpenMusicGang team</p>",
+};
+
+/// PASSWORD_RESET_EMAIL is the template of the email sent to reset a forgotten password.
+static PASSWORD_RESET_EMAIL: EmailTemplate = EmailTemplate {
+    subject: "Reset your password",
+    text: "Hi {{name}},\n\nchoose a new password by opening the link below:\n\n{{url}}\n\nThe link expires in {{expires_in}}. If you did not ask to reset your password, you can ignore this email.\n\nThe OpenMusicGang team",
+    html: "<p>Hi {{name}},</p><p>choose a new password by opening the link below:</p><p><a href=\"{{url}}\">{{url}}</a></p><p>The link expires in {{expires_in}}. If you did not ask to reset your password, you can ignore this email.</p><p>The OpenMusicGang team</p>",
 };
 
 /// UserService is a struct that implements the UserServiceTrait for the postgres crate.
+///
+/// Email verification and password reset tokens are opaque and stored hashed, they are sent through the
+/// email outbox as a link made of verification_url or reset_url followed by the token.
 pub struct UserService {
     db: Arc<Mutex<DB>>,
+    hasher: PasswordHasher,
+    /// Seconds after which an email verification token expires.
+    verification_ttl: u64,
+    /// Seconds a user has to wait before being sent another verification or password reset email.
+    resend_interval: u64,
+    verification_url: String,
+    /// Seconds after which a password reset token expires.
+    reset_ttl: u64,
+    reset_url: String,
 }
 
 impl UserService {
     /// Create a new UserService struct
-    pub fn new(db: Arc<Mutex<DB>>) -> UserService {
-        UserService { db }
+    pub fn new(
+        db: Arc<Mutex<DB>>,
+        hasher: PasswordHasher,
+        verification_ttl: u64,
+        resend_interval: u64,
+        verification_url: String,
+        reset_ttl: u64,
+        reset_url: String,
+    ) -> UserService {
+        UserService {
+            db,
+            hasher,
+            verification_ttl,
+            resend_interval,
+            verification_url,
+            reset_ttl,
+            reset_url,
+        }
     }
 }
 
@@ -42,7 +109,16 @@ impl UserServiceTrait for UserService {
 
         let mut tx = mutex_db.begin_tx()?;
 
-        create_user(_ctx, &mut tx, user)?;
+        create_user(_ctx.clone(), &mut tx, &self.hasher, user)?;
+
+        send_email_verification(
+            _ctx,
+            &mut tx,
+            user,
+            &user.email,
+            self.verification_ttl,
+            &self.verification_url,
+        )?;
 
         tx.commit().map_err(|_| {
             Error::new(
@@ -145,6 +221,202 @@ impl UserServiceTrait for UserService {
 
         find_users(ctx, &mut tx, filters)
     }
+
+    /// Verifies the password of a user, rehashing it if the hashing parameters changed.
+    fn verify_password(
+        &self,
+        ctx: AppContext,
+        email: String,
+        password: String,
+    ) -> Result<User, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let user = verify_password(ctx, &mut tx, &self.hasher, email, password)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(user)
+    }
+
+    /// Verifies the email address of a user with a verification token.
+    fn confirm_email(&self, ctx: AppContext, token: String) -> Result<User, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let user = confirm_email(ctx, &mut tx, token)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(user)
+    }
+
+    /// Sends a new verification email to the user of the context.
+    fn resend_verification_email(&self, ctx: AppContext) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        resend_verification_email(
+            ctx,
+            &mut tx,
+            self.verification_ttl,
+            self.resend_interval,
+            &self.verification_url,
+        )?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Asks to change the email address of the user of the context, sending a verification email to the new address.
+    fn change_email(&self, ctx: AppContext, email: String) -> Result<User, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let user = change_email(
+            ctx,
+            &mut tx,
+            email,
+            self.verification_ttl,
+            self.resend_interval,
+            &self.verification_url,
+        )?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(user)
+    }
+
+    /// Changes the password of the user of the context.
+    fn change_password(
+        &self,
+        ctx: AppContext,
+        current_password: String,
+        new_password: String,
+    ) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        change_password(ctx, &mut tx, &self.hasher, current_password, new_password)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Sends a password reset email.
+    fn request_password_reset(&self, ctx: AppContext, email: String) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        request_password_reset(
+            ctx,
+            &mut tx,
+            email,
+            self.reset_ttl,
+            self.resend_interval,
+            &self.reset_url,
+        )?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(())
+    }
+
+    /// Resets the password of a user with a password reset token.
+    fn reset_password(
+        &self,
+        ctx: AppContext,
+        token: String,
+        new_password: String,
+    ) -> Result<User, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on database".to_string(),
+            )
+        })?;
+
+        let mut tx = mutex_db.begin_tx()?;
+
+        let user = reset_password(ctx, &mut tx, &self.hasher, token, new_password)?;
+
+        tx.commit().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not commit transaction to database".to_string(),
+            )
+        })?;
+
+        Ok(user)
+    }
 }
 
 /// create_user inserts a new user into the database.
@@ -152,15 +424,34 @@ impl UserServiceTrait for UserService {
 /// Handles the create_user Business Logic.
 ///
 /// Returns EINVALID if the user is invalid.
-fn create_user(_ctx: AppContext, tx: &mut Transaction, user: &mut User) -> Result<(), Error> {
+///
+/// Returns ECONFLICT if another user has the email address.
+///
+/// The email address is normalized and not verified. The password, if provided, is stored as an Argon2id
+/// PHC string.
+fn create_user(
+    _ctx: AppContext,
+    tx: &mut Transaction,
+    hasher: &PasswordHasher,
+    user: &mut User,
+) -> Result<(), Error> {
     user.created_at = Utc::now();
     user.updated_at = Utc::now();
+    user.email = normalize_email(&user.email);
+    user.email_verified_at = None;
+    user.pending_email = None;
 
     user.validate()?;
 
+    ensure_email_available(tx, &user.email, None)?;
+
+    if let Some(password) = &user.password {
+        user.password = Some(hasher.hash_password(password)?);
+    }
+
     let row = tx
         .query_one(insert_user_sql!().as_str(), insert_user_params!(user))
-        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+        .map_err(email_taken_or_internal)?;
 
     user.id = row.get(0);
 
@@ -190,8 +481,10 @@ fn delete_user(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<(), Err
 /// Handles the find_user_by_email Business Logic.
 /// Returns ENOTFOUND if the user is not found.
 fn find_user_by_email(ctx: AppContext, tx: &mut Transaction, email: String) -> Result<User, Error> {
-    let mut filters = UserFilter::default();
-    filters.email = Some(email);
+    let filters = UserFilter {
+        email: Some(email),
+        ..Default::default()
+    };
 
     let result = find_users(ctx, tx, filters)?;
 
@@ -208,9 +501,15 @@ fn find_user_by_email(ctx: AppContext, tx: &mut Transaction, email: String) -> R
 /// find_user_by_id returns a user by id.
 /// Returns ENOTFOUND if the user does not exist.
 /// Handles the find_user_by_id Business Logic.
-fn find_user_by_id(ctx: AppContext, tx: &mut Transaction, id: i64) -> Result<User, Error> {
-    let mut filters = UserFilter::default();
-    filters.id = Some(id);
+pub(crate) fn find_user_by_id(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    id: i64,
+) -> Result<User, Error> {
+    let filters = UserFilter {
+        id: Some(id),
+        ..Default::default()
+    };
 
     let result = find_users(ctx, tx, filters)?;
 
@@ -234,6 +533,7 @@ fn find_users(
     let mut where_conditions = vec!["1 = 1".to_string()];
     let mut args: Vec<&(dyn ToSql + Sync)> = vec![];
     let mut args_counter = 1;
+    let email = filters.email.as_deref().map(normalize_email);
 
     if filters.id.is_some() {
         where_conditions.push(where_condition_eq!("id", args_counter));
@@ -247,11 +547,9 @@ fn find_users(
         args.push(&filters.name);
     }
 
-    if filters.email.is_some() {
+    if email.is_some() {
         where_conditions.push(where_condition_eq!("email", args_counter));
-        args_counter += 1;
-        drop(args_counter);
-        args.push(&filters.email);
+        args.push(&email);
     }
 
     let query = select_users_sql!(
@@ -275,12 +573,16 @@ fn find_users(
         user.password = row.get(3);
         user.created_at = row.get(4);
         user.updated_at = row.get(5);
-        tot_results = row.get(6);
+        user.email_verified_at = row.get(6);
+        user.sessions_revoked_at = row.get(7);
+        user.pending_email = row.get(8);
+        user.kept_session_id = row.get(9);
+        tot_results = row.get(10);
 
         users.push(user);
     }
 
-    return Ok((users, tot_results));
+    Ok((users, tot_results))
 }
 
 /// update_user updates a user in the database.
@@ -307,8 +609,8 @@ fn update_user(
         ));
     }
 
-    if update.name.is_some() {
-        user.name = update.name.unwrap();
+    if let Some(name) = update.name {
+        user.name = name;
     }
 
     user.updated_at = Utc::now();
@@ -321,40 +623,740 @@ fn update_user(
     Ok(user)
 }
 
+/// verify_password checks the password of the user with the given email.
+///
+/// Handles the verify_password Business Logic.
+///
+/// Returns EUNAUTHORIZED if the user does not exist, has no password or the password does not match.
+///
+/// The stored password is rehashed if it was created with outdated hashing parameters or is a legacy
+/// plaintext password. A password is hashed anyway when there is nothing to verify, so the response time
+/// does not reveal whether a user has the email.
+pub(crate) fn verify_password(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    hasher: &PasswordHasher,
+    email: String,
+    password: String,
+) -> Result<User, Error> {
+    let unauthorized = || {
+        Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "Invalid email or password".to_string(),
+        )
+    };
+
+    let mut user = match find_user_by_email(ctx, tx, email) {
+        Ok(user) => user,
+        Err(error) if error.code == ErrorCode::ENOTFOUND => {
+            hasher.verify_dummy_password(&password);
+            return Err(unauthorized());
+        }
+        Err(error) => return Err(error),
+    };
+
+    let phc = match &user.password {
+        Some(phc) => phc.clone(),
+        None => {
+            hasher.verify_dummy_password(&password);
+            return Err(unauthorized());
+        }
+    };
+
+    if !hasher.verify_password(&password, &phc)? {
+        return Err(unauthorized());
+    }
+
+    if hasher.needs_rehash(&phc) {
+        user.password = Some(hasher.hash_password(&password)?);
+        user.updated_at = Utc::now();
+
+        tx.execute(
+            update_user_password_sql!(),
+            update_user_password_params!(user),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+    }
+
+    Ok(user)
+}
+
+/// send_email_verification issues a verification token for an email address of a user, their current or
+/// pending one, and queues the email sending it.
+fn send_email_verification(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    user: &User,
+    email: &str,
+    verification_ttl: u64,
+    verification_url: &str,
+) -> Result<(), Error> {
+    let now = Utc::now();
+    let token = random_token(VERIFICATION_TOKEN_BYTES);
+
+    let mut verification = EmailVerification {
+        id: 0,
+        user_id: user.id,
+        email: email.to_string(),
+        token_hash: sha256_hex(token.as_bytes()),
+        created_at: now,
+        expires_at: now + Duration::seconds(verification_ttl as i64),
+        used_at: None,
+    };
+
+    let row = tx
+        .query_one(
+            insert_email_verification_sql!(),
+            insert_email_verification_params!(verification),
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    verification.id = row.get(0);
+
+    let url = format!("{}{}", verification_url, token);
+
+    queue_email(
+        ctx,
+        tx,
+        &mut VERIFICATION_EMAIL.render(
+            &verification.email,
+            &[
+                ("name", &user.name),
+                ("url", &url),
+                ("expires_in", &format_ttl(verification_ttl)),
+            ],
+        ),
+    )
+}
+
+/// resend_verification_email sends a new verification email to the user of the context.
+///
+/// Handles the resend_verification_email Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns ECONFLICT if the email address of the user is already verified and no email change is pending.
+///
+/// Returns ETOOMANYREQUESTS if the last verification email was sent less than resend_interval seconds ago.
+///
+/// The email is sent to the pending email address of the user if any. The tokens sent before are expired.
+fn resend_verification_email(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    verification_ttl: u64,
+    resend_interval: u64,
+    verification_url: &str,
+) -> Result<(), Error> {
+    let user_id = Context::user_id_from_context(ctx.clone());
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in to verify your email address".to_string(),
+        ));
+    }
+
+    let user = find_user_by_id(ctx.clone(), tx, user_id)?;
+
+    let email = match &user.pending_email {
+        Some(pending_email) => pending_email.clone(),
+        None if user.is_verified() => {
+            return Err(Error::new(
+                ErrorCode::ECONFLICT,
+                "Email address is already verified".to_string(),
+            ));
+        }
+        None => user.email.clone(),
+    };
+
+    let now = Utc::now();
+
+    ensure_verification_resend_allowed(tx, user.id, resend_interval, now)?;
+
+    tx.execute(expire_email_verifications_sql!(), &[&user.id, &now])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    send_email_verification(ctx, tx, &user, &email, verification_ttl, verification_url)
+}
+
+/// change_email stores the address the user of the context asked to change their email to and sends a
+/// verification email to it, the address is swapped in by confirm_email.
+///
+/// Handles the change_email Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EINVALID if the address is not an email address or is the current one of the user.
+///
+/// Returns ECONFLICT if another user has the address.
+///
+/// Returns ETOOMANYREQUESTS if the last verification email was sent less than resend_interval seconds ago.
+///
+/// The address is normalized, a previously pending address and the tokens sent before are discarded.
+fn change_email(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    email: String,
+    verification_ttl: u64,
+    resend_interval: u64,
+    verification_url: &str,
+) -> Result<User, Error> {
+    let user_id = Context::user_id_from_context(ctx.clone());
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in to change your email address".to_string(),
+        ));
+    }
+
+    let mut user = find_user_by_id(ctx.clone(), tx, user_id)?;
+
+    let email = normalize_email(&email);
+
+    if !is_email_address(&email) {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "email must be an email address".to_string(),
+        ));
+    }
+
+    if email == user.email {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "New email address is the current one".to_string(),
+        ));
+    }
+
+    ensure_email_available(tx, &email, Some(user.id))?;
+
+    let now = Utc::now();
+
+    ensure_verification_resend_allowed(tx, user.id, resend_interval, now)?;
+
+    tx.execute(expire_email_verifications_sql!(), &[&user.id, &now])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    user.pending_email = Some(email.clone());
+    user.updated_at = now;
+
+    tx.execute(
+        update_user_pending_email_sql!(),
+        update_user_pending_email_params!(user),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    send_email_verification(ctx, tx, &user, &email, verification_ttl, verification_url)?;
+
+    Ok(user)
+}
+
+/// ensure_verification_resend_allowed checks that no verification email was sent to the user less than
+/// resend_interval seconds before now.
+///
+/// Returns ETOOMANYREQUESTS otherwise.
+fn ensure_verification_resend_allowed(
+    tx: &mut Transaction,
+    user_id: i64,
+    resend_interval: u64,
+    now: DateTime<Utc>,
+) -> Result<(), Error> {
+    let last_sent_at: Option<DateTime<Utc>> = tx
+        .query_one(select_last_email_verification_at_sql!(), &[&user_id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?
+        .get(0);
+
+    if last_sent_at.is_some_and(|sent_at| sent_at + Duration::seconds(resend_interval as i64) > now)
+    {
+        return Err(Error::new(
+            ErrorCode::ETOOMANYREQUESTS,
+            "A verification email was sent recently, try again later".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// ensure_email_available checks that no user other than user_id has the normalized email address, compared
+/// case-insensitively.
+///
+/// Returns ECONFLICT otherwise.
+fn ensure_email_available(
+    tx: &mut Transaction,
+    email: &str,
+    user_id: Option<i64>,
+) -> Result<(), Error> {
+    let owner: Option<i64> = tx
+        .query_opt(select_user_id_by_email_sql!(), &[&email])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?
+        .map(|row| row.get(0));
+
+    if owner.is_some_and(|owner| Some(owner) != user_id) {
+        return Err(Error::new(
+            ErrorCode::ECONFLICT,
+            "Email address is already taken".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns ECONFLICT for the unique violation of an email address taken concurrently, EINTERNAL for any
+/// other database error.
+fn email_taken_or_internal(error: postgres::Error) -> Error {
+    if error.code() == Some(&SqlState::UNIQUE_VIOLATION) {
+        return Error::new(
+            ErrorCode::ECONFLICT,
+            "Email address is already taken".to_string(),
+        );
+    }
+
+    Error::new(ErrorCode::EINTERNAL, error.to_string())
+}
+
+/// confirm_email verifies the email address of a user with a verification token.
+///
+/// Handles the confirm_email Business Logic.
+///
+/// Returns EINVALID if the token does not exist, was already used, expired or was sent to another
+/// address than the current or pending one of the user.
+///
+/// Returns ECONFLICT if the token was sent to the pending address of the user and another user has it.
+///
+/// The token is marked as used, confirming an already verified address only uses the token. Confirming the
+/// pending address swaps it in as the verified email address of the user.
+fn confirm_email(ctx: AppContext, tx: &mut Transaction, token: String) -> Result<User, Error> {
+    let invalid = |message: &str| Error::new(ErrorCode::EINVALID, message.to_string());
+
+    let row = tx
+        .query_opt(
+            select_email_verification_by_hash_sql!(),
+            &[&sha256_hex(token.as_bytes())],
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?
+        .ok_or_else(|| invalid("Invalid verification token"))?;
+
+    let verification = email_verification_from_row(&row);
+
+    if verification.used_at.is_some() {
+        return Err(invalid("Verification token was already used"));
+    }
+
+    if verification.is_expired() {
+        return Err(invalid("Verification token expired"));
+    }
+
+    let mut user = find_user_by_id(ctx, tx, verification.user_id)?;
+
+    let changes_email = user.email != verification.email;
+
+    if changes_email && user.pending_email.as_ref() != Some(&verification.email) {
+        return Err(invalid(
+            "Verification token was sent to another email address",
+        ));
+    }
+
+    if changes_email {
+        ensure_email_available(tx, &verification.email, Some(user.id))?;
+    }
+
+    let now = Utc::now();
+
+    tx.execute(
+        update_email_verification_used_sql!(),
+        &[&now, &verification.id],
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    if changes_email {
+        user.email = verification.email;
+        user.pending_email = None;
+        user.email_verified_at = Some(now);
+        user.updated_at = now;
+
+        tx.execute(update_user_email_sql!(), &[&user.email, &now, &user.id])
+            .map_err(email_taken_or_internal)?;
+
+        tx.execute(expire_email_verifications_sql!(), &[&user.id, &now])
+            .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+    } else if !user.is_verified() {
+        user.email_verified_at = Some(now);
+        user.updated_at = now;
+
+        tx.execute(update_user_email_verified_sql!(), &[&now, &user.id])
+            .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+    }
+
+    Ok(user)
+}
+
+/// change_password changes the password of the user of the context.
+///
+/// Handles the change_password Business Logic.
+///
+/// Returns EUNAUTHORIZED if there is no user in the context, or if the current password does not match.
+///
+/// Returns EINVALID if the new password is empty.
+///
+/// The other sessions of the user are revoked, their refresh tokens and the password reset tokens sent before
+/// can no longer be used. The session of the context is kept.
+fn change_password(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    hasher: &PasswordHasher,
+    current_password: String,
+    new_password: String,
+) -> Result<(), Error> {
+    let user_id = Context::user_id_from_context(ctx.clone());
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You must be logged in to change your password".to_string(),
+        ));
+    }
+
+    let session_id = Context::session_id_from_context(ctx.clone());
+
+    let mut user = find_user_by_id(ctx, tx, user_id)?;
+
+    let matches = match &user.password {
+        Some(phc) => hasher.verify_password(&current_password, phc)?,
+        None => false,
+    };
+
+    if !matches {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "Invalid current password".to_string(),
+        ));
+    }
+
+    let now = Utc::now();
+
+    user.password = Some(new_password);
+    user.updated_at = now;
+    user.sessions_revoked_at = Some(now.trunc_subsecs(3));
+    user.kept_session_id = session_id;
+
+    user.validate()?;
+
+    user.password = Some(hasher.hash_password(user.password.as_ref().unwrap())?);
+
+    tx.execute(
+        update_user_password_sessions_sql!(),
+        update_user_password_sessions_params!(user),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    tx.execute(expire_password_resets_sql!(), &[&user.id, &now])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    match &user.kept_session_id {
+        Some(session_id) => tx.execute(
+            revoke_other_refresh_tokens_sql!(),
+            &[&now, &user.id, session_id],
+        ),
+        None => tx.execute(revoke_user_refresh_tokens_sql!(), &[&now, &user.id]),
+    }
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(())
+}
+
+/// request_password_reset issues a password reset token for the user with the given email and queues the
+/// email sending it.
+///
+/// Handles the request_password_reset Business Logic.
+///
+/// Nothing is sent if no user has the email, or if a password reset email was sent to the user less than
+/// resend_interval seconds ago, so the result never reveals whether a user has the email.
+///
+/// The tokens sent before are expired.
+fn request_password_reset(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    email: String,
+    reset_ttl: u64,
+    resend_interval: u64,
+    reset_url: &str,
+) -> Result<(), Error> {
+    let user = match find_user_by_email(ctx.clone(), tx, email) {
+        Ok(user) => user,
+        Err(error) if error.code == ErrorCode::ENOTFOUND => return Ok(()),
+        Err(error) => return Err(error),
+    };
+
+    let now = Utc::now();
+
+    let last_sent_at: Option<DateTime<Utc>> = tx
+        .query_one(select_last_password_reset_at_sql!(), &[&user.id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?
+        .get(0);
+
+    if last_sent_at.is_some_and(|sent_at| sent_at + Duration::seconds(resend_interval as i64) > now)
+    {
+        return Ok(());
+    }
+
+    tx.execute(expire_password_resets_sql!(), &[&user.id, &now])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let token = random_token(RESET_TOKEN_BYTES);
+
+    let reset = PasswordReset {
+        id: 0,
+        user_id: user.id,
+        token_hash: sha256_hex(token.as_bytes()),
+        created_at: now,
+        expires_at: now + Duration::seconds(reset_ttl as i64),
+        used_at: None,
+    };
+
+    tx.execute(
+        insert_password_reset_sql!(),
+        insert_password_reset_params!(reset),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let url = format!("{}{}", reset_url, token);
+
+    queue_email(
+        ctx,
+        tx,
+        &mut PASSWORD_RESET_EMAIL.render(
+            &user.email,
+            &[
+                ("name", &user.name),
+                ("url", &url),
+                ("expires_in", &format_ttl(reset_ttl)),
+            ],
+        ),
+    )
+}
+
+/// reset_password sets a new password with a password reset token.
+///
+/// Handles the reset_password Business Logic.
+///
+/// Returns EINVALID if the token does not exist, was already used or expired, or if the new password is empty.
+///
+/// The sessions of the user are revoked, their refresh tokens and the other password reset tokens can no
+/// longer be used.
+fn reset_password(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    hasher: &PasswordHasher,
+    token: String,
+    new_password: String,
+) -> Result<User, Error> {
+    let invalid = |message: &str| Error::new(ErrorCode::EINVALID, message.to_string());
+
+    let row = tx
+        .query_opt(
+            select_password_reset_by_hash_sql!(),
+            &[&sha256_hex(token.as_bytes())],
+        )
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?
+        .ok_or_else(|| invalid("Invalid password reset token"))?;
+
+    let reset = password_reset_from_row(&row);
+
+    if reset.used_at.is_some() {
+        return Err(invalid("Password reset token was already used"));
+    }
+
+    if reset.is_expired() {
+        return Err(invalid("Password reset token expired"));
+    }
+
+    let mut user = find_user_by_id(ctx, tx, reset.user_id)?;
+
+    let now = Utc::now();
+
+    user.password = Some(new_password);
+    user.updated_at = now;
+    user.sessions_revoked_at = Some(now.trunc_subsecs(3));
+    user.kept_session_id = None;
+
+    user.validate()?;
+
+    user.password = Some(hasher.hash_password(user.password.as_ref().unwrap())?);
+
+    tx.execute(
+        update_user_password_sessions_sql!(),
+        update_user_password_sessions_params!(user),
+    )
+    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    tx.execute(update_password_reset_used_sql!(), &[&now, &reset.id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    tx.execute(expire_password_resets_sql!(), &[&user.id, &now])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    tx.execute(revoke_user_refresh_tokens_sql!(), &[&now, &user.id])
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(user)
+}
+
+/// require_verified_user returns the user of the context if their email address is verified, action
+/// describes what they are trying to do, e.g. "create a gang".
+///
+/// Returns EUNAUTHORIZED if there is no user in the context.
+///
+/// Returns EFORBIDDEN if the email address of the user is not verified.
+pub(crate) fn require_verified_user(
+    ctx: AppContext,
+    tx: &mut Transaction,
+    action: &str,
+) -> Result<User, Error> {
+    let user_id = Context::user_id_from_context(ctx.clone());
+
+    if user_id == 0 {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            format!("You must be logged in to {}", action),
+        ));
+    }
+
+    let user = find_user_by_id(ctx, tx, user_id)?;
+
+    if !user.is_verified() {
+        return Err(Error::new(
+            ErrorCode::EFORBIDDEN,
+            format!("You must verify your email address to {}", action),
+        ));
+    }
+
+    Ok(user)
+}
+
+/// Returns the password reset of a row returned by select_password_reset_by_hash_sql.
+fn password_reset_from_row(row: &Row) -> PasswordReset {
+    PasswordReset {
+        id: row.get(0),
+        user_id: row.get(1),
+        token_hash: row.get(2),
+        created_at: row.get(3),
+        expires_at: row.get(4),
+        used_at: row.get(5),
+    }
+}
+
+/// Returns a time to live in seconds as read in an email, e.g. "24 hours" or "30 minutes".
+fn format_ttl(ttl: u64) -> String {
+    let (value, unit) = match ttl {
+        ttl if ttl >= 3600 => (ttl / 3600, "hour"),
+        ttl => ((ttl / 60).max(1), "minute"),
+    };
+
+    match value {
+        1 => format!("1 {}", unit),
+        value => format!("{} {}s", value, unit),
+    }
+}
+
+/// Returns the email verification of a row returned by select_email_verification_by_hash_sql.
+fn email_verification_from_row(row: &Row) -> EmailVerification {
+    EmailVerification {
+        id: row.get(0),
+        user_id: row.get(1),
+        email: row.get(2),
+        token_hash: row.get(3),
+        created_at: row.get(4),
+        expires_at: row.get(5),
+        used_at: row.get(6),
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use openmusicgang_app::context::Context;
 
-    use crate::test_utils::{must_open_db, must_truncate_table};
+    use crate::test_utils::{must_exec, must_lock_db, must_open_db, must_truncate_table};
 
     use super::*;
 
+    /// VERIFICATION_URL is the URL of the verification links of the test.
+    static VERIFICATION_URL: &str = "http://localhost/verify-email?token=";
+
+    /// RESET_URL is the URL of the password reset links of the test.
+    static RESET_URL: &str = "http://localhost/reset-password?token=";
+
+    /// Returns the token of the link starting with url in the last email queued to the given address.
+    fn must_find_token(db: &Arc<Mutex<DB>>, email: &str, url: &str) -> String {
+        let mut mutex_db = db.lock().unwrap();
+        let mut tx = mutex_db.begin_tx().unwrap();
+        let row = tx
+            .query_one(
+                "SELECT text_body FROM email_outbox WHERE recipient = $1 ORDER BY id DESC LIMIT 1",
+                &[&email],
+            )
+            .unwrap();
+        let text: String = row.get(0);
+
+        let start = text.find(url).unwrap() + url.len();
+        text[start..].split_whitespace().next().unwrap().to_string()
+    }
+
     /// ## Simple workflow
     ///
     /// 1) open database connection.
     /// 2) truncate table to start fresh.
     /// 3) create a user.
-    /// 4) retry the create user with the same email, should fail.
+    /// 4) retry the create user with the same email in another case, error should be ECONFLICT.
     /// 5) find the user by id.
-    /// 6) find the user by email.
+    /// 6) find the user by email in another case.
     /// 7) find a user with a non-existent id, error should be ENOTFOUND.
     /// 8) find a user with a non-existent email error should be ENOTFOUND.
     /// 9) update the user and check that the update was successful.
     /// 10) delete the user and check that the delete was successful.
     /// 11) create a new user, try to update but with another context, error should be EUNAUTHORIZED.
     /// 12) create a new user, try to delete but with another context, error should be EUNAUTHORIZED.
+    /// 13) verify the password of the user, a wrong password should be EUNAUTHORIZED.
+    /// 14) verify the password with new hashing parameters or in plaintext, the password should be rehashed.
+    /// 15) create a user with an invalid email, error should be EINVALID.
+    /// 16) create a user, they should be unverified and sent a verification email.
+    /// 17) resend the verification email right away, error should be ETOOMANYREQUESTS.
+    /// 18) confirm an invalid token, error should be EINVALID.
+    /// 19) resend the verification email later, the first token should be expired.
+    /// 20) confirm the new token, the user should be verified and the token used.
+    /// 21) resend the verification email of a verified user, error should be ECONFLICT.
+    /// 22) change the password with a wrong current password, error should be EUNAUTHORIZED.
+    /// 23) change the password, the new password should be verified and the other sessions revoked.
+    /// 24) request a password reset for an unknown email, nothing should be sent.
+    /// 25) request a password reset twice, a single email should be sent.
+    /// 26) reset the password with an invalid token, error should be EINVALID.
+    /// 27) reset the password, the sessions opened before should be revoked and the token used.
+    /// 28) change the email to the address of another user, error should be ECONFLICT.
+    /// 29) change the email, it should be normalized and pending, a retry should be ETOOMANYREQUESTS.
+    /// 30) confirm the pending email, it should be swapped in and verified.
+    /// 31) change the email to an address taken before it is confirmed, error should be ECONFLICT.
     #[test]
     fn test_user_service() {
         // 1) open database connection.
+        let _lock = must_lock_db();
         let mut db = must_open_db();
 
         // 2) truncate table to start fresh.
         must_truncate_table(&mut db, "users");
+        must_truncate_table(&mut db, "email_outbox");
 
         let db = Arc::new(Mutex::new(db));
 
-        let user_service = UserService::new(Arc::clone(&db));
+        let hasher = PasswordHasher::new(64, 1, 1).unwrap();
+        let user_service = UserService::new(
+            Arc::clone(&db),
+            hasher.clone(),
+            86400,
+            3600,
+            VERIFICATION_URL.to_string(),
+            3600,
+            RESET_URL.to_string(),
+        );
 
         let mut user = User::new();
 
@@ -368,9 +1370,13 @@ mod tests {
             panic!("{}", error);
         }
 
-        // 4) retry the create user with the same email, should fail.
-        let res = user_service.create_user(Context::background(), &mut user);
+        // 4) retry the create user with the same email in another case, error should be ECONFLICT.
+        let mut duplicate = user.clone();
+        duplicate.email = " Bob.Smith@Test.com ".to_string();
+
+        let res = user_service.create_user(Context::background(), &mut duplicate);
         assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
 
         // 5) find the user by id.
         let res = user_service.find_user_by_id(Context::background(), 1);
@@ -379,11 +1385,14 @@ mod tests {
         assert_eq!(user.id, 1);
         assert_eq!(user.name, "Bob Smith");
         assert_eq!(user.email, "bob.smith@test.com");
-        assert_eq!(user.password, Some("password".to_string()));
+        assert_ne!(user.password, Some("password".to_string()));
+        assert!(hasher
+            .verify_password("password", user.password.as_ref().unwrap())
+            .unwrap());
 
-        // 6) find the user by email.
+        // 6) find the user by email in another case.
         let res = user_service
-            .find_user_by_email(Context::background(), "bob.smith@test.com".to_string());
+            .find_user_by_email(Context::background(), "BOB.SMITH@test.com".to_string());
         assert!(res.is_ok());
 
         // 7) find a user with a non-existent id, error should be ENOTFOUND.
@@ -403,9 +1412,9 @@ mod tests {
 
         // 9) update the user and check that the update was successful.
         let ctx = Context::with_user(Context::background(), user.clone());
-        let mut update = UserUpdate::default();
-
-        update.name = Some("Mark Smith".to_string());
+        let update = UserUpdate {
+            name: Some("Mark Smith".to_string()),
+        };
 
         let res = user_service.update_user(ctx, user.id, update);
         assert!(res.is_ok());
@@ -434,8 +1443,9 @@ mod tests {
         let res = user_service.create_user(Context::background(), &mut user);
         assert!(res.is_ok());
 
-        let mut update = UserUpdate::default();
-        update.name = Some("Mark Smith".to_string());
+        let update = UserUpdate {
+            name: Some("Mark Smith".to_string()),
+        };
 
         let res = user_service.update_user(Context::background(), user.id, update);
         assert!(res.is_err());
@@ -458,5 +1468,358 @@ mod tests {
 
         let err = res.unwrap_err();
         assert_eq!(err.code, ErrorCode::EUNAUTHORIZED);
+
+        // 13) verify the password of the user, a wrong password should be EUNAUTHORIZED.
+        let res = user_service.verify_password(
+            Context::background(),
+            "steve.smith@test.com".to_string(),
+            "password".to_string(),
+        );
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap().id, user.id);
+
+        let res = user_service.verify_password(
+            Context::background(),
+            "steve.smith@test.com".to_string(),
+            "wrong password".to_string(),
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let res = user_service.verify_password(
+            Context::background(),
+            "nobody@test.com".to_string(),
+            "password".to_string(),
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 14) verify the password with new hashing parameters or in plaintext, the password should be rehashed.
+        let stronger = PasswordHasher::new(128, 2, 1).unwrap();
+        let user_service = UserService::new(
+            Arc::clone(&db),
+            stronger.clone(),
+            86400,
+            3600,
+            VERIFICATION_URL.to_string(),
+            3600,
+            RESET_URL.to_string(),
+        );
+
+        let res = user_service.verify_password(
+            Context::background(),
+            "steve.smith@test.com".to_string(),
+            "password".to_string(),
+        );
+        assert!(res.is_ok());
+
+        let user = user_service
+            .find_user_by_id(Context::background(), user.id)
+            .unwrap();
+        let phc = user.password.unwrap();
+        assert!(!stronger.needs_rehash(&phc));
+        assert!(stronger.verify_password("password", &phc).unwrap());
+
+        must_exec(
+            &mut db.lock().unwrap(),
+            "UPDATE users SET password = 'password' WHERE id = $1",
+            &[&user.id],
+        );
+
+        let res = user_service.verify_password(
+            Context::background(),
+            "steve.smith@test.com".to_string(),
+            "wrong password".to_string(),
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let res = user_service.verify_password(
+            Context::background(),
+            "steve.smith@test.com".to_string(),
+            "password".to_string(),
+        );
+        assert!(res.is_ok());
+
+        let user = user_service
+            .find_user_by_id(Context::background(), user.id)
+            .unwrap();
+        let phc = user.password.unwrap();
+        assert!(!stronger.needs_rehash(&phc));
+        assert!(phc.starts_with("$argon2id$"));
+        assert!(stronger.verify_password("password", &phc).unwrap());
+
+        // 15) create a user with an invalid email, error should be EINVALID.
+        let mut user = User::new();
+        user.name = "Mark Smith".to_string();
+        user.email = "mark.smith".to_string();
+
+        let res = user_service.create_user(Context::background(), &mut user);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 16) create a user, they should be unverified and sent a verification email.
+        user.email = "mark.smith@test.com".to_string();
+        user_service
+            .create_user(Context::background(), &mut user)
+            .unwrap();
+        assert!(!user.is_verified());
+
+        let first_token = must_find_token(&db, "mark.smith@test.com", VERIFICATION_URL);
+        assert_eq!(first_token.len(), 64);
+
+        let user = user_service
+            .find_user_by_id(Context::background(), user.id)
+            .unwrap();
+        assert!(!user.is_verified());
+
+        // 17) resend the verification email right away, error should be ETOOMANYREQUESTS.
+        let mark_ctx = || Context::with_user(Context::background(), user.clone());
+
+        let res = user_service.resend_verification_email(mark_ctx());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ETOOMANYREQUESTS);
+
+        let res = user_service.resend_verification_email(Context::background());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 18) confirm an invalid token, error should be EINVALID.
+        let res = user_service.confirm_email(Context::background(), "invalid".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 19) resend the verification email later, the first token should be expired.
+        must_exec(
+            &mut db.lock().unwrap(),
+            "UPDATE email_verifications SET created_at = created_at - INTERVAL '2 hours'",
+            &[],
+        );
+
+        user_service.resend_verification_email(mark_ctx()).unwrap();
+
+        let token = must_find_token(&db, "mark.smith@test.com", VERIFICATION_URL);
+        assert_ne!(token, first_token);
+
+        let res = user_service.confirm_email(Context::background(), first_token);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 20) confirm the new token, the user should be verified and the token used.
+        let verified = user_service
+            .confirm_email(Context::background(), token.clone())
+            .unwrap();
+        assert_eq!(verified.id, user.id);
+        assert!(verified.is_verified());
+
+        let user = user_service
+            .find_user_by_id(Context::background(), user.id)
+            .unwrap();
+        assert!(user.is_verified());
+
+        let res = user_service.confirm_email(Context::background(), token);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 21) resend the verification email of a verified user, error should be ECONFLICT.
+        let res = user_service.resend_verification_email(mark_ctx());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        // 22) change the password with a wrong current password, error should be EUNAUTHORIZED.
+        let steve = user_service
+            .find_user_by_email(Context::background(), "steve.smith@test.com".to_string())
+            .unwrap();
+        let steve_ctx = || Context::with_user(Context::background(), steve.clone());
+
+        let res = user_service.change_password(
+            steve_ctx(),
+            "wrong password".to_string(),
+            "new password".to_string(),
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let res = user_service.change_password(
+            Context::background(),
+            "password".to_string(),
+            "new password".to_string(),
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let res = user_service.change_password(steve_ctx(), "password".to_string(), "".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 23) change the password, the new password should be verified and the other sessions revoked.
+        user_service
+            .change_password(
+                Context::with_session_id(steve_ctx(), "current".to_string()),
+                "password".to_string(),
+                "new password".to_string(),
+            )
+            .unwrap();
+
+        let res = user_service.verify_password(
+            Context::background(),
+            "steve.smith@test.com".to_string(),
+            "password".to_string(),
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let res = user_service.verify_password(
+            Context::background(),
+            "steve.smith@test.com".to_string(),
+            "new password".to_string(),
+        );
+        assert!(res.is_ok());
+
+        let user = res.unwrap();
+        let revoked_at = user.sessions_revoked_at.unwrap();
+        assert_eq!(revoked_at, revoked_at.trunc_subsecs(3));
+        assert!(user.is_session_revoked("other", revoked_at - Duration::milliseconds(1)));
+        assert!(!user.is_session_revoked("current", revoked_at - Duration::milliseconds(1)));
+        assert!(!user.is_session_revoked("other", revoked_at));
+
+        // 24) request a password reset for an unknown email, nothing should be sent.
+        let count_reset_emails = |email: &str| -> i64 {
+            let mut mutex_db = db.lock().unwrap();
+            let mut tx = mutex_db.begin_tx().unwrap();
+            tx.query_one(
+                "SELECT COUNT(*) FROM email_outbox WHERE recipient = $1 AND subject = 'Reset your password'",
+                &[&email],
+            )
+            .unwrap()
+            .get(0)
+        };
+
+        user_service
+            .request_password_reset(Context::background(), "nobody@test.com".to_string())
+            .unwrap();
+        assert_eq!(count_reset_emails("nobody@test.com"), 0);
+
+        // 25) request a password reset twice, a single email should be sent.
+        for _ in 0..2 {
+            user_service
+                .request_password_reset(Context::background(), "steve.smith@test.com".to_string())
+                .unwrap();
+        }
+        assert_eq!(count_reset_emails("steve.smith@test.com"), 1);
+
+        let token = must_find_token(&db, "steve.smith@test.com", RESET_URL);
+
+        // 26) reset the password with an invalid token, error should be EINVALID.
+        let res = user_service.reset_password(
+            Context::background(),
+            "invalid".to_string(),
+            "reset password".to_string(),
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 27) reset the password, the sessions opened before should be revoked and the token used.
+        let user = user_service
+            .reset_password(
+                Context::background(),
+                token.clone(),
+                "reset password".to_string(),
+            )
+            .unwrap();
+        assert_eq!(user.id, steve.id);
+
+        let revoked_at = user.sessions_revoked_at.unwrap();
+        assert!(user.is_session_revoked("current", revoked_at - Duration::milliseconds(1)));
+        assert!(!user.is_session_revoked("current", revoked_at));
+
+        let res = user_service.verify_password(
+            Context::background(),
+            "steve.smith@test.com".to_string(),
+            "reset password".to_string(),
+        );
+        assert!(res.is_ok());
+        assert!(res.unwrap().sessions_revoked_at.is_some());
+
+        let res = user_service.reset_password(
+            Context::background(),
+            token,
+            "another password".to_string(),
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 28) change the email to the address of another user, error should be ECONFLICT.
+        let steve_ctx = || Context::with_user(Context::background(), user.clone());
+
+        let res = user_service.change_email(steve_ctx(), "Mark.Smith@test.com".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
+
+        let res = user_service.change_email(steve_ctx(), "steve.smith".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 29) change the email, it should be normalized and pending, a retry should be ETOOMANYREQUESTS.
+        let steve = user_service
+            .change_email(steve_ctx(), " Steve@Example.com ".to_string())
+            .unwrap();
+        assert_eq!(steve.email, "steve.smith@test.com");
+        assert_eq!(steve.pending_email.as_deref(), Some("steve@example.com"));
+
+        let res = user_service.change_email(steve_ctx(), "steve@example.org".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ETOOMANYREQUESTS);
+
+        let token = must_find_token(&db, "steve@example.com", VERIFICATION_URL);
+
+        let res =
+            user_service.find_user_by_email(Context::background(), "steve@example.com".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        // 30) confirm the pending email, it should be swapped in and verified.
+        let steve = user_service
+            .confirm_email(Context::background(), token)
+            .unwrap();
+        assert_eq!(steve.email, "steve@example.com");
+        assert_eq!(steve.pending_email, None);
+        assert!(steve.is_verified());
+
+        let res =
+            user_service.find_user_by_email(Context::background(), "Steve@Example.com".to_string());
+        assert_eq!(res.unwrap().id, steve.id);
+
+        let res = user_service
+            .find_user_by_email(Context::background(), "steve.smith@test.com".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        // 31) change the email to an address taken before it is confirmed, error should be ECONFLICT.
+        must_exec(
+            &mut db.lock().unwrap(),
+            "UPDATE email_verifications SET created_at = created_at - INTERVAL '2 hours'",
+            &[],
+        );
+
+        let steve_ctx = || Context::with_user(Context::background(), steve.clone());
+
+        user_service
+            .change_email(steve_ctx(), "steve.jones@test.com".to_string())
+            .unwrap();
+
+        let token = must_find_token(&db, "steve.jones@test.com", VERIFICATION_URL);
+
+        let mut jones = User::new();
+        jones.name = "Steve Jones".to_string();
+        jones.email = "Steve.Jones@test.com".to_string();
+        user_service
+            .create_user(Context::background(), &mut jones)
+            .unwrap();
+        assert_eq!(jones.email, "steve.jones@test.com");
+
+        let res = user_service.confirm_email(Context::background(), token);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ECONFLICT);
     }
 }
@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+use redis::Connection;
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::activity::{Activity, FeedPage};
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::activity_service::{
+    ActivityService as ActivityServiceTrait, FeedFilter,
+};
+
+use crate::redis::DB;
+
+/// FEED_PAGE_SIZE is the number of activities of a page of a feed when no limit is given.
+static FEED_PAGE_SIZE: i64 = 20;
+
+/// FEED_CACHE_SIZE is the number of recent activities cached in the feed of a user.
+static FEED_CACHE_SIZE: i64 = 100;
+
+/// Returns the key of the list holding the ids of the recent activities of the feed of the user, newest first.
+fn feed_key(user_id: i64) -> String {
+    format!("feed:{}", user_id)
+}
+
+/// Returns the key of the hash holding the activity.
+fn activity_key(id: i64) -> String {
+    format!("activity:{}", id)
+}
+
+/// ActivityService is a struct that implements the ActivityServiceTrait for the redis crate.
+///
+/// The first page of a feed is read from a hot cache of its recent activities, only the activities
+/// newer than the cached ones are read from the wrapped service. The cache of a feed is rebuilt after
+/// `ttl` seconds, so that the activities deleted with their gang or song disappear from it.
+pub struct ActivityService {
+    db: Arc<Mutex<DB>>,
+    activity_service: Arc<dyn ActivityServiceTrait + Send + Sync>,
+    ttl: u64,
+}
+
+impl ActivityService {
+    /// Create a new ActivityService struct
+    pub fn new(
+        db: Arc<Mutex<DB>>,
+        activity_service: Arc<dyn ActivityServiceTrait + Send + Sync>,
+        ttl: u64,
+    ) -> ActivityService {
+        ActivityService {
+            db,
+            activity_service,
+            ttl,
+        }
+    }
+}
+
+impl ActivityServiceTrait for ActivityService {
+    /// Returns a page of the feed of the user of the context, from the cache for the first page.
+    fn find_feed(&self, ctx: AppContext, filters: FeedFilter) -> Result<FeedPage, Error> {
+        let user_id = Context::user_id_from_context(ctx.clone());
+
+        let limit = match filters.limit {
+            limit if limit <= 0 => FEED_PAGE_SIZE,
+            limit => limit,
+        };
+
+        if user_id == 0
+            || filters.before.is_some()
+            || filters.after.is_some()
+            || limit > FEED_CACHE_SIZE
+        {
+            return self.activity_service.find_feed(ctx, filters);
+        }
+
+        let head = {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on redis".to_string(),
+                )
+            })?;
+
+            find_feed_head(ctx.clone(), mutex_db.conn()?, user_id)?
+        };
+
+        let recent = self.activity_service.find_feed(
+            ctx.clone(),
+            FeedFilter {
+                before: None,
+                after: head,
+                limit: FEED_CACHE_SIZE,
+            },
+        )?;
+
+        let page = {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on redis".to_string(),
+                )
+            })?;
+
+            let conn = mutex_db.conn()?;
+
+            // Activities missing between the cached ones and the recent ones make the cache start over.
+            let replace = head.is_none() || recent.next_cursor.is_some();
+            cache_feed(
+                ctx.clone(),
+                conn,
+                user_id,
+                &recent.activities,
+                replace,
+                self.ttl,
+            )?;
+
+            find_cached_feed(ctx.clone(), conn, user_id, limit)?
+        };
+
+        match page {
+            Some(page) => Ok(page),
+            None => self.activity_service.find_feed(ctx, filters),
+        }
+    }
+}
+
+/// find_feed_head returns the id of the newest cached activity of the feed of the user, or None if
+/// the feed is not cached.
+fn find_feed_head(
+    _ctx: AppContext,
+    conn: &mut Connection,
+    user_id: i64,
+) -> Result<Option<i64>, Error> {
+    redis::cmd("LINDEX")
+        .arg(feed_key(user_id))
+        .arg(0)
+        .query(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))
+}
+
+/// cache_feed stores the recent activities of the feed of the user, newest first, before the cached ones
+/// or in place of them.
+///
+/// The feed keeps the expiration it had when it was created, activities expire after `ttl` seconds.
+fn cache_feed(
+    _ctx: AppContext,
+    conn: &mut Connection,
+    user_id: i64,
+    activities: &[Activity],
+    replace: bool,
+    ttl: u64,
+) -> Result<(), Error> {
+    if activities.is_empty() {
+        return Ok(());
+    }
+
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+
+    for activity in activities {
+        pipe.cmd("HSET")
+            .arg(activity_key(activity.id))
+            .arg(activity_fields(activity))
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(activity_key(activity.id))
+            .arg(ttl)
+            .ignore();
+    }
+
+    if replace {
+        pipe.cmd("DEL").arg(feed_key(user_id)).ignore();
+    }
+
+    // Activities are pushed oldest first, so that the newest one ends up at the head of the list.
+    let ids: Vec<i64> = activities
+        .iter()
+        .rev()
+        .map(|activity| activity.id)
+        .collect();
+
+    pipe.cmd("LPUSH")
+        .arg(feed_key(user_id))
+        .arg(ids)
+        .ignore()
+        .cmd("LTRIM")
+        .arg(feed_key(user_id))
+        .arg(0)
+        .arg(FEED_CACHE_SIZE - 1)
+        .ignore();
+
+    if replace {
+        pipe.cmd("EXPIRE").arg(feed_key(user_id)).arg(ttl).ignore();
+    }
+
+    pipe.query::<()>(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))
+}
+
+/// find_cached_feed returns the first page of the cached feed of the user, or None if an activity of the
+/// page is missing from the cache, in which case the cached feed is deleted to be rebuilt.
+fn find_cached_feed(
+    _ctx: AppContext,
+    conn: &mut Connection,
+    user_id: i64,
+    limit: i64,
+) -> Result<Option<FeedPage>, Error> {
+    // One more activity than the page is read to know if there is a next page.
+    let (ids, length): (Vec<i64>, i64) = redis::pipe()
+        .cmd("LRANGE")
+        .arg(feed_key(user_id))
+        .arg(0)
+        .arg(limit)
+        .cmd("LLEN")
+        .arg(feed_key(user_id))
+        .query(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let page_ids = &ids[..ids.len().min(limit as usize)];
+    let mut activities = vec![];
+
+    for id in page_ids {
+        let fields: HashMap<String, String> = redis::cmd("HGETALL")
+            .arg(activity_key(*id))
+            .query(conn)
+            .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+        match activity_from_fields(*id, &fields) {
+            Some(activity) => activities.push(activity),
+            None => {
+                redis::cmd("DEL")
+                    .arg(feed_key(user_id))
+                    .query::<()>(conn)
+                    .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+                return Ok(None);
+            }
+        }
+    }
+
+    // Older activities than the cached ones may exist once the cache is full.
+    let next_cursor = match ids.len() > page_ids.len() || length >= FEED_CACHE_SIZE {
+        true => page_ids.last().copied(),
+        false => None,
+    };
+
+    Ok(Some(FeedPage {
+        activities,
+        next_cursor,
+    }))
+}
+
+/// Returns the fields of the hash of an activity, the missing optional values are left out.
+fn activity_fields(activity: &Activity) -> Vec<(&'static str, String)> {
+    let mut fields = vec![
+        ("kind", activity.kind.to_string()),
+        ("gang_id", activity.gang_id.to_string()),
+        ("summary", activity.summary.clone()),
+        (
+            "created_at",
+            activity.created_at.timestamp_micros().to_string(),
+        ),
+    ];
+
+    let optionals = [
+        ("actor_id", activity.actor_id),
+        ("song_id", activity.song_id),
+        ("revision", activity.revision.map(i64::from)),
+        ("subject_id", activity.subject_id),
+    ];
+
+    for (name, value) in optionals {
+        if let Some(value) = value {
+            fields.push((name, value.to_string()));
+        }
+    }
+
+    fields
+}
+
+/// Returns the activity of the fields of its hash, None if the hash is missing or invalid.
+fn activity_from_fields(id: i64, fields: &HashMap<String, String>) -> Option<Activity> {
+    let optional = |name: &str| fields.get(name).and_then(|value| value.parse::<i64>().ok());
+
+    Some(Activity {
+        id,
+        kind: fields.get("kind")?.parse().ok()?,
+        actor_id: optional("actor_id"),
+        gang_id: optional("gang_id")?,
+        song_id: optional("song_id"),
+        revision: optional("revision").map(|revision| revision as i32),
+        subject_id: optional("subject_id"),
+        summary: fields.get("summary")?.clone(),
+        created_at: DateTime::from_timestamp_micros(optional("created_at")?)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use once_cell::sync::Lazy;
+    use openmusicgang_entity::activity::ActivityKind;
+    use openmusicgang_entity::user::User;
+    use openmusicgang_mock::activity::ActivityService as MockActivityService;
+
+    use crate::test_utils::{must_delete_keys, must_open_db};
+
+    use super::*;
+
+    /// FEED is the feed of the user 1 in the mock activity service, newest first.
+    static FEED: Lazy<Mutex<Vec<Activity>>> = Lazy::new(Mutex::default);
+
+    /// FILTERS are the filters the mock activity service was called with.
+    static FILTERS: Lazy<Mutex<Vec<FeedFilter>>> = Lazy::new(Mutex::default);
+
+    fn push_activity(id: i64) {
+        let mut activity = Activity::new(ActivityKind::TrackUploaded, 2, 1);
+        activity.id = id;
+        activity.song_id = Some(1);
+        activity.revision = Some(id as i32);
+        activity.subject_id = Some(id);
+        activity.summary = format!("John Smith uploaded take {} to Segfault Blues", id);
+        // Postgres stores the timestamps in microseconds.
+        activity.created_at = activity.created_at.trunc_subsecs(6);
+
+        FEED.lock().unwrap().insert(0, activity);
+    }
+
+    fn mock_activity_service() -> MockActivityService {
+        MockActivityService {
+            find_feed_fn: Some(|_, filters| {
+                FILTERS.lock().unwrap().push(filters.clone());
+
+                let mut activities: Vec<Activity> = FEED
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|activity| filters.before.is_none_or(|id| activity.id < id))
+                    .filter(|activity| filters.after.is_none_or(|id| activity.id > id))
+                    .cloned()
+                    .collect();
+
+                let limit = if filters.limit > 0 { filters.limit } else { 20 } as usize;
+                let next_cursor = match activities.len() > limit {
+                    true => {
+                        activities.truncate(limit);
+                        activities.last().map(|activity| activity.id)
+                    }
+                    false => None,
+                };
+
+                Ok(FeedPage {
+                    activities,
+                    next_cursor,
+                })
+            }),
+        }
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) open redis connection and remove the feeds of previous runs.
+    /// 2) read the first page of a feed, the recent activities should be cached.
+    /// 3) read it again after a new activity, only the new activity should be read from the wrapped service.
+    /// 4) read the next page, it should be read from the wrapped service.
+    /// 5) read the first page after an activity expired, the feed should be read from the wrapped service and rebuilt.
+    #[test]
+    fn test_activity_service() {
+        // 1) open redis connection and remove the feeds of previous runs.
+        let mut db = must_open_db();
+        must_delete_keys(&mut db, &feed_key(1));
+        must_delete_keys(&mut db, "activity:*");
+
+        let db = Arc::new(Mutex::new(db));
+        let activity_service =
+            ActivityService::new(Arc::clone(&db), Arc::new(mock_activity_service()), 60);
+
+        for id in 1..=3 {
+            push_activity(id);
+        }
+
+        let mut bob = User::new();
+        bob.id = 1;
+        let ctx = Context::with_user(Context::background(), bob);
+
+        let filters = FeedFilter {
+            limit: 2,
+            ..Default::default()
+        };
+
+        // 2) read the first page of a feed, the recent activities should be cached.
+        let page = activity_service
+            .find_feed(ctx.clone(), filters.clone())
+            .unwrap();
+        assert_eq!(page.activities, FEED.lock().unwrap()[..2]);
+        assert_eq!(page.next_cursor, Some(2));
+        assert_eq!(FILTERS.lock().unwrap()[0].after, None);
+        assert_eq!(FILTERS.lock().unwrap()[0].limit, FEED_CACHE_SIZE);
+
+        // 3) read it again after a new activity, only the new activity should be read from the wrapped service.
+        push_activity(4);
+
+        let page = activity_service
+            .find_feed(ctx.clone(), filters.clone())
+            .unwrap();
+        assert_eq!(page.activities[0].id, 4);
+        assert_eq!(page.activities[0], FEED.lock().unwrap()[0]);
+        assert_eq!(page.activities[1].id, 3);
+        assert_eq!(page.next_cursor, Some(3));
+        assert_eq!(FILTERS.lock().unwrap()[1].after, Some(3));
+
+        let page = activity_service
+            .find_feed(
+                ctx.clone(),
+                FeedFilter {
+                    limit: 10,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(page.activities.len(), 4);
+        assert_eq!(page.next_cursor, None);
+
+        // 4) read the next page, it should be read from the wrapped service.
+        let page = activity_service
+            .find_feed(
+                ctx.clone(),
+                FeedFilter {
+                    before: Some(3),
+                    ..filters.clone()
+                },
+            )
+            .unwrap();
+        assert_eq!(page.activities.len(), 2);
+        assert_eq!(FILTERS.lock().unwrap().last().unwrap().before, Some(3));
+
+        // 5) read the first page after an activity expired, the feed should be read from the wrapped service and rebuilt.
+        must_delete_keys(&mut db.lock().unwrap(), &activity_key(3));
+
+        let page = activity_service
+            .find_feed(ctx.clone(), filters.clone())
+            .unwrap();
+        assert_eq!(page.activities[1].id, 3);
+        assert_eq!(FILTERS.lock().unwrap().last().unwrap().limit, 2);
+
+        let page = activity_service.find_feed(ctx, filters).unwrap();
+        assert_eq!(page.activities[1].id, 3);
+        assert_eq!(FILTERS.lock().unwrap().last().unwrap().after, None);
+    }
+}
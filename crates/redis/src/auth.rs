@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::prelude::*;
+use chrono::Duration;
+use redis::Connection;
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_crypto::digest::sha256_hex;
+use openmusicgang_crypto::random::random_token;
+use openmusicgang_entity::session::Session;
+use openmusicgang_entity::user::User;
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::auth_service::AuthService as AuthServiceTrait;
+use openmusicgang_service::user_service::UserService as UserServiceTrait;
+
+use crate::redis::DB;
+
+/// SESSION_TOKEN_BYTES is the number of random bytes of a session token.
+static SESSION_TOKEN_BYTES: usize = 32;
+
+/// Returns the key of the hash holding the session identified by the token.
+fn session_key(token: &str) -> String {
+    format!("session:{}", token)
+}
+
+/// Returns the id of the session identified by the token.
+fn session_id(token: &str) -> String {
+    sha256_hex(token.as_bytes())
+}
+
+/// Returns the key of the set holding the session tokens of the user.
+fn user_sessions_key(user_id: i64) -> String {
+    format!("user_sessions:{}", user_id)
+}
+
+/// AuthService is a struct that implements the AuthServiceTrait for the redis crate.
+///
+/// Sessions are stored as hashes that expire after `ttl` seconds of inactivity,
+/// the expiration is extended every time the session is resolved.
+pub struct AuthService {
+    db: Arc<Mutex<DB>>,
+    user_service: Arc<dyn UserServiceTrait + Send + Sync>,
+    ttl: u64,
+}
+
+impl AuthService {
+    /// Create a new AuthService struct
+    pub fn new(
+        db: Arc<Mutex<DB>>,
+        user_service: Arc<dyn UserServiceTrait + Send + Sync>,
+        ttl: u64,
+    ) -> AuthService {
+        AuthService {
+            db,
+            user_service,
+            ttl,
+        }
+    }
+}
+
+impl AuthServiceTrait for AuthService {
+    /// Verifies the credentials of the user and opens a new session.
+    fn login(&self, ctx: AppContext, email: String, password: String) -> Result<Session, Error> {
+        let user = self
+            .user_service
+            .verify_password(ctx.clone(), email, password)?;
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on redis".to_string(),
+            )
+        })?;
+
+        create_session(ctx, mutex_db.conn()?, user.id, self.ttl)
+    }
+
+    /// Closes a session.
+    fn logout(&self, ctx: AppContext, token: String) -> Result<(), Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on redis".to_string(),
+            )
+        })?;
+
+        delete_session(ctx, mutex_db.conn()?, token)
+    }
+
+    /// Resolves a session and its user, a session opened before the sessions of the user were revoked is closed.
+    fn resolve_session(&self, ctx: AppContext, token: String) -> Result<(Session, User), Error> {
+        let session = {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on redis".to_string(),
+                )
+            })?;
+
+            touch_session(ctx.clone(), mutex_db.conn()?, token.clone(), self.ttl)?
+        };
+
+        match self
+            .user_service
+            .find_user_by_id(ctx.clone(), session.user_id)
+        {
+            Ok(user) if !user.is_session_revoked(&session.id, session.created_at) => {
+                Ok((session, user))
+            }
+            Ok(_) => {
+                self.logout(ctx, token)?;
+
+                Err(Error::new(
+                    ErrorCode::EUNAUTHORIZED,
+                    "Invalid or expired session".to_string(),
+                ))
+            }
+            Err(error) if error.code == ErrorCode::ENOTFOUND => {
+                self.logout(ctx, token)?;
+
+                Err(Error::new(
+                    ErrorCode::EUNAUTHORIZED,
+                    "Invalid or expired session".to_string(),
+                ))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns the open sessions of a user.
+    fn find_sessions(&self, ctx: AppContext, user_id: i64) -> Result<Vec<Session>, Error> {
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on redis".to_string(),
+            )
+        })?;
+
+        find_sessions(ctx, mutex_db.conn()?, user_id)
+    }
+}
+
+/// create_session stores a new session for the user.
+fn create_session(
+    _ctx: AppContext,
+    conn: &mut Connection,
+    user_id: i64,
+    ttl: u64,
+) -> Result<Session, Error> {
+    let now = Utc::now();
+
+    let token = random_token(SESSION_TOKEN_BYTES);
+
+    let session = Session {
+        id: session_id(&token),
+        token,
+        user_id,
+        created_at: now,
+        expires_at: now + Duration::seconds(ttl as i64),
+    };
+
+    redis::pipe()
+        .atomic()
+        .cmd("HSET")
+        .arg(session_key(&session.token))
+        .arg("user_id")
+        .arg(user_id)
+        .arg("created_at_ms")
+        .arg(now.timestamp_millis())
+        .ignore()
+        .cmd("EXPIRE")
+        .arg(session_key(&session.token))
+        .arg(ttl)
+        .ignore()
+        .cmd("SADD")
+        .arg(user_sessions_key(user_id))
+        .arg(&session.token)
+        .ignore()
+        .cmd("EXPIRE")
+        .arg(user_sessions_key(user_id))
+        .arg(ttl)
+        .ignore()
+        .query::<()>(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    Ok(session)
+}
+
+/// delete_session removes the session identified by the token.
+///
+/// Deleting a session that does not exist is not an error.
+fn delete_session(_ctx: AppContext, conn: &mut Connection, token: String) -> Result<(), Error> {
+    let user_id: Option<i64> = redis::cmd("HGET")
+        .arg(session_key(&token))
+        .arg("user_id")
+        .query(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut pipe = redis::pipe();
+    pipe.atomic().cmd("DEL").arg(session_key(&token)).ignore();
+
+    if let Some(user_id) = user_id {
+        pipe.cmd("SREM")
+            .arg(user_sessions_key(user_id))
+            .arg(&token)
+            .ignore();
+    }
+
+    pipe.query::<()>(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))
+}
+
+/// find_session returns the session identified by the token, or None if it does not exist.
+fn find_session(
+    _ctx: AppContext,
+    conn: &mut Connection,
+    token: String,
+) -> Result<Option<Session>, Error> {
+    let (fields, ttl): (HashMap<String, i64>, i64) = redis::pipe()
+        .cmd("HGETALL")
+        .arg(session_key(&token))
+        .cmd("TTL")
+        .arg(session_key(&token))
+        .query(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    // sessions opened before the creation time was kept to the millisecond only have it in seconds.
+    let (user_id, created_at) = match (
+        fields.get("user_id"),
+        fields.get("created_at_ms"),
+        fields.get("created_at"),
+    ) {
+        (Some(user_id), Some(created_at_ms), _) => {
+            (*user_id, Utc.timestamp_millis_opt(*created_at_ms))
+        }
+        (Some(user_id), None, Some(created_at)) => (*user_id, Utc.timestamp_opt(*created_at, 0)),
+        _ => return Ok(None),
+    };
+
+    let created_at = created_at
+        .single()
+        .ok_or_else(|| Error::new(ErrorCode::EINTERNAL, "Invalid session".to_string()))?;
+
+    Ok(Some(Session {
+        id: session_id(&token),
+        token,
+        user_id,
+        created_at,
+        expires_at: Utc::now() + Duration::seconds(ttl.max(0)),
+    }))
+}
+
+/// touch_session returns the session identified by the token and extends its expiration.
+///
+/// Returns EUNAUTHORIZED if the session does not exist or is expired.
+fn touch_session(
+    ctx: AppContext,
+    conn: &mut Connection,
+    token: String,
+    ttl: u64,
+) -> Result<Session, Error> {
+    let mut session = find_session(ctx, conn, token)?.ok_or_else(|| {
+        Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "Invalid or expired session".to_string(),
+        )
+    })?;
+
+    redis::pipe()
+        .atomic()
+        .cmd("EXPIRE")
+        .arg(session_key(&session.token))
+        .arg(ttl)
+        .ignore()
+        .cmd("EXPIRE")
+        .arg(user_sessions_key(session.user_id))
+        .arg(ttl)
+        .ignore()
+        .query::<()>(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    session.expires_at = Utc::now() + Duration::seconds(ttl as i64);
+
+    Ok(session)
+}
+
+/// find_sessions returns the open sessions of the user, oldest first.
+///
+/// Tokens of expired sessions are removed from the set of the user.
+///
+/// Returns EUNAUTHORIZED if the user in the context is not the given user.
+fn find_sessions(
+    ctx: AppContext,
+    conn: &mut Connection,
+    user_id: i64,
+) -> Result<Vec<Session>, Error> {
+    if Context::user_id_from_context(ctx.clone()) != user_id {
+        return Err(Error::new(
+            ErrorCode::EUNAUTHORIZED,
+            "You do not have permission to list the sessions of this user".to_string(),
+        ));
+    }
+
+    let tokens: Vec<String> = redis::cmd("SMEMBERS")
+        .arg(user_sessions_key(user_id))
+        .query(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    let mut sessions = vec![];
+
+    for token in tokens {
+        match find_session(ctx.clone(), conn, token.clone())? {
+            Some(session) => sessions.push(session),
+            None => redis::cmd("SREM")
+                .arg(user_sessions_key(user_id))
+                .arg(&token)
+                .query::<()>(conn)
+                .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?,
+        }
+    }
+
+    sessions.sort_by_key(|session| session.created_at);
+
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use openmusicgang_mock::user::UserService as MockUserService;
+    use openmusicgang_service::auth_service::with_session;
+
+    use crate::test_utils::{must_delete_keys, must_open_db};
+
+    use super::*;
+
+    /// REVOKED_AT is the time in milliseconds the sessions of the user 1 were revoked at in the revoking mock user service.
+    static REVOKED_AT: AtomicI64 = AtomicI64::new(0);
+
+    fn bob() -> User {
+        let mut user = User::new();
+        user.id = 1;
+        user.name = "Bob Smith".to_string();
+        user.email = "bob.smith@test.com".to_string();
+        user
+    }
+
+    fn mock_user_service() -> MockUserService {
+        MockUserService {
+            create_user_fn: None,
+            delete_user_fn: None,
+            update_user_fn: None,
+            find_user_by_id_fn: Some(|_, id| match id {
+                1 => Ok(bob()),
+                _ => Err(Error::new(
+                    ErrorCode::ENOTFOUND,
+                    "User not found".to_string(),
+                )),
+            }),
+            find_user_by_email_fn: None,
+            find_users_fn: None,
+            verify_password_fn: Some(|_, email, password| {
+                if email == "bob.smith@test.com" && password == "password" {
+                    return Ok(bob());
+                }
+                Err(Error::new(
+                    ErrorCode::EUNAUTHORIZED,
+                    "Invalid email or password".to_string(),
+                ))
+            }),
+            confirm_email_fn: None,
+            resend_verification_email_fn: None,
+            change_email_fn: None,
+            change_password_fn: None,
+            request_password_reset_fn: None,
+            reset_password_fn: None,
+        }
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) open redis connection and remove the sessions of previous runs.
+    /// 2) login with a wrong password, error should be EUNAUTHORIZED.
+    /// 3) login and resolve the session, the user should be returned.
+    /// 4) resolve the session into a context carrying the user.
+    /// 5) resolve the session after its ttl was lowered, the expiration should be extended.
+    /// 6) login again and list the sessions of the user.
+    /// 7) list the sessions of the user with another context, error should be EUNAUTHORIZED.
+    /// 8) logout and resolve the session, error should be EUNAUTHORIZED.
+    /// 9) resolve a session opened when the sessions of the user were revoked, then one opened before, error should be EUNAUTHORIZED.
+    #[test]
+    fn test_auth_service() {
+        // 1) open redis connection and remove the sessions of previous runs.
+        let mut db = must_open_db();
+        must_delete_keys(&mut db, &user_sessions_key(1));
+
+        let db = Arc::new(Mutex::new(db));
+        let auth_service = AuthService::new(Arc::clone(&db), Arc::new(mock_user_service()), 60);
+
+        // 2) login with a wrong password, error should be EUNAUTHORIZED.
+        let res = auth_service.login(
+            Context::background(),
+            "bob.smith@test.com".to_string(),
+            "wrong password".to_string(),
+        );
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 3) login and resolve the session, the user should be returned.
+        let res = auth_service.login(
+            Context::background(),
+            "bob.smith@test.com".to_string(),
+            "password".to_string(),
+        );
+        assert!(res.is_ok());
+
+        let session = res.unwrap();
+        assert_eq!(session.user_id, 1);
+        assert_eq!(session.token.len(), SESSION_TOKEN_BYTES * 2);
+
+        let res = auth_service.resolve_session(Context::background(), session.token.clone());
+        assert!(res.is_ok());
+
+        let (resolved, user) = res.unwrap();
+        assert_eq!(resolved.token, session.token);
+        assert_eq!(
+            resolved.created_at.timestamp(),
+            session.created_at.timestamp()
+        );
+        assert_eq!(user.id, 1);
+        assert_eq!(user.email, "bob.smith@test.com");
+
+        // 4) resolve the session into a context carrying the user.
+        let ctx = with_session(&auth_service, Context::background(), session.token.clone());
+        assert!(ctx.is_ok());
+
+        let ctx = ctx.unwrap();
+        assert_eq!(Context::user_id_from_context(ctx.clone()), 1);
+        assert_eq!(
+            Context::session_id_from_context(ctx),
+            Some(session.id.clone())
+        );
+
+        let res = with_session(&auth_service, Context::background(), "invalid".to_string());
+        assert!(res.is_err());
+        assert_eq!(res.err().unwrap().code, ErrorCode::EUNAUTHORIZED);
+
+        // 5) resolve the session after its ttl was lowered, the expiration should be extended.
+        {
+            let mut mutex_db = db.lock().unwrap();
+            let conn = mutex_db.conn().unwrap();
+
+            redis::cmd("EXPIRE")
+                .arg(session_key(&session.token))
+                .arg(5)
+                .query::<()>(conn)
+                .unwrap();
+        }
+
+        assert!(auth_service
+            .resolve_session(Context::background(), session.token.clone())
+            .is_ok());
+
+        {
+            let mut mutex_db = db.lock().unwrap();
+            let conn = mutex_db.conn().unwrap();
+
+            let ttl: i64 = redis::cmd("TTL")
+                .arg(session_key(&session.token))
+                .query(conn)
+                .unwrap();
+            assert!(ttl > 5);
+        }
+
+        // 6) login again and list the sessions of the user.
+        let another = auth_service
+            .login(
+                Context::background(),
+                "bob.smith@test.com".to_string(),
+                "password".to_string(),
+            )
+            .unwrap();
+
+        let ctx = Context::with_user(Context::background(), bob());
+        let sessions = auth_service.find_sessions(ctx.clone(), 1).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().any(|s| s.token == session.token));
+        assert!(sessions.iter().any(|s| s.token == another.token));
+
+        // 7) list the sessions of the user with another context, error should be EUNAUTHORIZED.
+        let res = auth_service.find_sessions(Context::background(), 1);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        // 8) logout and resolve the session, error should be EUNAUTHORIZED.
+        assert!(auth_service
+            .logout(Context::background(), session.token.clone())
+            .is_ok());
+
+        let res = auth_service.resolve_session(Context::background(), session.token.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let sessions = auth_service.find_sessions(ctx.clone(), 1).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].token, another.token);
+
+        // 9) resolve a session opened when the sessions of the user were revoked, then one opened before, error should be EUNAUTHORIZED.
+        let revoked_user_service = MockUserService {
+            find_user_by_id_fn: Some(|_, _| {
+                let mut user = bob();
+                user.sessions_revoked_at =
+                    DateTime::from_timestamp_millis(REVOKED_AT.load(Ordering::SeqCst));
+                Ok(user)
+            }),
+            ..mock_user_service()
+        };
+        let auth_service = AuthService::new(Arc::clone(&db), Arc::new(revoked_user_service), 60);
+
+        REVOKED_AT.store(another.created_at.timestamp_millis(), Ordering::SeqCst);
+        assert!(auth_service
+            .resolve_session(Context::background(), another.token.clone())
+            .is_ok());
+
+        REVOKED_AT.store(another.created_at.timestamp_millis() + 1, Ordering::SeqCst);
+        let res = auth_service.resolve_session(Context::background(), another.token.clone());
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EUNAUTHORIZED);
+
+        let sessions = auth_service.find_sessions(ctx, 1).unwrap();
+        assert!(sessions.is_empty());
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use redis::Connection;
+
+use openmusicgang_app::context::{AppContext, Context};
+use openmusicgang_entity::follow::{Follow, FollowCounts, FollowTarget};
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::follow_service::{FollowFilter, FollowService as FollowServiceTrait};
+
+use crate::redis::DB;
+
+/// Returns the key of the hash holding the cached follow counts of a user or a gang.
+fn follow_counts_key(target: FollowTarget) -> String {
+    format!("follow_counts:{}", target)
+}
+
+/// FollowService is a struct that implements the FollowServiceTrait for the redis crate.
+///
+/// Follows are stored by the wrapped service, the follow counts of users and gangs are cached
+/// as hashes that expire after `ttl` seconds and are deleted every time a follow changes them.
+pub struct FollowService {
+    db: Arc<Mutex<DB>>,
+    follow_service: Arc<dyn FollowServiceTrait + Send + Sync>,
+    ttl: u64,
+}
+
+impl FollowService {
+    /// Create a new FollowService struct
+    pub fn new(
+        db: Arc<Mutex<DB>>,
+        follow_service: Arc<dyn FollowServiceTrait + Send + Sync>,
+        ttl: u64,
+    ) -> FollowService {
+        FollowService {
+            db,
+            follow_service,
+            ttl,
+        }
+    }
+}
+
+impl FollowServiceTrait for FollowService {
+    /// Follows a user or a gang and invalidates the counts of the follower and the target.
+    fn follow(&self, ctx: AppContext, target: FollowTarget) -> Result<Follow, Error> {
+        let follow = self.follow_service.follow(ctx.clone(), target)?;
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on redis".to_string(),
+            )
+        })?;
+
+        delete_follow_counts(
+            ctx,
+            mutex_db.conn()?,
+            &[FollowTarget::User(follow.follower_id), target],
+        )?;
+
+        Ok(follow)
+    }
+
+    /// Unfollows a user or a gang and invalidates the counts of the follower and the target.
+    fn unfollow(&self, ctx: AppContext, target: FollowTarget) -> Result<(), Error> {
+        self.follow_service.unfollow(ctx.clone(), target)?;
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on redis".to_string(),
+            )
+        })?;
+
+        let user_id = Context::user_id_from_context(ctx.clone());
+
+        delete_follow_counts(
+            ctx,
+            mutex_db.conn()?,
+            &[FollowTarget::User(user_id), target],
+        )
+    }
+
+    /// Returns a vector of follows based on passed filters, also returns the total number of follows.
+    fn find_follows(
+        &self,
+        ctx: AppContext,
+        filters: FollowFilter,
+    ) -> Result<(Vec<Follow>, i64), Error> {
+        self.follow_service.find_follows(ctx, filters)
+    }
+
+    /// Returns true if two users follow each other.
+    fn is_mutual_follow(
+        &self,
+        ctx: AppContext,
+        user_id: i64,
+        other_id: i64,
+    ) -> Result<bool, Error> {
+        self.follow_service.is_mutual_follow(ctx, user_id, other_id)
+    }
+
+    /// Returns the follow counts of a user or a gang, from the cache if they were counted recently.
+    fn find_follow_counts(
+        &self,
+        ctx: AppContext,
+        target: FollowTarget,
+    ) -> Result<FollowCounts, Error> {
+        {
+            let mut mutex_db = self.db.lock().map_err(|_| {
+                Error::new(
+                    ErrorCode::EINTERNAL,
+                    "Could not acquire lock on redis".to_string(),
+                )
+            })?;
+
+            if let Some(counts) = find_follow_counts(ctx.clone(), mutex_db.conn()?, target)? {
+                return Ok(counts);
+            }
+        }
+
+        let counts = self
+            .follow_service
+            .find_follow_counts(ctx.clone(), target)?;
+
+        let mut mutex_db = self.db.lock().map_err(|_| {
+            Error::new(
+                ErrorCode::EINTERNAL,
+                "Could not acquire lock on redis".to_string(),
+            )
+        })?;
+
+        cache_follow_counts(ctx, mutex_db.conn()?, target, counts, self.ttl)?;
+
+        Ok(counts)
+    }
+}
+
+/// find_follow_counts returns the cached follow counts of a user or a gang, or None if they are not cached.
+fn find_follow_counts(
+    _ctx: AppContext,
+    conn: &mut Connection,
+    target: FollowTarget,
+) -> Result<Option<FollowCounts>, Error> {
+    let fields: HashMap<String, i64> = redis::cmd("HGETALL")
+        .arg(follow_counts_key(target))
+        .query(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))?;
+
+    match (fields.get("followers"), fields.get("following")) {
+        (Some(followers), Some(following)) => Ok(Some(FollowCounts {
+            followers: *followers,
+            following: *following,
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// cache_follow_counts stores the follow counts of a user or a gang for `ttl` seconds.
+fn cache_follow_counts(
+    _ctx: AppContext,
+    conn: &mut Connection,
+    target: FollowTarget,
+    counts: FollowCounts,
+    ttl: u64,
+) -> Result<(), Error> {
+    redis::pipe()
+        .atomic()
+        .cmd("HSET")
+        .arg(follow_counts_key(target))
+        .arg("followers")
+        .arg(counts.followers)
+        .arg("following")
+        .arg(counts.following)
+        .ignore()
+        .cmd("EXPIRE")
+        .arg(follow_counts_key(target))
+        .arg(ttl)
+        .ignore()
+        .query::<()>(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))
+}
+
+/// delete_follow_counts removes the cached follow counts of users and gangs.
+fn delete_follow_counts(
+    _ctx: AppContext,
+    conn: &mut Connection,
+    targets: &[FollowTarget],
+) -> Result<(), Error> {
+    let keys: Vec<String> = targets.iter().map(|t| follow_counts_key(*t)).collect();
+
+    redis::cmd("DEL")
+        .arg(keys)
+        .query::<()>(conn)
+        .map_err(|error| Error::new(ErrorCode::EINTERNAL, error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    use openmusicgang_entity::user::User;
+    use openmusicgang_mock::follow::FollowService as MockFollowService;
+
+    use crate::test_utils::{must_delete_keys, must_open_db};
+
+    use super::*;
+
+    /// FOLLOWERS is the number of followers of the user 1 in the mock follow service.
+    static FOLLOWERS: AtomicI64 = AtomicI64::new(3);
+
+    /// COUNTS is the number of times the mock follow service counted follows.
+    static COUNTS: AtomicI64 = AtomicI64::new(0);
+
+    fn mock_follow_service() -> MockFollowService {
+        MockFollowService {
+            follow_fn: Some(|ctx, target| {
+                FOLLOWERS.fetch_add(1, Ordering::SeqCst);
+                Ok(Follow::new(Context::user_id_from_context(ctx), target))
+            }),
+            unfollow_fn: Some(|_, target| match target {
+                FollowTarget::User(1) => {
+                    FOLLOWERS.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }
+                _ => Err(Error::new(
+                    ErrorCode::ENOTFOUND,
+                    "You do not follow this gang".to_string(),
+                )),
+            }),
+            find_follow_counts_fn: Some(|_, target| {
+                COUNTS.fetch_add(1, Ordering::SeqCst);
+                match target {
+                    FollowTarget::User(1) => Ok(FollowCounts {
+                        followers: FOLLOWERS.load(Ordering::SeqCst),
+                        following: 2,
+                    }),
+                    _ => Ok(FollowCounts::default()),
+                }
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) open redis connection and remove the counts of previous runs.
+    /// 2) find the counts of a user twice, they should be counted once.
+    /// 3) follow the user, the counts should be counted again.
+    /// 4) unfollow a gang not followed, error should be ENOTFOUND and the counts should stay cached.
+    /// 5) unfollow the user, the counts should be counted again.
+    #[test]
+    fn test_follow_service() {
+        // 1) open redis connection and remove the counts of previous runs.
+        let mut db = must_open_db();
+        must_delete_keys(&mut db, "follow_counts:*");
+
+        let db = Arc::new(Mutex::new(db));
+        let follow_service =
+            FollowService::new(Arc::clone(&db), Arc::new(mock_follow_service()), 60);
+
+        let mut mark = User::new();
+        mark.id = 2;
+        let ctx = Context::with_user(Context::background(), mark);
+
+        // 2) find the counts of a user twice, they should be counted once.
+        for _ in 0..2 {
+            let counts = follow_service
+                .find_follow_counts(Context::background(), FollowTarget::User(1))
+                .unwrap();
+            assert_eq!(counts.followers, 3);
+            assert_eq!(counts.following, 2);
+        }
+        assert_eq!(COUNTS.load(Ordering::SeqCst), 1);
+
+        {
+            let mut mutex_db = db.lock().unwrap();
+            let conn = mutex_db.conn().unwrap();
+
+            let ttl: i64 = redis::cmd("TTL")
+                .arg(follow_counts_key(FollowTarget::User(1)))
+                .query(conn)
+                .unwrap();
+            assert!(ttl > 0 && ttl <= 60);
+        }
+
+        // 3) follow the user, the counts should be counted again.
+        let follow = follow_service
+            .follow(ctx.clone(), FollowTarget::User(1))
+            .unwrap();
+        assert_eq!(follow.follower_id, 2);
+
+        let counts = follow_service
+            .find_follow_counts(Context::background(), FollowTarget::User(1))
+            .unwrap();
+        assert_eq!(counts.followers, 4);
+        assert_eq!(COUNTS.load(Ordering::SeqCst), 2);
+
+        // 4) unfollow a gang not followed, error should be ENOTFOUND and the counts should stay cached.
+        let res = follow_service.unfollow(ctx.clone(), FollowTarget::Gang(1));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        follow_service
+            .find_follow_counts(Context::background(), FollowTarget::User(1))
+            .unwrap();
+        assert_eq!(COUNTS.load(Ordering::SeqCst), 2);
+
+        // 5) unfollow the user, the counts should be counted again.
+        follow_service.unfollow(ctx, FollowTarget::User(1)).unwrap();
+
+        let counts = follow_service
+            .find_follow_counts(Context::background(), FollowTarget::User(1))
+            .unwrap();
+        assert_eq!(counts.followers, 3);
+        assert_eq!(COUNTS.load(Ordering::SeqCst), 3);
+    }
+}
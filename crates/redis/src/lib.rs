@@ -1 +1,32 @@
+pub mod activity;
+pub mod auth;
+pub mod follow;
 pub mod redis;
+
+#[cfg(test)]
+pub mod test_utils {
+    use crate::redis::DB;
+
+    #[allow(dead_code)]
+    pub fn must_open_db() -> DB {
+        let dsn =
+            openmusicgang_config::app_config::AppConfig::new("../../config.toml").get_redis_dsn();
+        let mut db = DB::new(dsn);
+        db.open().unwrap();
+        db
+    }
+
+    #[allow(dead_code)]
+    pub fn must_delete_keys(db: &mut DB, pattern: &str) {
+        let conn = db.conn().unwrap();
+
+        let keys: Vec<String> = redis::cmd("KEYS").arg(pattern).query(conn).unwrap();
+        if keys.is_empty() {
+            return;
+        }
+
+        if let Err(error) = redis::cmd("DEL").arg(keys).query::<()>(conn) {
+            panic!("{}", error);
+        }
+    }
+}
@@ -11,22 +11,36 @@ static THE_RESOURCE: Lazy<Mutex<()>> = Lazy::new(Mutex::default);
 #[allow(dead_code)]
 static REDIS_CMD_PING: &str = "PING";
 
+/// DB is a struct that contains the connection to redis
 pub struct DB {
     conn: Option<Connection>,
     dsn: String,
 }
 
 impl DB {
+    /// Create a new DB struct
     pub fn new(dsn: String) -> DB {
-        DB {
-            conn: None,
-            dsn: dsn,
+        DB { conn: None, dsn }
+    }
+
+    /// Returns the connection to redis
+    pub fn conn(&mut self) -> Result<&mut Connection, Error> {
+        match self.conn {
+            Some(ref mut conn) => Ok(conn),
+            None => Err(Error::new(
+                ErrorCode::EINTERNAL,
+                "No connection to redis".to_string(),
+            )),
         }
     }
 
+    /// Connect to redis
     pub fn open(&mut self) -> Result<(), Error> {
-        if self.dsn == "" {
-            return Err(Error::new(ErrorCode::EINVALID, format!("No DSN provided")));
+        if self.dsn.is_empty() {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "No DSN provided".to_string(),
+            ));
         }
 
         let _shared = THE_RESOURCE.lock();
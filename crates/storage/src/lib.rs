@@ -0,0 +1,4 @@
+pub mod local;
+pub mod s3;
+mod sigv4;
+mod staging;
@@ -0,0 +1,287 @@
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use chrono::prelude::*;
+
+use openmusicgang_entity::blob::{is_blob_key, is_blob_key_prefix, Blob};
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::blob_store::{BlobStore as BlobStoreTrait, ByteRange};
+
+use crate::staging::{io_error, StagedFile};
+
+/// BlobStore is a struct that implements the BlobStoreTrait on the local filesystem.
+///
+/// Blobs are stored under the root directory as `ab/cd/abcd...`, where `abcd...` is the key of the blob.
+/// Uploads are written to `tmp/` first and renamed once complete, so a blob is never partially visible.
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    /// Create a new BlobStore struct, creating the root directory if it does not exist.
+    pub fn new(root: impl Into<PathBuf>) -> Result<BlobStore, Error> {
+        let root = root.into();
+
+        fs::create_dir_all(root.join("tmp")).map_err(io_error)?;
+
+        Ok(BlobStore { root })
+    }
+
+    /// Returns the path of the blob with the given key.
+    fn blob_path(&self, key: &str) -> PathBuf {
+        self.root.join(&key[0..2]).join(&key[2..4]).join(key)
+    }
+}
+
+impl BlobStoreTrait for BlobStore {
+    /// Stores a blob, the content is discarded if a blob with the same key already exists.
+    fn put_blob(&self, reader: &mut dyn Read) -> Result<Blob, Error> {
+        let staged = StagedFile::create(&self.root.join("tmp"), reader)?;
+        let path = self.blob_path(&staged.key);
+
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(io_error)?;
+            }
+
+            staged.persist(&path)?;
+        }
+
+        blob_from_path(&path)
+    }
+
+    /// Returns a reader of a blob.
+    fn get_blob(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<Box<dyn Read + Send + Sync>, Error> {
+        validate_key(key)?;
+
+        let mut file = File::open(self.blob_path(key)).map_err(io_error)?;
+
+        let range = match range {
+            Some(range) => range,
+            None => return Ok(Box::new(file)),
+        };
+
+        let size = file.metadata().map_err(io_error)?.len();
+
+        if range.offset > size {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "range starts after the end of the blob".to_string(),
+            ));
+        }
+
+        file.seek(SeekFrom::Start(range.offset)).map_err(io_error)?;
+
+        let length = range.length.unwrap_or(size - range.offset);
+
+        Ok(Box::new(file.take(length)))
+    }
+
+    /// Deletes a blob.
+    fn delete_blob(&self, key: &str) -> Result<(), Error> {
+        validate_key(key)?;
+
+        fs::remove_file(self.blob_path(key)).map_err(io_error)
+    }
+
+    /// Returns a blob.
+    fn stat_blob(&self, key: &str) -> Result<Blob, Error> {
+        validate_key(key)?;
+
+        blob_from_path(&self.blob_path(key))
+    }
+
+    /// Returns the blobs whose key starts with the prefix.
+    fn list_blobs(&self, prefix: &str) -> Result<Vec<Blob>, Error> {
+        if !is_blob_key_prefix(prefix) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "prefix must be made of lowercase hex digits".to_string(),
+            ));
+        }
+
+        let mut blobs = vec![];
+
+        for first in matching_entries(&self.root, prefix, 0)? {
+            for second in matching_entries(&first, prefix, 2)? {
+                for path in matching_entries(&second, prefix, 0)? {
+                    blobs.push(blob_from_path(&path)?);
+                }
+            }
+        }
+
+        blobs.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Ok(blobs)
+    }
+}
+
+/// Returns EINVALID if the key is not a valid blob key.
+fn validate_key(key: &str) -> Result<(), Error> {
+    if !is_blob_key(key) {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "key must be a SHA-256 hex digest".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns the entries of the directory whose name can lead to a key starting with the prefix.
+///
+/// `skip` is the number of characters of the prefix already matched by the parent directories.
+fn matching_entries(dir: &Path, prefix: &str, skip: usize) -> Result<Vec<PathBuf>, Error> {
+    let prefix = prefix.get(skip..).unwrap_or("");
+    let mut entries = vec![];
+
+    for entry in fs::read_dir(dir).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        // the tmp directory and the partial uploads are never part of the listing.
+        if !is_blob_key_prefix(&name) {
+            continue;
+        }
+
+        if name.starts_with(prefix) || prefix.starts_with(&name) {
+            entries.push(entry.path());
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Returns the blob stored at the given path.
+fn blob_from_path(path: &Path) -> Result<Blob, Error> {
+    let metadata = fs::metadata(path).map_err(io_error)?;
+    let modified = metadata.modified().map_err(io_error)?;
+
+    Ok(Blob {
+        key: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size: metadata.len(),
+        created_at: DateTime::<Utc>::from(modified),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_crypto::digest::sha256_hex;
+    use openmusicgang_crypto::random::random_token;
+
+    use super::*;
+
+    /// Reads the whole content of a reader.
+    fn must_read(mut reader: Box<dyn Read + Send + Sync>) -> Vec<u8> {
+        let mut buf = vec![];
+        reader.read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    /// ## Simple workflow
+    ///
+    /// 1) create a store in a new directory.
+    /// 2) put a blob, its key should be the digest of its content.
+    /// 3) put the same content again, the blob should be deduplicated.
+    /// 4) get the whole blob and ranges of it.
+    /// 5) list the blobs by prefix.
+    /// 6) use an invalid key, error should be EINVALID.
+    /// 7) delete a blob, then get it, error should be ENOTFOUND.
+    #[test]
+    fn test_local_blob_store() {
+        // 1) create a store in a new directory.
+        let root = std::env::temp_dir().join(format!("openmusicgang-{}", random_token(8)));
+        let store = BlobStore::new(&root).unwrap();
+
+        // 2) put a blob, its key should be the digest of its content.
+        let content = b"RIFF....WAVEfmt not really a wave file".to_vec();
+
+        let blob = store.put_blob(&mut content.as_slice()).unwrap();
+        assert_eq!(blob.key, sha256_hex(&content));
+        assert_eq!(blob.size, content.len() as u64);
+        assert!(root
+            .join(&blob.key[0..2])
+            .join(&blob.key[2..4])
+            .join(&blob.key)
+            .exists());
+
+        // 3) put the same content again, the blob should be deduplicated.
+        let again = store.put_blob(&mut content.as_slice()).unwrap();
+        assert_eq!(again.key, blob.key);
+        assert_eq!(fs::read_dir(root.join("tmp")).unwrap().count(), 0);
+
+        let other = store.put_blob(&mut &b"another stem"[..]).unwrap();
+        assert_ne!(other.key, blob.key);
+
+        // 4) get the whole blob and ranges of it.
+        let res = store.get_blob(&blob.key, None).unwrap();
+        assert_eq!(must_read(res), content);
+
+        let range = ByteRange {
+            offset: 4,
+            length: Some(4),
+        };
+        let res = store.get_blob(&blob.key, Some(range)).unwrap();
+        assert_eq!(must_read(res), b"....");
+
+        let range = ByteRange {
+            offset: 12,
+            length: None,
+        };
+        let res = store.get_blob(&blob.key, Some(range)).unwrap();
+        assert_eq!(must_read(res), &content[12..]);
+
+        let range = ByteRange {
+            offset: 1000,
+            length: None,
+        };
+        let res = store.get_blob(&blob.key, Some(range));
+        assert!(res.is_err());
+        assert_eq!(res.err().unwrap().code, ErrorCode::EINVALID);
+
+        // 5) list the blobs by prefix.
+        let blobs = store.list_blobs("").unwrap();
+        assert_eq!(blobs.len(), 2);
+        assert!(blobs[0].key < blobs[1].key);
+
+        let blobs = store.list_blobs(&blob.key[0..3]).unwrap();
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0].key, blob.key);
+
+        let blobs = store.list_blobs(&blob.key).unwrap();
+        assert_eq!(blobs.len(), 1);
+
+        // 6) use an invalid key, error should be EINVALID.
+        let res = store.stat_blob("../../etc/passwd");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        let res = store.list_blobs("../");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+
+        // 7) delete a blob, then get it, error should be ENOTFOUND.
+        store.delete_blob(&blob.key).unwrap();
+
+        let res = store.stat_blob(&blob.key);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        let res = store.delete_blob(&blob.key);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().code, ErrorCode::ENOTFOUND);
+
+        assert_eq!(store.list_blobs("").unwrap().len(), 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
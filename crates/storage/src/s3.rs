@@ -0,0 +1,436 @@
+use std::fs::File;
+use std::io::Read;
+
+use chrono::prelude::*;
+
+use openmusicgang_entity::blob::{is_blob_key, is_blob_key_prefix, Blob};
+use openmusicgang_err::error::{Error, ErrorCode};
+use openmusicgang_service::blob_store::{BlobStore as BlobStoreTrait, ByteRange};
+
+use crate::sigv4::{amz_date, authorization, canonical_query, Credentials, Request};
+use crate::sigv4::{uri_encode, EMPTY_PAYLOAD_HASH};
+use crate::staging::{io_error, StagedFile};
+
+/// BlobStore is a struct that implements the BlobStoreTrait on an S3-compatible object storage.
+///
+/// Blobs are stored as objects named after their key at the root of the bucket,
+/// the bucket is addressed in the path so that the store works with MinIO as well as AWS S3.
+pub struct BlobStore {
+    endpoint: String,
+    host: String,
+    bucket: String,
+    credentials: Credentials,
+    agent: ureq::Agent,
+}
+
+impl BlobStore {
+    /// Create a new BlobStore struct.
+    pub fn new(
+        endpoint: &str,
+        region: &str,
+        bucket: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> BlobStore {
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        let host = endpoint
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .to_string();
+
+        BlobStore {
+            endpoint,
+            host,
+            bucket: bucket.to_string(),
+            credentials: Credentials {
+                access_key: access_key.to_string(),
+                secret_key: secret_key.to_string(),
+                region: region.to_string(),
+            },
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    /// Returns a signed request to the given path of the bucket.
+    fn request(
+        &self,
+        method: &str,
+        key: &str,
+        query: &[(String, String)],
+        headers: &[(&str, String)],
+        payload_hash: &str,
+    ) -> ureq::Request {
+        let path = match key {
+            "" => format!("/{}", uri_encode(&self.bucket, true)),
+            _ => format!(
+                "/{}/{}",
+                uri_encode(&self.bucket, true),
+                uri_encode(key, false)
+            ),
+        };
+
+        let now = Utc::now();
+
+        let mut headers = headers.to_vec();
+        headers.push(("host", self.host.clone()));
+        headers.push(("x-amz-content-sha256", payload_hash.to_string()));
+        headers.push(("x-amz-date", amz_date(now)));
+
+        let signed = Request {
+            method,
+            path: &path,
+            query,
+            headers: &headers,
+            payload_hash,
+        };
+        let authorization = authorization(&self.credentials, now, &signed);
+
+        let mut url = format!("{}{}", self.endpoint, path);
+        if !query.is_empty() {
+            url = format!("{}?{}", url, canonical_query(query));
+        }
+
+        let mut request = self.agent.request(method, &url);
+
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.set(name, value);
+        }
+
+        request.set("Authorization", &authorization)
+    }
+}
+
+impl BlobStoreTrait for BlobStore {
+    /// Stores a blob, the upload is skipped if an object with the same key already exists.
+    fn put_blob(&self, reader: &mut dyn Read) -> Result<Blob, Error> {
+        let staged = StagedFile::create(&std::env::temp_dir().join("openmusicgang"), reader)?;
+
+        match self.stat_blob(&staged.key) {
+            Ok(blob) => return Ok(blob),
+            Err(error) if error.code == ErrorCode::ENOTFOUND => {}
+            Err(error) => return Err(error),
+        }
+
+        let file = File::open(&staged.path).map_err(io_error)?;
+
+        // the key is the SHA-256 digest of the content, so it is also the payload hash.
+        self.request(
+            "PUT",
+            &staged.key,
+            &[],
+            &[("content-length", staged.size.to_string())],
+            &staged.key,
+        )
+        .send(file)
+        .map_err(s3_error)?;
+
+        self.stat_blob(&staged.key)
+    }
+
+    /// Returns a reader of a blob, the content is streamed from the object storage.
+    fn get_blob(
+        &self,
+        key: &str,
+        range: Option<ByteRange>,
+    ) -> Result<Box<dyn Read + Send + Sync>, Error> {
+        validate_key(key)?;
+
+        let headers = match range {
+            // an empty range cannot be expressed as an HTTP range.
+            Some(ByteRange {
+                offset,
+                length: Some(0),
+            }) => return empty_range(self.stat_blob(key)?, offset),
+            Some(ByteRange {
+                offset,
+                length: Some(length),
+            }) => vec![("range", format!("bytes={}-{}", offset, offset + length - 1))],
+            Some(ByteRange {
+                offset,
+                length: None,
+            }) => vec![("range", format!("bytes={}-", offset))],
+            None => vec![],
+        };
+
+        match self
+            .request("GET", key, &[], &headers, EMPTY_PAYLOAD_HASH)
+            .call()
+        {
+            Ok(response) => Ok(Box::new(response.into_reader())),
+            Err(ureq::Error::Status(416, _)) => {
+                let offset = range.map(|range| range.offset).unwrap_or(0);
+                empty_range(self.stat_blob(key)?, offset)
+            }
+            Err(error) => Err(s3_error(error)),
+        }
+    }
+
+    /// Deletes a blob.
+    fn delete_blob(&self, key: &str) -> Result<(), Error> {
+        // S3 does not report deletes of missing objects.
+        self.stat_blob(key)?;
+
+        self.request("DELETE", key, &[], &[], EMPTY_PAYLOAD_HASH)
+            .call()
+            .map_err(s3_error)?;
+
+        Ok(())
+    }
+
+    /// Returns a blob.
+    fn stat_blob(&self, key: &str) -> Result<Blob, Error> {
+        validate_key(key)?;
+
+        let response = self
+            .request("HEAD", key, &[], &[], EMPTY_PAYLOAD_HASH)
+            .call()
+            .map_err(s3_error)?;
+
+        let size = response
+            .header("content-length")
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(0);
+
+        let created_at = response
+            .header("last-modified")
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+            .map(|date| date.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now);
+
+        Ok(Blob {
+            key: key.to_string(),
+            size,
+            created_at,
+        })
+    }
+
+    /// Returns the blobs whose key starts with the prefix, objects which are not blobs are ignored.
+    fn list_blobs(&self, prefix: &str) -> Result<Vec<Blob>, Error> {
+        if !is_blob_key_prefix(prefix) {
+            return Err(Error::new(
+                ErrorCode::EINVALID,
+                "prefix must be made of lowercase hex digits".to_string(),
+            ));
+        }
+
+        let mut blobs = vec![];
+        let mut continuation_token: Option<String> = None;
+
+        loop {
+            let mut query = vec![
+                ("list-type".to_string(), "2".to_string()),
+                ("prefix".to_string(), prefix.to_string()),
+            ];
+
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token".to_string(), token.clone()));
+            }
+
+            let body = self
+                .request("GET", "", &query, &[], EMPTY_PAYLOAD_HASH)
+                .call()
+                .map_err(s3_error)?
+                .into_string()
+                .map_err(io_error)?;
+
+            for contents in xml_elements(&body, "Contents") {
+                let key = xml_elements(contents, "Key").concat();
+
+                if !is_blob_key(&key) {
+                    continue;
+                }
+
+                blobs.push(Blob {
+                    key,
+                    size: xml_elements(contents, "Size").concat().parse().unwrap_or(0),
+                    created_at: DateTime::parse_from_rfc3339(
+                        &xml_elements(contents, "LastModified").concat(),
+                    )
+                    .map(|date| date.with_timezone(&Utc))
+                    .unwrap_or_else(|_| Utc::now()),
+                });
+            }
+
+            if xml_elements(&body, "IsTruncated").concat() != "true" {
+                break;
+            }
+
+            continuation_token = xml_elements(&body, "NextContinuationToken")
+                .first()
+                .map(|token| token.replace("&amp;", "&"));
+
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+
+        Ok(blobs)
+    }
+}
+
+/// Returns EINVALID if the key is not a valid blob key.
+fn validate_key(key: &str) -> Result<(), Error> {
+    if !is_blob_key(key) {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "key must be a SHA-256 hex digest".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns an empty reader if the offset is within the blob.
+/// Returns EINVALID if the offset is after the end of the blob.
+fn empty_range(blob: Blob, offset: u64) -> Result<Box<dyn Read + Send + Sync>, Error> {
+    if offset > blob.size {
+        return Err(Error::new(
+            ErrorCode::EINVALID,
+            "range starts after the end of the blob".to_string(),
+        ));
+    }
+
+    Ok(Box::new(std::io::empty()))
+}
+
+/// Returns the error of a failed request to the object storage.
+fn s3_error(error: ureq::Error) -> Error {
+    match error {
+        ureq::Error::Status(404, _) => {
+            Error::new(ErrorCode::ENOTFOUND, "Blob not found".to_string())
+        }
+        ureq::Error::Status(status, response) => Error::new(
+            ErrorCode::EINTERNAL,
+            format!(
+                "object storage responded {}: {}",
+                status,
+                response.into_string().unwrap_or_default()
+            ),
+        ),
+        error => Error::new(ErrorCode::EINTERNAL, error.to_string()),
+    }
+}
+
+/// Returns the content of the elements with the given tag, the responses of S3 are simple enough
+/// not to need a full XML parser.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+
+    let mut elements = vec![];
+    let mut rest = xml;
+
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+
+        match rest.find(&close) {
+            Some(end) => {
+                elements.push(&rest[..end]);
+                rest = &rest[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+
+    use openmusicgang_config::app_config::AppConfig;
+    use openmusicgang_crypto::digest::sha256_hex;
+    use openmusicgang_crypto::random::random_token;
+
+    use super::*;
+
+    #[test]
+    fn test_xml_elements() {
+        let xml = "<ListBucketResult><IsTruncated>false</IsTruncated>\
+            <Contents><Key>a</Key><Size>1</Size></Contents>\
+            <Contents><Key>b</Key><Size>2</Size></Contents></ListBucketResult>";
+
+        let contents = xml_elements(xml, "Contents");
+        assert_eq!(contents.len(), 2);
+        assert_eq!(xml_elements(contents[1], "Key"), vec!["b"]);
+        assert_eq!(xml_elements(xml, "IsTruncated"), vec!["false"]);
+        assert!(xml_elements(xml, "NextContinuationToken").is_empty());
+    }
+
+    /// ## Simple workflow
+    ///
+    /// Runs against the object storage configured in `[storage.s3]`, such as a local MinIO,
+    /// and is skipped when no endpoint is configured.
+    ///
+    /// 1) put a blob, its key should be the digest of its content.
+    /// 2) put the same content again, the blob should be deduplicated.
+    /// 3) get the whole blob and ranges of it.
+    /// 4) list the blobs by prefix.
+    /// 5) delete the blob, then get it, error should be ENOTFOUND.
+    #[test]
+    fn test_s3_blob_store() {
+        let cfg = AppConfig::new("../../config.toml").storage.s3;
+        if cfg.endpoint.is_empty() {
+            return;
+        }
+
+        let store = BlobStore::new(
+            &cfg.endpoint,
+            &cfg.region,
+            &cfg.bucket,
+            &cfg.access_key,
+            &cfg.secret_key,
+        );
+
+        // 1) put a blob, its key should be the digest of its content.
+        let content = format!("stem {}", random_token(16)).into_bytes();
+
+        let blob = store.put_blob(&mut content.as_slice()).unwrap();
+        assert_eq!(blob.key, sha256_hex(&content));
+        assert_eq!(blob.size, content.len() as u64);
+
+        // 2) put the same content again, the blob should be deduplicated.
+        let again = store.put_blob(&mut content.as_slice()).unwrap();
+        assert_eq!(again, blob);
+
+        // 3) get the whole blob and ranges of it.
+        let mut buf = vec![];
+        store
+            .get_blob(&blob.key, None)
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(buf, content);
+
+        let range = ByteRange {
+            offset: 0,
+            length: Some(4),
+        };
+        let mut buf = vec![];
+        store
+            .get_blob(&blob.key, Some(range))
+            .unwrap()
+            .read_to_end(&mut buf)
+            .unwrap();
+        assert_eq!(buf, b"stem");
+
+        let range = ByteRange {
+            offset: 1000,
+            length: None,
+        };
+        let res = store.get_blob(&blob.key, Some(range));
+        assert!(res.is_err());
+        assert_eq!(res.err().unwrap().code, ErrorCode::EINVALID);
+
+        // 4) list the blobs by prefix.
+        let blobs = store.list_blobs(&blob.key[0..8]).unwrap();
+        assert!(blobs.iter().any(|b| b.key == blob.key));
+
+        // 5) delete the blob, then get it, error should be ENOTFOUND.
+        store.delete_blob(&blob.key).unwrap();
+
+        let res = store.get_blob(&blob.key, None);
+        assert!(res.is_err());
+        assert_eq!(res.err().unwrap().code, ErrorCode::ENOTFOUND);
+    }
+}
@@ -0,0 +1,84 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use openmusicgang_crypto::random::random_token;
+use openmusicgang_err::error::{Error, ErrorCode};
+use sha2::{Digest, Sha256};
+
+/// StagedFile is a temporary copy of an upload, named after the SHA-256 digest of its content.
+///
+/// The file is removed when dropped, unless it has been persisted.
+pub(crate) struct StagedFile {
+    pub path: PathBuf,
+    /// Lowercase hex SHA-256 digest of the content, the key of the blob.
+    pub key: String,
+    pub size: u64,
+    persisted: bool,
+}
+
+impl StagedFile {
+    /// Copies the content of the reader to a new file in the given directory,
+    /// hashing it while it is written.
+    pub fn create(dir: &Path, reader: &mut dyn Read) -> Result<StagedFile, Error> {
+        fs::create_dir_all(dir).map_err(io_error)?;
+
+        let path = dir.join(format!("{}.part", random_token(16)));
+        let mut file = File::create(&path).map_err(io_error)?;
+
+        let mut staged = StagedFile {
+            path,
+            key: "".to_string(),
+            size: 0,
+            persisted: false,
+        };
+
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0u8; 64 * 1024];
+
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(io_error(error)),
+            };
+
+            hasher.update(&buf[..n]);
+            file.write_all(&buf[..n]).map_err(io_error)?;
+            staged.size += n as u64;
+        }
+
+        file.sync_all().map_err(io_error)?;
+
+        staged.key = format!("{:x}", hasher.finalize());
+
+        Ok(staged)
+    }
+
+    /// Moves the file to the given path, replacing it atomically.
+    pub fn persist(mut self, dest: &Path) -> Result<(), Error> {
+        fs::rename(&self.path, dest).map_err(io_error)?;
+        self.persisted = true;
+
+        Ok(())
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Returns the error of a failed filesystem operation.
+pub(crate) fn io_error(error: std::io::Error) -> Error {
+    match error.kind() {
+        std::io::ErrorKind::NotFound => {
+            Error::new(ErrorCode::ENOTFOUND, "Blob not found".to_string())
+        }
+        _ => Error::new(ErrorCode::EINTERNAL, error.to_string()),
+    }
+}
@@ -0,0 +1,417 @@
+use core::fmt;
+use std::str::FromStr;
+
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::interval::Interval;
+use crate::key::Key;
+use crate::pitch::Pitch;
+
+/// ChordKind is the quality of a chord, the intervals of its pitches from its root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChordKind {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Suspended2,
+    Suspended4,
+    Power,
+    Major6,
+    Minor6,
+    Dominant7,
+    Major7,
+    Minor7,
+    MinorMajor7,
+    HalfDiminished7,
+    Diminished7,
+    Augmented7,
+    Dominant7Suspended4,
+    Add9,
+    Dominant9,
+    Major9,
+    Minor9,
+    Dominant11,
+    Dominant13,
+}
+
+impl ChordKind {
+    pub const ALL: [ChordKind; 23] = [
+        ChordKind::Major,
+        ChordKind::Minor,
+        ChordKind::Diminished,
+        ChordKind::Augmented,
+        ChordKind::Suspended2,
+        ChordKind::Suspended4,
+        ChordKind::Power,
+        ChordKind::Major6,
+        ChordKind::Minor6,
+        ChordKind::Dominant7,
+        ChordKind::Major7,
+        ChordKind::Minor7,
+        ChordKind::MinorMajor7,
+        ChordKind::HalfDiminished7,
+        ChordKind::Diminished7,
+        ChordKind::Augmented7,
+        ChordKind::Dominant7Suspended4,
+        ChordKind::Add9,
+        ChordKind::Dominant9,
+        ChordKind::Major9,
+        ChordKind::Minor9,
+        ChordKind::Dominant11,
+        ChordKind::Dominant13,
+    ];
+
+    /// Returns the suffix of the kind in chord symbols, e.g. "m7" in "Am7".
+    pub fn symbol(self) -> &'static str {
+        match self {
+            ChordKind::Major => "",
+            ChordKind::Minor => "m",
+            ChordKind::Diminished => "dim",
+            ChordKind::Augmented => "aug",
+            ChordKind::Suspended2 => "sus2",
+            ChordKind::Suspended4 => "sus4",
+            ChordKind::Power => "5",
+            ChordKind::Major6 => "6",
+            ChordKind::Minor6 => "m6",
+            ChordKind::Dominant7 => "7",
+            ChordKind::Major7 => "maj7",
+            ChordKind::Minor7 => "m7",
+            ChordKind::MinorMajor7 => "mMaj7",
+            ChordKind::HalfDiminished7 => "m7b5",
+            ChordKind::Diminished7 => "dim7",
+            ChordKind::Augmented7 => "aug7",
+            ChordKind::Dominant7Suspended4 => "7sus4",
+            ChordKind::Add9 => "add9",
+            ChordKind::Dominant9 => "9",
+            ChordKind::Major9 => "maj9",
+            ChordKind::Minor9 => "m9",
+            ChordKind::Dominant11 => "11",
+            ChordKind::Dominant13 => "13",
+        }
+    }
+
+    /// Returns the other suffixes accepted for the kind in chord symbols.
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            ChordKind::Major => &["M", "maj"],
+            ChordKind::Minor => &["min", "-"],
+            ChordKind::Diminished => &["°", "o"],
+            ChordKind::Augmented => &["+"],
+            ChordKind::Suspended4 => &["sus"],
+            ChordKind::Minor6 => &["min6", "-6"],
+            ChordKind::Major7 => &["M7", "Δ7", "Δ"],
+            ChordKind::Minor7 => &["min7", "-7"],
+            ChordKind::MinorMajor7 => &["mM7", "mmaj7", "-Δ7"],
+            ChordKind::HalfDiminished7 => &["ø7", "ø", "-7b5", "min7b5"],
+            ChordKind::Diminished7 => &["°7", "o7"],
+            ChordKind::Augmented7 => &["+7", "7#5"],
+            ChordKind::Major9 => &["M9", "Δ9"],
+            ChordKind::Minor9 => &["min9", "-9"],
+            _ => &[],
+        }
+    }
+
+    /// Returns the intervals of the pitches of the chord from its root, starting with the unison.
+    pub fn intervals(self) -> &'static [Interval] {
+        use Interval as I;
+
+        match self {
+            ChordKind::Major => &[I::UNISON, I::MAJOR_THIRD, I::PERFECT_FIFTH],
+            ChordKind::Minor => &[I::UNISON, I::MINOR_THIRD, I::PERFECT_FIFTH],
+            ChordKind::Diminished => &[I::UNISON, I::MINOR_THIRD, I::DIMINISHED_FIFTH],
+            ChordKind::Augmented => &[I::UNISON, I::MAJOR_THIRD, I::AUGMENTED_FIFTH],
+            ChordKind::Suspended2 => &[I::UNISON, I::MAJOR_SECOND, I::PERFECT_FIFTH],
+            ChordKind::Suspended4 => &[I::UNISON, I::PERFECT_FOURTH, I::PERFECT_FIFTH],
+            ChordKind::Power => &[I::UNISON, I::PERFECT_FIFTH],
+            ChordKind::Major6 => &[I::UNISON, I::MAJOR_THIRD, I::PERFECT_FIFTH, I::MAJOR_SIXTH],
+            ChordKind::Minor6 => &[I::UNISON, I::MINOR_THIRD, I::PERFECT_FIFTH, I::MAJOR_SIXTH],
+            ChordKind::Dominant7 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+            ChordKind::Major7 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SEVENTH,
+            ],
+            ChordKind::Minor7 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+            ChordKind::MinorMajor7 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SEVENTH,
+            ],
+            ChordKind::HalfDiminished7 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::DIMINISHED_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+            ChordKind::Diminished7 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::DIMINISHED_FIFTH,
+                I::DIMINISHED_SEVENTH,
+            ],
+            ChordKind::Augmented7 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::AUGMENTED_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+            ChordKind::Dominant7Suspended4 => &[
+                I::UNISON,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+            ChordKind::Add9 => &[I::UNISON, I::MAJOR_THIRD, I::PERFECT_FIFTH, I::MAJOR_NINTH],
+            ChordKind::Dominant9 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+                I::MAJOR_NINTH,
+            ],
+            ChordKind::Major9 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SEVENTH,
+                I::MAJOR_NINTH,
+            ],
+            ChordKind::Minor9 => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+                I::MAJOR_NINTH,
+            ],
+            ChordKind::Dominant11 => &[
+                I::UNISON,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+                I::MAJOR_NINTH,
+                I::PERFECT_ELEVENTH,
+            ],
+            ChordKind::Dominant13 => &[
+                I::UNISON,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+                I::MAJOR_NINTH,
+                I::MAJOR_THIRTEENTH,
+            ],
+        }
+    }
+
+    /// Returns true if the chord has a minor third, its Roman numeral is written in lowercase.
+    pub fn is_minor(self) -> bool {
+        self.intervals().contains(&Interval::MINOR_THIRD)
+    }
+
+    /// Returns true if the chord is a triad or a seventh chord stacked in thirds, which can be inverted.
+    pub fn is_tertian(self) -> bool {
+        let intervals = self.intervals();
+
+        matches!(intervals.len(), 3 | 4)
+            && intervals
+                .iter()
+                .enumerate()
+                .all(|(index, interval)| interval.steps as usize == index * 2)
+    }
+}
+
+/// Chord is a chord symbol, a root with its kind and an optional bass, e.g. "F#m7b5" or "Bb/D".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub root: Pitch,
+    pub kind: ChordKind,
+    /// Bass of a slash chord, None if the root is in the bass.
+    pub bass: Option<Pitch>,
+}
+
+impl Chord {
+    pub fn new(root: Pitch, kind: ChordKind) -> Chord {
+        Chord {
+            root,
+            kind,
+            bass: None,
+        }
+    }
+
+    /// Returns the pitches of the chord from its root, without its bass.
+    ///
+    /// # Example
+    /// ```
+    /// use openmusicgang_theory::chord::Chord;
+    /// let chord: Chord = "F#m7b5".parse().unwrap();
+    /// let pitches: Vec<String> = chord.pitches().iter().map(|p| p.to_string()).collect();
+    /// assert_eq!(pitches, ["F#", "A", "C", "E"]);
+    /// ```
+    pub fn pitches(&self) -> Vec<Pitch> {
+        self.kind
+            .intervals()
+            .iter()
+            .map(|interval| self.root.transpose(*interval))
+            .collect()
+    }
+
+    /// Returns the chord an interval above, e.g. "Bb/D" a major second above is "C/E".
+    pub fn transpose(&self, interval: Interval) -> Chord {
+        Chord {
+            root: self.root.transpose(interval),
+            kind: self.kind,
+            bass: self.bass.map(|bass| bass.transpose(interval)),
+        }
+    }
+
+    /// Returns the chord an interval below.
+    pub fn transpose_down(&self, interval: Interval) -> Chord {
+        Chord {
+            root: self.root.transpose_down(interval),
+            kind: self.kind,
+            bass: self.bass.map(|bass| bass.transpose_down(interval)),
+        }
+    }
+
+    /// Returns the chord with its root and bass spelled like in the given key, e.g. "A#" is "Bb" in "F".
+    pub fn respell(&self, key: &Key) -> Chord {
+        Chord {
+            root: key.spell(self.root.semitone()),
+            kind: self.kind,
+            bass: self.bass.map(|bass| key.spell(bass.semitone())),
+        }
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.root, self.kind.symbol())?;
+
+        if let Some(bass) = self.bass {
+            write!(f, "/{}", bass)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Chord {
+    type Err = Error;
+
+    /// Returns the chord of the given symbol, e.g. "C", "Ebm", "F#m7b5", "Gsus4" or "Bb/D".
+    ///
+    /// Returns EINVALID if the string is not a known chord symbol.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::new(ErrorCode::EINVALID, format!("Invalid chord symbol {}", s));
+
+        let (root, rest) = Pitch::parse_prefix(s).ok_or_else(invalid)?;
+
+        let (suffix, bass) = match rest.rsplit_once('/') {
+            Some((suffix, bass)) => (suffix, Some(bass.parse::<Pitch>().map_err(|_| invalid())?)),
+            None => (rest, None),
+        };
+
+        let kind = ChordKind::ALL
+            .into_iter()
+            .find(|kind| kind.symbol() == suffix || kind.aliases().contains(&suffix))
+            .ok_or_else(invalid)?;
+
+        Ok(Chord { root, kind, bass })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) parse chord symbols and their aliases, they should be formatted with the canonical symbol.
+    /// 2) parse invalid chord symbols, error should be EINVALID.
+    /// 3) build the pitches of chords.
+    /// 4) transpose chords and respell them in a key.
+    #[test]
+    fn test_chord() {
+        let chord = |s: &str| s.parse::<Chord>().unwrap();
+
+        // 1) parse chord symbols and their aliases, they should be formatted with the canonical symbol.
+        let parsed = chord("F#m7b5");
+        assert_eq!(parsed.root, "F#".parse().unwrap());
+        assert_eq!(parsed.kind, ChordKind::HalfDiminished7);
+        assert_eq!(parsed.bass, None);
+
+        let parsed = chord("Bb/D");
+        assert_eq!(parsed.root, "Bb".parse().unwrap());
+        assert_eq!(parsed.kind, ChordKind::Major);
+        assert_eq!(parsed.bass, Some("D".parse().unwrap()));
+
+        for (symbol, formatted) in [
+            ("C", "C"),
+            ("Ebm", "Ebm"),
+            ("Bbmaj7", "Bbmaj7"),
+            ("CΔ7", "Cmaj7"),
+            ("A-7", "Am7"),
+            ("Bø7", "Bm7b5"),
+            ("G#°7", "G#dim7"),
+            ("Dsus", "Dsus4"),
+            ("E+", "Eaug"),
+            ("Abm9/Gb", "Abm9/Gb"),
+            ("C5", "C5"),
+            ("F13", "F13"),
+            ("DmM7", "DmMaj7"),
+        ] {
+            assert_eq!(chord(symbol).to_string(), formatted);
+        }
+
+        // 2) parse invalid chord symbols, error should be EINVALID.
+        for s in ["", "H7", "Cm7b9", "C/", "C/H", "cm", "C#b#m"] {
+            let res = s.parse::<Chord>();
+            assert!(res.is_err(), "{}", s);
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+
+        // 3) build the pitches of chords.
+        let names = |chord: Chord| -> Vec<String> {
+            chord.pitches().iter().map(|p| p.to_string()).collect()
+        };
+        assert_eq!(names(chord("Ebm")), ["Eb", "Gb", "Bb"]);
+        assert_eq!(names(chord("Bdim7")), ["B", "D", "F", "Ab"]);
+        assert_eq!(names(chord("G13")), ["G", "B", "D", "F", "A", "E"]);
+        assert!(ChordKind::Minor7.is_minor());
+        assert!(!ChordKind::Dominant7.is_minor());
+        assert!(ChordKind::HalfDiminished7.is_tertian());
+        assert!(!ChordKind::Suspended4.is_tertian());
+        assert!(!ChordKind::Dominant9.is_tertian());
+
+        // 4) transpose chords and respell them in a key.
+        assert_eq!(
+            chord("Bb/D").transpose(Interval::MAJOR_SECOND),
+            chord("C/E")
+        );
+        assert_eq!(chord("Am7").transpose(Interval::MINOR_THIRD), chord("Cm7"));
+        assert_eq!(
+            chord("F#").transpose(Interval::AUGMENTED_FOURTH),
+            chord("B#")
+        );
+        assert_eq!(
+            chord("E").transpose_down(Interval::MINOR_SECOND),
+            chord("D#")
+        );
+
+        let f_major: Key = "F".parse().unwrap();
+        assert_eq!(chord("A#/D").respell(&f_major), chord("Bb/D"));
+        assert_eq!(chord("Cb7").respell(&f_major), chord("B7"));
+    }
+}
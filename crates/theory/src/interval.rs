@@ -0,0 +1,175 @@
+use core::fmt;
+use std::str::FromStr;
+
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::pitch::Pitch;
+
+/// Interval is the distance between two pitches, counted in letters and in semitones,
+/// e.g. a minor third is 2 steps and 3 semitones, an augmented second 1 step and 3 semitones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Interval {
+    /// Number of letters between the pitches, 0 for a unison, 7 for an octave.
+    pub steps: u8,
+    pub semitones: u8,
+}
+
+impl Interval {
+    pub const UNISON: Interval = Interval::new(0, 0);
+    pub const MINOR_SECOND: Interval = Interval::new(1, 1);
+    pub const MAJOR_SECOND: Interval = Interval::new(1, 2);
+    pub const AUGMENTED_SECOND: Interval = Interval::new(1, 3);
+    pub const MINOR_THIRD: Interval = Interval::new(2, 3);
+    pub const MAJOR_THIRD: Interval = Interval::new(2, 4);
+    pub const PERFECT_FOURTH: Interval = Interval::new(3, 5);
+    pub const AUGMENTED_FOURTH: Interval = Interval::new(3, 6);
+    pub const DIMINISHED_FIFTH: Interval = Interval::new(4, 6);
+    pub const PERFECT_FIFTH: Interval = Interval::new(4, 7);
+    pub const AUGMENTED_FIFTH: Interval = Interval::new(4, 8);
+    pub const MINOR_SIXTH: Interval = Interval::new(5, 8);
+    pub const MAJOR_SIXTH: Interval = Interval::new(5, 9);
+    pub const DIMINISHED_SEVENTH: Interval = Interval::new(6, 9);
+    pub const MINOR_SEVENTH: Interval = Interval::new(6, 10);
+    pub const MAJOR_SEVENTH: Interval = Interval::new(6, 11);
+    pub const OCTAVE: Interval = Interval::new(7, 12);
+    pub const MAJOR_NINTH: Interval = Interval::new(8, 14);
+    pub const PERFECT_ELEVENTH: Interval = Interval::new(10, 17);
+    pub const MAJOR_THIRTEENTH: Interval = Interval::new(12, 21);
+
+    pub const fn new(steps: u8, semitones: u8) -> Interval {
+        Interval { steps, semitones }
+    }
+
+    /// Returns the interval from a pitch up to another one, within an octave.
+    ///
+    /// # Example
+    /// ```
+    /// use openmusicgang_theory::interval::Interval;
+    /// let between = |a: &str, b: &str| Interval::between(a.parse().unwrap(), b.parse().unwrap());
+    /// assert_eq!(between("C", "Eb"), Interval::MINOR_THIRD);
+    /// assert_eq!(between("C", "D#"), Interval::AUGMENTED_SECOND);
+    /// assert_eq!(between("A", "C"), Interval::MINOR_THIRD);
+    /// ```
+    pub fn between(from: Pitch, to: Pitch) -> Interval {
+        Interval::new(
+            from.letter.steps_to(to.letter) as u8,
+            (to.semitone() - from.semitone()).rem_euclid(12) as u8,
+        )
+    }
+
+    /// Returns the number of the interval, 1 for a unison, 3 for a third, 8 for an octave.
+    pub fn number(&self) -> u8 {
+        self.steps + 1
+    }
+
+    /// Returns the interval reduced to an octave, e.g. a major ninth is a major second.
+    pub fn simple(&self) -> Interval {
+        Interval::new(self.steps % 7, self.semitones % 12)
+    }
+
+    /// Returns true if the unison, fourth, fifth and octave qualities apply to the interval.
+    fn is_perfect_kind(&self) -> bool {
+        matches!(self.steps % 7, 0 | 3 | 4)
+    }
+
+    /// Returns the number of semitones of the major or perfect interval with the same number of steps.
+    fn major_semitones(&self) -> i32 {
+        [0, 2, 4, 5, 7, 9, 11][(self.steps % 7) as usize] + 12 * (self.steps / 7) as i32
+    }
+}
+
+impl fmt::Display for Interval {
+    /// Formats the interval by quality and number, e.g. "P5", "m3", "M9", "A4" or "d7".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let difference = self.semitones as i32 - self.major_semitones();
+
+        let quality = match (self.is_perfect_kind(), difference) {
+            (true, 0) => "P".to_string(),
+            (false, 0) => "M".to_string(),
+            (false, -1) => "m".to_string(),
+            (_, difference) if difference > 0 => "A".repeat(difference as usize),
+            (true, difference) => "d".repeat(difference.unsigned_abs() as usize),
+            (false, difference) => "d".repeat(difference.unsigned_abs() as usize - 1),
+        };
+
+        write!(f, "{}{}", quality, self.number())
+    }
+}
+
+impl FromStr for Interval {
+    type Err = Error;
+
+    /// Returns the interval of the given string, written by quality and number, e.g. "P5", "m3" or "A4".
+    ///
+    /// Returns EINVALID if the string is not an interval.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::new(ErrorCode::EINVALID, format!("Invalid interval {}", s));
+
+        let split = s.find(|c: char| c.is_ascii_digit()).ok_or_else(invalid)?;
+        let (quality, number) = s.split_at(split);
+
+        let steps = match number.parse::<u8>() {
+            Ok(number) if (1..=22).contains(&number) => number - 1,
+            _ => return Err(invalid()),
+        };
+
+        let interval = Interval::new(steps, 0);
+        let count = quality.chars().count() as i32;
+
+        let difference = match quality.chars().next() {
+            Some('P') if count == 1 && interval.is_perfect_kind() => 0,
+            Some('M') if count == 1 && !interval.is_perfect_kind() => 0,
+            Some('m') if count == 1 && !interval.is_perfect_kind() => -1,
+            Some('A') if quality.chars().all(|c| c == 'A') => count,
+            Some('d') if quality.chars().all(|c| c == 'd') && interval.is_perfect_kind() => -count,
+            Some('d') if quality.chars().all(|c| c == 'd') => -count - 1,
+            _ => return Err(invalid()),
+        };
+
+        let semitones =
+            u8::try_from(interval.major_semitones() + difference).map_err(|_| invalid())?;
+
+        Ok(Interval::new(steps, semitones))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) format intervals by quality and number.
+    /// 2) parse intervals, they should be formatted back the same.
+    /// 3) parse invalid intervals, error should be EINVALID.
+    #[test]
+    fn test_interval() {
+        // 1) format intervals by quality and number.
+        assert_eq!(Interval::UNISON.to_string(), "P1");
+        assert_eq!(Interval::MINOR_THIRD.to_string(), "m3");
+        assert_eq!(Interval::AUGMENTED_FOURTH.to_string(), "A4");
+        assert_eq!(Interval::DIMINISHED_FIFTH.to_string(), "d5");
+        assert_eq!(Interval::DIMINISHED_SEVENTH.to_string(), "d7");
+        assert_eq!(Interval::OCTAVE.to_string(), "P8");
+        assert_eq!(Interval::MAJOR_THIRTEENTH.to_string(), "M13");
+        assert_eq!(Interval::new(1, 0).to_string(), "d2");
+        assert_eq!(Interval::MAJOR_NINTH.simple(), Interval::MAJOR_SECOND);
+
+        // 2) parse intervals, they should be formatted back the same.
+        for s in [
+            "P1", "m2", "A2", "M3", "P4", "A4", "d5", "P5", "m6", "d7", "M7", "P8", "M9", "P11",
+            "AA4", "dd5",
+        ] {
+            assert_eq!(s.parse::<Interval>().unwrap().to_string(), s);
+        }
+        assert_eq!("m7".parse::<Interval>().unwrap(), Interval::MINOR_SEVENTH);
+
+        // 3) parse invalid intervals, error should be EINVALID.
+        for s in ["P3", "M5", "m4", "5", "X5", "P0", "P23", "d1", "M"] {
+            let res = s.parse::<Interval>();
+            assert!(res.is_err(), "{}", s);
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+    }
+}
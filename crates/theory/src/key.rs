@@ -0,0 +1,346 @@
+use core::fmt;
+use std::str::FromStr;
+
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::chord::{Chord, ChordKind};
+use crate::interval::Interval;
+use crate::pitch::{Letter, Pitch, Spelling};
+use crate::scale::{Scale, ScaleKind};
+
+/// Numerals of the degrees of a key, from the tonic.
+const NUMERALS: [&str; 7] = ["I", "II", "III", "IV", "V", "VI", "VII"];
+
+/// Mode is the mode of a key, major or minor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Major,
+    Minor,
+}
+
+/// Key is the tonal center of a song, written like "Bb" for a major key and "F#m" for a minor key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub tonic: Pitch,
+    pub mode: Mode,
+}
+
+impl Key {
+    pub fn new(tonic: Pitch, mode: Mode) -> Key {
+        Key { tonic, mode }
+    }
+
+    /// Returns the number of sharps of the key signature, negative for flats, e.g. -3 for "Eb" and "Cm".
+    ///
+    /// Keys beyond 7 sharps or flats, such as "G#", have a theoretical signature with double accidentals.
+    pub fn sharps(&self) -> i32 {
+        let fifths = [0, 2, 4, -1, 1, 3, 5][self.tonic.letter as usize] + 7 * self.tonic.accidental;
+
+        match self.mode {
+            Mode::Major => fifths,
+            Mode::Minor => fifths - 3,
+        }
+    }
+
+    /// Returns the key of a signature of at most 7 sharps or flats, None if the signature has more.
+    ///
+    /// # Example
+    /// ```
+    /// use openmusicgang_theory::key::{Key, Mode};
+    /// assert_eq!(Key::from_sharps(-2, Mode::Major).unwrap().to_string(), "Bb");
+    /// assert_eq!(Key::from_sharps(3, Mode::Minor).unwrap().to_string(), "F#m");
+    /// assert_eq!(Key::from_sharps(8, Mode::Major), None);
+    /// ```
+    pub fn from_sharps(sharps: i32, mode: Mode) -> Option<Key> {
+        if sharps.abs() > 7 {
+            return None;
+        }
+
+        let fifths = match mode {
+            Mode::Major => sharps,
+            Mode::Minor => sharps + 3,
+        };
+
+        let tonic = Pitch::from_semitone(7 * fifths, Spelling::Sharps)
+            .respell(Letter::C.step(4 * fifths))?;
+
+        Some(Key::new(tonic, mode))
+    }
+
+    /// Returns the major or natural minor scale of the key.
+    pub fn scale(&self) -> Scale {
+        let kind = match self.mode {
+            Mode::Major => ScaleKind::Major,
+            Mode::Minor => ScaleKind::NaturalMinor,
+        };
+
+        Scale::new(self.tonic, kind)
+    }
+
+    /// Returns the key with the same signature and the other mode, e.g. "Am" for "C".
+    pub fn relative(&self) -> Key {
+        match self.mode {
+            Mode::Major => Key::new(
+                self.tonic.transpose_down(Interval::MINOR_THIRD),
+                Mode::Minor,
+            ),
+            Mode::Minor => Key::new(self.tonic.transpose(Interval::MINOR_THIRD), Mode::Major),
+        }
+    }
+
+    /// Returns the key with the same tonic and the other mode, e.g. "Cm" for "C".
+    pub fn parallel(&self) -> Key {
+        match self.mode {
+            Mode::Major => Key::new(self.tonic, Mode::Minor),
+            Mode::Minor => Key::new(self.tonic, Mode::Major),
+        }
+    }
+
+    /// Returns the enharmonic key with a signature of at most 7 sharps or flats, e.g. "Bb" for "A#".
+    pub fn simplify(&self) -> Key {
+        let sharps = self.sharps();
+
+        if sharps.abs() <= 7 {
+            return *self;
+        }
+
+        Key::from_sharps(sharps - 12 * sharps.signum(), self.mode).unwrap_or(*self)
+    }
+
+    /// Returns the key an interval above, simplified to a signature of at most 7 sharps or flats.
+    pub fn transpose(&self, interval: Interval) -> Key {
+        Key::new(self.tonic.transpose(interval), self.mode).simplify()
+    }
+
+    /// Returns the key an interval below, simplified to a signature of at most 7 sharps or flats.
+    pub fn transpose_down(&self, interval: Interval) -> Key {
+        Key::new(self.tonic.transpose_down(interval), self.mode).simplify()
+    }
+
+    /// Returns the pitch of a pitch class spelled like in the key, as in its scale if possible,
+    /// otherwise with sharps or flats like its signature.
+    pub fn spell(&self, semitone: i32) -> Pitch {
+        let semitone = semitone.rem_euclid(12);
+
+        if let Some(pitch) = self
+            .scale()
+            .pitches()
+            .into_iter()
+            .find(|pitch| pitch.semitone() == semitone)
+        {
+            return pitch;
+        }
+
+        let spelling = if self.sharps() < 0 {
+            Spelling::Flats
+        } else {
+            Spelling::Sharps
+        };
+
+        Pitch::from_semitone(semitone, spelling)
+    }
+
+    /// Returns the Roman numeral of a chord in the key, e.g. "V7" for "G7" in "C".
+    ///
+    /// The degree of the root is altered against the major or natural minor scale of the key, e.g. "bVII" for "Bb" in "C".
+    /// The numeral is in lowercase for chords with a minor third, inverted triads and seventh chords are
+    /// written with figured bass, e.g. "I6" for "C/E" and "V65" for "G7/B", other slash chords keep their bass.
+    ///
+    /// # Example
+    /// ```
+    /// use openmusicgang_theory::key::Key;
+    /// let key: Key = "C".parse().unwrap();
+    /// let numeral = |chord: &str| key.roman_numeral(&chord.parse().unwrap());
+    /// assert_eq!(numeral("Dm7"), "ii7");
+    /// assert_eq!(numeral("Bm7b5"), "viiø7");
+    /// assert_eq!(numeral("Bb"), "bVII");
+    /// assert_eq!(numeral("F/C"), "IV64");
+    /// ```
+    pub fn roman_numeral(&self, chord: &Chord) -> String {
+        let steps = self.tonic.letter.steps_to(chord.root.letter) as usize;
+        let degree = self.scale().pitches()[steps];
+
+        let alteration = (chord.root.semitone() - degree.semitone() + 6).rem_euclid(12) - 6;
+        let accidental = if alteration < 0 { "b" } else { "#" };
+
+        let numeral = if chord.kind.is_minor() {
+            NUMERALS[steps].to_lowercase()
+        } else {
+            NUMERALS[steps].to_string()
+        };
+
+        let (quality, seventh) = match chord.kind {
+            ChordKind::Major | ChordKind::Minor => ("", false),
+            ChordKind::Diminished => ("°", false),
+            ChordKind::Augmented => ("+", false),
+            ChordKind::Dominant7 | ChordKind::Minor7 => ("", true),
+            ChordKind::Major7 | ChordKind::MinorMajor7 => ("maj", true),
+            ChordKind::HalfDiminished7 => ("ø", true),
+            ChordKind::Diminished7 => ("°", true),
+            ChordKind::Augmented7 => ("+", true),
+            ChordKind::Major6 | ChordKind::Minor6 => ("add6", false),
+            ChordKind::Minor9 => ("9", false),
+            kind => (kind.symbol(), false),
+        };
+
+        let inversion = chord.bass.and_then(|bass| {
+            chord
+                .pitches()
+                .iter()
+                .position(|pitch| pitch.is_enharmonic(&bass))
+        });
+
+        let figure = match (chord.bass, inversion, seventh) {
+            (None, _, false) => "".to_string(),
+            (None, _, true) => "7".to_string(),
+            (Some(_), Some(inversion), _) if chord.kind.is_tertian() => {
+                match (seventh, inversion) {
+                    (false, 0) => "",
+                    (false, 1) => "6",
+                    (false, _) => "64",
+                    (true, 0) => "7",
+                    (true, 1) => "65",
+                    (true, 2) => "43",
+                    (true, _) => "42",
+                }
+                .to_string()
+            }
+            (Some(bass), _, seventh) => {
+                format!("{}/{}", if seventh { "7" } else { "" }, bass)
+            }
+        };
+
+        format!(
+            "{}{}{}{}",
+            accidental.repeat(alteration.unsigned_abs() as usize),
+            numeral,
+            quality,
+            figure
+        )
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            Mode::Major => write!(f, "{}", self.tonic),
+            Mode::Minor => write!(f, "{}m", self.tonic),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = Error;
+
+    /// Returns the key of the given string, e.g. "C", "Bb", "F#m", "Eb minor" or "A major".
+    ///
+    /// Returns EINVALID if the string is not a key.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::new(ErrorCode::EINVALID, format!("Invalid key {}", s));
+
+        let (tonic, mode) = Pitch::parse_prefix(s).ok_or_else(invalid)?;
+
+        let mode = match mode {
+            "" | "maj" | " major" => Mode::Major,
+            "m" | "min" | " minor" => Mode::Minor,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Key::new(tonic, mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) parse and format keys, invalid keys should be EINVALID.
+    /// 2) compute the signatures of keys and their relative keys.
+    /// 3) transpose keys, theoretical keys should be simplified.
+    /// 4) spell pitches in keys.
+    /// 5) write the Roman numerals of chords in a major and a minor key.
+    #[test]
+    fn test_key() {
+        let key = |s: &str| s.parse::<Key>().unwrap();
+
+        // 1) parse and format keys, invalid keys should be EINVALID.
+        assert_eq!(key("F#m"), Key::new("F#".parse().unwrap(), Mode::Minor));
+        assert_eq!(key("Eb minor").to_string(), "Ebm");
+        assert_eq!(key("A major").to_string(), "A");
+        assert_eq!(key("Bbmin").to_string(), "Bbm");
+
+        for s in ["", "H", "Cm7", "c", "C dorian", "Am "] {
+            let res = s.parse::<Key>();
+            assert!(res.is_err(), "{}", s);
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+
+        // 2) compute the signatures of keys and their relative keys.
+        assert_eq!(key("C").sharps(), 0);
+        assert_eq!(key("Eb").sharps(), -3);
+        assert_eq!(key("Cm").sharps(), -3);
+        assert_eq!(key("C#").sharps(), 7);
+        assert_eq!(key("Abm").sharps(), -7);
+        assert_eq!(key("G#").sharps(), 8);
+        assert_eq!(key("C").relative(), key("Am"));
+        assert_eq!(key("Ebm").relative(), key("Gb"));
+        assert_eq!(key("D").parallel(), key("Dm"));
+
+        for sharps in -7..=7 {
+            for mode in [Mode::Major, Mode::Minor] {
+                assert_eq!(Key::from_sharps(sharps, mode).unwrap().sharps(), sharps);
+            }
+        }
+
+        // 3) transpose keys, theoretical keys should be simplified.
+        assert_eq!(key("A#").simplify(), key("Bb"));
+        assert_eq!(key("D#m").simplify(), key("D#m"));
+        assert_eq!(key("E").transpose(Interval::MAJOR_SECOND), key("F#"));
+        assert_eq!(key("E").transpose(Interval::MAJOR_THIRD), key("Ab"));
+        assert_eq!(
+            key("Bbm").transpose_down(Interval::MAJOR_SECOND),
+            key("Abm")
+        );
+
+        // 4) spell pitches in keys.
+        assert_eq!(key("F").spell(10).to_string(), "Bb");
+        assert_eq!(key("E").spell(3).to_string(), "D#");
+        assert_eq!(key("Eb").spell(6).to_string(), "Gb");
+        assert_eq!(key("A").spell(3).to_string(), "D#");
+
+        // 5) write the Roman numerals of chords in a major and a minor key.
+        let c_major = key("C");
+        for (chord, numeral) in [
+            ("C", "I"),
+            ("C/E", "I6"),
+            ("G7", "V7"),
+            ("G7/B", "V65"),
+            ("G7/D", "V43"),
+            ("G7/F", "V42"),
+            ("Fm", "iv"),
+            ("Ab", "bVI"),
+            ("E7", "III7"),
+            ("Cmaj7", "Imaj7"),
+            ("Bdim", "vii°"),
+            ("Dsus4", "IIsus4"),
+            ("C/Bb", "I/Bb"),
+            ("F#m7b5", "#ivø7"),
+        ] {
+            assert_eq!(c_major.roman_numeral(&chord.parse().unwrap()), numeral);
+        }
+
+        let a_minor = key("Am");
+        for (chord, numeral) in [
+            ("Am", "i"),
+            ("E7", "V7"),
+            ("C", "III"),
+            ("G#dim7", "#vii°7"),
+            ("Dm/F", "iv6"),
+        ] {
+            assert_eq!(a_minor.roman_numeral(&chord.parse().unwrap()), numeral);
+        }
+    }
+}
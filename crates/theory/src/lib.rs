@@ -0,0 +1,6 @@
+pub mod chord;
+pub mod interval;
+pub mod key;
+pub mod pitch;
+pub mod scale;
+pub mod time_signature;
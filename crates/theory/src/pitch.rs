@@ -0,0 +1,279 @@
+use core::fmt;
+use std::str::FromStr;
+
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::interval::Interval;
+
+/// Letter is the name of a natural note.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Letter {
+    C,
+    D,
+    E,
+    F,
+    G,
+    A,
+    B,
+}
+
+impl Letter {
+    pub const ALL: [Letter; 7] = [
+        Letter::C,
+        Letter::D,
+        Letter::E,
+        Letter::F,
+        Letter::G,
+        Letter::A,
+        Letter::B,
+    ];
+
+    /// Returns the number of semitones of the natural note above C.
+    pub fn semitone(self) -> i32 {
+        [0, 2, 4, 5, 7, 9, 11][self as usize]
+    }
+
+    /// Returns the letter the given number of steps above, e.g. 2 steps above A is C.
+    pub fn step(self, steps: i32) -> Letter {
+        Letter::ALL[(self as i32 + steps).rem_euclid(7) as usize]
+    }
+
+    /// Returns the number of steps from the letter up to the other one, from 0 to 6.
+    pub fn steps_to(self, other: Letter) -> i32 {
+        (other as i32 - self as i32).rem_euclid(7)
+    }
+
+    pub fn as_char(self) -> char {
+        ['C', 'D', 'E', 'F', 'G', 'A', 'B'][self as usize]
+    }
+
+    fn from_char(c: char) -> Option<Letter> {
+        Letter::ALL.into_iter().find(|letter| letter.as_char() == c)
+    }
+}
+
+/// Spelling is the accidental preferred to name the pitches that are not natural.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spelling {
+    Sharps,
+    Flats,
+}
+
+/// Pitch is a pitch class spelled as a letter with its accidental, e.g. "F#" or "Bb".
+///
+/// Enharmonic pitches, such as "F#" and "Gb", sound the same but are different values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Pitch {
+    pub letter: Letter,
+    /// Number of sharps, negative for flats, from -2 to 2.
+    pub accidental: i32,
+}
+
+impl Pitch {
+    pub fn new(letter: Letter, accidental: i32) -> Pitch {
+        Pitch { letter, accidental }
+    }
+
+    /// Returns the pitch class, the number of semitones above C from 0 to 11.
+    pub fn semitone(&self) -> i32 {
+        (self.letter.semitone() + self.accidental).rem_euclid(12)
+    }
+
+    /// Returns true if both pitches sound the same, e.g. "F#" and "Gb".
+    pub fn is_enharmonic(&self, other: &Pitch) -> bool {
+        self.semitone() == other.semitone()
+    }
+
+    /// Returns the pitch of a pitch class, natural if possible, otherwise with one sharp or flat.
+    ///
+    /// # Example
+    /// ```
+    /// use openmusicgang_theory::pitch::{Pitch, Spelling};
+    /// assert_eq!(Pitch::from_semitone(10, Spelling::Sharps).to_string(), "A#");
+    /// assert_eq!(Pitch::from_semitone(10, Spelling::Flats).to_string(), "Bb");
+    /// assert_eq!(Pitch::from_semitone(-1, Spelling::Flats).to_string(), "B");
+    /// ```
+    pub fn from_semitone(semitone: i32, spelling: Spelling) -> Pitch {
+        let semitone = semitone.rem_euclid(12);
+
+        if let Some(letter) = Letter::ALL
+            .into_iter()
+            .find(|letter| letter.semitone() == semitone)
+        {
+            return Pitch::new(letter, 0);
+        }
+
+        match spelling {
+            Spelling::Sharps => Pitch::from_semitone(semitone - 1, spelling).with_accidental(1),
+            Spelling::Flats => Pitch::from_semitone(semitone + 1, spelling).with_accidental(-1),
+        }
+    }
+
+    /// Returns the enharmonic spelling of the pitch with the given letter, None if it needs more than two accidentals.
+    ///
+    /// # Example
+    /// ```
+    /// use openmusicgang_theory::pitch::{Letter, Pitch};
+    /// let f_sharp: Pitch = "F#".parse().unwrap();
+    /// assert_eq!(f_sharp.respell(Letter::G).unwrap().to_string(), "Gb");
+    /// assert_eq!(f_sharp.respell(Letter::E).unwrap().to_string(), "E##");
+    /// assert_eq!(f_sharp.respell(Letter::A), None);
+    /// ```
+    pub fn respell(&self, letter: Letter) -> Option<Pitch> {
+        let accidental = (self.semitone() - letter.semitone() + 6).rem_euclid(12) - 6;
+
+        (accidental.abs() <= 2).then_some(Pitch::new(letter, accidental))
+    }
+
+    /// Returns the enharmonic spelling of the pitch with the fewest accidentals, keeping its sharps or flats,
+    /// e.g. "Cb" is "B" and "F##" is "G".
+    pub fn simplify(&self) -> Pitch {
+        let spelling = if self.accidental < 0 {
+            Spelling::Flats
+        } else {
+            Spelling::Sharps
+        };
+
+        Pitch::from_semitone(self.semitone(), spelling)
+    }
+
+    /// Returns the pitch an interval above, spelled with the letter of the interval,
+    /// e.g. a major third above "Eb" is "G" and an augmented fourth above "F" is "B".
+    ///
+    /// Pitches that would need more than two accidentals are simplified.
+    pub fn transpose(&self, interval: Interval) -> Pitch {
+        self.transpose_by(interval.steps as i32, interval.semitones as i32)
+    }
+
+    /// Returns the pitch an interval below, spelled with the letter of the interval.
+    pub fn transpose_down(&self, interval: Interval) -> Pitch {
+        self.transpose_by(-(interval.steps as i32), -(interval.semitones as i32))
+    }
+
+    fn transpose_by(&self, steps: i32, semitones: i32) -> Pitch {
+        let pitch = Pitch::from_semitone(self.semitone() + semitones, Spelling::Sharps);
+
+        pitch.respell(self.letter.step(steps)).unwrap_or(pitch)
+    }
+
+    fn with_accidental(self, accidental: i32) -> Pitch {
+        Pitch::new(self.letter, accidental)
+    }
+
+    /// Returns the pitch at the start of the string and the rest of the string, None if the string does not start with a pitch.
+    ///
+    /// Accidentals are written "#" and "b", or "x" for a double sharp, sharps and flats cannot be mixed.
+    pub(crate) fn parse_prefix(s: &str) -> Option<(Pitch, &str)> {
+        let mut chars = s.char_indices();
+        let letter = Letter::from_char(chars.next()?.1)?;
+
+        let mut sharps = 0;
+        let mut flats = 0;
+        let mut end = s.len();
+
+        for (index, c) in chars {
+            match c {
+                '#' | '♯' => sharps += 1,
+                'x' => sharps += 2,
+                'b' | '♭' => flats += 1,
+                _ => {
+                    end = index;
+                    break;
+                }
+            }
+        }
+
+        if (sharps > 0 && flats > 0) || sharps > 2 || flats > 2 {
+            return None;
+        }
+
+        Some((Pitch::new(letter, sharps - flats), &s[end..]))
+    }
+}
+
+impl fmt::Display for Pitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let accidental = if self.accidental < 0 { "b" } else { "#" };
+
+        write!(
+            f,
+            "{}{}",
+            self.letter.as_char(),
+            accidental.repeat(self.accidental.unsigned_abs() as usize)
+        )
+    }
+}
+
+impl FromStr for Pitch {
+    type Err = Error;
+
+    /// Returns the pitch of the given string, e.g. "C", "F#", "Bb" or "Ebb".
+    ///
+    /// Returns EINVALID if the string is not a pitch.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Pitch::parse_prefix(s) {
+            Some((pitch, "")) => Ok(pitch),
+            _ => Err(Error::new(
+                ErrorCode::EINVALID,
+                format!("Invalid pitch {}", s),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) parse and format pitches, unknown letters and triple accidentals should be EINVALID.
+    /// 2) compare enharmonic pitches and simplify them.
+    /// 3) transpose pitches by intervals, the letters should follow the intervals.
+    #[test]
+    fn test_pitch() {
+        // 1) parse and format pitches, unknown letters and triple accidentals should be EINVALID.
+        let pitch = |s: &str| s.parse::<Pitch>().unwrap();
+
+        assert_eq!(pitch("C"), Pitch::new(Letter::C, 0));
+        assert_eq!(pitch("Bb"), Pitch::new(Letter::B, -1));
+        assert_eq!(pitch("Fx"), Pitch::new(Letter::F, 2));
+        assert_eq!(pitch("E♭"), Pitch::new(Letter::E, -1));
+        assert_eq!(pitch("Fx").to_string(), "F##");
+        assert_eq!(pitch("Abb").to_string(), "Abb");
+
+        for s in ["H", "c", "C###", "", "C#m", "C#b"] {
+            let res = s.parse::<Pitch>();
+            assert!(res.is_err());
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+
+        // 2) compare enharmonic pitches and simplify them.
+        assert_eq!(pitch("B#").semitone(), 0);
+        assert_eq!(pitch("Cb").semitone(), 11);
+        assert!(pitch("F#").is_enharmonic(&pitch("Gb")));
+        assert!(!pitch("F#").is_enharmonic(&pitch("G")));
+        assert_eq!(pitch("Cb").simplify(), pitch("B"));
+        assert_eq!(pitch("E#").simplify(), pitch("F"));
+        assert_eq!(pitch("F##").simplify(), pitch("G"));
+        assert_eq!(pitch("Abb").simplify(), pitch("G"));
+        assert_eq!(pitch("Db").simplify(), pitch("Db"));
+
+        // 3) transpose pitches by intervals, the letters should follow the intervals.
+        assert_eq!(pitch("Eb").transpose(Interval::MAJOR_THIRD), pitch("G"));
+        assert_eq!(pitch("F").transpose(Interval::AUGMENTED_FOURTH), pitch("B"));
+        assert_eq!(
+            pitch("F").transpose(Interval::DIMINISHED_FIFTH),
+            pitch("Cb")
+        );
+        assert_eq!(pitch("B").transpose(Interval::MINOR_SECOND), pitch("C"));
+        assert_eq!(pitch("A#").transpose(Interval::MAJOR_THIRD), pitch("C##"));
+        assert_eq!(
+            pitch("C##").transpose(Interval::AUGMENTED_FIFTH),
+            pitch("A#")
+        );
+        assert_eq!(pitch("C").transpose_down(Interval::MINOR_THIRD), pitch("A"));
+        assert_eq!(pitch("D").transpose(Interval::MAJOR_NINTH), pitch("E"));
+    }
+}
@@ -0,0 +1,287 @@
+use core::fmt;
+use std::str::FromStr;
+
+use openmusicgang_err::error::{Error, ErrorCode};
+
+use crate::interval::Interval;
+use crate::pitch::Pitch;
+
+/// ScaleKind is the pattern of intervals of a scale from its tonic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScaleKind {
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+    MelodicMinor,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+    MajorPentatonic,
+    MinorPentatonic,
+}
+
+impl ScaleKind {
+    pub const ALL: [ScaleKind; 11] = [
+        ScaleKind::Major,
+        ScaleKind::NaturalMinor,
+        ScaleKind::HarmonicMinor,
+        ScaleKind::MelodicMinor,
+        ScaleKind::Dorian,
+        ScaleKind::Phrygian,
+        ScaleKind::Lydian,
+        ScaleKind::Mixolydian,
+        ScaleKind::Locrian,
+        ScaleKind::MajorPentatonic,
+        ScaleKind::MinorPentatonic,
+    ];
+
+    /// Returns the intervals of the degrees of the scale from its tonic, starting with the unison.
+    pub fn intervals(self) -> &'static [Interval] {
+        use Interval as I;
+
+        match self {
+            ScaleKind::Major => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MAJOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MAJOR_SEVENTH,
+            ],
+            ScaleKind::NaturalMinor => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MINOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+            ScaleKind::HarmonicMinor => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MINOR_SIXTH,
+                I::MAJOR_SEVENTH,
+            ],
+            ScaleKind::MelodicMinor => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MAJOR_SEVENTH,
+            ],
+            ScaleKind::Dorian => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+            ScaleKind::Phrygian => &[
+                I::UNISON,
+                I::MINOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MINOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+            ScaleKind::Lydian => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MAJOR_THIRD,
+                I::AUGMENTED_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MAJOR_SEVENTH,
+            ],
+            ScaleKind::Mixolydian => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MAJOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+            ScaleKind::Locrian => &[
+                I::UNISON,
+                I::MINOR_SECOND,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::DIMINISHED_FIFTH,
+                I::MINOR_SIXTH,
+                I::MINOR_SEVENTH,
+            ],
+            ScaleKind::MajorPentatonic => &[
+                I::UNISON,
+                I::MAJOR_SECOND,
+                I::MAJOR_THIRD,
+                I::PERFECT_FIFTH,
+                I::MAJOR_SIXTH,
+            ],
+            ScaleKind::MinorPentatonic => &[
+                I::UNISON,
+                I::MINOR_THIRD,
+                I::PERFECT_FOURTH,
+                I::PERFECT_FIFTH,
+                I::MINOR_SEVENTH,
+            ],
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScaleKind::Major => "major",
+            ScaleKind::NaturalMinor => "minor",
+            ScaleKind::HarmonicMinor => "harmonic minor",
+            ScaleKind::MelodicMinor => "melodic minor",
+            ScaleKind::Dorian => "dorian",
+            ScaleKind::Phrygian => "phrygian",
+            ScaleKind::Lydian => "lydian",
+            ScaleKind::Mixolydian => "mixolydian",
+            ScaleKind::Locrian => "locrian",
+            ScaleKind::MajorPentatonic => "major pentatonic",
+            ScaleKind::MinorPentatonic => "minor pentatonic",
+        }
+    }
+}
+
+/// Scale is a set of pitches built from a tonic, e.g. "D dorian".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Scale {
+    pub tonic: Pitch,
+    pub kind: ScaleKind,
+}
+
+impl Scale {
+    pub fn new(tonic: Pitch, kind: ScaleKind) -> Scale {
+        Scale { tonic, kind }
+    }
+
+    /// Returns the pitches of the scale from its tonic, spelled with one letter per degree.
+    ///
+    /// # Example
+    /// ```
+    /// use openmusicgang_theory::scale::{Scale, ScaleKind};
+    /// let scale = Scale::new("Eb".parse().unwrap(), ScaleKind::Major);
+    /// let pitches: Vec<String> = scale.pitches().iter().map(|p| p.to_string()).collect();
+    /// assert_eq!(pitches, ["Eb", "F", "G", "Ab", "Bb", "C", "D"]);
+    /// ```
+    pub fn pitches(&self) -> Vec<Pitch> {
+        self.kind
+            .intervals()
+            .iter()
+            .map(|interval| self.tonic.transpose(*interval))
+            .collect()
+    }
+
+    /// Returns the degree of a pitch in the scale, 1 for the tonic, None if the pitch is not in the scale.
+    ///
+    /// Enharmonic pitches have the same degree, e.g. "D#" is the third degree of "C minor".
+    pub fn degree(&self, pitch: &Pitch) -> Option<usize> {
+        self.pitches()
+            .iter()
+            .position(|degree| degree.is_enharmonic(pitch))
+            .map(|index| index + 1)
+    }
+
+    pub fn contains(&self, pitch: &Pitch) -> bool {
+        self.degree(pitch).is_some()
+    }
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.tonic, self.kind.as_str())
+    }
+}
+
+impl FromStr for Scale {
+    type Err = Error;
+
+    /// Returns the scale of the given string, its tonic followed by its kind, e.g. "A minor" or "F# dorian".
+    ///
+    /// Returns EINVALID if the string is not a scale.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::new(ErrorCode::EINVALID, format!("Invalid scale {}", s));
+
+        let (tonic, kind) = s.split_once(' ').ok_or_else(invalid)?;
+        let tonic = tonic.parse::<Pitch>().map_err(|_| invalid())?;
+        let kind = ScaleKind::ALL
+            .into_iter()
+            .find(|scale_kind| scale_kind.as_str() == kind.to_lowercase())
+            .ok_or_else(invalid)?;
+
+        Ok(Scale::new(tonic, kind))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) build the pitches of scales, each degree should have its own letter.
+    /// 2) find the degrees of pitches, enharmonic pitches should match.
+    /// 3) parse and format scales, unknown kinds should be EINVALID.
+    #[test]
+    fn test_scale() {
+        let names = |scale: Scale| -> Vec<String> {
+            scale.pitches().iter().map(|p| p.to_string()).collect()
+        };
+
+        // 1) build the pitches of scales, each degree should have its own letter.
+        let scale = |s: &str| s.parse::<Scale>().unwrap();
+
+        assert_eq!(
+            names(scale("F# major")),
+            ["F#", "G#", "A#", "B", "C#", "D#", "E#"]
+        );
+        assert_eq!(
+            names(scale("C harmonic minor")),
+            ["C", "D", "Eb", "F", "G", "Ab", "B"]
+        );
+        assert_eq!(
+            names(scale("D dorian")),
+            ["D", "E", "F", "G", "A", "B", "C"]
+        );
+        assert_eq!(
+            names(scale("B locrian")),
+            ["B", "C", "D", "E", "F", "G", "A"]
+        );
+        assert_eq!(
+            names(scale("A minor pentatonic")),
+            ["A", "C", "D", "E", "G"]
+        );
+        assert_eq!(names(scale("G# minor"))[6], "F#");
+
+        // 2) find the degrees of pitches, enharmonic pitches should match.
+        let c_minor = scale("C minor");
+        assert_eq!(c_minor.degree(&"C".parse().unwrap()), Some(1));
+        assert_eq!(c_minor.degree(&"D#".parse().unwrap()), Some(3));
+        assert!(!c_minor.contains(&"E".parse().unwrap()));
+
+        // 3) parse and format scales, unknown kinds should be EINVALID.
+        assert_eq!(scale("Bb Mixolydian").to_string(), "Bb mixolydian");
+        assert_eq!(scale("Bb Mixolydian").kind, ScaleKind::Mixolydian);
+
+        for s in ["C", "C blues", "H major", "C  major"] {
+            let res = s.parse::<Scale>();
+            assert!(res.is_err());
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use core::fmt;
+use std::str::FromStr;
+
+use openmusicgang_err::error::{Error, ErrorCode};
+
+/// TimeSignature is the meter of a song, a number of beats per measure and the note value of a beat, e.g. "6/8".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TimeSignature {
+    pub beats: u8,
+    /// Note value of a beat, 4 for a quarter note, 8 for an eighth note.
+    pub unit: u8,
+}
+
+impl TimeSignature {
+    pub fn new(beats: u8, unit: u8) -> TimeSignature {
+        TimeSignature { beats, unit }
+    }
+
+    /// Returns true if the signature has from 1 to 32 beats and a power of two up to 64 as unit.
+    pub fn is_valid(&self) -> bool {
+        (1..=32).contains(&self.beats) && self.unit <= 64 && self.unit.is_power_of_two()
+    }
+
+    /// Returns true if the beats of the signature are grouped by three, e.g. "6/8" or "12/8".
+    pub fn is_compound(&self) -> bool {
+        self.unit >= 8 && self.beats > 3 && self.beats.is_multiple_of(3)
+    }
+
+    /// Returns the length of a measure in quarter notes, e.g. 3.5 for "7/8".
+    pub fn quarter_notes(&self) -> f64 {
+        self.beats as f64 * 4.0 / self.unit as f64
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.beats, self.unit)
+    }
+}
+
+impl FromStr for TimeSignature {
+    type Err = Error;
+
+    /// Returns the time signature of the given string, e.g. "4/4" or "7/8".
+    ///
+    /// Returns EINVALID if the string is not a valid time signature.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::new(ErrorCode::EINVALID, format!("Invalid time signature {}", s));
+
+        let (beats, unit) = s.split_once('/').ok_or_else(invalid)?;
+
+        let time_signature = match (beats.parse::<u8>(), unit.parse::<u8>()) {
+            (Ok(beats), Ok(unit)) => TimeSignature::new(beats, unit),
+            _ => return Err(invalid()),
+        };
+
+        if !time_signature.is_valid() {
+            return Err(invalid());
+        }
+
+        Ok(time_signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// ## Simple workflow
+    ///
+    /// 1) parse and format time signatures.
+    /// 2) parse invalid time signatures, error should be EINVALID.
+    /// 3) check simple and compound meters and the length of their measures.
+    #[test]
+    fn test_time_signature() {
+        // 1) parse and format time signatures.
+        let time_signature = |s: &str| s.parse::<TimeSignature>().unwrap();
+
+        assert_eq!(time_signature("7/8"), TimeSignature::new(7, 8));
+        assert_eq!(time_signature("12/8").to_string(), "12/8");
+
+        // 2) parse invalid time signatures, error should be EINVALID.
+        for s in ["4/3", "0/4", "33/4", "4/128", "four", "4/", "3/4/4"] {
+            let res = s.parse::<TimeSignature>();
+            assert!(res.is_err(), "{}", s);
+            assert_eq!(res.unwrap_err().code, ErrorCode::EINVALID);
+        }
+
+        // 3) check simple and compound meters and the length of their measures.
+        assert!(time_signature("6/8").is_compound());
+        assert!(time_signature("12/16").is_compound());
+        assert!(!time_signature("3/8").is_compound());
+        assert!(!time_signature("6/4").is_compound());
+        assert_eq!(time_signature("7/8").quarter_notes(), 3.5);
+        assert_eq!(time_signature("2/2").quarter_notes(), 4.0);
+        assert!(!TimeSignature::new(4, 0).is_valid());
+    }
+}